@@ -5,11 +5,14 @@ use tardis::{
     basic::{dto::TardisContext, result::TardisResult},
     cache::cache_client::TardisCacheClient,
     chrono::Local,
-    log, TardisFuns, TardisFunsInst,
+    log,
+    serde_json::Value,
+    TardisFuns, TardisFunsInst,
 };
 
 const TASK_IN_CTX_FLAG: &str = "task_id";
 const NOTIFY_EVENT_IN_CTX_FLAG: &str = "notify";
+const TASK_PROCESS_DATA_FLAG: &str = ":process";
 
 pub struct TaskProcessor;
 
@@ -42,16 +45,43 @@ impl TaskProcessor {
         Ok(result1 && result2)
     }
 
+    /// Save the intermediate data of the task, e.g. the progress, which can be read before the task is completed
+    pub async fn set_process_data(cache_key: &str, task_id: i64, data: Value, cache_client: &TardisCacheClient) -> TardisResult<()> {
+        cache_client.hset(&format!("{cache_key}{TASK_PROCESS_DATA_FLAG}"), &task_id.to_string(), &TardisFuns::json.obj_to_string(&data)?).await
+    }
+
+    pub async fn get_process_data(cache_key: &str, task_id: i64, cache_client: &TardisCacheClient) -> TardisResult<Option<Value>> {
+        if let Some(data) = cache_client.hget(&format!("{cache_key}{TASK_PROCESS_DATA_FLAG}"), &task_id.to_string()).await? {
+            Ok(Some(TardisFuns::json.str_to_json(&data)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Remove the intermediate data of the task, e.g. when the task is finished and its result is no longer needed
+    pub async fn delete_process_data(cache_key: &str, task_id: i64, cache_client: &TardisCacheClient) -> TardisResult<()> {
+        cache_client.hdel(&format!("{cache_key}{TASK_PROCESS_DATA_FLAG}"), &task_id.to_string()).await
+    }
+
     pub async fn execute_task<P, T>(cache_key: &str, process: P, funs: &TardisFunsInst) -> TardisResult<i64>
     where
         P: FnOnce() -> T + Send + Sync + 'static,
         T: Future<Output = TardisResult<()>> + Send + 'static,
+    {
+        Self::execute_task_with_process(cache_key, move |_| process(), funs).await
+    }
+
+    /// Same as `execute_task`, but the task id is passed to the process to report its progress by `set_process_data`
+    pub async fn execute_task_with_process<P, T>(cache_key: &str, process: P, funs: &TardisFunsInst) -> TardisResult<i64>
+    where
+        P: FnOnce(i64) -> T + Send + Sync + 'static,
+        T: Future<Output = TardisResult<()>> + Send + 'static,
     {
         let task_id = TaskProcessor::init_task(cache_key, funs.cache()).await?;
         let cache_client = funs.cache();
         let cache_key = cache_key.to_string();
         tardis::tokio::spawn(async move {
-            let result = process().await;
+            let result = process(task_id).await;
             match result {
                 Ok(_) => match TaskProcessor::set_status(&cache_key, task_id, true, cache_client).await {
                     Ok(_) => {}
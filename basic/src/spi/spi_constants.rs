@@ -4,4 +4,4 @@ pub(crate) const SPI_KIND_CODE_FLAG: &str = "__SPI_KIND_CODE__";
 pub(crate) const SPI_ISOLATION_FLAG: &str = "__isolation__";
 pub const SPI_PG_KIND_CODE: &str = "spi-bs-pg";
 pub const SPI_ES_KIND_CODE: &str = "spi-bs-es";
-pub(crate) const GLOBAL_STORAGE_FLAG: &str = "starsys";
+pub const GLOBAL_STORAGE_FLAG: &str = "starsys";
@@ -5,6 +5,10 @@ use serde::Serialize;
 use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
 use tardis::log::{info, warn};
+use tardis::tokio::net::TcpStream;
+use tardis::web::tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tardis::web::tokio_tungstenite::tungstenite::http::{HeaderName, HeaderValue};
+use tardis::web::tokio_tungstenite::{connect_async_tls_with_config, Connector, MaybeTlsStream, WebSocketStream};
 use tardis::web::poem_openapi::types::{ParseFromJSON, ToJSON};
 use tardis::web::web_client::TardisWebClient;
 use tardis::web::web_resp::{TardisResp, Void};
@@ -112,6 +116,11 @@ impl TestHttpClient {
         result
     }
 
+    pub async fn put_to_str<B: Serialize + Debug>(&self, url: &str, body: &B) -> String {
+        info!(">>>>[PUT]|{}:{:#?}", url, body);
+        self.client.put_obj_to_str(format!("{}{}", self.base_url, url).as_str(), body, None).await.unwrap().body.unwrap()
+    }
+
     pub async fn put<B: Serialize + Debug, T>(&self, url: &str, body: &B) -> T
     where
         T: DeserializeOwned + ParseFromJSON + ToJSON + Serialize + Send + Sync + Debug,
@@ -163,4 +172,20 @@ impl TestHttpClient {
         info!("<<<<[PATCH]|{}:{:#?}", url, result);
         result
     }
+
+    /// Connect to the websocket endpoint with the context set by [`TestHttpClient::set_auth`].
+    ///
+    /// The certificate of the test web server is self-signed, so it is not verified.
+    pub async fn ws_connect(&self, url: &str) -> WebSocketStream<MaybeTlsStream<TcpStream>> {
+        info!(">>>>[WS]|{}", url);
+        let mut request = format!("{}{}", self.base_url.replacen("http", "ws", 1), url).into_client_request().unwrap();
+        let ctx_base64 = TardisFuns::crypto.base64.encode(&TardisFuns::json.obj_to_string(&self.context).unwrap());
+        request.headers_mut().insert(
+            HeaderName::from_bytes(TardisFuns::fw_config().web_server.context_conf.context_header_name.as_bytes()).unwrap(),
+            HeaderValue::from_str(&ctx_base64).unwrap(),
+        );
+        let tls_connector = native_tls::TlsConnector::builder().danger_accept_invalid_certs(true).danger_accept_invalid_hostnames(true).build().unwrap();
+        let (stream, _) = connect_async_tls_with_config(request, None, false, Some(Connector::NativeTls(tls_connector))).await.unwrap();
+        stream
+    }
 }
@@ -1,4 +1,5 @@
 use bios_basic::{process::ci_processor::AppKeyConfig, rbum::rbum_config::RbumConfig};
+use bios_sdk_invoke::invoke_config::InvokeConfig;
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 
@@ -16,6 +17,8 @@ pub struct ScheduleConfig {
     pub distributed_lock_expire_sec: u32,
     /// The expire key prefix of the distributed lock, default "schedual:job:lock:"
     pub distributed_lock_key_prefix: String,
+    /// The distributed lock is acquired from spi-cache
+    pub invoke: InvokeConfig,
 }
 
 impl Default for ScheduleConfig {
@@ -30,6 +33,7 @@ impl Default for ScheduleConfig {
             cache_key_job_changed_timer_sec: 30,
             distributed_lock_expire_sec: 1,
             distributed_lock_key_prefix: "schedual:job:lock:".to_string(),
+            invoke: InvokeConfig::default(),
         }
     }
 }
@@ -5,7 +5,7 @@
 /// - 每个实例和本地缓存对比，对于本地缺失的添加，对于本地多余的删除 / Compare with local cache, add for missing, delete for extra
 /// - 检查周期为配置中所配置的 / Check cycle is configured in the configuration
 /// ### 已被执行的任务 / Executed tasks
-/// `{config.distributed_lock_key_prefix}{code}`: `'executing'`
+/// `{config.distributed_lock_key_prefix}{code}`: 分布式锁，由spi-cache获取 / Distributed lock, acquired from spi-cache
 /// - 已被其他实例执行的任务，加分布式锁 / Tasks that have been executed by other instances, add distributed lock
 /// - 不再执行已加锁的任务 / No longer execute locked tasks
 /// ## 优先级 / Priority
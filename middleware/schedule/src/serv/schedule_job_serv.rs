@@ -3,6 +3,7 @@ use std::sync::Arc;
 use std::time::Duration;
 use std::vec;
 
+use bios_sdk_invoke::clients::spi_cache_client::{LockAcquireResp, SpiCacheClient};
 use tardis::basic::dto::TardisContext;
 use tardis::basic::error::TardisError;
 use tardis::basic::result::TardisResult;
@@ -432,19 +433,12 @@ impl OwnedScheduleTaskServ {
             let code = code.clone();
             let headers = headers.clone();
             let lock_key = lock_key.clone();
+            let ctx = ctx.clone();
             Box::pin(async move {
-                let cache_client = TardisFuns::cache();
-                // about set and setnx, see:
-                // 1. https://redis.io/commands/set/
-                // 2. https://redis.io/commands/setnx/
-                // At Redis version 2.6.12, setnx command is regarded as deprecated. see: https://redis.io/commands/setnx/
-                // "executing" could be any string now, it's just a placeholder
-                match cache_client.set_nx(&lock_key, "executing").await {
-                    Ok(true) => {
-                        // safety: it's ok to unwrap in this closure, scheduler will restart this job when after panic
-                        let Ok(()) = cache_client.expire(&lock_key, distributed_lock_expire_sec as usize).await else {
-                            return
-                        };
+                let funs = TardisFuns::inst(DOMAIN_CODE.to_string(), None);
+                // The lock is acquired with its expiration atomically, and it is not released, so the task is executed only once in the period by all nodes
+                match SpiCacheClient::lock_acquire(&lock_key, None, distributed_lock_expire_sec as u64, &funs, &ctx).await {
+                    Ok(Some(LockAcquireResp { acquired: true, .. })) => {
                         trace!("executing schedule task {code}");
                         // 1. write log exec start
                         let Ok(_) = TardisFuns::web_client()
@@ -485,7 +479,7 @@ impl OwnedScheduleTaskServ {
                             };
                         trace!("executed schedule task {code}");
                     }
-                    Ok(false) => {
+                    Ok(_) => {
                         trace!("schedule task {} is executed by other nodes, skip", code);
                     }
                     Err(e) => {
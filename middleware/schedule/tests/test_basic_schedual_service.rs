@@ -17,6 +17,7 @@ async fn test_basic_schedual_service() -> TardisResult<()> {
     init_tardis().await?;
 
     let counter = mock_webserver().await?;
+    init_cache_spi().await?;
     let test_env = TestEnv { counter };
     let config = ScheduleConfig::default();
 
@@ -12,6 +12,7 @@ use bios_basic::{
     test::test_http_client::TestHttpClient,
 };
 use bios_mw_schedule::{schedule_constants::DOMAIN_CODE, schedule_initializer, serv::schedule_job_serv::OwnedScheduleTaskServ};
+use bios_spi_cache::{cache_constants, cache_initializer};
 use bios_spi_kv::kv_initializer;
 use bios_spi_log::log_initializer;
 use tardis::{
@@ -46,8 +47,8 @@ pub async fn init_tardis() -> TardisResult<()> {
     TardisFuns::init(Some("tests/config")).await?;
     // rbum_initializer::init("", RbumConfig::default()).await?;
     let web_server = TardisFuns::web_server();
-    // cache_initializer::init(web_server).await?;
     bios_basic::rbum::rbum_initializer::init("bios-spi", bios_basic::rbum::rbum_config::RbumConfig::default()).await?;
+    cache_initializer::init(web_server).await?;
     log_initializer::init(web_server).await?;
     kv_initializer::init(web_server).await?;
     schedule_initializer::init(web_server).await?;
@@ -123,3 +124,40 @@ pub async fn init_spi(code: &str) -> TardisResult<()> {
 
     Ok(())
 }
+
+/// Bind the redis backend of spi-cache to the app, the distributed locks of the tasks are acquired from it
+#[allow(dead_code)]
+pub async fn init_cache_spi() -> TardisResult<()> {
+    let mut client = TestHttpClient::new(format!("https://localhost:8080/{}", cache_constants::DOMAIN_CODE));
+    let funs = TardisFuns::inst_with_db_conn(DOMAIN_CODE.to_string(), None);
+
+    let kind_id = RbumKindServ::get_rbum_kind_id_by_code(cache_constants::SPI_REDIS_KIND_CODE, &funs).await?.unwrap();
+    client.set_auth(&TardisContext {
+        own_paths: "".to_string(),
+        ak: "".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: "".to_string(),
+        ..Default::default()
+    })?;
+
+    let bs_id: String = client
+        .post(
+            "/ci/manage/bs",
+            &SpiBsAddReq {
+                name: TrimString("test-spi-cache".to_string()),
+                kind_id: TrimString(kind_id),
+                conn_uri: env::var("TARDIS_FW.CACHE.URL").unwrap(),
+                ak: TrimString("".to_string()),
+                sk: TrimString("".to_string()),
+                ext: "{}".to_string(),
+                private: false,
+                disabled: None,
+            },
+        )
+        .await;
+
+    let _: Void = client.put(&format!("/ci/manage/bs/{}/rel/app001", bs_id), &Void {}).await;
+
+    Ok(())
+}
@@ -26,6 +26,7 @@ async fn test_multi() -> TardisResult<()> {
 
     init_tardis().await?;
     let counter = mock_webserver().await?;
+    init_cache_spi().await?;
     let mut serve_group = init_task_serve_group(5).await?;
     let test_env = TestEnv { counter };
     let rng = &mut rand::thread_rng();
@@ -5,6 +5,7 @@ use bios_basic::test::init_rbum_test_container;
 
 use bios_mw_schedule::schedule_constants::DOMAIN_CODE;
 use bios_mw_schedule::schedule_initializer;
+use bios_spi_cache::cache_initializer;
 use bios_spi_kv::kv_initializer;
 use bios_spi_log::log_initializer;
 
@@ -13,7 +14,7 @@ use tardis::tokio::time::sleep;
 
 use tardis::{testcontainers, tokio, TardisFuns};
 
-use crate::test_common::init_spi;
+use crate::test_common::{init_cache_spi, init_spi};
 mod test_common;
 mod test_schedule_item;
 #[tokio::test]
@@ -38,6 +39,7 @@ async fn init_data() -> TardisResult<()> {
     schedule_initializer::init(web_server).await?;
     log_initializer::init(web_server).await?;
     kv_initializer::init(web_server).await?;
+    cache_initializer::init(web_server).await?;
 
     tokio::spawn(async move {
         web_server.start().await.unwrap();
@@ -48,6 +50,7 @@ async fn init_data() -> TardisResult<()> {
     const KV_DOMAIN_CODE: &str = bios_spi_kv::kv_constants::DOMAIN_CODE;
     init_spi(LOG_DOMAIN_CODE).await?;
     init_spi(KV_DOMAIN_CODE).await?;
+    init_cache_spi().await?;
     let mut client = test_common::init_client().await?;
     let funs = TardisFuns::inst_with_db_conn(DOMAIN_CODE.to_string(), None);
 
@@ -1,7 +1,11 @@
 mod base_spi_client;
+#[cfg(feature = "spi_cache")]
+pub mod spi_cache_client;
 #[cfg(feature = "spi_kv")]
 pub mod spi_kv_client;
 #[cfg(feature = "spi_log")]
 pub mod spi_log_client;
+#[cfg(feature = "spi_object")]
+pub mod spi_object_client;
 #[cfg(feature = "spi_search")]
 pub mod spi_search_client;
@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::serde_json::json;
+use tardis::web::poem_openapi;
+use tardis::web::web_resp::TardisResp;
+use tardis::TardisFunsInst;
+
+use crate::invoke_enumeration::InvokeModuleKind;
+
+use super::base_spi_client::BaseSpiClient;
+
+pub struct SpiCacheClient;
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct LockAcquireResp {
+    pub acquired: bool,
+    pub owner: String,
+    pub fencing_token: i64,
+}
+
+#[derive(poem_openapi::Enum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[oai(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitKind {
+    FixedWindow,
+    SlidingWindowLog,
+    TokenBucket,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct RateLimitResp {
+    pub allowed: bool,
+    pub limit: u64,
+    pub remaining: u64,
+    pub reset_after_ms: u64,
+    pub retry_after_ms: u64,
+}
+
+impl SpiCacheClient {
+    /// Try to acquire a distributed lock, a random owner token is generated when `owner` is None
+    pub async fn lock_acquire(key: &str, owner: Option<&str>, exp_sec: u64, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Option<LockAcquireResp>> {
+        let cache_url = BaseSpiClient::module_url(InvokeModuleKind::Cache, funs).await?;
+        let headers = BaseSpiClient::headers(None, funs, ctx).await?;
+        let resp = funs
+            .web_client()
+            .put::<_, TardisResp<LockAcquireResp>>(
+                &format!("{cache_url}/ci/lock/acquire"),
+                &json!({
+                    "key": key,
+                    "owner": owner,
+                    "exp_sec": exp_sec,
+                }),
+                headers,
+            )
+            .await?;
+        BaseSpiClient::package_resp(resp)
+    }
+
+    pub async fn lock_renew(key: &str, owner: &str, exp_sec: u64, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<bool> {
+        let cache_url = BaseSpiClient::module_url(InvokeModuleKind::Cache, funs).await?;
+        let headers = BaseSpiClient::headers(None, funs, ctx).await?;
+        let resp = funs
+            .web_client()
+            .put::<_, TardisResp<bool>>(
+                &format!("{cache_url}/ci/lock/renew"),
+                &json!({
+                    "key": key,
+                    "owner": owner,
+                    "exp_sec": exp_sec,
+                }),
+                headers,
+            )
+            .await?;
+        Ok(BaseSpiClient::package_resp(resp)?.unwrap_or(false))
+    }
+
+    pub async fn lock_release(key: &str, owner: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<bool> {
+        let cache_url = BaseSpiClient::module_url(InvokeModuleKind::Cache, funs).await?;
+        let headers = BaseSpiClient::headers(None, funs, ctx).await?;
+        let resp = funs
+            .web_client()
+            .put::<_, TardisResp<bool>>(
+                &format!("{cache_url}/ci/lock/release"),
+                &json!({
+                    "key": key,
+                    "owner": owner,
+                }),
+                headers,
+            )
+            .await?;
+        Ok(BaseSpiClient::package_resp(resp)?.unwrap_or(false))
+    }
+
+    /// Check and consume the rate limit quota of the tenant
+    pub async fn rate_limit(key: &str, kind: RateLimitKind, limit: u64, window_ms: u64, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Option<RateLimitResp>> {
+        let cache_url = BaseSpiClient::module_url(InvokeModuleKind::Cache, funs).await?;
+        let headers = BaseSpiClient::headers(None, funs, ctx).await?;
+        let resp = funs
+            .web_client()
+            .put::<_, TardisResp<RateLimitResp>>(
+                &format!("{cache_url}/ci/rate-limit/check"),
+                &json!({
+                    "key": key,
+                    "kind": kind,
+                    "limit": limit,
+                    "window_ms": window_ms,
+                }),
+                headers,
+            )
+            .await?;
+        BaseSpiClient::package_resp(resp)
+    }
+}
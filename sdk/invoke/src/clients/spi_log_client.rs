@@ -1,14 +1,22 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
 use serde::{Deserialize, Serialize};
 
 use tardis::{
-    basic::{dto::TardisContext, field::TrimString, result::TardisResult},
+    basic::{dto::TardisContext, error::TardisError, field::TrimString, result::TardisResult},
     chrono::{DateTime, Utc},
+    log::warn,
     serde_json::{json, Value},
+    tokio::{
+        self,
+        sync::{mpsc, oneshot},
+    },
     web::{
         poem_openapi,
-        web_resp::{TardisPage, TardisResp},
+        web_resp::{TardisPage, TardisResp, Void},
     },
-    TardisFunsInst,
+    TardisFuns, TardisFunsInst,
 };
 
 use crate::{clients::base_spi_client::BaseSpiClient, invoke_enumeration::InvokeModuleKind};
@@ -26,6 +34,7 @@ pub struct LogItemFindReq {
     pub rel_keys: Option<Vec<TrimString>>,
     pub ts_start: Option<DateTime<Utc>>,
     pub ts_end: Option<DateTime<Utc>>,
+    pub q: Option<String>,
     pub page_number: u32,
     pub page_size: u16,
 }
@@ -41,6 +50,34 @@ pub struct LogItemFindResp {
     pub op: String,
     pub rel_key: String,
     pub ts: DateTime<Utc>,
+    pub highlight: Option<String>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct LogItemAddReq {
+    pub tag: String,
+    pub content: String,
+    pub kind: Option<String>,
+    pub ext: Option<Value>,
+    pub key: Option<String>,
+    pub op: Option<String>,
+    pub rel_key: Option<String>,
+    pub ts: Option<DateTime<Utc>>,
+    pub owner: Option<String>,
+    pub own_paths: Option<String>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct LogItemBatchAddResp {
+    pub succeed: u32,
+    pub errors: Vec<LogItemBatchAddErrorResp>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct LogItemBatchAddErrorResp {
+    pub index: u32,
+    pub code: String,
+    pub msg: String,
 }
 
 impl SpiLogClient {
@@ -76,10 +113,187 @@ impl SpiLogClient {
         Ok(())
     }
 
+    /// Add items in a batch, the number of items is limited by `batch_add_max_items` of spi-log.
+    pub async fn batch_add(add_reqs: &[LogItemAddReq], funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Option<LogItemBatchAddResp>> {
+        let log_url: String = BaseSpiClient::module_url(InvokeModuleKind::Log, funs).await?;
+        let headers = BaseSpiClient::headers(None, funs, ctx).await?;
+        let resp = funs.web_client().post::<&[LogItemAddReq], TardisResp<LogItemBatchAddResp>>(&format!("{log_url}/ci/item/batch"), &add_reqs, headers.clone()).await?;
+        BaseSpiClient::package_resp(resp)
+    }
+
     pub async fn find(find_req: LogItemFindReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Option<TardisPage<LogItemFindResp>>> {
         let log_url: String = BaseSpiClient::module_url(InvokeModuleKind::Log, funs).await?;
         let headers = BaseSpiClient::headers(None, funs, ctx).await?;
         let resp = funs.web_client().put::<LogItemFindReq, TardisResp<TardisPage<LogItemFindResp>>>(&format!("{log_url}/ci/item"), &find_req, headers.clone()).await?;
         BaseSpiClient::package_resp(resp)
     }
+
+    /// Add or modify the retention policy of the tag, the policy applies to all tags when `tag` is None.
+    ///
+    /// A None limit keeps the current value, and 0 means unlimited.
+    pub async fn modify_retention(tag: Option<&str>, max_days: Option<u32>, max_size_mb: Option<u64>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        let log_url: String = BaseSpiClient::module_url(InvokeModuleKind::Log, funs).await?;
+        let headers = BaseSpiClient::headers(None, funs, ctx).await?;
+        let body = json!({
+            "tag": tag,
+            "max_days": max_days,
+            "max_size_mb": max_size_mb,
+        });
+        let resp = funs.web_client().put::<_, TardisResp<Void>>(&format!("{log_url}/ci/retention"), &body, headers.clone()).await?;
+        BaseSpiClient::package_resp(resp)?;
+        Ok(())
+    }
+}
+
+enum BufferedCommand {
+    Write(LogItemAddReq),
+    Flush(oneshot::Sender<TardisResult<()>>),
+}
+
+/// Buffers the items in memory and adds them in batches,
+/// the buffer is flushed when it's full, by the interval, or when the writer is dropped.
+///
+/// The flushing task is started by the first write, so the writer can be created outside of the async runtime.
+/// Items failed to be added are discarded, the failure of a flush in the background is returned by the next [`Self::write`] or [`Self::flush`].
+/// It's suitable for high-volume logs that can tolerate loss, use [`SpiLogClient::add`] for the others.
+pub struct SpiLogBufferedWriter {
+    sender: mpsc::Sender<BufferedCommand>,
+    // Taken by the first write or flush to start the flushing task
+    worker: Mutex<Option<BufferedWorker>>,
+    // The first failure of the flushes in the background since it was last returned
+    failure: Arc<Mutex<Option<TardisError>>>,
+}
+
+struct BufferedWorker {
+    receiver: mpsc::Receiver<BufferedCommand>,
+    module_code: String,
+    ctx: TardisContext,
+    max_items: usize,
+    flush_interval: Duration,
+}
+
+impl SpiLogBufferedWriter {
+    /// `module_code` is the code that the invoke config is initialized with, `ctx` is used for all items.
+    pub fn new(module_code: &str, ctx: TardisContext, max_items: usize, flush_interval: Duration) -> Self {
+        let max_items = max_items.max(1);
+        let (sender, receiver) = mpsc::channel(max_items);
+        SpiLogBufferedWriter {
+            sender,
+            worker: Mutex::new(Some(BufferedWorker {
+                receiver,
+                module_code: module_code.to_string(),
+                ctx,
+                max_items,
+                flush_interval,
+            })),
+            failure: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Put the item into the buffer, waits when the buffer is being flushed and the queue is full.
+    ///
+    /// If a flush in the background failed, the failure is returned and the item is not put.
+    pub async fn write(&self, add_req: LogItemAddReq) -> TardisResult<()> {
+        self.start();
+        if let Some(failure) = Self::take_failure(&self.failure) {
+            return Err(failure);
+        }
+        self.sender.send(BufferedCommand::Write(add_req)).await.map_err(|_| TardisError::internal_error("The buffered writer of spi-log is closed", "500-spi-log-writer-closed"))
+    }
+
+    /// Add the buffered items immediately, the failure of an earlier flush in the background is returned first.
+    pub async fn flush(&self) -> TardisResult<()> {
+        self.start();
+        let (resp_sender, resp_receiver) = oneshot::channel();
+        self.sender
+            .send(BufferedCommand::Flush(resp_sender))
+            .await
+            .map_err(|_| TardisError::internal_error("The buffered writer of spi-log is closed", "500-spi-log-writer-closed"))?;
+        resp_receiver.await.map_err(|_| TardisError::internal_error("The buffered writer of spi-log is closed", "500-spi-log-writer-closed"))?
+    }
+
+    fn take_failure(failure: &Mutex<Option<TardisError>>) -> Option<TardisError> {
+        failure.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+
+    fn keep_failure(failure: &Mutex<Option<TardisError>>, result: TardisResult<()>) {
+        if let Err(e) = result {
+            failure.lock().unwrap_or_else(|e| e.into_inner()).get_or_insert(e);
+        }
+    }
+
+    fn start(&self) {
+        let Some(worker) = self.worker.lock().unwrap_or_else(|e| e.into_inner()).take() else {
+            return;
+        };
+        let failure = self.failure.clone();
+        tokio::spawn(async move {
+            let BufferedWorker {
+                mut receiver,
+                module_code,
+                ctx,
+                max_items,
+                flush_interval,
+            } = worker;
+            let mut buffer = Vec::with_capacity(max_items);
+            let mut interval = tokio::time::interval(flush_interval);
+            loop {
+                tokio::select! {
+                    command = receiver.recv() => {
+                        match command {
+                            Some(BufferedCommand::Write(add_req)) => {
+                                buffer.push(add_req);
+                                if buffer.len() >= max_items {
+                                    Self::keep_failure(&failure, Self::do_flush(&mut buffer, &module_code, &ctx).await);
+                                }
+                            }
+                            Some(BufferedCommand::Flush(resp)) => {
+                                let result = Self::do_flush(&mut buffer, &module_code, &ctx).await;
+                                let _ = resp.send(match Self::take_failure(&failure) {
+                                    Some(failure) => Err(failure),
+                                    None => result,
+                                });
+                            }
+                            None => {
+                                // The writer is dropped, there is no one to return the failure to
+                                let _ = Self::do_flush(&mut buffer, &module_code, &ctx).await;
+                                break;
+                            }
+                        }
+                    }
+                    _ = interval.tick() => {
+                        Self::keep_failure(&failure, Self::do_flush(&mut buffer, &module_code, &ctx).await);
+                    }
+                }
+            }
+        });
+    }
+
+    async fn do_flush(buffer: &mut Vec<LogItemAddReq>, module_code: &str, ctx: &TardisContext) -> TardisResult<()> {
+        if buffer.is_empty() {
+            return Ok(());
+        }
+        let add_reqs = std::mem::take(buffer);
+        let funs = TardisFuns::inst(module_code.to_string(), None);
+        match SpiLogClient::batch_add(&add_reqs, &funs, ctx).await {
+            Ok(resp) => {
+                let errors = resp.map(|resp| resp.errors).unwrap_or_default();
+                for error in &errors {
+                    let tag = add_reqs.get(error.index as usize).map(|add_req| add_req.tag.as_str()).unwrap_or_default();
+                    warn!("[SPI-Log] Discarded item [{}] of tag [{}]: {}-{}", error.index, tag, error.code, error.msg);
+                }
+                match errors.first() {
+                    Some(error) => Err(TardisError::bad_request(
+                        &format!("{} of {} items are discarded, the first is [{}]: {}-{}", errors.len(), add_reqs.len(), error.index, error.code, error.msg),
+                        "400-spi-log-writer-items-discarded",
+                    )),
+                    None => Ok(()),
+                }
+            }
+            Err(e) => {
+                warn!("[SPI-Log] Discarded {} items: {:?}", add_reqs.len(), e);
+                Err(e)
+            }
+        }
+    }
 }
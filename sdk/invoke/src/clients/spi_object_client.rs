@@ -0,0 +1,33 @@
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::web::web_resp::TardisResp;
+use tardis::TardisFunsInst;
+
+use crate::invoke_enumeration::InvokeModuleKind;
+
+use super::base_spi_client::BaseSpiClient;
+
+pub struct SpiObjectClient;
+
+impl SpiObjectClient {
+    /// Fetch the URL for temporary authorization of the object, `presign_kind` is one of `put`, `view` and `delete`
+    pub async fn presign_obj_url(
+        presign_kind: &str,
+        object_path: &str,
+        exp_secs: u32,
+        private: bool,
+        funs: &TardisFunsInst,
+        ctx: &TardisContext,
+    ) -> TardisResult<Option<String>> {
+        let object_url = BaseSpiClient::module_url(InvokeModuleKind::Object, funs).await?;
+        let headers = BaseSpiClient::headers(None, funs, ctx).await?;
+        let resp = funs
+            .web_client()
+            .get::<TardisResp<String>>(
+                &format!("{object_url}/ci/obj/presign/{presign_kind}?object_path={object_path}&exp_secs={exp_secs}&private={private}"),
+                headers.clone(),
+            )
+            .await?;
+        BaseSpiClient::package_resp(resp)
+    }
+}
@@ -1,8 +1,9 @@
 use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
+use tardis::web::web_resp::TardisResp;
 use tardis::TardisFunsInst;
 
-use crate::dto::search_item_dto::{SearchItemAddReq, SearchItemModifyReq};
+use crate::dto::search_item_dto::{SearchItemAddReq, SearchItemBulkReq, SearchItemBulkResp, SearchItemModifyReq};
 use crate::invoke_enumeration::InvokeModuleKind;
 
 use super::base_spi_client::BaseSpiClient;
@@ -37,4 +38,17 @@ impl SpiSearchClient {
         funs.web_client().delete_to_void(&format!("{search_url}/ci/item/{tag}/{key}"), headers.clone()).await?;
         Ok(())
     }
+
+    /// Upsert and delete items in one request, the failed items are returned in the response.
+    pub async fn bulk(bulk_req: &SearchItemBulkReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Option<SearchItemBulkResp>> {
+        let search_url = BaseSpiClient::module_url(InvokeModuleKind::Search, funs).await?;
+        let headers = BaseSpiClient::headers(None, funs, ctx).await?;
+        let resp = funs.web_client().put::<_, TardisResp<SearchItemBulkResp>>(&format!("{search_url}/ci/item/bulk"), bulk_req, headers.clone()).await?;
+        let resp = BaseSpiClient::package_resp(resp)?;
+        for add_req in bulk_req.upserts.iter().flatten() {
+            let name = if let Some(name) = add_req.name.clone() { name } else { add_req.title.clone() };
+            SpiKvClient::add_or_modify_key_name(&format!("{}:{}", add_req.tag, add_req.key), &name, funs, ctx).await?;
+        }
+        Ok(resp)
+    }
 }
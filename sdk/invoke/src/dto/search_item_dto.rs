@@ -28,6 +28,7 @@ pub struct SearchItemAddReq {
     pub update_time: Option<DateTime<Utc>>,
     pub ext: Option<Value>,
     pub visit_keys: Option<SearchItemVisitKeysReq>,
+    pub vector: Option<Vec<f32>>,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
@@ -50,6 +51,7 @@ pub struct SearchItemModifyReq {
     // Overwrites the original content when it is true
     pub ext_override: Option<bool>,
     pub visit_keys: Option<SearchItemVisitKeysReq>,
+    pub vector: Option<Vec<f32>>,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
@@ -60,3 +62,30 @@ pub struct SearchItemVisitKeysReq {
     pub roles: Option<Vec<String>>,
     pub groups: Option<Vec<String>>,
 }
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Default)]
+pub struct SearchItemBulkReq {
+    pub upserts: Option<Vec<SearchItemAddReq>>,
+    pub deletes: Option<Vec<SearchItemBulkDeleteReq>>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct SearchItemBulkDeleteReq {
+    #[oai(validator(pattern = r"^[a-z0-9-_]+$"))]
+    pub tag: String,
+    pub key: TrimString,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct SearchItemBulkResp {
+    pub succeed: u32,
+    pub errors: Vec<SearchItemBulkErrorResp>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct SearchItemBulkErrorResp {
+    pub tag: String,
+    pub key: String,
+    pub code: String,
+    pub msg: String,
+}
@@ -20,6 +20,8 @@ impl Default for InvokeConfig {
                 (InvokeModuleKind::Kv.to_string(), "http://127.0.0.1:8080/spi-kv".to_string()),
                 (InvokeModuleKind::Log.to_string(), "http://127.0.0.1:8080/spi-log".to_string()),
                 (InvokeModuleKind::Search.to_string(), "http://127.0.0.1:8080/spi-search".to_string()),
+                (InvokeModuleKind::Cache.to_string(), "http://127.0.0.1:8080/spi-cache".to_string()),
+                (InvokeModuleKind::Object.to_string(), "http://127.0.0.1:8080/spi-object".to_string()),
                 (InvokeModuleKind::Schedule.to_string(), "http://127.0.0.1:8080/schedule".to_string()),
             ]),
         }
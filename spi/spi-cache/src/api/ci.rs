@@ -1 +1,3 @@
+pub mod cache_ci_lock_api;
 pub mod cache_ci_proc_api;
+pub mod cache_ci_rate_limit_api;
@@ -0,0 +1,35 @@
+use tardis::web::context_extractor::TardisContextExtractor;
+
+use tardis::web::poem_openapi;
+use tardis::web::poem_openapi::payload::Json;
+use tardis::web::web_resp::{TardisApiResult, TardisResp};
+
+use crate::dto::cache_lock_dto::{LockAcquireReq, LockAcquireResp, LockReleaseReq, LockRenewReq};
+use crate::serv::cache_lock_serv;
+#[derive(Clone)]
+pub struct CacheCiLockApi;
+
+/// Interface Console Cache Distributed Lock API
+#[poem_openapi::OpenApi(prefix_path = "/ci/lock", tag = "bios_basic::ApiTag::Interface")]
+impl CacheCiLockApi {
+    /// Acquire lock
+    #[oai(path = "/acquire", method = "put")]
+    async fn acquire(&self, req: Json<LockAcquireReq>, ctx: TardisContextExtractor) -> TardisApiResult<LockAcquireResp> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_lock_serv::acquire(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// Renew lock, only the owner can renew it
+    #[oai(path = "/renew", method = "put")]
+    async fn renew(&self, req: Json<LockRenewReq>, ctx: TardisContextExtractor) -> TardisApiResult<bool> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_lock_serv::renew(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// Release lock, only the owner can release it
+    #[oai(path = "/release", method = "put")]
+    async fn release(&self, req: Json<LockReleaseReq>, ctx: TardisContextExtractor) -> TardisApiResult<bool> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_lock_serv::release(&req.0, &funs, &ctx.0).await?)
+    }
+}
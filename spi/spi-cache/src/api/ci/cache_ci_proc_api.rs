@@ -2,11 +2,15 @@ use std::collections::HashMap;
 
 use tardis::web::context_extractor::TardisContextExtractor;
 
+use tardis::web::poem::web::websocket::{BoxWebSocketUpgraded, WebSocket};
 use tardis::web::poem_openapi;
+use tardis::web::poem_openapi::param::Path;
 use tardis::web::poem_openapi::payload::Json;
 use tardis::web::web_resp::{TardisApiResult, TardisResp, Void};
 
-use crate::dto::cache_proc_dto::{ExpReq, KIncrReq, KReq, KbRagngeReq, KbReq, KbvReq, KfIncrReq, KfReq, KfvReq, KvReq, KvWithExReq};
+use crate::dto::cache_proc_dto::{
+    ExpReq, KIncrReq, KRangeReq, KReq, KbRagngeReq, KbReq, KbvReq, KfIncrReq, KfReq, KfvReq, KsIncrReq, KsRangeReq, KsvReq, KvReq, KvWithExReq, KvsReq, PublishReq, ZMemberResp,
+};
 use crate::serv::cache_proc_serv;
 #[derive(Clone)]
 pub struct CacheCiProcApi;
@@ -110,6 +114,35 @@ impl CacheCiProcApi {
         TardisResp::ok(cache_proc_serv::llen(&req.0, &funs, &ctx.0).await?)
     }
 
+    /// rpush
+    #[oai(path = "/rpush", method = "put")]
+    async fn rpush(&self, req: Json<KvReq>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        cache_proc_serv::rpush(&req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// lpop
+    #[oai(path = "/lpop", method = "put")]
+    async fn lpop(&self, req: Json<KReq>, ctx: TardisContextExtractor) -> TardisApiResult<Option<String>> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::lpop(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// rpop
+    #[oai(path = "/rpop", method = "put")]
+    async fn rpop(&self, req: Json<KReq>, ctx: TardisContextExtractor) -> TardisApiResult<Option<String>> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::rpop(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// lrange
+    #[oai(path = "/lrange", method = "put")]
+    async fn lrange(&self, req: Json<KRangeReq>, ctx: TardisContextExtractor) -> TardisApiResult<Vec<String>> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::lrange(&req.0, &funs, &ctx.0).await?)
+    }
+
     /// hget
     #[oai(path = "/hget", method = "put")]
     async fn hget(&self, req: Json<KfReq>, ctx: TardisContextExtractor) -> TardisApiResult<Option<String>> {
@@ -182,6 +215,97 @@ impl CacheCiProcApi {
         TardisResp::ok(cache_proc_serv::hlen(&req.0, &funs, &ctx.0).await?)
     }
 
+    /// sadd
+    #[oai(path = "/sadd", method = "put")]
+    async fn sadd(&self, req: Json<KvsReq>, ctx: TardisContextExtractor) -> TardisApiResult<u64> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::sadd(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// srem
+    #[oai(path = "/srem", method = "put")]
+    async fn srem(&self, req: Json<KvsReq>, ctx: TardisContextExtractor) -> TardisApiResult<u64> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::srem(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// smembers
+    #[oai(path = "/smembers", method = "put")]
+    async fn smembers(&self, req: Json<KReq>, ctx: TardisContextExtractor) -> TardisApiResult<Vec<String>> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::smembers(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// sismember
+    #[oai(path = "/sismember", method = "put")]
+    async fn sismember(&self, req: Json<KvReq>, ctx: TardisContextExtractor) -> TardisApiResult<bool> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::sismember(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// scard
+    #[oai(path = "/scard", method = "put")]
+    async fn scard(&self, req: Json<KReq>, ctx: TardisContextExtractor) -> TardisApiResult<u64> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::scard(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// zadd
+    #[oai(path = "/zadd", method = "put")]
+    async fn zadd(&self, req: Json<KsvReq>, ctx: TardisContextExtractor) -> TardisApiResult<bool> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::zadd(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// zincrby
+    #[oai(path = "/zincrby", method = "post")]
+    async fn zincrby(&self, req: Json<KsIncrReq>, ctx: TardisContextExtractor) -> TardisApiResult<f64> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::zincrby(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// zrem
+    #[oai(path = "/zrem", method = "put")]
+    async fn zrem(&self, req: Json<KvsReq>, ctx: TardisContextExtractor) -> TardisApiResult<u64> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::zrem(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// zrange
+    #[oai(path = "/zrange", method = "put")]
+    async fn zrange(&self, req: Json<KRangeReq>, ctx: TardisContextExtractor) -> TardisApiResult<Vec<ZMemberResp>> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::zrange(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// zrevrange
+    #[oai(path = "/zrevrange", method = "put")]
+    async fn zrevrange(&self, req: Json<KRangeReq>, ctx: TardisContextExtractor) -> TardisApiResult<Vec<ZMemberResp>> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::zrevrange(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// zrangebyscore
+    #[oai(path = "/zrangebyscore", method = "put")]
+    async fn zrangebyscore(&self, req: Json<KsRangeReq>, ctx: TardisContextExtractor) -> TardisApiResult<Vec<ZMemberResp>> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::zrangebyscore(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// zscore
+    #[oai(path = "/zscore", method = "put")]
+    async fn zscore(&self, req: Json<KvReq>, ctx: TardisContextExtractor) -> TardisApiResult<Option<f64>> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::zscore(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// zcard
+    #[oai(path = "/zcard", method = "put")]
+    async fn zcard(&self, req: Json<KReq>, ctx: TardisContextExtractor) -> TardisApiResult<u64> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::zcard(&req.0, &funs, &ctx.0).await?)
+    }
+
     /// setbit
     #[oai(path = "/setbit", method = "put")]
     async fn setbit(&self, req: Json<KbvReq>, ctx: TardisContextExtractor) -> TardisApiResult<bool> {
@@ -209,4 +333,20 @@ impl CacheCiProcApi {
         let funs = crate::get_tardis_inst();
         TardisResp::ok(cache_proc_serv::bitcount_range_by_bit(&req.0, &funs, &ctx.0).await?)
     }
+
+    /// publish
+    #[oai(path = "/publish", method = "post")]
+    async fn publish(&self, req: Json<PublishReq>, ctx: TardisContextExtractor) -> TardisApiResult<u64> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::publish(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// subscribe
+    ///
+    /// Push the messages published to the channel through websocket
+    #[oai(path = "/subscribe/:channel", method = "get")]
+    async fn subscribe(&self, channel: Path<String>, websocket: WebSocket, ctx: TardisContextExtractor) -> tardis::web::poem::Result<BoxWebSocketUpgraded> {
+        let funs = crate::get_tardis_inst();
+        Ok(cache_proc_serv::subscribe(&channel.0, websocket, &funs, &ctx.0).await?)
+    }
 }
@@ -0,0 +1,21 @@
+use tardis::web::context_extractor::TardisContextExtractor;
+
+use tardis::web::poem_openapi;
+use tardis::web::poem_openapi::payload::Json;
+use tardis::web::web_resp::{TardisApiResult, TardisResp};
+
+use crate::dto::cache_rate_limit_dto::{RateLimitReq, RateLimitResp};
+use crate::serv::cache_rate_limit_serv;
+#[derive(Clone)]
+pub struct CacheCiRateLimitApi;
+
+/// Interface Console Cache Rate Limit API
+#[poem_openapi::OpenApi(prefix_path = "/ci/rate-limit", tag = "bios_basic::ApiTag::Interface")]
+impl CacheCiRateLimitApi {
+    /// Check and consume the quota
+    #[oai(path = "/check", method = "put")]
+    async fn check(&self, req: Json<RateLimitReq>, ctx: TardisContextExtractor) -> TardisApiResult<RateLimitResp> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_rate_limit_serv::check(&req.0, &funs, &ctx.0).await?)
+    }
+}
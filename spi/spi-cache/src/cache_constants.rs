@@ -1,2 +1,7 @@
 pub const DOMAIN_CODE: &str = "spi-cache";
 pub const SPI_REDIS_KIND_CODE: &str = "spi-bs-redis";
+pub const SPI_MEMORY_KIND_CODE: &str = "spi-bs-memory";
+pub(crate) const CONN_URI_FLAG: &str = "__conn_uri__";
+pub const LOCK_KEY_PREFIX: &str = "__lock__:";
+pub const LOCK_FENCING_KEY_PREFIX: &str = "__lock_fencing__:";
+pub const RATE_LIMIT_KEY_PREFIX: &str = "__rate_limit__:";
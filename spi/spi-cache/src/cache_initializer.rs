@@ -6,7 +6,7 @@ use tardis::{
 };
 
 use crate::{
-    api::ci::cache_ci_proc_api,
+    api::ci::{cache_ci_lock_api, cache_ci_proc_api, cache_ci_rate_limit_api},
     cache_config::CacheConfig,
     cache_constants::{self, DOMAIN_CODE},
     serv,
@@ -24,11 +24,22 @@ pub async fn init(web_server: &TardisWebServer) -> TardisResult<()> {
 
 async fn init_db(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
     spi_initializer::add_kind(cache_constants::SPI_REDIS_KIND_CODE, funs, ctx).await?;
+    spi_initializer::add_kind(cache_constants::SPI_MEMORY_KIND_CODE, funs, ctx).await?;
     Ok(())
 }
 
 async fn init_api(web_server: &TardisWebServer) -> TardisResult<()> {
-    web_server.add_module(DOMAIN_CODE, (spi_ci_bs_api::SpiCiBsApi, cache_ci_proc_api::CacheCiProcApi)).await;
+    web_server
+        .add_module(
+            DOMAIN_CODE,
+            (
+                spi_ci_bs_api::SpiCiBsApi,
+                cache_ci_proc_api::CacheCiProcApi,
+                cache_ci_lock_api::CacheCiLockApi,
+                cache_ci_rate_limit_api::CacheCiRateLimitApi,
+            ),
+        )
+        .await;
     Ok(())
 }
 
@@ -36,6 +47,8 @@ pub async fn init_fun(bs_cert: SpiBsCertResp, ctx: &TardisContext, mgr: bool) ->
     match bs_cert.kind_code.as_str() {
         #[cfg(feature = "spi-redis")]
         cache_constants::SPI_REDIS_KIND_CODE => serv::redis::cache_redis_initializer::init(&bs_cert, ctx, mgr).await,
+        #[cfg(feature = "spi-memory")]
+        cache_constants::SPI_MEMORY_KIND_CODE => serv::memory::cache_memory_initializer::init(&bs_cert, ctx, mgr).await,
         _ => Err(bs_cert.bs_not_implemented())?,
     }
 }
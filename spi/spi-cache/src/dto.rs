@@ -1 +1,3 @@
+pub mod cache_lock_dto;
 pub mod cache_proc_dto;
+pub mod cache_rate_limit_dto;
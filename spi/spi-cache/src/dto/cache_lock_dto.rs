@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+use tardis::{basic::field::TrimString, web::poem_openapi};
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct LockAcquireReq {
+    #[oai(validator(min_length = "1"))]
+    pub key: TrimString,
+    // Token identifying the lock holder, a random token is generated when it is empty
+    pub owner: Option<String>,
+    #[oai(validator(minimum(value = "1", exclusive = "false")))]
+    pub exp_sec: u64,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct LockAcquireResp {
+    pub acquired: bool,
+    pub owner: String,
+    // Monotonically increasing token of each successful acquisition, 0 when the lock is not acquired.
+    // The protected resource should reject requests carrying a smaller token than it has seen.
+    pub fencing_token: i64,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct LockRenewReq {
+    #[oai(validator(min_length = "1"))]
+    pub key: TrimString,
+    #[oai(validator(min_length = "1"))]
+    pub owner: String,
+    #[oai(validator(minimum(value = "1", exclusive = "false")))]
+    pub exp_sec: u64,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct LockReleaseReq {
+    #[oai(validator(min_length = "1"))]
+    pub key: TrimString,
+    #[oai(validator(min_length = "1"))]
+    pub owner: String,
+}
@@ -84,3 +84,57 @@ pub struct KbRagngeReq {
     pub start: u32,
     pub end: u32,
 }
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct KvsReq {
+    #[oai(validator(min_length = "1"))]
+    pub key: TrimString,
+    #[oai(validator(min_items = "1"))]
+    pub values: Vec<String>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct KRangeReq {
+    #[oai(validator(min_length = "1"))]
+    pub key: TrimString,
+    // Index based, supports negative index, e.g. -1 is the last element
+    pub start: i64,
+    pub stop: i64,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct KsvReq {
+    #[oai(validator(min_length = "1"))]
+    pub key: TrimString,
+    pub value: String,
+    pub score: f64,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct KsIncrReq {
+    #[oai(validator(min_length = "1"))]
+    pub key: TrimString,
+    pub value: String,
+    pub delta: f64,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct KsRangeReq {
+    #[oai(validator(min_length = "1"))]
+    pub key: TrimString,
+    pub min: f64,
+    pub max: f64,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct ZMemberResp {
+    pub value: String,
+    pub score: f64,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct PublishReq {
+    #[oai(validator(min_length = "1"))]
+    pub channel: TrimString,
+    pub message: String,
+}
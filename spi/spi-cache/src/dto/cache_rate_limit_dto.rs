@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+use tardis::{basic::field::TrimString, web::poem_openapi};
+
+#[derive(poem_openapi::Enum, Serialize, Deserialize, Debug, Clone)]
+pub enum RateLimitKind {
+    // Count requests in consecutive fixed windows
+    #[oai(rename = "fixed_window")]
+    FixedWindow,
+    // Keep the timestamp of each request and count the ones in the last window
+    #[oai(rename = "sliding_window_log")]
+    SlidingWindowLog,
+    // Tokens are refilled at a constant rate and each request takes tokens from the bucket
+    #[oai(rename = "token_bucket")]
+    TokenBucket,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct RateLimitReq {
+    #[oai(validator(min_length = "1"))]
+    pub key: TrimString,
+    pub kind: RateLimitKind,
+    // Maximum number of requests in a window, or the capacity of the bucket when using token bucket
+    #[oai(validator(minimum(value = "1", exclusive = "false")))]
+    pub limit: u64,
+    // Window size in milliseconds, or the time to refill an empty bucket when using token bucket
+    #[oai(validator(minimum(value = "1", exclusive = "false")))]
+    pub window_ms: u64,
+    // Number of permits consumed by this request, default is 1
+    pub cost: Option<u64>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct RateLimitResp {
+    pub allowed: bool,
+    // Can be used as `X-RateLimit-Limit`
+    pub limit: u64,
+    // Can be used as `X-RateLimit-Remaining`
+    pub remaining: u64,
+    // Milliseconds until the quota is fully restored, can be used as `X-RateLimit-Reset`
+    pub reset_after_ms: u64,
+    // Milliseconds to wait before retrying when not allowed, can be used as `Retry-After`
+    pub retry_after_ms: u64,
+}
@@ -1,3 +1,7 @@
+pub mod cache_lock_serv;
 pub mod cache_proc_serv;
+pub mod cache_rate_limit_serv;
+#[cfg(feature = "spi-memory")]
+pub mod memory;
 #[cfg(feature = "spi-redis")]
 pub mod redis;
@@ -0,0 +1,27 @@
+use bios_basic::spi::spi_funs::SpiBsInstExtractor;
+
+use tardis::basic::result::TardisResult;
+
+use crate::dto::cache_lock_dto::*;
+use crate::{cache_constants, cache_initializer};
+use bios_basic::spi_dispatch_service;
+
+#[cfg(feature = "spi-memory")]
+use super::memory;
+#[cfg(feature = "spi-redis")]
+use super::redis;
+spi_dispatch_service! {
+    @mgr: true,
+    @init: cache_initializer::init_fun,
+    @dispatch: {
+        #[cfg(feature = "spi-redis")]
+        cache_constants::SPI_REDIS_KIND_CODE => redis::cache_redis_lock_serv,
+        #[cfg(feature = "spi-memory")]
+        cache_constants::SPI_MEMORY_KIND_CODE => memory::cache_memory_lock_serv,
+    },
+    @method: {
+        acquire(req: &LockAcquireReq) -> TardisResult<LockAcquireResp>;
+        renew(req: &LockRenewReq) -> TardisResult<bool>;
+        release(req: &LockReleaseReq) -> TardisResult<bool>;
+    }
+}
@@ -3,11 +3,15 @@ use std::collections::HashMap;
 use bios_basic::spi::spi_funs::SpiBsInstExtractor;
 
 use tardis::basic::result::TardisResult;
+use tardis::web::poem::web::websocket::{BoxWebSocketUpgraded, WebSocket};
 
 use crate::dto::cache_proc_dto::*;
 use crate::{cache_constants, cache_initializer};
 use bios_basic::spi_dispatch_service;
 
+#[cfg(feature = "spi-memory")]
+use super::memory;
+#[cfg(feature = "spi-redis")]
 use super::redis;
 spi_dispatch_service! {
     @mgr: true,
@@ -15,6 +19,8 @@ spi_dispatch_service! {
     @dispatch: {
         #[cfg(feature = "spi-redis")]
         cache_constants::SPI_REDIS_KIND_CODE => redis::cache_redis_proc_serv,
+        #[cfg(feature = "spi-memory")]
+        cache_constants::SPI_MEMORY_KIND_CODE => memory::cache_memory_proc_serv,
     },
     @method: {
         set(req: &KvReq) -> TardisResult<()>;
@@ -30,6 +36,10 @@ spi_dispatch_service! {
         lpush(req: &KvReq) -> TardisResult<()>;
         lrangeall(req: &KReq) -> TardisResult<Vec<String>>;
         llen(req: &KReq) -> TardisResult<u64>;
+        rpush(req: &KvReq) -> TardisResult<()>;
+        lpop(req: &KReq) -> TardisResult<Option<String>>;
+        rpop(req: &KReq) -> TardisResult<Option<String>>;
+        lrange(req: &KRangeReq) -> TardisResult<Vec<String>>;
         hget(req: &KfReq) -> TardisResult<Option<String>>;
         hset(req: &KfvReq) -> TardisResult<()>;
         hset_nx(req: &KfvReq) -> TardisResult<bool>;
@@ -40,9 +50,24 @@ spi_dispatch_service! {
         hvals(req: &KReq) -> TardisResult<Vec<String>>;
         hgetall(req: &KReq) -> TardisResult<HashMap<String, String>>;
         hlen(req: &KReq) -> TardisResult<u64>;
+        sadd(req: &KvsReq) -> TardisResult<u64>;
+        srem(req: &KvsReq) -> TardisResult<u64>;
+        smembers(req: &KReq) -> TardisResult<Vec<String>>;
+        sismember(req: &KvReq) -> TardisResult<bool>;
+        scard(req: &KReq) -> TardisResult<u64>;
+        zadd(req: &KsvReq) -> TardisResult<bool>;
+        zincrby(req: &KsIncrReq) -> TardisResult<f64>;
+        zrem(req: &KvsReq) -> TardisResult<u64>;
+        zrange(req: &KRangeReq) -> TardisResult<Vec<ZMemberResp>>;
+        zrevrange(req: &KRangeReq) -> TardisResult<Vec<ZMemberResp>>;
+        zrangebyscore(req: &KsRangeReq) -> TardisResult<Vec<ZMemberResp>>;
+        zscore(req: &KvReq) -> TardisResult<Option<f64>>;
+        zcard(req: &KReq) -> TardisResult<u64>;
         setbit(req: &KbvReq) -> TardisResult<bool>;
         getbit(req: &KbReq) -> TardisResult<bool>;
         bitcount(req: &KReq) -> TardisResult<u32>;
         bitcount_range_by_bit(req: &KbRagngeReq) -> TardisResult<u32>;
+        publish(req: &PublishReq) -> TardisResult<u64>;
+        subscribe(channel: &str, websocket: WebSocket) -> TardisResult<BoxWebSocketUpgraded>;
     }
 }
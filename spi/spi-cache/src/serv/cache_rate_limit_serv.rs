@@ -0,0 +1,40 @@
+use bios_basic::spi::spi_funs::SpiBsInstExtractor;
+
+use tardis::basic::result::TardisResult;
+use tardis::TardisFunsInst;
+
+use crate::dto::cache_rate_limit_dto::*;
+use crate::{cache_constants, cache_initializer};
+use bios_basic::spi_dispatch_service;
+
+#[cfg(feature = "spi-memory")]
+use super::memory;
+#[cfg(feature = "spi-redis")]
+use super::redis;
+spi_dispatch_service! {
+    @mgr: true,
+    @init: cache_initializer::init_fun,
+    @dispatch: {
+        #[cfg(feature = "spi-redis")]
+        cache_constants::SPI_REDIS_KIND_CODE => redis::cache_redis_rate_limit_serv,
+        #[cfg(feature = "spi-memory")]
+        cache_constants::SPI_MEMORY_KIND_CODE => memory::cache_memory_rate_limit_serv,
+    },
+    @method: {
+        check(req: &RateLimitReq) -> TardisResult<RateLimitResp>;
+    }
+}
+
+/// Get the cost of the request, it must be between 1 and the limit
+pub(crate) fn get_cost(req: &RateLimitReq, funs: &TardisFunsInst) -> TardisResult<u64> {
+    let cost = req.cost.unwrap_or(1);
+    if cost == 0 || cost > req.limit {
+        return Err(funs.err().bad_request(
+            "rate_limit",
+            "check",
+            &format!("cost [{cost}] must be between 1 and limit [{}]", req.limit),
+            "400-spi-cache-rate-limit-cost-illegal",
+        ));
+    }
+    Ok(cost)
+}
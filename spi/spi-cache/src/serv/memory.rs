@@ -0,0 +1,5 @@
+pub mod cache_memory_client;
+pub mod cache_memory_initializer;
+pub mod cache_memory_lock_serv;
+pub mod cache_memory_proc_serv;
+pub mod cache_memory_rate_limit_serv;
@@ -0,0 +1,152 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use lazy_static::lazy_static;
+use tardis::tokio::sync::{broadcast, Mutex, MutexGuard};
+
+lazy_static! {
+    // Backend services with the same connection uri share the same store, just like connecting to the same redis
+    static ref CLIENTS: std::sync::RwLock<HashMap<String, Arc<CacheMemoryClient>>> = std::sync::RwLock::new(HashMap::new());
+}
+
+// Expired keys are removed when they are accessed, the whole store is swept at this interval to release the keys which are never accessed again
+const PURGE_INTERVAL: Duration = Duration::from_secs(60);
+const CHANNEL_CAPACITY: usize = 1024;
+
+pub enum CacheMemoryValue {
+    // Strings are stored as bytes so that bitmap operations can be applied on them
+    String(Vec<u8>),
+    List(VecDeque<String>),
+    Hash(HashMap<String, String>),
+    Set(HashSet<String>),
+    SortedSet(HashMap<String, f64>),
+}
+
+impl CacheMemoryValue {
+    fn is_empty(&self) -> bool {
+        match self {
+            CacheMemoryValue::String(_) => false,
+            CacheMemoryValue::List(list) => list.is_empty(),
+            CacheMemoryValue::Hash(hash) => hash.is_empty(),
+            CacheMemoryValue::Set(set) => set.is_empty(),
+            CacheMemoryValue::SortedSet(sorted_set) => sorted_set.is_empty(),
+        }
+    }
+}
+
+pub struct CacheMemoryEntry {
+    pub value: CacheMemoryValue,
+    pub expire_at: Option<Instant>,
+}
+
+impl CacheMemoryEntry {
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expire_at.map(|expire_at| expire_at <= now).unwrap_or(false)
+    }
+}
+
+pub struct CacheMemoryStore {
+    entries: HashMap<String, CacheMemoryEntry>,
+    last_purge_time: Instant,
+}
+
+impl CacheMemoryStore {
+    pub fn get(&mut self, key: &str) -> Option<&mut CacheMemoryEntry> {
+        if self.entries.get(key).map(|entry| entry.is_expired(Instant::now())).unwrap_or(false) {
+            self.entries.remove(key);
+        }
+        self.entries.get_mut(key)
+    }
+
+    pub fn get_or_insert_with(&mut self, key: &str, value: impl FnOnce() -> CacheMemoryValue) -> &mut CacheMemoryEntry {
+        if self.entries.get(key).map(|entry| entry.is_expired(Instant::now())).unwrap_or(false) {
+            self.entries.remove(key);
+        }
+        self.entries.entry(key.to_string()).or_insert_with(|| CacheMemoryEntry { value: value(), expire_at: None })
+    }
+
+    pub fn insert(&mut self, key: &str, value: CacheMemoryValue, expire_at: Option<Instant>) {
+        self.entries.insert(key.to_string(), CacheMemoryEntry { value, expire_at });
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<CacheMemoryEntry> {
+        self.entries.remove(key).filter(|entry| !entry.is_expired(Instant::now()))
+    }
+
+    /// Remove the key when its container is empty, same as redis
+    pub fn remove_if_empty(&mut self, key: &str) {
+        if self.entries.get(key).map(|entry| entry.value.is_empty()).unwrap_or(false) {
+            self.entries.remove(key);
+        }
+    }
+
+    fn purge(&mut self) {
+        let now = Instant::now();
+        if now.duration_since(self.last_purge_time) < PURGE_INTERVAL {
+            return;
+        }
+        self.entries.retain(|_, entry| !entry.is_expired(now));
+        self.last_purge_time = now;
+    }
+}
+
+pub struct CacheMemoryClient {
+    store: Mutex<CacheMemoryStore>,
+    channels: Mutex<HashMap<String, broadcast::Sender<String>>>,
+}
+
+impl CacheMemoryClient {
+    pub fn get_or_init(conn_uri: &str) -> Arc<CacheMemoryClient> {
+        if let Some(client) = CLIENTS.read().ok().and_then(|clients| clients.get(conn_uri).cloned()) {
+            return client;
+        }
+        let mut clients = CLIENTS.write().unwrap_or_else(|e| e.into_inner());
+        clients
+            .entry(conn_uri.to_string())
+            .or_insert_with(|| {
+                Arc::new(CacheMemoryClient {
+                    store: Mutex::new(CacheMemoryStore {
+                        entries: HashMap::new(),
+                        last_purge_time: Instant::now(),
+                    }),
+                    channels: Mutex::new(HashMap::new()),
+                })
+            })
+            .clone()
+    }
+
+    pub async fn store(&self) -> MutexGuard<'_, CacheMemoryStore> {
+        let mut store = self.store.lock().await;
+        store.purge();
+        store
+    }
+
+    /// Publish a message, returns the number of subscribers that received it
+    pub async fn publish(&self, channel: &str, message: String) -> u64 {
+        let mut channels = self.channels.lock().await;
+        let Some(sender) = channels.get(channel) else {
+            return 0;
+        };
+        match sender.send(message) {
+            Ok(receivers) => receivers as u64,
+            Err(_) => {
+                // All subscribers are gone
+                channels.remove(channel);
+                0
+            }
+        }
+    }
+
+    pub async fn subscribe(&self, channel: &str) -> broadcast::Receiver<String> {
+        let mut channels = self.channels.lock().await;
+        if let Some(sender) = channels.get(channel) {
+            return sender.subscribe();
+        }
+        let (sender, receiver) = broadcast::channel(CHANNEL_CAPACITY);
+        channels.insert(channel.to_string(), sender);
+        receiver
+    }
+}
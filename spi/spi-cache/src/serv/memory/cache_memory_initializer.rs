@@ -0,0 +1,16 @@
+use std::collections::HashMap;
+
+use bios_basic::spi::{dto::spi_bs_dto::SpiBsCertResp, spi_funs::SpiBsInst, spi_initializer};
+use tardis::basic::{dto::TardisContext, result::TardisResult};
+
+use super::cache_memory_client::CacheMemoryClient;
+
+pub async fn init(bs_cert: &SpiBsCertResp, ctx: &TardisContext, _: bool) -> TardisResult<SpiBsInst> {
+    let client = CacheMemoryClient::get_or_init(&bs_cert.conn_uri);
+    let mut ext = HashMap::new();
+    if !bs_cert.private {
+        let key_prefix = spi_initializer::common::get_isolation_flag_from_context(ctx);
+        spi_initializer::common::set_isolation_flag_to_ext(&key_prefix, &mut ext);
+    };
+    Ok(SpiBsInst { client: Box::new(client), ext })
+}
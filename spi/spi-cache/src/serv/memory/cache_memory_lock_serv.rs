@@ -0,0 +1,82 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use bios_basic::spi::spi_funs::SpiBsInst;
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    TardisFuns, TardisFunsInst,
+};
+
+use crate::cache_constants::{LOCK_FENCING_KEY_PREFIX, LOCK_KEY_PREFIX};
+use crate::dto::cache_lock_dto::{LockAcquireReq, LockAcquireResp, LockReleaseReq, LockRenewReq};
+
+use super::cache_memory_client::{CacheMemoryClient, CacheMemoryStore, CacheMemoryValue};
+use super::cache_memory_proc_serv::{format_key, parse_int, wrong_type};
+
+fn is_held_by(store: &mut CacheMemoryStore, lock_key: &str, owner: &str) -> bool {
+    matches!(store.get(lock_key).map(|entry| &entry.value), Some(CacheMemoryValue::String(current)) if current == owner.as_bytes())
+}
+
+pub async fn acquire(req: &LockAcquireReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<LockAcquireResp> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    let owner = match &req.owner {
+        Some(owner) if !owner.is_empty() => owner.to_string(),
+        _ => TardisFuns::field.nanoid(),
+    };
+    let lock_key = format_key(&format!("{LOCK_KEY_PREFIX}{}", req.key), bs_inst.1);
+    let fencing_key = format_key(&format!("{LOCK_FENCING_KEY_PREFIX}{}", req.key), bs_inst.1);
+    let expire_at = Some(Instant::now() + Duration::from_secs(req.exp_sec));
+    let mut store = bs_inst.0.store().await;
+    // Same as the redis backend, re-acquiring by the same owner extends the lock and keeps the token
+    let fencing_token = if store.get(&lock_key).is_none() {
+        store.insert(&lock_key, CacheMemoryValue::String(owner.as_bytes().to_vec()), expire_at);
+        let entry = store.get_or_insert_with(&fencing_key, || CacheMemoryValue::String(b"0".to_vec()));
+        let CacheMemoryValue::String(token) = &mut entry.value else {
+            return Err(wrong_type("acquire", funs));
+        };
+        let fencing_token = parse_int(token, "acquire", funs)? + 1;
+        *token = fencing_token.to_string().into_bytes();
+        fencing_token
+    } else if is_held_by(&mut store, &lock_key, &owner) {
+        if let Some(entry) = store.get(&lock_key) {
+            entry.expire_at = expire_at;
+        }
+        match store.get(&fencing_key).map(|entry| &entry.value) {
+            Some(CacheMemoryValue::String(token)) => parse_int(token, "acquire", funs)?,
+            _ => 0,
+        }
+    } else {
+        0
+    };
+    Ok(LockAcquireResp {
+        acquired: fencing_token > 0,
+        owner,
+        fencing_token,
+    })
+}
+
+pub async fn renew(req: &LockRenewReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    let lock_key = format_key(&format!("{LOCK_KEY_PREFIX}{}", req.key), bs_inst.1);
+    let mut store = bs_inst.0.store().await;
+    if !is_held_by(&mut store, &lock_key, &req.owner) {
+        return Ok(false);
+    }
+    if let Some(entry) = store.get(&lock_key) {
+        entry.expire_at = Some(Instant::now() + Duration::from_secs(req.exp_sec));
+    }
+    Ok(true)
+}
+
+pub async fn release(req: &LockReleaseReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    let lock_key = format_key(&format!("{LOCK_KEY_PREFIX}{}", req.key), bs_inst.1);
+    let mut store = bs_inst.0.store().await;
+    if !is_held_by(&mut store, &lock_key, &req.owner) {
+        return Ok(false);
+    }
+    store.remove(&lock_key);
+    Ok(true)
+}
@@ -0,0 +1,556 @@
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use bios_basic::spi::{spi_funs::SpiBsInst, spi_initializer::common};
+use tardis::{
+    basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    futures::{SinkExt, StreamExt},
+    log::warn,
+    tokio::{self, sync::broadcast::error::RecvError},
+    web::poem::web::websocket::{BoxWebSocketUpgraded, Message, WebSocket},
+    TardisFunsInst,
+};
+
+use crate::dto::cache_proc_dto::{
+    ExpReq, KIncrReq, KRangeReq, KReq, KbRagngeReq, KbReq, KbvReq, KfIncrReq, KfReq, KfvReq, KsIncrReq, KsRangeReq, KsvReq, KvReq, KvWithExReq, KvsReq, PublishReq, ZMemberResp,
+};
+
+use super::cache_memory_client::{CacheMemoryClient, CacheMemoryValue};
+
+pub(crate) fn format_key(req_key: &str, ext: &HashMap<String, String>) -> String {
+    if let Some(key_prefix) = common::get_isolation_flag_from_ext(ext) {
+        format!("{key_prefix}{req_key}")
+    } else {
+        req_key.to_string()
+    }
+}
+
+pub(crate) fn wrong_type(op: &str, funs: &TardisFunsInst) -> TardisError {
+    funs.err().bad_request("proc", op, "Operation against a key holding the wrong kind of value", "400-spi-cache-wrong-type")
+}
+
+pub(crate) fn parse_int(value: &[u8], op: &str, funs: &TardisFunsInst) -> TardisResult<i64> {
+    std::str::from_utf8(value)
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .ok_or_else(|| funs.err().bad_request("proc", op, "Value is not an integer or out of range", "400-spi-cache-not-integer"))
+}
+
+/// Convert the redis style index range (inclusive, supports negative index) to the slice range
+fn range_bounds(len: usize, start: i64, stop: i64) -> Option<(usize, usize)> {
+    let len = len as i64;
+    let start = if start < 0 { (len + start).max(0) } else { start };
+    let stop = if stop < 0 { len + stop } else { stop.min(len - 1) };
+    if start > stop || start >= len {
+        None
+    } else {
+        Some((start as usize, stop as usize))
+    }
+}
+
+fn sorted_members(sorted_set: &HashMap<String, f64>) -> Vec<ZMemberResp> {
+    let mut members = sorted_set.iter().map(|(value, score)| ZMemberResp { value: value.clone(), score: *score }).collect::<Vec<_>>();
+    members.sort_by(|a, b| a.score.partial_cmp(&b.score).unwrap_or(Ordering::Equal).then_with(|| a.value.cmp(&b.value)));
+    members
+}
+
+pub async fn set(req: &KvReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    bs_inst.0.store().await.insert(&format_key(&req.key, bs_inst.1), CacheMemoryValue::String(req.value.as_bytes().to_vec()), None);
+    Ok(())
+}
+
+pub async fn set_ex(req: &KvWithExReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    bs_inst.0.store().await.insert(
+        &format_key(&req.key, bs_inst.1),
+        CacheMemoryValue::String(req.value.as_bytes().to_vec()),
+        Some(Instant::now() + Duration::from_secs(req.exp_sec)),
+    );
+    Ok(())
+}
+
+pub async fn set_nx(req: &KvReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    let key = format_key(&req.key, bs_inst.1);
+    let mut store = bs_inst.0.store().await;
+    if store.get(&key).is_some() {
+        return Ok(false);
+    }
+    store.insert(&key, CacheMemoryValue::String(req.value.as_bytes().to_vec()), None);
+    Ok(true)
+}
+
+pub async fn get(req: &KReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<String>> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    match bs_inst.0.store().await.get(&format_key(&req.key, bs_inst.1)).map(|entry| &entry.value) {
+        None => Ok(None),
+        Some(CacheMemoryValue::String(value)) => Ok(Some(String::from_utf8_lossy(value).to_string())),
+        Some(_) => Err(wrong_type("get", funs)),
+    }
+}
+
+pub async fn getset(req: &KvReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<String>> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    let key = format_key(&req.key, bs_inst.1);
+    let mut store = bs_inst.0.store().await;
+    let old_value = match store.get(&key).map(|entry| &entry.value) {
+        None => None,
+        Some(CacheMemoryValue::String(value)) => Some(String::from_utf8_lossy(value).to_string()),
+        Some(_) => return Err(wrong_type("getset", funs)),
+    };
+    store.insert(&key, CacheMemoryValue::String(req.value.as_bytes().to_vec()), None);
+    Ok(old_value)
+}
+
+pub async fn incr(req: &KIncrReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<i64> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    let mut store = bs_inst.0.store().await;
+    let entry = store.get_or_insert_with(&format_key(&req.key, bs_inst.1), || CacheMemoryValue::String(b"0".to_vec()));
+    let CacheMemoryValue::String(value) = &mut entry.value else {
+        return Err(wrong_type("incr", funs));
+    };
+    let result = parse_int(value, "incr", funs)?
+        .checked_add(req.delta)
+        .ok_or_else(|| funs.err().bad_request("proc", "incr", "Increment or decrement would overflow", "400-spi-cache-overflow"))?;
+    *value = result.to_string().into_bytes();
+    Ok(result)
+}
+
+pub async fn del(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    bs_inst.0.store().await.remove(&format_key(&req.key, bs_inst.1));
+    Ok(())
+}
+
+pub async fn exists(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    Ok(bs_inst.0.store().await.get(&format_key(&req.key, bs_inst.1)).is_some())
+}
+
+pub async fn expire(req: &ExpReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    if let Some(entry) = bs_inst.0.store().await.get(&format_key(&req.key, bs_inst.1)) {
+        entry.expire_at = Some(Instant::now() + Duration::from_secs(req.exp_sec));
+    }
+    Ok(())
+}
+
+pub async fn ttl(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    // Keep the same result as redis: -2 if the key does not exist, -1 if the key has no expiration
+    let ttl: i64 = match bs_inst.0.store().await.get(&format_key(&req.key, bs_inst.1)) {
+        None => -2,
+        Some(entry) => match entry.expire_at {
+            None => -1,
+            Some(expire_at) => ((expire_at.saturating_duration_since(Instant::now()).as_millis() + 500) / 1000) as i64,
+        },
+    };
+    Ok(ttl as u64)
+}
+
+// list operations
+
+async fn push(key: &str, value: &str, front: bool, funs: &TardisFunsInst, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    let mut store = bs_inst.0.store().await;
+    let entry = store.get_or_insert_with(&format_key(key, bs_inst.1), || CacheMemoryValue::List(VecDeque::new()));
+    let CacheMemoryValue::List(list) = &mut entry.value else {
+        return Err(wrong_type("push", funs));
+    };
+    if front {
+        list.push_front(value.to_string());
+    } else {
+        list.push_back(value.to_string());
+    }
+    Ok(())
+}
+
+async fn pop(key: &str, front: bool, funs: &TardisFunsInst, inst: &SpiBsInst) -> TardisResult<Option<String>> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    let key = format_key(key, bs_inst.1);
+    let mut store = bs_inst.0.store().await;
+    let value = match store.get(&key).map(|entry| &mut entry.value) {
+        None => None,
+        Some(CacheMemoryValue::List(list)) => {
+            if front {
+                list.pop_front()
+            } else {
+                list.pop_back()
+            }
+        }
+        Some(_) => return Err(wrong_type("pop", funs)),
+    };
+    store.remove_if_empty(&key);
+    Ok(value)
+}
+
+async fn range(key: &str, start: i64, stop: i64, funs: &TardisFunsInst, inst: &SpiBsInst) -> TardisResult<Vec<String>> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    match bs_inst.0.store().await.get(&format_key(key, bs_inst.1)).map(|entry| &entry.value) {
+        None => Ok(vec![]),
+        Some(CacheMemoryValue::List(list)) => Ok(range_bounds(list.len(), start, stop).map(|(start, stop)| list.range(start..=stop).cloned().collect()).unwrap_or_default()),
+        Some(_) => Err(wrong_type("lrange", funs)),
+    }
+}
+
+pub async fn lpush(req: &KvReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    push(&req.key, &req.value, true, funs, inst).await
+}
+
+pub async fn lrangeall(req: &KReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<String>> {
+    range(&req.key, 0, -1, funs, inst).await
+}
+
+pub async fn llen(req: &KReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    match bs_inst.0.store().await.get(&format_key(&req.key, bs_inst.1)).map(|entry| &entry.value) {
+        None => Ok(0),
+        Some(CacheMemoryValue::List(list)) => Ok(list.len() as u64),
+        Some(_) => Err(wrong_type("llen", funs)),
+    }
+}
+
+pub async fn rpush(req: &KvReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    push(&req.key, &req.value, false, funs, inst).await
+}
+
+pub async fn lpop(req: &KReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<String>> {
+    pop(&req.key, true, funs, inst).await
+}
+
+pub async fn rpop(req: &KReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<String>> {
+    pop(&req.key, false, funs, inst).await
+}
+
+pub async fn lrange(req: &KRangeReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<String>> {
+    range(&req.key, req.start, req.stop, funs, inst).await
+}
+
+// hash operations
+
+async fn read_hash<T>(key: &str, op: &str, fun: impl FnOnce(Option<&HashMap<String, String>>) -> T, funs: &TardisFunsInst, inst: &SpiBsInst) -> TardisResult<T> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    match bs_inst.0.store().await.get(&format_key(key, bs_inst.1)).map(|entry| &entry.value) {
+        None => Ok(fun(None)),
+        Some(CacheMemoryValue::Hash(hash)) => Ok(fun(Some(hash))),
+        Some(_) => Err(wrong_type(op, funs)),
+    }
+}
+
+pub async fn hget(req: &KfReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<String>> {
+    read_hash(&req.key, "hget", |hash| hash.and_then(|hash| hash.get(&*req.field).cloned()), funs, inst).await
+}
+
+pub async fn hset(req: &KfvReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    let mut store = bs_inst.0.store().await;
+    let entry = store.get_or_insert_with(&format_key(&req.key, bs_inst.1), || CacheMemoryValue::Hash(HashMap::new()));
+    let CacheMemoryValue::Hash(hash) = &mut entry.value else {
+        return Err(wrong_type("hset", funs));
+    };
+    hash.insert(req.field.to_string(), req.value.clone());
+    Ok(())
+}
+
+pub async fn hset_nx(req: &KfvReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    let mut store = bs_inst.0.store().await;
+    let entry = store.get_or_insert_with(&format_key(&req.key, bs_inst.1), || CacheMemoryValue::Hash(HashMap::new()));
+    let CacheMemoryValue::Hash(hash) = &mut entry.value else {
+        return Err(wrong_type("hset_nx", funs));
+    };
+    if hash.contains_key(&*req.field) {
+        return Ok(false);
+    }
+    hash.insert(req.field.to_string(), req.value.clone());
+    Ok(true)
+}
+
+pub async fn hdel(req: &KfReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    let key = format_key(&req.key, bs_inst.1);
+    let mut store = bs_inst.0.store().await;
+    match store.get(&key).map(|entry| &mut entry.value) {
+        None => {}
+        Some(CacheMemoryValue::Hash(hash)) => {
+            hash.remove(&*req.field);
+        }
+        Some(_) => return Err(wrong_type("hdel", funs)),
+    }
+    store.remove_if_empty(&key);
+    Ok(())
+}
+
+pub async fn hincr(req: &KfIncrReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<i64> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    let mut store = bs_inst.0.store().await;
+    let entry = store.get_or_insert_with(&format_key(&req.key, bs_inst.1), || CacheMemoryValue::Hash(HashMap::new()));
+    let CacheMemoryValue::Hash(hash) = &mut entry.value else {
+        return Err(wrong_type("hincr", funs));
+    };
+    let value = hash.entry(req.field.to_string()).or_insert_with(|| "0".to_string());
+    let result = parse_int(value.as_bytes(), "hincr", funs)?
+        .checked_add(req.delta)
+        .ok_or_else(|| funs.err().bad_request("proc", "hincr", "Increment or decrement would overflow", "400-spi-cache-overflow"))?;
+    *value = result.to_string();
+    Ok(result)
+}
+
+pub async fn hexists(req: &KfReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    read_hash(&req.key, "hexists", |hash| hash.map(|hash| hash.contains_key(&*req.field)).unwrap_or(false), funs, inst).await
+}
+
+pub async fn hkeys(req: &KReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<String>> {
+    read_hash(&req.key, "hkeys", |hash| hash.map(|hash| hash.keys().cloned().collect()).unwrap_or_default(), funs, inst).await
+}
+
+pub async fn hvals(req: &KReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<String>> {
+    read_hash(&req.key, "hvals", |hash| hash.map(|hash| hash.values().cloned().collect()).unwrap_or_default(), funs, inst).await
+}
+
+pub async fn hgetall(req: &KReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<HashMap<String, String>> {
+    read_hash(&req.key, "hgetall", |hash| hash.cloned().unwrap_or_default(), funs, inst).await
+}
+
+pub async fn hlen(req: &KReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
+    read_hash(&req.key, "hlen", |hash| hash.map(|hash| hash.len() as u64).unwrap_or(0), funs, inst).await
+}
+
+// set operations
+
+async fn read_set<T>(key: &str, op: &str, fun: impl FnOnce(Option<&HashSet<String>>) -> T, funs: &TardisFunsInst, inst: &SpiBsInst) -> TardisResult<T> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    match bs_inst.0.store().await.get(&format_key(key, bs_inst.1)).map(|entry| &entry.value) {
+        None => Ok(fun(None)),
+        Some(CacheMemoryValue::Set(set)) => Ok(fun(Some(set))),
+        Some(_) => Err(wrong_type(op, funs)),
+    }
+}
+
+pub async fn sadd(req: &KvsReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    let key = format_key(&req.key, bs_inst.1);
+    let mut store = bs_inst.0.store().await;
+    let entry = store.get_or_insert_with(&key, || CacheMemoryValue::Set(HashSet::new()));
+    let CacheMemoryValue::Set(set) = &mut entry.value else {
+        return Err(wrong_type("sadd", funs));
+    };
+    let added = req.values.iter().filter(|value| set.insert(value.to_string())).count() as u64;
+    store.remove_if_empty(&key);
+    Ok(added)
+}
+
+pub async fn srem(req: &KvsReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    let key = format_key(&req.key, bs_inst.1);
+    let mut store = bs_inst.0.store().await;
+    let removed = match store.get(&key).map(|entry| &mut entry.value) {
+        None => 0,
+        Some(CacheMemoryValue::Set(set)) => req.values.iter().filter(|value| set.remove(value.as_str())).count() as u64,
+        Some(_) => return Err(wrong_type("srem", funs)),
+    };
+    store.remove_if_empty(&key);
+    Ok(removed)
+}
+
+pub async fn smembers(req: &KReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<String>> {
+    read_set(&req.key, "smembers", |set| set.map(|set| set.iter().cloned().collect()).unwrap_or_default(), funs, inst).await
+}
+
+pub async fn sismember(req: &KvReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    read_set(&req.key, "sismember", |set| set.map(|set| set.contains(&req.value)).unwrap_or(false), funs, inst).await
+}
+
+pub async fn scard(req: &KReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
+    read_set(&req.key, "scard", |set| set.map(|set| set.len() as u64).unwrap_or(0), funs, inst).await
+}
+
+// sorted set operations
+
+async fn read_sorted_set<T>(key: &str, op: &str, fun: impl FnOnce(Option<&HashMap<String, f64>>) -> T, funs: &TardisFunsInst, inst: &SpiBsInst) -> TardisResult<T> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    match bs_inst.0.store().await.get(&format_key(key, bs_inst.1)).map(|entry| &entry.value) {
+        None => Ok(fun(None)),
+        Some(CacheMemoryValue::SortedSet(sorted_set)) => Ok(fun(Some(sorted_set))),
+        Some(_) => Err(wrong_type(op, funs)),
+    }
+}
+
+pub async fn zadd(req: &KsvReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    let mut store = bs_inst.0.store().await;
+    let entry = store.get_or_insert_with(&format_key(&req.key, bs_inst.1), || CacheMemoryValue::SortedSet(HashMap::new()));
+    let CacheMemoryValue::SortedSet(sorted_set) = &mut entry.value else {
+        return Err(wrong_type("zadd", funs));
+    };
+    Ok(sorted_set.insert(req.value.clone(), req.score).is_none())
+}
+
+pub async fn zincrby(req: &KsIncrReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<f64> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    let mut store = bs_inst.0.store().await;
+    let entry = store.get_or_insert_with(&format_key(&req.key, bs_inst.1), || CacheMemoryValue::SortedSet(HashMap::new()));
+    let CacheMemoryValue::SortedSet(sorted_set) = &mut entry.value else {
+        return Err(wrong_type("zincrby", funs));
+    };
+    let score = sorted_set.entry(req.value.clone()).or_insert(0.0);
+    *score += req.delta;
+    Ok(*score)
+}
+
+pub async fn zrem(req: &KvsReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    let key = format_key(&req.key, bs_inst.1);
+    let mut store = bs_inst.0.store().await;
+    let removed = match store.get(&key).map(|entry| &mut entry.value) {
+        None => 0,
+        Some(CacheMemoryValue::SortedSet(sorted_set)) => req.values.iter().filter(|value| sorted_set.remove(value.as_str()).is_some()).count() as u64,
+        Some(_) => return Err(wrong_type("zrem", funs)),
+    };
+    store.remove_if_empty(&key);
+    Ok(removed)
+}
+
+pub async fn zrange(req: &KRangeReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<ZMemberResp>> {
+    read_sorted_set(
+        &req.key,
+        "zrange",
+        |sorted_set| {
+            let members = sorted_set.map(sorted_members).unwrap_or_default();
+            range_bounds(members.len(), req.start, req.stop).map(|(start, stop)| members.into_iter().skip(start).take(stop - start + 1).collect()).unwrap_or_default()
+        },
+        funs,
+        inst,
+    )
+    .await
+}
+
+pub async fn zrevrange(req: &KRangeReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<ZMemberResp>> {
+    read_sorted_set(
+        &req.key,
+        "zrevrange",
+        |sorted_set| {
+            let members = sorted_set.map(sorted_members).unwrap_or_default();
+            range_bounds(members.len(), req.start, req.stop).map(|(start, stop)| members.into_iter().rev().skip(start).take(stop - start + 1).collect()).unwrap_or_default()
+        },
+        funs,
+        inst,
+    )
+    .await
+}
+
+pub async fn zrangebyscore(req: &KsRangeReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<ZMemberResp>> {
+    read_sorted_set(
+        &req.key,
+        "zrangebyscore",
+        |sorted_set| sorted_set.map(sorted_members).unwrap_or_default().into_iter().filter(|member| member.score >= req.min && member.score <= req.max).collect(),
+        funs,
+        inst,
+    )
+    .await
+}
+
+pub async fn zscore(req: &KvReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<f64>> {
+    read_sorted_set(&req.key, "zscore", |sorted_set| sorted_set.and_then(|sorted_set| sorted_set.get(&req.value).copied()), funs, inst).await
+}
+
+pub async fn zcard(req: &KReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
+    read_sorted_set(&req.key, "zcard", |sorted_set| sorted_set.map(|sorted_set| sorted_set.len() as u64).unwrap_or(0), funs, inst).await
+}
+
+// bitmap operations, the bits are ordered from the most significant bit of each byte, same as redis
+
+pub async fn setbit(req: &KbvReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    let mut store = bs_inst.0.store().await;
+    let entry = store.get_or_insert_with(&format_key(&req.key, bs_inst.1), || CacheMemoryValue::String(vec![]));
+    let CacheMemoryValue::String(bytes) = &mut entry.value else {
+        return Err(wrong_type("setbit", funs));
+    };
+    let (index, mask) = ((req.offset / 8) as usize, 0x80u8 >> (req.offset % 8));
+    if bytes.len() <= index {
+        bytes.resize(index + 1, 0);
+    }
+    let original = bytes[index] & mask != 0;
+    if req.value {
+        bytes[index] |= mask;
+    } else {
+        bytes[index] &= !mask;
+    }
+    Ok(original)
+}
+
+pub async fn getbit(req: &KbReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    match bs_inst.0.store().await.get(&format_key(&req.key, bs_inst.1)).map(|entry| &entry.value) {
+        None => Ok(false),
+        Some(CacheMemoryValue::String(bytes)) => Ok(bytes.get((req.offset / 8) as usize).map(|byte| byte & (0x80u8 >> (req.offset % 8)) != 0).unwrap_or(false)),
+        Some(_) => Err(wrong_type("getbit", funs)),
+    }
+}
+
+pub async fn bitcount(req: &KReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u32> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    match bs_inst.0.store().await.get(&format_key(&req.key, bs_inst.1)).map(|entry| &entry.value) {
+        None => Ok(0),
+        Some(CacheMemoryValue::String(bytes)) => Ok(bytes.iter().map(|byte| byte.count_ones()).sum()),
+        Some(_) => Err(wrong_type("bitcount", funs)),
+    }
+}
+
+pub async fn bitcount_range_by_bit(req: &KbRagngeReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u32> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    match bs_inst.0.store().await.get(&format_key(&req.key, bs_inst.1)).map(|entry| &entry.value) {
+        None => Ok(0),
+        Some(CacheMemoryValue::String(bytes)) => {
+            let end = (req.end as usize).min(bytes.len() * 8);
+            Ok((req.start as usize..=end)
+                .filter(|offset| bytes.get(offset / 8).map(|byte| byte & (0x80u8 >> (offset % 8)) != 0).unwrap_or(false))
+                .count() as u32)
+        }
+        Some(_) => Err(wrong_type("bitcount_range_by_bit", funs)),
+    }
+}
+
+// pub/sub operations
+
+pub async fn publish(req: &PublishReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    Ok(bs_inst.0.publish(&format_key(&req.channel, bs_inst.1), req.message.clone()).await)
+}
+
+pub async fn subscribe(channel: &str, websocket: WebSocket, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<BoxWebSocketUpgraded> {
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    let mut receiver = bs_inst.0.subscribe(&format_key(channel, bs_inst.1)).await;
+    Ok(websocket
+        .on_upgrade(move |socket| async move {
+            let (mut sink, mut stream) = socket.split();
+            loop {
+                tokio::select! {
+                    message = receiver.recv() => {
+                        match message {
+                            Ok(payload) => {
+                                if sink.send(Message::Text(payload)).await.is_err() {
+                                    break;
+                                }
+                            }
+                            Err(RecvError::Lagged(skipped)) => warn!("[SPI-Cache] Subscriber is lagging, {skipped} messages are skipped"),
+                            Err(RecvError::Closed) => break,
+                        }
+                    }
+                    client_message = stream.next() => {
+                        match client_message {
+                            Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        })
+        .boxed())
+}
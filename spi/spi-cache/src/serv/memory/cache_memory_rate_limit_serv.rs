@@ -0,0 +1,107 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use bios_basic::{
+    rbum::{helper::rbum_scope_helper, rbum_enumeration::RbumScopeLevelKind},
+    spi::spi_funs::SpiBsInst,
+};
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    chrono::Utc,
+    TardisFuns, TardisFunsInst,
+};
+
+use crate::cache_constants::RATE_LIMIT_KEY_PREFIX;
+use crate::dto::cache_rate_limit_dto::{RateLimitKind, RateLimitReq, RateLimitResp};
+use crate::serv::cache_rate_limit_serv;
+
+use super::cache_memory_client::{CacheMemoryClient, CacheMemoryValue};
+use super::cache_memory_proc_serv::{format_key, parse_int, wrong_type};
+
+pub async fn check(req: &RateLimitReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<RateLimitResp> {
+    let cost = cache_rate_limit_serv::get_cost(req, funs)?;
+    let bs_inst = inst.inst::<Arc<CacheMemoryClient>>();
+    let tenant = rbum_scope_helper::get_path_item(RbumScopeLevelKind::L1.to_int(), &ctx.own_paths).unwrap_or_default();
+    let kind = match req.kind {
+        RateLimitKind::FixedWindow => "fw",
+        RateLimitKind::SlidingWindowLog => "swl",
+        RateLimitKind::TokenBucket => "tb",
+    };
+    let key = format_key(&format!("{RATE_LIMIT_KEY_PREFIX}{kind}:{tenant}:{}", req.key), bs_inst.1);
+    let (limit, window) = (req.limit, req.window_ms);
+    let now = Instant::now();
+    let mut store = bs_inst.0.store().await;
+    // (allowed, remaining, reset after, retry after)
+    let (allowed, remaining, reset_after_ms, retry_after_ms) = match req.kind {
+        RateLimitKind::FixedWindow => {
+            let entry = store.get_or_insert_with(&key, || CacheMemoryValue::String(b"0".to_vec()));
+            let expire_at = *entry.expire_at.get_or_insert(now + Duration::from_millis(window));
+            let CacheMemoryValue::String(counter) = &mut entry.value else {
+                return Err(wrong_type("check", funs));
+            };
+            let current = parse_int(counter, "check", funs)?.max(0) as u64;
+            let reset = expire_at.saturating_duration_since(now).as_millis() as u64;
+            if current + cost > limit {
+                (false, limit.saturating_sub(current), reset, reset)
+            } else {
+                *counter = (current + cost).to_string().into_bytes();
+                (true, limit - current - cost, reset, 0)
+            }
+        }
+        RateLimitKind::SlidingWindowLog => {
+            let now_ms = Utc::now().timestamp_millis() as f64;
+            let entry = store.get_or_insert_with(&key, || CacheMemoryValue::SortedSet(HashMap::new()));
+            let CacheMemoryValue::SortedSet(log) = &mut entry.value else {
+                return Err(wrong_type("check", funs));
+            };
+            log.retain(|_, ts| *ts > now_ms - window as f64);
+            let count = log.len() as u64;
+            let allowed = count + cost <= limit;
+            if allowed {
+                let request_id = TardisFuns::field.nanoid();
+                for i in 1..=cost {
+                    log.insert(format!("{request_id}:{i}"), now_ms);
+                }
+            }
+            let count = log.len() as u64;
+            let reset = log.values().copied().reduce(f64::min).map(|oldest| (oldest + window as f64 - now_ms).max(0.0) as u64).unwrap_or(0);
+            entry.expire_at = Some(now + Duration::from_millis(window));
+            if allowed {
+                (true, limit - count, reset, 0)
+            } else {
+                (false, limit.saturating_sub(count), reset, reset)
+            }
+        }
+        RateLimitKind::TokenBucket => {
+            let now_ms = Utc::now().timestamp_millis();
+            let rate = limit as f64 / window as f64;
+            let entry = store.get_or_insert_with(&key, || CacheMemoryValue::Hash(HashMap::new()));
+            let CacheMemoryValue::Hash(bucket) = &mut entry.value else {
+                return Err(wrong_type("check", funs));
+            };
+            let tokens = bucket.get("tokens").and_then(|tokens| tokens.parse::<f64>().ok()).unwrap_or(limit as f64);
+            let ts = bucket.get("ts").and_then(|ts| ts.parse::<i64>().ok()).unwrap_or(now_ms);
+            let mut tokens = (tokens + (now_ms - ts).max(0) as f64 * rate).min(limit as f64);
+            let (allowed, retry) = if tokens >= cost as f64 {
+                tokens -= cost as f64;
+                (true, 0)
+            } else {
+                (false, ((cost as f64 - tokens) / rate).ceil() as u64)
+            };
+            bucket.insert("tokens".to_string(), tokens.to_string());
+            bucket.insert("ts".to_string(), now_ms.to_string());
+            entry.expire_at = Some(now + Duration::from_millis(window));
+            (allowed, tokens.floor() as u64, ((limit as f64 - tokens) / rate).ceil() as u64, retry)
+        }
+    };
+    Ok(RateLimitResp {
+        allowed,
+        limit,
+        remaining,
+        reset_after_ms,
+        retry_after_ms,
+    })
+}
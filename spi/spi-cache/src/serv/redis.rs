@@ -1,2 +1,4 @@
 pub mod cache_redis_initializer;
+pub mod cache_redis_lock_serv;
 pub mod cache_redis_proc_serv;
+pub mod cache_redis_rate_limit_serv;
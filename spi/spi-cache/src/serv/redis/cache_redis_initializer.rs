@@ -6,9 +6,12 @@ use tardis::{
     cache::cache_client::TardisCacheClient,
 };
 
+use crate::cache_constants;
+
 pub async fn init(bs_cert: &SpiBsCertResp, ctx: &TardisContext, _: bool) -> TardisResult<SpiBsInst> {
     let client = TardisCacheClient::init(&bs_cert.conn_uri).await?;
     let mut ext = HashMap::new();
+    ext.insert(cache_constants::CONN_URI_FLAG.to_string(), bs_cert.conn_uri.clone());
     if !bs_cert.private {
         let key_prefix = spi_initializer::common::get_isolation_flag_from_context(ctx);
         spi_initializer::common::set_isolation_flag_to_ext(&key_prefix, &mut ext);
@@ -0,0 +1,87 @@
+use bios_basic::spi::spi_funs::SpiBsInst;
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    cache::{cache_client::TardisCacheClient, Script},
+    TardisFuns, TardisFunsInst,
+};
+
+use crate::cache_constants::{LOCK_FENCING_KEY_PREFIX, LOCK_KEY_PREFIX};
+use crate::dto::cache_lock_dto::{LockAcquireReq, LockAcquireResp, LockReleaseReq, LockRenewReq};
+
+use super::cache_redis_proc_serv::format_key;
+
+// KEYS[1]: lock key, KEYS[2]: fencing counter key
+// ARGV[1]: owner, ARGV[2]: expire seconds
+// Returns the fencing token, re-acquiring by the same owner extends the lock and keeps the token.
+const ACQUIRE_SCRIPT: &str = r#"
+if redis.call('SET', KEYS[1], ARGV[1], 'NX', 'EX', ARGV[2]) then
+    return redis.call('INCR', KEYS[2])
+end
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    redis.call('EXPIRE', KEYS[1], ARGV[2])
+    return tonumber(redis.call('GET', KEYS[2]) or '0')
+end
+return 0
+"#;
+
+// KEYS[1]: lock key
+// ARGV[1]: owner, ARGV[2]: expire seconds
+const RENEW_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('EXPIRE', KEYS[1], ARGV[2])
+end
+return 0
+"#;
+
+// KEYS[1]: lock key
+// ARGV[1]: owner
+const RELEASE_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+return 0
+"#;
+
+pub async fn acquire(req: &LockAcquireReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<LockAcquireResp> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let owner = match &req.owner {
+        Some(owner) if !owner.is_empty() => owner.to_string(),
+        _ => TardisFuns::field.nanoid(),
+    };
+    let mut conn = bs_inst.0.cmd().await?;
+    let fencing_token: i64 = Script::new(ACQUIRE_SCRIPT)
+        .key(format_key(&format!("{LOCK_KEY_PREFIX}{}", req.key), bs_inst.1))
+        .key(format_key(&format!("{LOCK_FENCING_KEY_PREFIX}{}", req.key), bs_inst.1))
+        .arg(&owner)
+        .arg(req.exp_sec)
+        .invoke_async(&mut conn)
+        .await?;
+    Ok(LockAcquireResp {
+        acquired: fencing_token > 0,
+        owner,
+        fencing_token,
+    })
+}
+
+pub async fn renew(req: &LockRenewReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let mut conn = bs_inst.0.cmd().await?;
+    let renewed: i64 = Script::new(RENEW_SCRIPT)
+        .key(format_key(&format!("{LOCK_KEY_PREFIX}{}", req.key), bs_inst.1))
+        .arg(&req.owner)
+        .arg(req.exp_sec)
+        .invoke_async(&mut conn)
+        .await?;
+    Ok(renewed == 1)
+}
+
+pub async fn release(req: &LockReleaseReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let mut conn = bs_inst.0.cmd().await?;
+    let released: i64 = Script::new(RELEASE_SCRIPT)
+        .key(format_key(&format!("{LOCK_KEY_PREFIX}{}", req.key), bs_inst.1))
+        .arg(&req.owner)
+        .invoke_async(&mut conn)
+        .await?;
+    Ok(released == 1)
+}
@@ -3,11 +3,18 @@ use std::collections::HashMap;
 use bios_basic::spi::{spi_funs::SpiBsInst, spi_initializer::common};
 use tardis::{
     basic::{dto::TardisContext, result::TardisResult},
-    cache::cache_client::TardisCacheClient,
+    cache::{cache_client::TardisCacheClient, cmd, AsyncCommands, Client},
+    futures::{SinkExt, StreamExt},
+    log::warn,
+    tokio,
+    web::poem::web::websocket::{BoxWebSocketUpgraded, Message, WebSocket},
     TardisFunsInst,
 };
 
-use crate::dto::cache_proc_dto::{ExpReq, KIncrReq, KReq, KbRagngeReq, KbReq, KbvReq, KfIncrReq, KfReq, KfvReq, KvReq, KvWithExReq};
+use crate::cache_constants;
+use crate::dto::cache_proc_dto::{
+    ExpReq, KIncrReq, KRangeReq, KReq, KbRagngeReq, KbReq, KbvReq, KfIncrReq, KfReq, KfvReq, KsIncrReq, KsRangeReq, KsvReq, KvReq, KvWithExReq, KvsReq, PublishReq, ZMemberResp,
+};
 
 pub(crate) fn format_key(req_key: &str, ext: &HashMap<String, String>) -> String {
     if let Some(key_prefix) = common::get_isolation_flag_from_ext(ext) {
@@ -84,6 +91,31 @@ pub async fn llen(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst
     Ok(bs_inst.0.llen(&format_key(&req.key, bs_inst.1)).await? as u64)
 }
 
+pub async fn rpush(req: &KvReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let mut conn = bs_inst.0.cmd().await?;
+    conn.rpush(format_key(&req.key, bs_inst.1), &req.value).await?;
+    Ok(())
+}
+
+pub async fn lpop(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<String>> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let mut conn = bs_inst.0.cmd().await?;
+    Ok(cmd("LPOP").arg(format_key(&req.key, bs_inst.1)).query_async(&mut conn).await?)
+}
+
+pub async fn rpop(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<String>> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let mut conn = bs_inst.0.cmd().await?;
+    Ok(cmd("RPOP").arg(format_key(&req.key, bs_inst.1)).query_async(&mut conn).await?)
+}
+
+pub async fn lrange(req: &KRangeReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<String>> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let mut conn = bs_inst.0.cmd().await?;
+    Ok(conn.lrange(format_key(&req.key, bs_inst.1), req.start as isize, req.stop as isize).await?)
+}
+
 // hash operations
 
 pub async fn hget(req: &KfReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<String>> {
@@ -136,6 +168,92 @@ pub async fn hlen(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst
     Ok(bs_inst.0.hlen(&format_key(&req.key, bs_inst.1)).await? as u64)
 }
 
+// set operations
+
+pub async fn sadd(req: &KvsReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let mut conn = bs_inst.0.cmd().await?;
+    Ok(conn.sadd(format_key(&req.key, bs_inst.1), &req.values).await?)
+}
+
+pub async fn srem(req: &KvsReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let mut conn = bs_inst.0.cmd().await?;
+    Ok(conn.srem(format_key(&req.key, bs_inst.1), &req.values).await?)
+}
+
+pub async fn smembers(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<String>> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let mut conn = bs_inst.0.cmd().await?;
+    Ok(conn.smembers(format_key(&req.key, bs_inst.1)).await?)
+}
+
+pub async fn sismember(req: &KvReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let mut conn = bs_inst.0.cmd().await?;
+    Ok(conn.sismember(format_key(&req.key, bs_inst.1), &req.value).await?)
+}
+
+pub async fn scard(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let mut conn = bs_inst.0.cmd().await?;
+    Ok(conn.scard(format_key(&req.key, bs_inst.1)).await?)
+}
+
+// sorted set operations
+
+pub async fn zadd(req: &KsvReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let mut conn = bs_inst.0.cmd().await?;
+    let added: u64 = conn.zadd(format_key(&req.key, bs_inst.1), &req.value, req.score).await?;
+    Ok(added > 0)
+}
+
+pub async fn zincrby(req: &KsIncrReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<f64> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let mut conn = bs_inst.0.cmd().await?;
+    Ok(conn.zincr(format_key(&req.key, bs_inst.1), &req.value, req.delta).await?)
+}
+
+pub async fn zrem(req: &KvsReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let mut conn = bs_inst.0.cmd().await?;
+    Ok(conn.zrem(format_key(&req.key, bs_inst.1), &req.values).await?)
+}
+
+pub async fn zrange(req: &KRangeReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<ZMemberResp>> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let mut conn = bs_inst.0.cmd().await?;
+    let members: Vec<(String, f64)> = conn.zrange_withscores(format_key(&req.key, bs_inst.1), req.start as isize, req.stop as isize).await?;
+    Ok(members.into_iter().map(|(value, score)| ZMemberResp { value, score }).collect())
+}
+
+pub async fn zrevrange(req: &KRangeReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<ZMemberResp>> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let mut conn = bs_inst.0.cmd().await?;
+    let members: Vec<(String, f64)> = conn.zrevrange_withscores(format_key(&req.key, bs_inst.1), req.start as isize, req.stop as isize).await?;
+    Ok(members.into_iter().map(|(value, score)| ZMemberResp { value, score }).collect())
+}
+
+pub async fn zrangebyscore(req: &KsRangeReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<ZMemberResp>> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let mut conn = bs_inst.0.cmd().await?;
+    let members: Vec<(String, f64)> = conn.zrangebyscore_withscores(format_key(&req.key, bs_inst.1), req.min, req.max).await?;
+    Ok(members.into_iter().map(|(value, score)| ZMemberResp { value, score }).collect())
+}
+
+pub async fn zscore(req: &KvReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<f64>> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let mut conn = bs_inst.0.cmd().await?;
+    Ok(conn.zscore(format_key(&req.key, bs_inst.1), &req.value).await?)
+}
+
+pub async fn zcard(req: &KReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let mut conn = bs_inst.0.cmd().await?;
+    Ok(conn.zcard(format_key(&req.key, bs_inst.1)).await?)
+}
+
 // bitmap operations
 
 pub async fn setbit(req: &KbvReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
@@ -157,3 +275,55 @@ pub async fn bitcount_range_by_bit(req: &KbRagngeReq, _funs: &TardisFunsInst, _c
     let bs_inst = inst.inst::<TardisCacheClient>();
     Ok(bs_inst.0.bitcount_range_by_bit(&format_key(&req.key, bs_inst.1), req.start as usize, req.end as usize).await? as u32)
 }
+
+// pub/sub operations
+
+pub async fn publish(req: &PublishReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let mut conn = bs_inst.0.cmd().await?;
+    Ok(conn.publish(format_key(&req.channel, bs_inst.1), &req.message).await?)
+}
+
+pub async fn subscribe(channel: &str, websocket: WebSocket, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<BoxWebSocketUpgraded> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let conn_uri = bs_inst.1.get(cache_constants::CONN_URI_FLAG).ok_or_else(|| {
+        funs.err().internal_error(
+            "proc",
+            "subscribe",
+            "The connection uri of backend service is not found",
+            "500-spi-cache-conn-uri-not-found",
+        )
+    })?;
+    // Subscribing occupies the whole connection, so a dedicated connection is created for each subscriber
+    let mut pub_sub = Client::open(conn_uri.as_str())?.get_async_connection().await?.into_pubsub();
+    pub_sub.subscribe(format_key(channel, bs_inst.1)).await?;
+    Ok(websocket
+        .on_upgrade(move |socket| async move {
+            let (mut sink, mut stream) = socket.split();
+            let mut messages = pub_sub.into_on_message();
+            loop {
+                tokio::select! {
+                    message = messages.next() => {
+                        let Some(message) = message else {
+                            break;
+                        };
+                        match message.get_payload::<String>() {
+                            Ok(payload) => {
+                                if sink.send(Message::Text(payload)).await.is_err() {
+                                    break;
+                                }
+                            }
+                            Err(e) => warn!("[SPI-Cache] Subscribed message decode error: {e}"),
+                        }
+                    }
+                    client_message = stream.next() => {
+                        match client_message {
+                            Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        })
+        .boxed())
+}
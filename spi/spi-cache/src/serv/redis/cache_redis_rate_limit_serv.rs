@@ -0,0 +1,125 @@
+use bios_basic::{
+    rbum::{helper::rbum_scope_helper, rbum_enumeration::RbumScopeLevelKind},
+    spi::spi_funs::SpiBsInst,
+};
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    cache::{cache_client::TardisCacheClient, Script},
+    TardisFuns, TardisFunsInst,
+};
+
+use crate::cache_constants::RATE_LIMIT_KEY_PREFIX;
+use crate::dto::cache_rate_limit_dto::{RateLimitKind, RateLimitReq, RateLimitResp};
+use crate::serv::cache_rate_limit_serv;
+
+use super::cache_redis_proc_serv::format_key;
+
+// All scripts use the clock of the redis server, so that the result is consistent among multiple service instances.
+// All scripts return {allowed, remaining, reset after (ms), retry after (ms)}.
+
+// KEYS[1]: counter key
+// ARGV[1]: limit, ARGV[2]: window (ms), ARGV[3]: cost
+const FIXED_WINDOW_SCRIPT: &str = r#"
+local limit = tonumber(ARGV[1])
+local window = tonumber(ARGV[2])
+local cost = tonumber(ARGV[3])
+local current = tonumber(redis.call('GET', KEYS[1]) or '0')
+if current + cost > limit then
+    local ttl = redis.call('PTTL', KEYS[1])
+    if ttl < 0 then ttl = window end
+    return {0, math.max(limit - current, 0), ttl, ttl}
+end
+current = redis.call('INCRBY', KEYS[1], cost)
+local ttl = redis.call('PTTL', KEYS[1])
+if ttl < 0 then
+    redis.call('PEXPIRE', KEYS[1], window)
+    ttl = window
+end
+return {1, limit - current, ttl, 0}
+"#;
+
+// KEYS[1]: sorted set key, the score of each member is the request time
+// ARGV[1]: limit, ARGV[2]: window (ms), ARGV[3]: cost, ARGV[4]: unique request id
+const SLIDING_WINDOW_LOG_SCRIPT: &str = r#"
+redis.replicate_commands()
+local limit = tonumber(ARGV[1])
+local window = tonumber(ARGV[2])
+local cost = tonumber(ARGV[3])
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+redis.call('ZREMRANGEBYSCORE', KEYS[1], '-inf', now - window)
+local count = redis.call('ZCARD', KEYS[1])
+local allowed = 0
+if count + cost <= limit then
+    for i = 1, cost do
+        redis.call('ZADD', KEYS[1], now, ARGV[4] .. ':' .. i)
+    end
+    count = count + cost
+    allowed = 1
+end
+local reset = 0
+local oldest = redis.call('ZRANGE', KEYS[1], 0, 0, 'WITHSCORES')
+if oldest[2] then
+    reset = tonumber(oldest[2]) + window - now
+    redis.call('PEXPIRE', KEYS[1], window)
+end
+if allowed == 1 then
+    return {1, limit - count, reset, 0}
+end
+return {0, math.max(limit - count, 0), reset, reset}
+"#;
+
+// KEYS[1]: bucket hash key, the fields are `tokens` and `ts` (last refill time)
+// ARGV[1]: capacity, ARGV[2]: time to refill an empty bucket (ms), ARGV[3]: cost
+const TOKEN_BUCKET_SCRIPT: &str = r#"
+redis.replicate_commands()
+local capacity = tonumber(ARGV[1])
+local window = tonumber(ARGV[2])
+local cost = tonumber(ARGV[3])
+local rate = capacity / window
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+local bucket = redis.call('HMGET', KEYS[1], 'tokens', 'ts')
+local tokens = tonumber(bucket[1]) or capacity
+local ts = tonumber(bucket[2]) or now
+tokens = math.min(capacity, tokens + math.max(now - ts, 0) * rate)
+local allowed = 0
+local retry = 0
+if tokens >= cost then
+    tokens = tokens - cost
+    allowed = 1
+else
+    retry = math.ceil((cost - tokens) / rate)
+end
+redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'ts', now)
+redis.call('PEXPIRE', KEYS[1], window)
+return {allowed, math.floor(tokens), math.ceil((capacity - tokens) / rate), retry}
+"#;
+
+pub async fn check(req: &RateLimitReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<RateLimitResp> {
+    let cost = cache_rate_limit_serv::get_cost(req, funs)?;
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    // The quota is counted per tenant, requests from different apps of the same tenant share it
+    let tenant = rbum_scope_helper::get_path_item(RbumScopeLevelKind::L1.to_int(), &ctx.own_paths).unwrap_or_default();
+    let (script, kind) = match req.kind {
+        RateLimitKind::FixedWindow => (FIXED_WINDOW_SCRIPT, "fw"),
+        RateLimitKind::SlidingWindowLog => (SLIDING_WINDOW_LOG_SCRIPT, "swl"),
+        RateLimitKind::TokenBucket => (TOKEN_BUCKET_SCRIPT, "tb"),
+    };
+    let mut conn = bs_inst.0.cmd().await?;
+    let (allowed, remaining, reset_after_ms, retry_after_ms): (i64, i64, i64, i64) = Script::new(script)
+        .key(format_key(&format!("{RATE_LIMIT_KEY_PREFIX}{kind}:{tenant}:{}", req.key), bs_inst.1))
+        .arg(req.limit)
+        .arg(req.window_ms)
+        .arg(cost)
+        .arg(TardisFuns::field.nanoid())
+        .invoke_async(&mut conn)
+        .await?;
+    Ok(RateLimitResp {
+        allowed: allowed == 1,
+        limit: req.limit,
+        remaining: remaining.max(0) as u64,
+        reset_after_ms: reset_after_ms.max(0) as u64,
+        retry_after_ms: retry_after_ms.max(0) as u64,
+    })
+}
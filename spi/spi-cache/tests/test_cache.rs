@@ -14,7 +14,9 @@ use tardis::basic::result::TardisResult;
 use tardis::tokio::time::sleep;
 use tardis::web::web_resp::Void;
 use tardis::{testcontainers, tokio, TardisFuns};
+mod test_cache_lock;
 mod test_cache_proc;
+mod test_cache_rate_limit;
 
 #[tokio::test]
 async fn test_cache() -> TardisResult<()> {
@@ -41,7 +43,6 @@ async fn init_data() -> TardisResult<()> {
     sleep(Duration::from_millis(500)).await;
 
     let funs = TardisFuns::inst_with_db_conn(DOMAIN_CODE.to_string(), None);
-    let kind_id = RbumKindServ::get_rbum_kind_id_by_code(cache_constants::SPI_REDIS_KIND_CODE, &funs).await?.unwrap();
     let ctx = TardisContext {
         own_paths: "".to_string(),
         ak: "".to_string(),
@@ -53,27 +54,35 @@ async fn init_data() -> TardisResult<()> {
 
     let mut client = TestHttpClient::new(format!("https://localhost:8080/{}", DOMAIN_CODE));
 
-    client.set_auth(&ctx)?;
+    // Run the same tests on each backend service, bound to different apps
+    for (kind_code, conn_uri, app_code) in [
+        (cache_constants::SPI_REDIS_KIND_CODE, env::var("TARDIS_FW.CACHE.URL").unwrap(), "app001"),
+        (cache_constants::SPI_MEMORY_KIND_CODE, "memory://test".to_string(), "app002"),
+    ] {
+        client.set_auth(&ctx)?;
+        let kind_id = RbumKindServ::get_rbum_kind_id_by_code(kind_code, &funs).await?.unwrap();
+        let bs_id: String = client
+            .post(
+                "/ci/manage/bs",
+                &SpiBsAddReq {
+                    name: TrimString(format!("test-spi-{app_code}")),
+                    kind_id: TrimString(kind_id),
+                    conn_uri,
+                    ak: TrimString("".to_string()),
+                    sk: TrimString("".to_string()),
+                    ext: "{}".to_string(),
+                    private: false,
+                    disabled: None,
+                },
+            )
+            .await;
 
-    let bs_id: String = client
-        .post(
-            "/ci/manage/bs",
-            &SpiBsAddReq {
-                name: TrimString("test-spi".to_string()),
-                kind_id: TrimString(kind_id),
-                conn_uri: env::var("TARDIS_FW.CACHE.URL").unwrap(),
-                ak: TrimString("".to_string()),
-                sk: TrimString("".to_string()),
-                ext: "{}".to_string(),
-                private: false,
-                disabled: None,
-            },
-        )
-        .await;
+        let _: Void = client.put(&format!("/ci/manage/bs/{}/rel/{}", bs_id, app_code), &Void {}).await;
 
-    let _: Void = client.put(&format!("/ci/manage/bs/{}/rel/app001", bs_id), &Void {}).await;
-
-    test_cache_proc::test(&mut client).await?;
+        test_cache_proc::test(app_code, &mut client).await?;
+        test_cache_lock::test(app_code, &mut client).await?;
+        test_cache_rate_limit::test(app_code, &mut client).await?;
+    }
 
     Ok(())
 }
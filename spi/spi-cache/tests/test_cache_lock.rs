@@ -0,0 +1,105 @@
+use bios_basic::test::test_http_client::TestHttpClient;
+use bios_spi_cache::dto::cache_lock_dto::{LockAcquireReq, LockAcquireResp, LockReleaseReq, LockRenewReq};
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::log::info;
+
+pub async fn test(app_code: &str, client: &mut TestHttpClient) -> TardisResult<()> {
+    client.set_auth(&TardisContext {
+        own_paths: format!("t1/{app_code}"),
+        ak: "".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: app_code.to_string(),
+        ..Default::default()
+    })?;
+
+    info!("【test_cache_lock】");
+    let result: LockAcquireResp = client
+        .put(
+            "/ci/lock/acquire",
+            &LockAcquireReq {
+                key: "job".into(),
+                owner: Some("node1".to_string()),
+                exp_sec: 1,
+            },
+        )
+        .await;
+    assert!(result.acquired);
+    assert_eq!(result.owner, "node1");
+    let fencing_token = result.fencing_token;
+    assert!(fencing_token > 0);
+
+    let result: LockAcquireResp = client
+        .put(
+            "/ci/lock/acquire",
+            &LockAcquireReq {
+                key: "job".into(),
+                owner: Some("node2".to_string()),
+                exp_sec: 1,
+            },
+        )
+        .await;
+    assert!(!result.acquired);
+    assert_eq!(result.fencing_token, 0);
+
+    let result: bool = client
+        .put(
+            "/ci/lock/renew",
+            &LockRenewReq {
+                key: "job".into(),
+                owner: "node2".to_string(),
+                exp_sec: 2,
+            },
+        )
+        .await;
+    assert!(!result);
+    let result: bool = client
+        .put(
+            "/ci/lock/renew",
+            &LockRenewReq {
+                key: "job".into(),
+                owner: "node1".to_string(),
+                exp_sec: 2,
+            },
+        )
+        .await;
+    assert!(result);
+
+    let result: bool = client
+        .put(
+            "/ci/lock/release",
+            &LockReleaseReq {
+                key: "job".into(),
+                owner: "node2".to_string(),
+            },
+        )
+        .await;
+    assert!(!result);
+    let result: bool = client
+        .put(
+            "/ci/lock/release",
+            &LockReleaseReq {
+                key: "job".into(),
+                owner: "node1".to_string(),
+            },
+        )
+        .await;
+    assert!(result);
+
+    let result: LockAcquireResp = client
+        .put(
+            "/ci/lock/acquire",
+            &LockAcquireReq {
+                key: "job".into(),
+                owner: None,
+                exp_sec: 1,
+            },
+        )
+        .await;
+    assert!(result.acquired);
+    assert!(!result.owner.is_empty());
+    assert!(result.fencing_token > fencing_token);
+
+    Ok(())
+}
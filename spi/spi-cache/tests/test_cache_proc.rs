@@ -1,19 +1,24 @@
 use std::collections::HashMap;
 
 use bios_basic::test::test_http_client::TestHttpClient;
-use bios_spi_cache::dto::cache_proc_dto::{ExpReq, KIncrReq, KReq, KbReq, KbvReq, KfIncrReq, KfReq, KfvReq, KvReq, KvWithExReq};
+use bios_spi_cache::dto::cache_proc_dto::{
+    ExpReq, KIncrReq, KRangeReq, KReq, KbReq, KbvReq, KfIncrReq, KfReq, KfvReq, KsIncrReq, KsRangeReq, KsvReq, KvReq, KvWithExReq, KvsReq, PublishReq, ZMemberResp,
+};
 use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
+use tardis::futures::{SinkExt, StreamExt};
 use tardis::log::info;
+use tardis::tokio::time::{sleep, timeout, Duration};
+use tardis::web::tokio_tungstenite::tungstenite::Message;
 use tardis::web::web_resp::{TardisResp, Void};
 
-pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
+pub async fn test(app_code: &str, client: &mut TestHttpClient) -> TardisResult<()> {
     client.set_auth(&TardisContext {
-        own_paths: "t1/app001".to_string(),
+        own_paths: format!("t1/{app_code}"),
         ak: "".to_string(),
         roles: vec![],
         groups: vec![],
-        owner: "app001".to_string(),
+        owner: app_code.to_string(),
         ..Default::default()
     })?;
 
@@ -325,5 +330,167 @@ pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
     let result: u32 = client.put("/ci/proc/bitcount", &KReq { key: "k_bitmap".into() }).await;
     assert_eq!(result, 2);
 
+    info!("【test_cache_list】");
+    for value in ["l1", "l2", "l3"] {
+        let _: Void = client
+            .put(
+                "/ci/proc/rpush",
+                &KvReq {
+                    key: "k_list_range".into(),
+                    value: value.to_string(),
+                },
+            )
+            .await;
+    }
+    let result: Vec<String> = client
+        .put(
+            "/ci/proc/lrange",
+            &KRangeReq {
+                key: "k_list_range".into(),
+                start: 1,
+                stop: -1,
+            },
+        )
+        .await;
+    assert_eq!(result, vec!["l2".to_string(), "l3".to_string()]);
+    let result: Option<String> = client.put("/ci/proc/lpop", &KReq { key: "k_list_range".into() }).await;
+    assert_eq!(result, Some("l1".to_string()));
+    let result: Option<String> = client.put("/ci/proc/rpop", &KReq { key: "k_list_range".into() }).await;
+    assert_eq!(result, Some("l3".to_string()));
+
+    info!("【test_cache_set】");
+    let result: u64 = client
+        .put(
+            "/ci/proc/sadd",
+            &KvsReq {
+                key: "k_set".into(),
+                values: vec!["u1".to_string(), "u2".to_string(), "u1".to_string()],
+            },
+        )
+        .await;
+    assert_eq!(result, 2);
+    let result: bool = client
+        .put(
+            "/ci/proc/sismember",
+            &KvReq {
+                key: "k_set".into(),
+                value: "u2".to_string(),
+            },
+        )
+        .await;
+    assert!(result);
+    let result: u64 = client
+        .put(
+            "/ci/proc/srem",
+            &KvsReq {
+                key: "k_set".into(),
+                values: vec!["u2".to_string()],
+            },
+        )
+        .await;
+    assert_eq!(result, 1);
+    let result: Vec<String> = client.put("/ci/proc/smembers", &KReq { key: "k_set".into() }).await;
+    assert_eq!(result, vec!["u1".to_string()]);
+
+    info!("【test_cache_sorted_set】");
+    for (value, score) in [("p1", 10.0), ("p2", 30.0), ("p3", 20.0)] {
+        let result: bool = client
+            .put(
+                "/ci/proc/zadd",
+                &KsvReq {
+                    key: "k_zset".into(),
+                    value: value.to_string(),
+                    score,
+                },
+            )
+            .await;
+        assert!(result);
+    }
+    let result: f64 = client
+        .post(
+            "/ci/proc/zincrby",
+            &KsIncrReq {
+                key: "k_zset".into(),
+                value: "p1".to_string(),
+                delta: 15.0,
+            },
+        )
+        .await;
+    assert_eq!(result, 25.0);
+    let result: Vec<ZMemberResp> = client
+        .put(
+            "/ci/proc/zrevrange",
+            &KRangeReq {
+                key: "k_zset".into(),
+                start: 0,
+                stop: 1,
+            },
+        )
+        .await;
+    assert_eq!(result.iter().map(|m| m.value.as_str()).collect::<Vec<_>>(), vec!["p2", "p1"]);
+    let result: Vec<ZMemberResp> = client
+        .put(
+            "/ci/proc/zrangebyscore",
+            &KsRangeReq {
+                key: "k_zset".into(),
+                min: 20.0,
+                max: 25.0,
+            },
+        )
+        .await;
+    assert_eq!(result.iter().map(|m| m.value.as_str()).collect::<Vec<_>>(), vec!["p3", "p1"]);
+    let result: u64 = client
+        .put(
+            "/ci/proc/zrem",
+            &KvsReq {
+                key: "k_zset".into(),
+                values: vec!["p3".to_string()],
+            },
+        )
+        .await;
+    assert_eq!(result, 1);
+    let result: u64 = client.put("/ci/proc/zcard", &KReq { key: "k_zset".into() }).await;
+    assert_eq!(result, 2);
+
+    // -------------------- pub/sub --------------------
+
+    let mut subscriber = client.ws_connect("/ci/proc/subscribe/news").await;
+    sleep(Duration::from_millis(200)).await;
+    let result: u64 = client
+        .post(
+            "/ci/proc/publish",
+            &PublishReq {
+                channel: "news".into(),
+                message: "hello".to_string(),
+            },
+        )
+        .await;
+    assert_eq!(result, 1);
+    let result: u64 = client
+        .post(
+            "/ci/proc/publish",
+            &PublishReq {
+                channel: "other".into(),
+                message: "ignored".to_string(),
+            },
+        )
+        .await;
+    assert_eq!(result, 0);
+    let result: u64 = client
+        .post(
+            "/ci/proc/publish",
+            &PublishReq {
+                channel: "news".into(),
+                message: "world".to_string(),
+            },
+        )
+        .await;
+    assert_eq!(result, 1);
+    for expected in ["hello", "world"] {
+        let message = timeout(Duration::from_secs(5), subscriber.next()).await.unwrap().unwrap().unwrap();
+        assert_eq!(message, Message::text(expected));
+    }
+    subscriber.send(Message::Close(None)).await.unwrap();
+
     Ok(())
 }
@@ -0,0 +1,76 @@
+use std::time::Duration;
+
+use bios_basic::test::test_http_client::TestHttpClient;
+use bios_spi_cache::dto::cache_rate_limit_dto::{RateLimitKind, RateLimitReq, RateLimitResp};
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::log::info;
+use tardis::tokio::time::sleep;
+
+pub async fn test(app_code: &str, client: &mut TestHttpClient) -> TardisResult<()> {
+    client.set_auth(&TardisContext {
+        own_paths: format!("t1/{app_code}"),
+        ak: "".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: app_code.to_string(),
+        ..Default::default()
+    })?;
+
+    for (kind, key) in [
+        (RateLimitKind::FixedWindow, "api-fw"),
+        (RateLimitKind::SlidingWindowLog, "api-swl"),
+        (RateLimitKind::TokenBucket, "api-tb"),
+    ] {
+        info!("【test_cache_rate_limit】 {:?}", kind);
+        let req = RateLimitReq {
+            key: key.into(),
+            kind,
+            limit: 2,
+            window_ms: 1000,
+            cost: None,
+        };
+        let result: RateLimitResp = client.put("/ci/rate-limit/check", &req).await;
+        assert!(result.allowed);
+        assert_eq!(result.limit, 2);
+        assert_eq!(result.remaining, 1);
+        assert!(result.reset_after_ms <= 1000);
+        let result: RateLimitResp = client.put("/ci/rate-limit/check", &req).await;
+        assert!(result.allowed);
+        assert_eq!(result.remaining, 0);
+        let result: RateLimitResp = client.put("/ci/rate-limit/check", &req).await;
+        assert!(!result.allowed);
+        assert_eq!(result.remaining, 0);
+        assert!(result.retry_after_ms > 0 && result.retry_after_ms <= 1000);
+
+        sleep(Duration::from_millis(1100)).await;
+        let result: RateLimitResp = client.put("/ci/rate-limit/check", &req).await;
+        assert!(result.allowed);
+    }
+
+    // The quota is counted per tenant
+    client.set_auth(&TardisContext {
+        own_paths: format!("t2/{app_code}"),
+        ak: "".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: app_code.to_string(),
+        ..Default::default()
+    })?;
+    let result: RateLimitResp = client
+        .put(
+            "/ci/rate-limit/check",
+            &RateLimitReq {
+                key: "api-fw".into(),
+                kind: RateLimitKind::FixedWindow,
+                limit: 2,
+                window_ms: 1000,
+                cost: Some(2),
+            },
+        )
+        .await;
+    assert!(result.allowed);
+    assert_eq!(result.remaining, 0);
+
+    Ok(())
+}
@@ -1,3 +1,4 @@
 pub const DOMAIN_CODE: &str = "spi-kv";
+pub const SPI_REDIS_KIND_CODE: &str = "spi-bs-redis";
 pub const KEY_PREFIX_BY_KEY_NAME: &str = "__k_n__:";
 pub const KEY_PREFIX_BY_TAG: &str = "__tag__:";
@@ -5,7 +5,12 @@ use tardis::{
     TardisFuns, TardisFunsInst,
 };
 
-use crate::{api::ci::kv_ci_item_api, kv_config::KvConfig, kv_constants::DOMAIN_CODE};
+use crate::{
+    api::ci::kv_ci_item_api,
+    kv_config::KvConfig,
+    kv_constants::{self, DOMAIN_CODE},
+    serv,
+};
 
 pub async fn init(web_server: &TardisWebServer) -> TardisResult<()> {
     let mut funs = crate::get_tardis_inst();
@@ -18,7 +23,10 @@ pub async fn init(web_server: &TardisWebServer) -> TardisResult<()> {
 }
 
 async fn init_db(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    #[cfg(feature = "spi-pg")]
     spi_initializer::add_kind(spi_constants::SPI_PG_KIND_CODE, funs, ctx).await?;
+    #[cfg(feature = "spi-redis")]
+    spi_initializer::add_kind(kv_constants::SPI_REDIS_KIND_CODE, funs, ctx).await?;
     Ok(())
 }
 
@@ -31,6 +39,8 @@ pub async fn init_fun(bs_cert: SpiBsCertResp, ctx: &TardisContext, mgr: bool) ->
     match bs_cert.kind_code.as_str() {
         #[cfg(feature = "spi-pg")]
         spi_constants::SPI_PG_KIND_CODE => spi_initializer::common_pg::init(&bs_cert, ctx, mgr).await,
+        #[cfg(feature = "spi-redis")]
+        kv_constants::SPI_REDIS_KIND_CODE => serv::redis::kv_redis_initializer::init(&bs_cert, ctx, mgr).await,
         _ => Err(bs_cert.bs_not_implemented())?,
     }
 }
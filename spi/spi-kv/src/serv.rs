@@ -1,2 +1,4 @@
 pub mod kv_item_serv;
 pub mod pg;
+#[cfg(feature = "spi-redis")]
+pub mod redis;
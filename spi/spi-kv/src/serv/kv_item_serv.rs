@@ -13,12 +13,17 @@ use crate::dto::kv_item_dto::{
 use crate::{kv_constants, kv_initializer};
 
 use super::pg;
+#[cfg(feature = "spi-redis")]
+use super::redis;
+
 spi_dispatch_service! {
     @mgr: true,
     @init: kv_initializer::init_fun,
     @dispatch: {
         #[cfg(feature = "spi-pg")]
         spi_constants::SPI_PG_KIND_CODE => pg::kv_pg_item_serv,
+        #[cfg(feature = "spi-redis")]
+        kv_constants::SPI_REDIS_KIND_CODE => redis::kv_redis_item_serv,
     },
     @method: {
         add_or_modify_item(add_or_modify_req: &mut KvItemAddOrModifyReq) -> TardisResult<()>;
@@ -39,6 +44,8 @@ pub async fn add_or_modify_key_name(add_or_modify_req: &mut KvNameAddOrModifyReq
     match inst.kind_code() {
         #[cfg(feature = "spi-pg")]
         spi_constants::SPI_PG_KIND_CODE => pg::kv_pg_item_serv::add_or_modify_item(&req, funs, ctx, inst).await,
+        #[cfg(feature = "spi-redis")]
+        kv_constants::SPI_REDIS_KIND_CODE => redis::kv_redis_item_serv::add_or_modify_item(&req, funs, ctx, inst).await,
         kind_code => Err(funs.bs_not_implemented(kind_code)),
     }
 }
@@ -49,6 +56,8 @@ pub async fn find_key_names(keys: Vec<String>, funs: &TardisFunsInst, ctx: &Tard
     match inst.kind_code() {
         #[cfg(feature = "spi-pg")]
         spi_constants::SPI_PG_KIND_CODE => pg::kv_pg_item_serv::find_items(keys, None, funs, ctx, inst).await,
+        #[cfg(feature = "spi-redis")]
+        kv_constants::SPI_REDIS_KIND_CODE => redis::kv_redis_item_serv::find_items(keys, None, funs, ctx, inst).await,
         kind_code => Err(funs.bs_not_implemented(kind_code)),
     }
     .and_then(|items| {
@@ -76,6 +85,8 @@ pub async fn add_or_modify_tag(add_or_modify_req: &mut KvTagAddOrModifyReq, funs
     match inst.kind_code() {
         #[cfg(feature = "spi-pg")]
         spi_constants::SPI_PG_KIND_CODE => pg::kv_pg_item_serv::add_or_modify_item(&req, funs, ctx, inst).await,
+        #[cfg(feature = "spi-redis")]
+        kv_constants::SPI_REDIS_KIND_CODE => redis::kv_redis_item_serv::add_or_modify_item(&req, funs, ctx, inst).await,
         kind_code => Err(funs.bs_not_implemented(kind_code)),
     }
 }
@@ -99,6 +110,21 @@ pub async fn find_tags(key_prefix: String, page_number: u32, page_size: u16, fun
             )
             .await
         }
+        #[cfg(feature = "spi-redis")]
+        kv_constants::SPI_REDIS_KIND_CODE => {
+            redis::kv_redis_item_serv::match_items(
+                KvItemMatchReq {
+                    key_prefix,
+                    page_number,
+                    page_size,
+                    ..Default::default()
+                },
+                funs,
+                ctx,
+                inst,
+            )
+            .await
+        }
         kind_code => Err(funs.bs_not_implemented(kind_code)),
     }
     .and_then(|items| {
@@ -0,0 +1,2 @@
+pub mod kv_redis_initializer;
+pub mod kv_redis_item_serv;
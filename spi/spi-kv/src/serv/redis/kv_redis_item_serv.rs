@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+
+use bios_basic::spi::{spi_funs::SpiBsInst, spi_initializer::common};
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    cache::{cache_client::TardisCacheClient, AsyncCommands, Script},
+    chrono::{DateTime, Utc},
+    serde_json::Value,
+    web::web_resp::TardisPage,
+    TardisFuns, TardisFunsInst,
+};
+
+use crate::dto::kv_item_dto::{KvItemAddOrModifyReq, KvItemDetailResp, KvItemMatchReq, KvItemSummaryResp};
+
+// Each item is stored as a hash, the fields are the same as the columns of the PG backend.
+// The batch scripts touch keys of different hash slots, so the backend only supports a single-node redis, not a redis cluster.
+const KEY_SPACE: &str = "kv:";
+const FIELD_VALUE: &str = "v";
+const FIELD_INFO: &str = "info";
+const FIELD_CREATE_TIME: &str = "create_time";
+const FIELD_UPDATE_TIME: &str = "update_time";
+
+// KEYS[1]: item key
+// ARGV[1]: value, ARGV[2]: whether to set info ('1' or '0'), ARGV[3]: info, ARGV[4]: current time
+const ADD_OR_MODIFY_SCRIPT: &str = r#"
+redis.call('HSETNX', KEYS[1], 'create_time', ARGV[4])
+if ARGV[2] == '1' then
+    redis.call('HSET', KEYS[1], 'info', ARGV[3])
+else
+    redis.call('HSETNX', KEYS[1], 'info', '')
+end
+redis.call('HSET', KEYS[1], 'v', ARGV[1], 'update_time', ARGV[4])
+return 1
+"#;
+
+// KEYS: item keys
+// Returns the fields of each item as a flat array of field names and values, empty if the item does not exist
+const BATCH_GET_SCRIPT: &str = r#"
+local items = {}
+for i, key in ipairs(KEYS) do
+    items[i] = redis.call('HGETALL', key)
+end
+return items
+"#;
+
+// Number of items read in one round trip
+const BATCH_GET_SIZE: usize = 200;
+
+fn format_key(req_key: &str, ext: &HashMap<String, String>) -> String {
+    if let Some(key_prefix) = common::get_isolation_flag_from_ext(ext) {
+        format!("{key_prefix}{KEY_SPACE}{req_key}")
+    } else {
+        format!("{KEY_SPACE}{req_key}")
+    }
+}
+
+/// Escape the glob-style special characters of the `SCAN MATCH` pattern
+fn escape_pattern(key_prefix: &str) -> String {
+    key_prefix.chars().fold(String::with_capacity(key_prefix.len()), |mut pattern, c| {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+        pattern
+    })
+}
+
+/// Get the fields of the items in batches, one round trip per batch instead of one per item
+async fn batch_get_fields(keys: &[String], client: &TardisCacheClient) -> TardisResult<Vec<HashMap<String, String>>> {
+    let mut conn = client.cmd().await?;
+    let mut items = Vec::with_capacity(keys.len());
+    for chunk in keys.chunks(BATCH_GET_SIZE) {
+        let mut script = Script::new(BATCH_GET_SCRIPT).prepare_invoke();
+        for key in chunk {
+            script.key(key);
+        }
+        let chunk_items = script.invoke_async::<_, Vec<Vec<String>>>(&mut conn).await?;
+        items.extend(chunk_items.into_iter().map(|fields| {
+            let mut fields = fields.into_iter();
+            let mut item = HashMap::new();
+            while let (Some(field), Some(value)) = (fields.next(), fields.next()) {
+                item.insert(field, value);
+            }
+            item
+        }));
+    }
+    Ok(items)
+}
+
+fn parse_time(field: &str, fields: &HashMap<String, String>, funs: &TardisFunsInst) -> TardisResult<DateTime<Utc>> {
+    fields
+        .get(field)
+        .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+        .map(|time| time.with_timezone(&Utc))
+        .ok_or_else(|| funs.err().format_error("item", "parse", &format!("the field [{field}] of item is not legal"), "500-spi-kv-item-format-error"))
+}
+
+fn parse_item(key: String, fields: HashMap<String, String>, extract: Option<&str>, funs: &TardisFunsInst) -> TardisResult<KvItemSummaryResp> {
+    let value = TardisFuns::json.str_to_json(fields.get(FIELD_VALUE).map(|v| v.as_str()).unwrap_or("null"))?;
+    let value = if let Some(extract) = extract { value.get(extract).cloned().unwrap_or(Value::Null) } else { value };
+    Ok(KvItemSummaryResp {
+        key,
+        value,
+        info: fields.get(FIELD_INFO).cloned().unwrap_or_default(),
+        create_time: parse_time(FIELD_CREATE_TIME, &fields, funs)?,
+        update_time: parse_time(FIELD_UPDATE_TIME, &fields, funs)?,
+    })
+}
+
+pub async fn add_or_modify_item(add_or_modify_req: &KvItemAddOrModifyReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let mut conn = bs_inst.0.cmd().await?;
+    Script::new(ADD_OR_MODIFY_SCRIPT)
+        .key(format_key(&add_or_modify_req.key, bs_inst.1))
+        .arg(TardisFuns::json.json_to_string(add_or_modify_req.value.clone())?)
+        .arg(if add_or_modify_req.info.is_some() { "1" } else { "0" })
+        .arg(add_or_modify_req.info.as_deref().unwrap_or(""))
+        .arg(Utc::now().to_rfc3339())
+        .invoke_async::<_, i32>(&mut conn)
+        .await?;
+    Ok(())
+}
+
+pub async fn get_item(key: String, extract: Option<String>, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<KvItemDetailResp>> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let fields = bs_inst.0.hgetall(&format_key(&key, bs_inst.1)).await?;
+    if fields.is_empty() {
+        return Ok(None);
+    }
+    let item = parse_item(key, fields, extract.as_deref(), funs)?;
+    Ok(Some(KvItemDetailResp {
+        key: item.key,
+        value: item.value,
+        info: item.info,
+        create_time: item.create_time,
+        update_time: item.update_time,
+    }))
+}
+
+pub async fn find_items(keys: Vec<String>, extract: Option<String>, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<KvItemSummaryResp>> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let items = batch_get_fields(&keys.iter().map(|key| format_key(key, bs_inst.1)).collect::<Vec<_>>(), bs_inst.0).await?;
+    let mut result = Vec::with_capacity(keys.len());
+    for (key, fields) in keys.into_iter().zip(items) {
+        if fields.is_empty() {
+            continue;
+        }
+        result.push(parse_item(key, fields, extract.as_deref(), funs)?);
+    }
+    Ok(result)
+}
+
+pub async fn match_items(match_req: KvItemMatchReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<TardisPage<KvItemSummaryResp>> {
+    if match_req.query_path.is_some() {
+        return Err(funs.err().bad_request(
+            "item",
+            "match",
+            "The redis backend service does not support query by json path",
+            "400-spi-kv-redis-query-path-not-supported",
+        ));
+    }
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let key_prefix = format_key("", bs_inst.1);
+    let mut keys = {
+        let mut conn = bs_inst.0.cmd().await?;
+        let mut keys = Vec::new();
+        // The isolation prefix is escaped too, otherwise a tenant code with the special characters matches the keys of other tenants
+        let mut res_iter = conn.scan_match::<_, String>(format!("{}*", escape_pattern(&format_key(&match_req.key_prefix, bs_inst.1)))).await?;
+        while let Some(key) = res_iter.next_item().await {
+            keys.push(key);
+        }
+        keys
+    };
+    keys.sort();
+
+    let items = batch_get_fields(&keys, bs_inst.0).await?;
+    let mut matched_items = Vec::new();
+    for (key, fields) in keys.into_iter().zip(items) {
+        if fields.is_empty() {
+            continue;
+        }
+        let key = key.strip_prefix(&key_prefix).unwrap_or(&key).to_string();
+        let item = parse_item(key, fields, match_req.extract.as_deref(), funs)?;
+        if match_req.create_time_start.map(|start| item.create_time < start).unwrap_or(false)
+            || match_req.create_time_end.map(|end| item.create_time > end).unwrap_or(false)
+            || match_req.update_time_start.map(|start| item.update_time < start).unwrap_or(false)
+            || match_req.update_time_end.map(|end| item.update_time > end).unwrap_or(false)
+        {
+            continue;
+        }
+        matched_items.push(item);
+    }
+
+    let total_size = matched_items.len();
+    let records = matched_items
+        .into_iter()
+        .skip(((match_req.page_number.max(1) - 1) * match_req.page_size as u32) as usize)
+        .take(match_req.page_size as usize)
+        .collect::<Vec<_>>();
+    Ok(TardisPage {
+        page_size: match_req.page_size as u64,
+        page_number: match_req.page_number as u64,
+        total_size: total_size as u64,
+        records,
+    })
+}
+
+pub async fn delete_item(key: String, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    Ok(bs_inst.0.del(&format_key(&key, bs_inst.1)).await?)
+}
@@ -7,7 +7,7 @@ use bios_basic::spi::dto::spi_bs_dto::SpiBsAddReq;
 use bios_basic::spi::spi_constants;
 use bios_basic::test::init_rbum_test_container;
 use bios_basic::test::test_http_client::TestHttpClient;
-use bios_spi_kv::kv_constants::DOMAIN_CODE;
+use bios_spi_kv::kv_constants::{self, DOMAIN_CODE};
 use bios_spi_kv::kv_initializer;
 use tardis::basic::dto::TardisContext;
 use tardis::basic::field::TrimString;
@@ -16,6 +16,7 @@ use tardis::tokio::time::sleep;
 use tardis::web::web_resp::Void;
 use tardis::{testcontainers, tokio, TardisFuns};
 mod test_kv_item;
+mod test_kv_redis_item;
 
 #[tokio::test]
 async fn test_kv() -> TardisResult<()> {
@@ -78,5 +79,27 @@ async fn init_data() -> TardisResult<()> {
 
     test_kv_item::test(&mut client).await?;
 
+    // Redis backend
+    client.set_auth(&ctx)?;
+    let kind_id = RbumKindServ::get_rbum_kind_id_by_code(kv_constants::SPI_REDIS_KIND_CODE, &funs).await?.unwrap();
+    let bs_id: String = client
+        .post(
+            "/ci/manage/bs",
+            &SpiBsAddReq {
+                name: TrimString("test-spi-redis".to_string()),
+                kind_id: TrimString(kind_id),
+                conn_uri: env::var("TARDIS_FW.CACHE.URL").unwrap(),
+                ak: TrimString("".to_string()),
+                sk: TrimString("".to_string()),
+                ext: "{}".to_string(),
+                private: false,
+                disabled: None,
+            },
+        )
+        .await;
+    let _: Void = client.put(&format!("/ci/manage/bs/{}/rel/app002", bs_id), &Void {}).await;
+
+    test_kv_redis_item::test(&mut client).await?;
+
     Ok(())
 }
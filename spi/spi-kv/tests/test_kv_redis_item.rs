@@ -0,0 +1,149 @@
+use std::time::Duration;
+
+use bios_basic::test::test_http_client::TestHttpClient;
+use bios_spi_kv::dto::kv_item_dto::{KvItemDetailResp, KvItemSummaryResp};
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::chrono::Utc;
+use tardis::serde_json::json;
+use tardis::tokio::time::sleep;
+use tardis::web::web_resp::{TardisPage, TardisResp, Void};
+
+pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
+    client.set_auth(&TardisContext {
+        own_paths: "t1/app002".to_string(),
+        ak: "".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: "app002".to_string(),
+        ..Default::default()
+    })?;
+
+    for i in 1..=5 {
+        let _: Void = client
+            .put(
+                "/ci/item",
+                &json!({
+                    "key":format!("db_info:00{i}"),
+                    "value": {
+                        "url": format!("redis://xxxx00{i}"),
+                    },
+                    "info":format!("00{i}系统的数据库信息"),
+                }),
+            )
+            .await;
+    }
+    let _: Void = client
+        .put(
+            "/ci/item",
+            &json!({
+                "key":"db_url",
+                "value": "redis://xxxx",
+            }),
+        )
+        .await;
+    // Special characters of the match pattern are matched literally
+    let _: Void = client
+        .put(
+            "/ci/item",
+            &json!({
+                "key":"db*:001",
+                "value": "redis://yyyy",
+            }),
+        )
+        .await;
+    sleep(Duration::from_millis(100)).await;
+    let modify_time = Utc::now();
+    sleep(Duration::from_millis(100)).await;
+    let _: Void = client
+        .put(
+            "/ci/item",
+            &json!({
+                "key":"db_info:005",
+                "value": {
+                    "url": "redis://xxxx005-new",
+                },
+            }),
+        )
+        .await;
+
+    let result: KvItemDetailResp = client.get("/ci/item/?key=db_info:005").await;
+    assert_eq!(result.key, "db_info:005");
+    assert_eq!(result.info, "005系统的数据库信息");
+    assert_eq!(result.value.get("url").unwrap().as_str().unwrap(), "redis://xxxx005-new");
+    assert!(result.create_time < result.update_time);
+
+    let result: Vec<KvItemSummaryResp> = client.get("/ci/items/?keys=db_info:002&keys=db_info:999&keys=db_info:001&extract=url").await;
+    assert_eq!(result.len(), 2);
+    assert_eq!(result[0].key, "db_info:002");
+    assert_eq!(result[0].value.as_str().unwrap(), "redis://xxxx002");
+    assert_eq!(result[1].key, "db_info:001");
+
+    let result: TardisPage<KvItemSummaryResp> = client.get("/ci/item/match?key_prefix=db_info&page_number=1&page_size=2").await;
+    assert_eq!(result.total_size, 5);
+    assert_eq!(result.records.len(), 2);
+    assert_eq!(result.records[0].key, "db_info:001");
+    assert_eq!(result.records[1].key, "db_info:002");
+    assert_eq!(result.records[1].info, "002系统的数据库信息");
+
+    let result: TardisPage<KvItemSummaryResp> = client.get("/ci/item/match?key_prefix=db_info&extract=url&page_number=3&page_size=2").await;
+    assert_eq!(result.total_size, 5);
+    assert_eq!(result.records.len(), 1);
+    assert_eq!(result.records[0].key, "db_info:005");
+    assert_eq!(result.records[0].value.as_str().unwrap(), "redis://xxxx005-new");
+
+    let result: TardisPage<KvItemSummaryResp> = client.get("/ci/item/match?key_prefix=db*&page_number=1&page_size=10").await;
+    assert_eq!(result.total_size, 1);
+    assert_eq!(result.records[0].key, "db*:001");
+
+    let result: TardisPage<KvItemSummaryResp> = client
+        .put(
+            "/ci/item/match",
+            &json!({
+                "key_prefix":"db_info",
+                "update_time_start": modify_time,
+                "page_number":1,
+                "page_size":10
+            }),
+        )
+        .await;
+    assert_eq!(result.total_size, 1);
+    assert_eq!(result.records[0].key, "db_info:005");
+
+    let result: TardisPage<KvItemSummaryResp> = client
+        .put(
+            "/ci/item/match",
+            &json!({
+                "key_prefix":"db_info",
+                "create_time_end": modify_time,
+                "page_number":1,
+                "page_size":10
+            }),
+        )
+        .await;
+    assert_eq!(result.total_size, 5);
+
+    let result: TardisResp<TardisPage<KvItemSummaryResp>> = client
+        .put_resp(
+            "/ci/item/match",
+            &json!({
+                "key_prefix":"db_info",
+                "query_path":"$.url ? (@ == $url)",
+                "query_values": {
+                    "url": "redis://xxxx002"
+                },
+                "page_number":1,
+                "page_size":10
+            }),
+        )
+        .await;
+    assert_eq!(result.code, "400-spi-kv-item-match");
+
+    client.delete("/ci/item?key=db_info:001").await;
+    let result: TardisResp<KvItemDetailResp> = client.get_resp("/ci/item/?key=db_info:001").await;
+    assert!(result.data.is_none());
+    let result: TardisPage<KvItemSummaryResp> = client.get("/ci/item/match?key_prefix=db_info&page_number=1&page_size=10").await;
+    assert_eq!(result.total_size, 4);
+
+    Ok(())
+}
@@ -1 +1,3 @@
 pub mod log_ci_item_api;
+pub mod log_ci_retention_api;
+pub mod log_ci_integrity_api;
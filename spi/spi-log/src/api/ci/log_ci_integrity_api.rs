@@ -0,0 +1,41 @@
+use tardis::web::context_extractor::TardisContextExtractor;
+
+use tardis::web::poem_openapi;
+use tardis::web::poem_openapi::payload::Json;
+use tardis::web::web_resp::{TardisApiResult, TardisResp, Void};
+
+use crate::dto::log_integrity_dto::{LogIntegrityAddOrModifyReq, LogIntegrityResp, LogIntegrityVerifyReq, LogIntegrityVerifyResp};
+use crate::serv::log_integrity_serv;
+
+#[derive(Clone)]
+pub struct LogCiIntegrityApi;
+
+/// Interface Console Log Integrity API
+#[poem_openapi::OpenApi(prefix_path = "/ci/integrity", tag = "bios_basic::ApiTag::Interface")]
+impl LogCiIntegrityApi {
+    /// Enable Integrity Mode Of Tag
+    ///
+    /// Items added afterwards are chained by hash, the mode can't be disabled once enabled
+    #[oai(path = "/", method = "put")]
+    async fn add_or_modify(&self, req: Json<LogIntegrityAddOrModifyReq>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        log_integrity_serv::add_or_modify(&req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Find Tags In Integrity Mode
+    #[oai(path = "/", method = "get")]
+    async fn find(&self, ctx: TardisContextExtractor) -> TardisApiResult<Vec<LogIntegrityResp>> {
+        let funs = crate::get_tardis_inst();
+        let resp = log_integrity_serv::find(&funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Verify Integrity Chain
+    #[oai(path = "/verify", method = "put")]
+    async fn verify(&self, req: Json<LogIntegrityVerifyReq>, ctx: TardisContextExtractor) -> TardisApiResult<LogIntegrityVerifyResp> {
+        let funs = crate::get_tardis_inst();
+        let resp = log_integrity_serv::verify(&req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+}
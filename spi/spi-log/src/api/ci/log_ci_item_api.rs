@@ -1,11 +1,22 @@
+use std::io;
+
+use tardis::basic::field::TrimString;
+use tardis::futures::StreamExt;
+use tardis::serde_json::Value;
+use tardis::TardisFuns;
 use tardis::web::context_extractor::TardisContextExtractor;
 
+use tardis::web::poem::web::websocket::{BoxWebSocketUpgraded, WebSocket};
+use tardis::web::poem::{self, Body};
 use tardis::web::poem_openapi;
-use tardis::web::poem_openapi::payload::Json;
+use tardis::web::poem_openapi::param::{Path, Query};
+use tardis::web::poem_openapi::payload::{Attachment, Json};
 use tardis::web::web_resp::{TardisApiResult, TardisPage, TardisResp, Void};
 
-use crate::dto::log_item_dto::{LogItemAddReq, LogItemFindReq, LogItemFindResp};
-use crate::serv::log_item_serv;
+use crate::dto::log_item_dto::{
+    LogItemAddReq, LogItemAggReq, LogItemAggResp, LogItemBatchAddResp, LogItemExportObjResp, LogItemExportReq, LogItemFilterReq, LogItemFindReq, LogItemFindResp,
+};
+use crate::serv::{log_item_serv, log_tail_serv};
 
 #[derive(Clone)]
 pub struct LogCiItemApi;
@@ -21,6 +32,16 @@ impl LogCiItemApi {
         TardisResp::ok(Void {})
     }
 
+    /// Batch Add Items
+    ///
+    /// Each item has the same structure as adding an item, the illegal or failed items are returned with their index.
+    #[oai(path = "/batch", method = "post")]
+    async fn batch_add(&self, add_reqs: Json<Vec<Value>>, ctx: TardisContextExtractor) -> TardisApiResult<LogItemBatchAddResp> {
+        let funs = crate::get_tardis_inst();
+        let resp = log_item_serv::batch_add(add_reqs.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
     /// Find Items
     #[oai(path = "/find", method = "put")]
     async fn find(&self, mut find_req: Json<LogItemFindReq>, ctx: TardisContextExtractor) -> TardisApiResult<TardisPage<LogItemFindResp>> {
@@ -28,4 +49,82 @@ impl LogCiItemApi {
         let resp = log_item_serv::find(&mut find_req.0, &funs, &ctx.0).await?;
         TardisResp::ok(resp)
     }
+
+    /// Aggregate Items
+    #[oai(path = "/agg", method = "put")]
+    async fn agg(&self, mut agg_req: Json<LogItemAggReq>, ctx: TardisContextExtractor) -> TardisApiResult<Vec<LogItemAggResp>> {
+        let funs = crate::get_tardis_inst();
+        let resp = log_item_serv::agg(&mut agg_req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Export Items
+    ///
+    /// The items are written as a chunked response in CSV or JSON lines, ordered by time
+    #[oai(path = "/export", method = "put")]
+    async fn export(&self, export_req: Json<LogItemExportReq>, ctx: TardisContextExtractor) -> poem::Result<Attachment<Body>> {
+        let funs = crate::get_tardis_inst();
+        let stream = log_item_serv::export(&export_req.0, &funs, &ctx.0).await?;
+        let body = Body::from_bytes_stream(stream.map(|chunk| chunk.map_err(|e| io::Error::new(io::ErrorKind::Other, format!("{e:?}")))));
+        Ok(Attachment::new(body).filename(format!("{}.{}", export_req.0.filter.tag, export_req.0.format.extension())))
+    }
+
+    /// Export Items To Object Storage
+    ///
+    /// The file is written by an asynchronous task, the returned URL is available after the task is finished
+    #[oai(path = "/export/obj", method = "put")]
+    async fn export_to_obj(&self, export_req: Json<LogItemExportReq>, ctx: TardisContextExtractor) -> TardisApiResult<LogItemExportObjResp> {
+        let funs = crate::get_tardis_inst();
+        let resp = log_item_serv::export_to_obj(&export_req.0, &funs, &ctx.0).await?;
+        TardisResp::accepted(resp)
+    }
+
+    /// Get Export Task Status
+    ///
+    /// Only the tasks created by the current own paths are found
+    #[oai(path = "/export/task/:task_id", method = "get")]
+    async fn check_export_task_status(&self, task_id: Path<i64>, ctx: TardisContextExtractor) -> TardisApiResult<bool> {
+        let funs = crate::get_tardis_inst();
+        let resp = log_item_serv::check_export_task_status(task_id.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Tail Items
+    ///
+    /// Push the newly added items of the tag through websocket.
+    /// `kinds`, `keys`, `ops`, `owners` and `rel_keys` are separated by commas,
+    /// `ext` and `ext_or` are JSON arrays of conditions, the same as finding.
+    #[oai(path = "/tail/:tag", method = "get")]
+    #[allow(clippy::too_many_arguments)]
+    async fn tail(
+        &self,
+        #[oai(validator(pattern = r"^[a-z0-9_]+$"))] tag: Path<String>,
+        kinds: Query<Option<String>>,
+        keys: Query<Option<String>>,
+        ops: Query<Option<String>>,
+        owners: Query<Option<String>>,
+        own_paths: Query<Option<String>>,
+        rel_keys: Query<Option<String>>,
+        ext: Query<Option<String>>,
+        ext_or: Query<Option<String>>,
+        websocket: WebSocket,
+        ctx: TardisContextExtractor,
+    ) -> poem::Result<BoxWebSocketUpgraded> {
+        let funs = crate::get_tardis_inst();
+        let split = |values: Option<String>| values.map(|values| values.split(',').map(|value| value.trim().to_string()).collect::<Vec<_>>());
+        let trim = |values: Option<Vec<String>>| values.map(|values| values.into_iter().map(TrimString).collect::<Vec<_>>());
+        let tail_req = LogItemFilterReq {
+            tag: tag.0,
+            kinds: trim(split(kinds.0)),
+            keys: trim(split(keys.0)),
+            ops: split(ops.0),
+            owners: split(owners.0),
+            own_paths: own_paths.0,
+            rel_keys: trim(split(rel_keys.0)),
+            ext: ext.0.map(|ext| TardisFuns::json.str_to_obj(&ext)).transpose()?,
+            ext_or: ext_or.0.map(|ext_or| TardisFuns::json.str_to_obj(&ext_or)).transpose()?,
+            ..Default::default()
+        };
+        Ok(log_tail_serv::tail(&tail_req, websocket, &funs, &ctx.0).await?)
+    }
 }
@@ -0,0 +1,62 @@
+use tardis::web::context_extractor::TardisContextExtractor;
+
+use tardis::web::poem_openapi;
+use tardis::web::poem_openapi::param::Query;
+use tardis::web::poem_openapi::payload::Json;
+use tardis::web::web_resp::{TardisApiResult, TardisResp, Void};
+
+use crate::dto::log_retention_dto::{LogRetentionAddOrModifyReq, LogRetentionPurgeResp, LogRetentionResp};
+use crate::serv::log_retention_serv;
+
+#[derive(Clone)]
+pub struct LogCiRetentionApi;
+
+/// Interface Console Log Retention API
+#[poem_openapi::OpenApi(prefix_path = "/ci/retention", tag = "bios_basic::ApiTag::Interface")]
+impl LogCiRetentionApi {
+    /// Add Or Modify Retention Policy
+    #[oai(path = "/", method = "put")]
+    async fn add_or_modify(&self, req: Json<LogRetentionAddOrModifyReq>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        log_retention_serv::add_or_modify(&req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Find Retention Policies
+    #[oai(path = "/", method = "get")]
+    async fn find(&self, ctx: TardisContextExtractor) -> TardisApiResult<Vec<LogRetentionResp>> {
+        let funs = crate::get_tardis_inst();
+        let resp = log_retention_serv::find(&funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Delete Retention Policy
+    ///
+    /// The policy for all tags is deleted when the tag is empty
+    #[oai(path = "/", method = "delete")]
+    async fn delete(&self, tag: Query<Option<String>>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        log_retention_serv::delete(tag.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Purge Expired Logs
+    #[oai(path = "/purge", method = "put")]
+    async fn purge(&self, ctx: TardisContextExtractor) -> TardisApiResult<LogRetentionPurgeResp> {
+        let funs = crate::get_tardis_inst();
+        let resp = log_retention_serv::purge(&funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Purge Expired Logs By Schedule
+    ///
+    /// The callback of the schedule service, which only sends GET requests.
+    /// Register a job in the schedule service with `callback_url` set to `{spi-log url}/ci/retention/purge`
+    /// and `cron` set to the purge period (e.g. `0 0 2 * * *` for daily), the logs of the app configured as `spi_app_id` of the schedule service are purged.
+    #[oai(path = "/purge", method = "get")]
+    async fn purge_by_schedule(&self, ctx: TardisContextExtractor) -> TardisApiResult<LogRetentionPurgeResp> {
+        let funs = crate::get_tardis_inst();
+        let resp = log_retention_serv::purge(&funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+}
@@ -1 +1,3 @@
 pub mod log_item_dto;
+pub mod log_retention_dto;
+pub mod log_integrity_dto;
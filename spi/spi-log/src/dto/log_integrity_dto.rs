@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+use tardis::{
+    chrono::{DateTime, Utc},
+    web::poem_openapi,
+};
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct LogIntegrityAddOrModifyReq {
+    #[oai(validator(pattern = r"^[a-z0-9_]+$"))]
+    pub tag: String,
+    // A signed checkpoint is generated every this number of items, default is 1000
+    #[oai(validator(minimum(value = "1", exclusive = "false")))]
+    pub checkpoint_interval: Option<u32>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct LogIntegrityResp {
+    pub tag: String,
+    pub checkpoint_interval: u32,
+    // Sequence and hash of the last item of the chain
+    pub last_seq: u64,
+    pub last_hash: String,
+    pub create_time: DateTime<Utc>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct LogIntegrityVerifyReq {
+    #[oai(validator(pattern = r"^[a-z0-9_]+$"))]
+    pub tag: String,
+    pub ts_start: Option<DateTime<Utc>>,
+    pub ts_end: Option<DateTime<Utc>>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Default)]
+pub struct LogIntegrityVerifyResp {
+    pub verified_items: u64,
+    // Checkpoints whose hash matches and signature is verified
+    pub verified_checkpoints: u64,
+    // Checkpoints whose hash matches but signature is not verified because the SM2 key is not configured,
+    // they are unsigned/unverifiable and don't prove the chain is not rewritten
+    pub unverifiable_checkpoints: u64,
+    // The first broken link, None means the chain in the range is intact
+    pub broken: Option<LogIntegrityBrokenResp>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct LogIntegrityBrokenResp {
+    pub seq: u64,
+    // Empty when the item is missing
+    pub key: Option<String>,
+    pub ts: Option<DateTime<Utc>>,
+    pub reason: String,
+}
@@ -1,7 +1,9 @@
+use std::collections::HashMap;
+
 use bios_basic::dto::BasicQueryCondInfo;
 use serde::{Deserialize, Serialize};
 use tardis::{
-    basic::field::TrimString,
+    basic::{error::TardisError, field::TrimString, result::TardisResult},
     chrono::{DateTime, Utc},
     serde_json::Value,
     web::poem_openapi,
@@ -29,7 +31,33 @@ pub struct LogItemAddReq {
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
-pub struct LogItemFindReq {
+pub struct LogItemBatchAddResp {
+    pub succeed: u32,
+    // Sorted by the index of the item
+    pub errors: Vec<LogItemBatchAddErrorResp>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct LogItemBatchAddErrorResp {
+    // Index of the item in the request
+    pub index: u32,
+    pub code: String,
+    pub msg: String,
+}
+
+impl LogItemBatchAddErrorResp {
+    pub fn new(index: u32, error: &TardisError) -> Self {
+        LogItemBatchAddErrorResp {
+            index,
+            code: error.code.clone(),
+            msg: error.message.clone(),
+        }
+    }
+}
+
+/// Filtering conditions shared by finding, aggregating, exporting and live tail
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LogItemFilterReq {
     #[oai(validator(pattern = r"^[a-z0-9_]+$"))]
     pub tag: String,
     pub kinds: Option<Vec<TrimString>>,
@@ -43,11 +71,21 @@ pub struct LogItemFindReq {
     pub rel_keys: Option<Vec<TrimString>>,
     pub ts_start: Option<DateTime<Utc>>,
     pub ts_end: Option<DateTime<Utc>>,
+    /// Full-text search on content, supports quoted phrases, `or` and `-` to exclude a word.
+    /// The results are ordered by relevance when it is specified.
+    pub q: Option<String>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct LogItemFindReq {
+    #[serde(flatten)]
+    #[oai(flatten)]
+    pub filter: LogItemFilterReq,
     pub page_number: u32,
     pub page_size: u16,
 }
 
-#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
 pub struct LogItemFindResp {
     #[oai(validator(min_length = "2"))]
     pub content: String,
@@ -59,4 +97,121 @@ pub struct LogItemFindResp {
     pub op: String,
     pub rel_key: String,
     pub ts: DateTime<Utc>,
+    // Snippets of the HTML-escaped content with the matched words wrapped in `<em>`, only returned when searching by `q`
+    pub highlight: Option<String>,
+}
+
+impl LogItemFilterReq {
+    /// Check the item in memory, used by live tail to filter the newly added items.
+    ///
+    /// The full-text search `q` is not checked, live tail doesn't accept it.
+    pub fn is_match(&self, item: &LogItemFindResp) -> TardisResult<bool> {
+        fn contains<T: ToString>(values: &Option<Vec<T>>, value: &str) -> bool {
+            values.as_ref().map(|values| values.iter().any(|v| v.to_string() == value)).unwrap_or(true)
+        }
+        if !contains(&self.kinds, &item.kind)
+            || !contains(&self.keys, &item.key)
+            || !contains(&self.ops, &item.op)
+            || !contains(&self.owners, &item.owner)
+            || !contains(&self.rel_keys, &item.rel_key)
+            || !self.own_paths.as_ref().map(|own_paths| item.own_paths.starts_with(own_paths)).unwrap_or(true)
+            || !self.ts_start.map(|ts_start| item.ts >= ts_start).unwrap_or(true)
+            || !self.ts_end.map(|ts_end| item.ts <= ts_end).unwrap_or(true)
+        {
+            return Ok(false);
+        }
+        if self.ext.is_none() && self.ext_or.is_none() {
+            return Ok(true);
+        }
+        let ext = item.ext.as_object().map(|ext| ext.iter().map(|(k, v)| (k.clone(), v.clone())).collect::<HashMap<_, _>>()).unwrap_or_default();
+        if let Some(ext_conds) = &self.ext {
+            if !BasicQueryCondInfo::check_or_and_conds(&vec![ext_conds.clone()], &ext)? {
+                return Ok(false);
+            }
+        }
+        if let Some(ext_or_conds) = &self.ext_or {
+            if !BasicQueryCondInfo::check_or_and_conds(&ext_or_conds.iter().map(|cond| vec![cond.clone()]).collect(), &ext)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[derive(poem_openapi::Enum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[oai(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum LogItemAggIntervalKind {
+    Minute,
+    Hour,
+    Day,
+}
+
+impl LogItemAggIntervalKind {
+    pub fn to_sql(&self) -> &'static str {
+        match self {
+            LogItemAggIntervalKind::Minute => "minute",
+            LogItemAggIntervalKind::Hour => "hour",
+            LogItemAggIntervalKind::Day => "day",
+        }
+    }
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct LogItemAggReq {
+    #[serde(flatten)]
+    #[oai(flatten)]
+    pub filter: LogItemFilterReq,
+    /// Fields to group by, one of `kind`, `op`, `owner`, `key`, `rel_key`, `own_paths`,
+    /// or a path of ext prefixed with `ext.`, e.g. `ext.status`, `ext.detail.level`
+    pub group_by: Option<Vec<String>>,
+    /// Date histogram over ts with the bucket size, in UTC
+    pub interval: Option<LogItemAggIntervalKind>,
+    /// Maximum number of buckets returned, default 1000
+    #[oai(validator(minimum(value = "1", exclusive = "false"), maximum(value = "10000", exclusive = "false")))]
+    pub limit: Option<u32>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct LogItemAggResp {
+    /// Start time of the bucket, only returned when `interval` is specified
+    pub ts: Option<DateTime<Utc>>,
+    /// Values of the `group_by` fields, keyed by field name
+    pub group: HashMap<String, Option<String>>,
+    pub count: u64,
+}
+
+#[derive(poem_openapi::Enum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[oai(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum LogItemExportFormatKind {
+    Csv,
+    // One json object per line
+    Jsonl,
+}
+
+impl LogItemExportFormatKind {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            LogItemExportFormatKind::Csv => "csv",
+            LogItemExportFormatKind::Jsonl => "jsonl",
+        }
+    }
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct LogItemExportReq {
+    #[serde(flatten)]
+    #[oai(flatten)]
+    pub filter: LogItemFilterReq,
+    pub format: LogItemExportFormatKind,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct LogItemExportObjResp {
+    // Id of the asynchronous task writing the file
+    pub task_id: Option<String>,
+    pub object_path: String,
+    // Presigned download URL, available after the task is finished
+    pub url: String,
 }
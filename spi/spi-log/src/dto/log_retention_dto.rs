@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+use tardis::{
+    chrono::{DateTime, Utc},
+    web::poem_openapi,
+};
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct LogRetentionAddOrModifyReq {
+    // Empty means all tags of the tenant:
+    // `max_days` is used by the tags without their own policy, and `max_size_mb` limits the total size of all tags
+    #[oai(validator(pattern = r"^[a-z0-9_]+$"))]
+    pub tag: Option<String>,
+    // None means unchanged, 0 means unlimited
+    pub max_days: Option<u32>,
+    // None means unchanged, 0 means unlimited
+    pub max_size_mb: Option<u64>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct LogRetentionResp {
+    pub tag: Option<String>,
+    pub max_days: u32,
+    pub max_size_mb: u64,
+    pub update_time: DateTime<Utc>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Default)]
+pub struct LogRetentionPurgeResp {
+    pub dropped_partitions: Vec<String>,
+    pub deleted_rows: u64,
+}
@@ -1,5 +1,7 @@
 #![warn(clippy::unwrap_used)]
 
+extern crate lazy_static;
+
 mod api;
 pub mod dto;
 pub mod log_config;
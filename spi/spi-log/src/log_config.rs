@@ -1,9 +1,44 @@
 use bios_basic::rbum::rbum_config::RbumConfig;
+use bios_sdk_invoke::invoke_config::InvokeConfig;
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 
-#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(default)]
 pub struct LogConfig {
     pub rbum: RbumConfig,
+    // Number of monthly partitions created in advance for each tag
+    pub partition_premake_months: u8,
+    // Text search configuration used to tokenize the content, e.g. `public.chinese_zh` provided by zhparser for Chinese
+    pub text_search_config: String,
+    // Serialized SM2 private key used to sign the checkpoints of integrity chains, the same key as bios-auth can be used.
+    // Checkpoints are not generated when it is empty
+    pub integrity_sm2_private_key: Option<String>,
+    // Date format of the suffix of ES indexes, an index is created for each tag in each period, monthly by default
+    pub es_index_date_format: String,
+    // Maximum number of items added in one batch
+    pub batch_add_max_items: usize,
+    pub cache_key_async_task_status: String,
+    // Expiration of the download URL of the exported file
+    pub export_url_exp_secs: u32,
+    // Idle timeout of the transaction of an export cursor, the transaction is aborted if the client stops reading for longer
+    pub export_idle_timeout_secs: u32,
+    pub invoke: InvokeConfig,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        LogConfig {
+            rbum: Default::default(),
+            partition_premake_months: 2,
+            text_search_config: "simple".to_string(),
+            integrity_sm2_private_key: None,
+            es_index_date_format: "%Y.%m".to_string(),
+            batch_add_max_items: 1000,
+            cache_key_async_task_status: "spi-log:cache:task:status".to_string(),
+            export_url_exp_secs: 3600 * 24,
+            export_idle_timeout_secs: 300,
+            invoke: InvokeConfig::default(),
+        }
+    }
 }
@@ -1 +1,4 @@
 pub const DOMAIN_CODE: &str = "spi-log";
+pub(crate) const CONN_URI_FLAG: &str = "__conn_uri__";
+// Channel of PG NOTIFY to push newly added items to the live tail subscribers on other nodes
+pub(crate) const TAIL_NOTIFY_CHANNEL: &str = "spi_log_tail";
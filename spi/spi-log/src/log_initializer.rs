@@ -1,15 +1,22 @@
 use bios_basic::spi::{api::spi_ci_bs_api, dto::spi_bs_dto::SpiBsCertResp, spi_constants, spi_funs::SpiBsInst, spi_initializer};
+use bios_sdk_invoke::invoke_initializer;
 use tardis::{
     basic::{dto::TardisContext, result::TardisResult},
     web::web_server::TardisWebServer,
     TardisFuns, TardisFunsInst,
 };
 
-use crate::{api::ci::log_ci_item_api, log_config::LogConfig, log_constants::DOMAIN_CODE};
+use crate::{
+    api::ci::{log_ci_integrity_api, log_ci_item_api, log_ci_retention_api},
+    log_config::LogConfig,
+    log_constants::DOMAIN_CODE,
+    serv,
+};
 
 pub async fn init(web_server: &TardisWebServer) -> TardisResult<()> {
     let mut funs = crate::get_tardis_inst();
     bios_basic::rbum::rbum_initializer::init(funs.module_code(), funs.conf::<LogConfig>().rbum.clone()).await?;
+    invoke_initializer::init(funs.module_code(), funs.conf::<LogConfig>().invoke.clone())?;
     funs.begin().await?;
     let ctx = spi_initializer::init(DOMAIN_CODE, &funs).await?;
     init_db(&funs, &ctx).await?;
@@ -18,19 +25,34 @@ pub async fn init(web_server: &TardisWebServer) -> TardisResult<()> {
 }
 
 async fn init_db(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    #[cfg(feature = "spi-pg")]
     spi_initializer::add_kind(spi_constants::SPI_PG_KIND_CODE, funs, ctx).await?;
+    #[cfg(feature = "spi-es")]
+    spi_initializer::add_kind(spi_constants::SPI_ES_KIND_CODE, funs, ctx).await?;
     Ok(())
 }
 
 async fn init_api(web_server: &TardisWebServer) -> TardisResult<()> {
-    web_server.add_module(DOMAIN_CODE, (spi_ci_bs_api::SpiCiBsApi, log_ci_item_api::LogCiItemApi)).await;
+    web_server
+        .add_module(
+            DOMAIN_CODE,
+            (
+                spi_ci_bs_api::SpiCiBsApi,
+                log_ci_item_api::LogCiItemApi,
+                log_ci_retention_api::LogCiRetentionApi,
+                log_ci_integrity_api::LogCiIntegrityApi,
+            ),
+        )
+        .await;
     Ok(())
 }
 
 pub async fn init_fun(bs_cert: SpiBsCertResp, ctx: &TardisContext, mgr: bool) -> TardisResult<SpiBsInst> {
     match bs_cert.kind_code.as_str() {
         #[cfg(feature = "spi-pg")]
-        spi_constants::SPI_PG_KIND_CODE => spi_initializer::common_pg::init(&bs_cert, ctx, mgr).await,
+        spi_constants::SPI_PG_KIND_CODE => serv::pg::log_pg_initializer::init(&bs_cert, ctx, mgr).await,
+        #[cfg(feature = "spi-es")]
+        spi_constants::SPI_ES_KIND_CODE => serv::es::log_es_initializer::init(&bs_cert, ctx, mgr).await,
         _ => Err(bs_cert.bs_not_implemented())?,
     }
 }
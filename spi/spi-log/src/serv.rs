@@ -1,2 +1,7 @@
+#[cfg(feature = "spi-es")]
+pub mod es;
 pub mod log_item_serv;
 pub mod pg;
+pub mod log_retention_serv;
+pub mod log_integrity_serv;
+pub mod log_tail_serv;
@@ -0,0 +1,2 @@
+pub mod log_es_initializer;
+pub mod log_es_item_serv;
@@ -0,0 +1,55 @@
+use std::collections::{HashMap, HashSet};
+
+use bios_basic::spi::{dto::spi_bs_dto::SpiBsCertResp, spi_funs::SpiBsInst, spi_initializer};
+use lazy_static::lazy_static;
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    search::search_client::TardisSearchClient,
+    tokio::sync::RwLock,
+};
+
+use crate::log_constants;
+
+lazy_static! {
+    // Indexes known to exist, to avoid checking on every insert
+    static ref INDEXES: RwLock<HashSet<String>> = RwLock::new(HashSet::new());
+}
+
+const INDEX_MAPPINGS: &str = r#"{
+    "mappings": {
+        "properties": {
+            "ts":{"type": "date"},
+            "kind":{"type": "keyword"},
+            "key":{"type": "keyword"},
+            "op":{"type": "keyword"},
+            "content":{"type": "text"},
+            "owner":{"type": "keyword"},
+            "own_paths":{"type": "keyword"},
+            "ext":{"type": "flattened"},
+            "rel_key":{"type": "keyword"}
+        }
+    }
+}"#;
+
+pub async fn init(bs_cert: &SpiBsCertResp, ctx: &TardisContext, _mgr: bool) -> TardisResult<SpiBsInst> {
+    let client = TardisSearchClient::init(&bs_cert.conn_uri, 60)?;
+    let mut ext = HashMap::new();
+    ext.insert(log_constants::CONN_URI_FLAG.to_string(), bs_cert.conn_uri.clone());
+    if !bs_cert.private {
+        let key_prefix = spi_initializer::common::get_isolation_flag_from_context(ctx);
+        spi_initializer::common::set_isolation_flag_to_ext(&key_prefix, &mut ext);
+    };
+    Ok(SpiBsInst { client: Box::new(client), ext })
+}
+
+/// Create the index with the mappings of log items if it does not exist
+pub async fn init_index(client: &TardisSearchClient, index: &str) -> TardisResult<()> {
+    if INDEXES.read().await.contains(index) {
+        return Ok(());
+    }
+    if !client.check_index_exist(index).await? {
+        client.create_index(index, Some(INDEX_MAPPINGS)).await?;
+    }
+    INDEXES.write().await.insert(index.to_string());
+    Ok(())
+}
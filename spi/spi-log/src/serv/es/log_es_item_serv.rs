@@ -0,0 +1,337 @@
+use std::collections::HashMap;
+
+use bios_basic::{
+    basic_enumeration::BasicQueryOpKind,
+    dto::BasicQueryCondInfo,
+    spi::{spi_funs::SpiBsInst, spi_initializer::common},
+};
+use serde::{Deserialize, Serialize};
+use tardis::{
+    basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    chrono::{DateTime, Utc},
+    futures::stream::BoxStream,
+    search::search_client::TardisSearchClient,
+    serde_json::{json, Value},
+    web::web_resp::TardisPage,
+    TardisFuns, TardisFunsInst,
+};
+
+use crate::{
+    dto::log_item_dto::{
+        LogItemAddReq, LogItemAggReq, LogItemAggResp, LogItemBatchAddErrorResp, LogItemExportReq, LogItemFilterReq, LogItemFindReq, LogItemFindResp,
+    },
+    log_config::LogConfig,
+    log_constants,
+};
+
+use super::log_es_initializer;
+
+/// Document stored in ES
+#[derive(Serialize, Deserialize, Debug)]
+struct LogEsItem {
+    ts: DateTime<Utc>,
+    kind: String,
+    key: String,
+    op: String,
+    content: String,
+    owner: String,
+    own_paths: String,
+    ext: Value,
+    rel_key: String,
+}
+
+/// Each tag is stored in indexes rolled over by the date of `ts`, e.g. `log_audit-2023.06`,
+/// the date format is specified by `es_index_date_format` of the config.
+fn format_index_prefix(tag: &str, ext: &HashMap<String, String>) -> String {
+    if let Some(key_prefix) = common::get_isolation_flag_from_ext(ext) {
+        format!("{key_prefix}log_{tag}")
+    } else {
+        format!("log_{tag}")
+    }
+}
+
+pub async fn add(add_req: &mut LogItemAddReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let (client, ext, _) = inst.inst::<TardisSearchClient>();
+    let item = package_add_item(add_req, funs)?;
+    let index = format!("{}-{}", format_index_prefix(&add_req.tag, ext), item.ts.format(&funs.conf::<LogConfig>().es_index_date_format));
+    log_es_initializer::init_index(client, &index).await?;
+    client.create_record(&index, &TardisFuns::json.obj_to_string(&item)?).await?;
+    Ok(())
+}
+
+/// Add the items with one `_bulk` request, the errors are returned by the index of the item.
+///
+/// The items become searchable after the next refresh of the index, the same as adding one item.
+pub async fn add_items(
+    add_reqs: &mut [(u32, LogItemAddReq)],
+    funs: &TardisFunsInst,
+    _ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<Vec<LogItemBatchAddErrorResp>> {
+    let (client, ext, _) = inst.inst::<TardisSearchClient>();
+    let conn_uri = ext.get(log_constants::CONN_URI_FLAG).ok_or_else(|| {
+        funs.err().internal_error(
+            "item",
+            "add_items",
+            "The connection uri of backend service is not found",
+            "500-spi-log-conn-uri-not-found",
+        )
+    })?;
+    let mut errors = Vec::new();
+    let mut actions = Vec::with_capacity(add_reqs.len() * 2);
+    let mut action_indexes = Vec::with_capacity(add_reqs.len());
+    for (index, add_req) in add_reqs.iter_mut() {
+        let result = async {
+            let item = package_add_item(add_req, funs)?;
+            let es_index = format!("{}-{}", format_index_prefix(&add_req.tag, ext), item.ts.format(&funs.conf::<LogConfig>().es_index_date_format));
+            log_es_initializer::init_index(client, &es_index).await?;
+            Ok::<_, TardisError>((es_index, TardisFuns::json.obj_to_string(&item)?))
+        }
+        .await;
+        match result {
+            Ok((es_index, item)) => {
+                actions.push(json!({"index": {"_index": es_index}}).to_string());
+                actions.push(item);
+                action_indexes.push(*index);
+            }
+            Err(e) => errors.push(LogItemBatchAddErrorResp::new(*index, &e)),
+        }
+    }
+    if actions.is_empty() {
+        return Ok(errors);
+    }
+    let resp = funs
+        .web_client()
+        .post_str_to_str(
+            &format!("{conn_uri}/_bulk"),
+            &format!("{}\n", actions.join("\n")),
+            Some(vec![("Content-Type".to_string(), "application/x-ndjson".to_string())]),
+        )
+        .await?;
+    let body = resp.body.unwrap_or_default();
+    if resp.code != 200 {
+        return Err(funs.err().internal_error("item", "add_items", &format!("Bulk request error: {body}"), "500-spi-log-es-bulk-error"));
+    }
+    let result = TardisFuns::json.str_to_json(&body)?;
+    if result["errors"].as_bool().unwrap_or(false) {
+        for (item, index) in result["items"].as_array().into_iter().flatten().zip(action_indexes) {
+            // Each item is like `{"index": {"status": 400, "error": {...}}}`
+            if let Some(error) = item["index"].get("error") {
+                errors.push(LogItemBatchAddErrorResp {
+                    index,
+                    code: format!("{}-spi-log-es-bulk-item-error", item["index"]["status"].as_u64().unwrap_or(500)),
+                    msg: error.to_string(),
+                });
+            }
+        }
+        errors.sort_by_key(|error| error.index);
+    }
+    Ok(errors)
+}
+
+fn package_add_item(add_req: &mut LogItemAddReq, funs: &TardisFunsInst) -> TardisResult<LogEsItem> {
+    let ext = add_req.ext.clone().unwrap_or_else(|| json!({}));
+    // Mapped as a flattened field, which only accepts objects
+    if !ext.is_object() {
+        return Err(funs.err().bad_request("item", "add", "The ext must be an object", "400-spi-log-ext-not-object"));
+    }
+    Ok(LogEsItem {
+        ts: *add_req.ts.get_or_insert_with(Utc::now),
+        kind: add_req.kind.as_ref().map(|kind| kind.to_string()).unwrap_or_default(),
+        key: add_req.key.as_ref().map(|key| key.to_string()).unwrap_or_default(),
+        op: add_req.op.clone().unwrap_or_default(),
+        content: add_req.content.clone(),
+        owner: add_req.owner.clone().unwrap_or_default(),
+        own_paths: add_req.own_paths.clone().unwrap_or_default(),
+        ext,
+        rel_key: add_req.rel_key.as_ref().map(|rel_key| rel_key.to_string()).unwrap_or_default(),
+    })
+}
+
+pub async fn find(find_req: &mut LogItemFindReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<TardisPage<LogItemFindResp>> {
+    let filter = &find_req.filter;
+    let (client, ext, _) = inst.inst::<TardisSearchClient>();
+    let index = format!("{}-*", format_index_prefix(&filter.tag, ext));
+    if !client.check_index_exist(&index).await? {
+        return Err(funs.err().bad_request("item", "find", "The requested tag does not exist", "400-spi-log-tag-not-exist"));
+    }
+    let mut sort = vec![json!({"ts": {"order": "desc"}})];
+    if filter.q.is_some() {
+        sort.insert(0, json!({"_score": {"order": "desc"}}));
+    }
+    let q = json!({
+        "query": package_query(filter, funs)?,
+        "sort": sort,
+        "track_total_hits": true,
+    });
+    let result = client
+        .raw_search(
+            &index,
+            &q.to_string(),
+            Some(find_req.page_size as i32),
+            Some(((find_req.page_number - 1) * find_req.page_size as u32) as i32),
+            None,
+        )
+        .await?;
+    let records = result
+        .hits
+        .hits
+        .iter()
+        .map(|raw_item| {
+            let item = TardisFuns::json
+                .json_to_obj::<LogEsItem>(raw_item._source.clone())
+                .map_err(|_| funs.err().format_error("item", "find", "search result format error", "500-spi-log-result-format-error"))?;
+            Ok(LogItemFindResp {
+                content: item.content,
+                kind: item.kind,
+                ext: item.ext,
+                owner: item.owner,
+                own_paths: item.own_paths,
+                key: item.key,
+                op: item.op,
+                rel_key: item.rel_key,
+                ts: item.ts,
+                highlight: None,
+            })
+        })
+        .collect::<TardisResult<Vec<_>>>()?;
+    Ok(TardisPage {
+        page_size: find_req.page_size as u64,
+        page_number: find_req.page_number as u64,
+        total_size: result.hits.total.value as u64,
+        records,
+    })
+}
+
+pub async fn agg(_agg_req: &mut LogItemAggReq, _funs: &TardisFunsInst, _ctx: &TardisContext, _inst: &SpiBsInst) -> TardisResult<Vec<LogItemAggResp>> {
+    Err(TardisError::not_implemented("Aggregation is not supported by the Elasticsearch backend", "501-spi-log-es-not-supported"))
+}
+
+pub async fn export(
+    _export_req: &LogItemExportReq,
+    _funs: &TardisFunsInst,
+    _ctx: &TardisContext,
+    _inst: &SpiBsInst,
+) -> TardisResult<BoxStream<'static, TardisResult<String>>> {
+    Err(TardisError::not_implemented("Export is not supported by the Elasticsearch backend", "501-spi-log-es-not-supported"))
+}
+
+fn package_query(filter: &LogItemFilterReq, funs: &TardisFunsInst) -> TardisResult<Value> {
+    let mut must_q = vec![];
+    let mut must_not_q = vec![];
+    let mut filter_q = vec![];
+
+    if let Some(kinds) = &filter.kinds {
+        filter_q.push(json!({"terms": {"kind": kinds.iter().map(|kind| kind.to_string()).collect::<Vec<_>>()}}));
+    }
+    if let Some(owners) = &filter.owners {
+        filter_q.push(json!({"terms": {"owner": owners}}));
+    }
+    if let Some(keys) = &filter.keys {
+        filter_q.push(json!({"terms": {"key": keys.iter().map(|key| key.to_string()).collect::<Vec<_>>()}}));
+    }
+    if let Some(ops) = &filter.ops {
+        filter_q.push(json!({"terms": {"op": ops}}));
+    }
+    if let Some(rel_keys) = &filter.rel_keys {
+        filter_q.push(json!({"terms": {"rel_key": rel_keys.iter().map(|rel_key| rel_key.to_string()).collect::<Vec<_>>()}}));
+    }
+    if let Some(own_paths) = &filter.own_paths {
+        filter_q.push(json!({"prefix": {"own_paths": own_paths}}));
+    }
+    if let Some(ts_start) = filter.ts_start {
+        filter_q.push(json!({"range": {"ts": {"gte": ts_start}}}));
+    }
+    if let Some(ts_end) = filter.ts_end {
+        filter_q.push(json!({"range": {"ts": {"lte": ts_end}}}));
+    }
+    if let Some(q) = &filter.q {
+        must_q.push(json!({
+            "simple_query_string": {
+                "query": q,
+                "fields": ["content"],
+                "default_operator": "and"
+            }
+        }));
+    }
+    if let Some(ext) = &filter.ext {
+        for cond in ext {
+            let cond_q = package_ext_cond(cond, funs)?;
+            if cond.op == BasicQueryOpKind::Ne {
+                must_not_q.push(cond_q);
+            } else {
+                filter_q.push(cond_q);
+            }
+        }
+    }
+    if let Some(ext_or) = &filter.ext_or {
+        let should_q = ext_or
+            .iter()
+            .map(|cond| {
+                let cond_q = package_ext_cond(cond, funs)?;
+                Ok(if cond.op == BasicQueryOpKind::Ne {
+                    json!({"bool": {"must_not": cond_q}})
+                } else {
+                    cond_q
+                })
+            })
+            .collect::<TardisResult<Vec<_>>>()?;
+        filter_q.push(json!({"bool": {"should": should_q, "minimum_should_match": 1}}));
+    }
+    Ok(json!({
+        "bool": {
+            "must": must_q,
+            "must_not": must_not_q,
+            "filter": filter_q,
+        }
+    }))
+}
+
+/// Translate the condition on ext into ES query, `Ne` is translated into the positive query to be negated by the caller.
+///
+/// The leaf values of a flattened field are indexed as keywords, so values are compared as strings,
+/// and the range operators compare lexicographically, e.g. `"10" < "9"`.
+/// So the range operators only accept strings, such as dates in the same format, and numbers are rejected.
+fn package_ext_cond(cond: &BasicQueryCondInfo, funs: &TardisFunsInst) -> TardisResult<Value> {
+    let field = format!("ext.{}", cond.field);
+    if matches!(cond.op, BasicQueryOpKind::Gt | BasicQueryOpKind::Ge | BasicQueryOpKind::Lt | BasicQueryOpKind::Le) && !cond.value.is_string() {
+        return Err(funs.err().bad_request(
+            "item",
+            "find",
+            &format!(
+                "The ext field=[{}] operation=[{}] only accepts strings, values of ext are compared as strings by the Elasticsearch backend",
+                &cond.field, &cond.op
+            ),
+            "400-spi-log-es-ext-range-not-string",
+        ));
+    }
+    let to_keyword = |value: &Value| match value {
+        Value::String(value) => Ok(value.clone()),
+        Value::Number(_) | Value::Bool(_) => Ok(value.to_string()),
+        _ => Err(funs.err().not_found(
+            "item",
+            "log",
+            &format!("The ext field=[{}] value=[{}] operation=[{}] is not legal.", &cond.field, cond.value, &cond.op),
+            "404-spi-log-op-not-legal",
+        )),
+    };
+    let cond_q = match cond.op {
+        BasicQueryOpKind::Eq | BasicQueryOpKind::Ne => json!({"term": {field: to_keyword(&cond.value)?}}),
+        BasicQueryOpKind::Gt => json!({"range": {field: {"gt": to_keyword(&cond.value)?}}}),
+        BasicQueryOpKind::Ge => json!({"range": {field: {"gte": to_keyword(&cond.value)?}}}),
+        BasicQueryOpKind::Lt => json!({"range": {field: {"lt": to_keyword(&cond.value)?}}}),
+        BasicQueryOpKind::Le => json!({"range": {field: {"lte": to_keyword(&cond.value)?}}}),
+        BasicQueryOpKind::Like => json!({"wildcard": {field: {"value": format!("*{}*", to_keyword(&cond.value)?)}}}),
+        BasicQueryOpKind::In => {
+            let values = match &cond.value {
+                Value::Array(values) => values.iter().map(to_keyword).collect::<TardisResult<Vec<_>>>()?,
+                value => vec![to_keyword(value)?],
+            };
+            if values.is_empty() {
+                return Err(funs.err().bad_request("item", "log", "Request item using 'IN' operator show hava a value", "400-spi-item-op-in-without-value"));
+            }
+            json!({"terms": {field: values}})
+        }
+    };
+    Ok(cond_q)
+}
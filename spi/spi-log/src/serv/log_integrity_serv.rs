@@ -0,0 +1,23 @@
+use bios_basic::spi::spi_constants;
+use bios_basic::spi::spi_funs::SpiBsInstExtractor;
+use bios_basic::spi_dispatch_service;
+
+use tardis::basic::result::TardisResult;
+
+use crate::dto::log_integrity_dto::{LogIntegrityAddOrModifyReq, LogIntegrityResp, LogIntegrityVerifyReq, LogIntegrityVerifyResp};
+use crate::log_initializer;
+
+use super::pg;
+spi_dispatch_service! {
+    @mgr: true,
+    @init: log_initializer::init_fun,
+    @dispatch: {
+        #[cfg(feature = "spi-pg")]
+        spi_constants::SPI_PG_KIND_CODE => pg::log_pg_integrity_serv,
+    },
+    @method: {
+        add_or_modify(req: &LogIntegrityAddOrModifyReq) -> TardisResult<()>;
+        find() -> TardisResult<Vec<LogIntegrityResp>>;
+        verify(req: &LogIntegrityVerifyReq) -> TardisResult<LogIntegrityVerifyResp>;
+    }
+}
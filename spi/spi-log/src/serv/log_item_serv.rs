@@ -1,23 +1,181 @@
+use std::env;
+use std::io;
+use std::path::PathBuf;
+
+use bios_basic::process::task_processor::TaskProcessor;
 use bios_basic::spi::spi_constants;
 use bios_basic::spi::spi_funs::SpiBsInstExtractor;
 use bios_basic::spi_dispatch_service;
+use bios_sdk_invoke::clients::spi_object_client::SpiObjectClient;
 
+use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
+use tardis::futures::stream::{self, BoxStream};
+use tardis::futures::StreamExt;
+use tardis::log::warn;
+use tardis::serde_json::Value;
+use tardis::tokio::fs::File;
+use tardis::tokio::io::{AsyncReadExt, AsyncWriteExt, BufWriter};
+use tardis::web::reqwest::header::CONTENT_LENGTH;
+use tardis::web::reqwest::Body;
+use tardis::web::poem_openapi::types::ParseFromJSON;
 use tardis::web::web_resp::TardisPage;
+use tardis::{TardisFuns, TardisFunsInst};
 
-use crate::dto::log_item_dto::{LogItemAddReq, LogItemFindReq, LogItemFindResp};
+use crate::dto::log_item_dto::{
+    LogItemAddReq, LogItemAggReq, LogItemAggResp, LogItemBatchAddErrorResp, LogItemBatchAddResp, LogItemExportObjResp, LogItemExportReq, LogItemFindReq, LogItemFindResp,
+};
+use crate::log_config::LogConfig;
 use crate::log_initializer;
 
+#[cfg(feature = "spi-es")]
+use super::es;
 use super::pg;
+
+// Size of the chunks read from the temporary file when uploading
+const EXPORT_UPLOAD_CHUNK_SIZE: usize = 64 * 1024;
+// Cache key infix of the owner of an export task
+const EXPORT_TASK_OWNER_FLAG: &str = ":export_owner:";
+
 spi_dispatch_service! {
     @mgr: true,
     @init: log_initializer::init_fun,
     @dispatch: {
         #[cfg(feature = "spi-pg")]
         spi_constants::SPI_PG_KIND_CODE => pg::log_pg_item_serv,
+        #[cfg(feature = "spi-es")]
+        spi_constants::SPI_ES_KIND_CODE => es::log_es_item_serv,
     },
     @method: {
         add(add_req: &mut LogItemAddReq) -> TardisResult<()>;
+        add_items(add_reqs: &mut [(u32, LogItemAddReq)]) -> TardisResult<Vec<LogItemBatchAddErrorResp>>;
         find(find_req: &mut LogItemFindReq) -> TardisResult<TardisPage<LogItemFindResp>>;
+        agg(agg_req: &mut LogItemAggReq) -> TardisResult<Vec<LogItemAggResp>>;
+        export(export_req: &LogItemExportReq) -> TardisResult<BoxStream<'static, TardisResult<String>>>;
+    }
+}
+
+/// Add items in a batch.
+///
+/// Each item is parsed and validated separately, so that an illegal item only fails itself.
+pub async fn batch_add(add_reqs: Vec<Value>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<LogItemBatchAddResp> {
+    let max_items = funs.conf::<LogConfig>().batch_add_max_items;
+    if add_reqs.len() > max_items {
+        return Err(funs.err().bad_request(
+            "item",
+            "batch_add",
+            &format!("The number of items exceeds the limit {max_items}"),
+            "400-spi-log-batch-too-many-items",
+        ));
+    }
+    let total = add_reqs.len() as u32;
+    let mut items = Vec::with_capacity(add_reqs.len());
+    let mut errors = Vec::new();
+    for (index, add_req) in add_reqs.into_iter().enumerate() {
+        match LogItemAddReq::parse_from_json(Some(add_req)) {
+            Ok(add_req) => items.push((index as u32, add_req)),
+            Err(e) => errors.push(LogItemBatchAddErrorResp::new(
+                index as u32,
+                &funs.err().bad_request("item", "batch_add", e.message(), "400-spi-log-item-illegal"),
+            )),
+        }
+    }
+    if !items.is_empty() {
+        errors.extend(add_items(&mut items, funs, ctx).await?);
+        errors.sort_by_key(|error| error.index);
+    }
+    Ok(LogItemBatchAddResp {
+        succeed: total - errors.len() as u32,
+        errors,
+    })
+}
+
+/// Export the items to spi-object in an asynchronous task, and return the presigned download URL.
+///
+/// The presigned upload requires the content length, so the items are streamed into a temporary file first and then uploaded from it,
+/// the memory usage does not grow with the number of items.
+pub async fn export_to_obj(export_req: &LogItemExportReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<LogItemExportObjResp> {
+    let conf = funs.conf::<LogConfig>();
+    let object_path = format!("spi-log/export/{}/{}.{}", export_req.filter.tag, TardisFuns::field.nanoid(), export_req.format.extension());
+    let put_url = SpiObjectClient::presign_obj_url("put", &object_path, conf.export_url_exp_secs, true, funs, ctx)
+        .await?
+        .ok_or_else(|| funs.err().internal_error("item", "export", "Failed to presign the upload URL", "500-spi-log-export-presign-failed"))?;
+    let url = SpiObjectClient::presign_obj_url("view", &object_path, conf.export_url_exp_secs, true, funs, ctx)
+        .await?
+        .ok_or_else(|| funs.err().internal_error("item", "export", "Failed to presign the download URL", "500-spi-log-export-presign-failed"))?;
+    let task_export_req = export_req.clone();
+    let task_ctx = ctx.clone();
+    let task_id = TaskProcessor::execute_task(
+        &conf.cache_key_async_task_status,
+        move || async move {
+            let funs = crate::get_tardis_inst();
+            let file = ExportFile(env::temp_dir().join(format!("spi-log-export-{}", TardisFuns::field.nanoid())));
+            let mut writer = BufWriter::new(File::create(&file.0).await?);
+            let mut stream = export(&task_export_req, &funs, &task_ctx).await?;
+            while let Some(chunk) = stream.next().await {
+                writer.write_all(chunk?.as_bytes()).await?;
+            }
+            writer.flush().await?;
+            drop(writer);
+            let reader = File::open(&file.0).await?;
+            let content_length = reader.metadata().await?.len();
+            let body = stream::unfold(reader, |mut reader| async move {
+                let mut buf = vec![0; EXPORT_UPLOAD_CHUNK_SIZE];
+                match reader.read(&mut buf).await {
+                    Ok(0) => None,
+                    Ok(size) => {
+                        buf.truncate(size);
+                        Some((Ok::<_, io::Error>(buf), reader))
+                    }
+                    Err(e) => Some((Err(e), reader)),
+                }
+            });
+            let resp = funs.web_client().raw().put(&put_url).header(CONTENT_LENGTH, content_length).body(Body::wrap_stream(body)).send().await?;
+            if !resp.status().is_success() {
+                return Err(funs.err().internal_error(
+                    "item",
+                    "export",
+                    &format!("Failed to upload the exported file, status: {}", resp.status()),
+                    "500-spi-log-export-upload-failed",
+                ));
+            }
+            Ok(())
+        },
+        funs,
+    )
+    .await?;
+    // Only the owner can check the status of the task, the record expires with the download URL
+    funs.cache()
+        .set_ex(
+            &format!("{}{EXPORT_TASK_OWNER_FLAG}{task_id}", conf.cache_key_async_task_status),
+            &ctx.own_paths,
+            conf.export_url_exp_secs as usize,
+        )
+        .await?;
+    Ok(LogItemExportObjResp {
+        task_id: Some(task_id.to_string()),
+        object_path,
+        url,
+    })
+}
+
+pub async fn check_export_task_status(task_id: i64, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<bool> {
+    let cache_key = &funs.conf::<LogConfig>().cache_key_async_task_status;
+    if funs.cache().get(&format!("{cache_key}{EXPORT_TASK_OWNER_FLAG}{task_id}")).await?.as_deref() != Some(ctx.own_paths.as_str()) {
+        return Err(funs.err().not_found("item", "export", "The export task is not found or expired", "404-spi-log-export-task-not-exist"));
+    }
+    TaskProcessor::check_status(cache_key, task_id, funs.cache()).await
+}
+
+/// Temporary file of the exported items, removed when the task is finished
+struct ExportFile(PathBuf);
+
+impl Drop for ExportFile {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.0) {
+            if e.kind() != io::ErrorKind::NotFound {
+                warn!("[SPI-Log] Failed to remove the exported file {}: {e}", self.0.display());
+            }
+        }
     }
 }
@@ -0,0 +1,22 @@
+use bios_basic::spi::spi_constants;
+use bios_basic::spi::spi_funs::SpiBsInstExtractor;
+use bios_basic::spi_dispatch_service;
+
+use tardis::basic::result::TardisResult;
+use tardis::web::poem::web::websocket::{BoxWebSocketUpgraded, WebSocket};
+
+use crate::dto::log_item_dto::LogItemFilterReq;
+use crate::log_initializer;
+
+use super::pg;
+spi_dispatch_service! {
+    @mgr: false,
+    @init: log_initializer::init_fun,
+    @dispatch: {
+        #[cfg(feature = "spi-pg")]
+        spi_constants::SPI_PG_KIND_CODE => pg::log_pg_tail_serv,
+    },
+    @method: {
+        tail(tail_req: &LogItemFilterReq, websocket: WebSocket) -> TardisResult<BoxWebSocketUpgraded>;
+    }
+}
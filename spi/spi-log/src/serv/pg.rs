@@ -1,2 +1,5 @@
 pub mod log_pg_initializer;
 pub mod log_pg_item_serv;
+pub mod log_pg_retention_serv;
+pub mod log_pg_integrity_serv;
+pub mod log_pg_tail_serv;
@@ -1,37 +1,227 @@
-use bios_basic::spi::{spi_funs::TypedSpiBsInst, spi_initializer};
+use std::collections::HashSet;
+
+use bios_basic::spi::{
+    dto::spi_bs_dto::SpiBsCertResp,
+    spi_constants::GLOBAL_STORAGE_FLAG,
+    spi_funs::{SpiBsInst, TypedSpiBsInst},
+    spi_initializer::common_pg,
+};
+use lazy_static::lazy_static;
 use tardis::{
     basic::{dto::TardisContext, result::TardisResult},
-    db::reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
+    chrono::{DateTime, Datelike, Utc},
+    db::{
+        reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
+        sea_orm::Value,
+    },
+    tokio::sync::RwLock,
+    TardisFunsInst,
 };
 
-pub async fn init_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, tag: &str, ctx: &TardisContext, mgr: bool) -> TardisResult<(TardisRelDBlConnection, String)> {
-    spi_initializer::common_pg::init_table_and_conn(
-        bs_inst,
-        ctx,
-        mgr,
-        Some(tag),
-        "log",
-        r#"ts timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+use crate::{log_config::LogConfig, log_constants};
+
+lazy_static! {
+    // Partitions known to exist, to avoid querying the catalog on every insert
+    static ref PARTITIONS: RwLock<HashSet<String>> = RwLock::new(HashSet::new());
+    // Tables known to have the columns added after the first version
+    static ref UPGRADED_TABLES: RwLock<HashSet<String>> = RwLock::new(HashSet::new());
+}
+
+const TABLE_CREATE_CONTENT: &str = r#"ts timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
     key character varying NOT NULL,
     op character varying NOT NULL,
     content text NOT NULL,
+    content_tsv tsvector,
     kind character varying NOT NULL,
     owner character varying NOT NULL,
     own_paths character varying NOT NULL,
     ext jsonb NOT NULL,
-    rel_key character varying NOT NULL"#,
-        vec![
-            ("kind", "btree"),
-            ("ts", "btree"),
-            ("key", "btree"),
-            ("op", "btree"),
-            ("ext", "gin"),
-            ("owner", "btree"),
-            ("own_paths", "btree"),
-            ("rel_key", "btree"),
-        ],
-        None,
-        None,
+    rel_key character varying NOT NULL,
+    chain_seq bigint,
+    prev_hash character varying,
+    hash character varying,
+    id character varying"#;
+
+const TABLE_INDEXES: [(&str, &str); 10] = [
+    ("kind", "btree"),
+    ("ts", "btree"),
+    ("key", "btree"),
+    ("op", "btree"),
+    ("ext", "gin"),
+    ("owner", "btree"),
+    ("own_paths", "btree"),
+    ("rel_key", "btree"),
+    ("content_tsv", "gin"),
+    ("chain_seq", "btree"),
+];
+// Columns added after the first version, with the position in [TABLE_INDEXES]
+const UPGRADE_COLUMNS: [(&str, &str, Option<usize>); 5] = [
+    ("content_tsv", "tsvector", Some(8)),
+    ("chain_seq", "bigint", Some(9)),
+    ("prev_hash", "character varying", None),
+    ("hash", "character varying", None),
+    ("id", "character varying", None),
+];
+
+pub async fn init(bs_cert: &SpiBsCertResp, ctx: &TardisContext, mgr: bool) -> TardisResult<SpiBsInst> {
+    let mut inst = common_pg::init(bs_cert, ctx, mgr).await?;
+    // Live tail listens on a dedicated connection
+    inst.ext.insert(log_constants::CONN_URI_FLAG.to_string(), bs_cert.conn_uri.clone());
+    Ok(inst)
+}
+
+/// Each tag is stored in a table partitioned by month on `ts`.
+///
+/// Tables created by older versions are not partitioned, they are still readable and writable,
+/// but the retention policy can only delete rows from them.
+pub async fn init_table_and_conn(
+    bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>,
+    tag: &str,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    mgr: bool,
+) -> TardisResult<(TardisRelDBlConnection, String)> {
+    let conn = bs_inst.0.conn();
+    let schema_name = common_pg::get_schema_name_from_ext(bs_inst.1)
+        .ok_or_else(|| funs.err().internal_error("item", "init", "The schema of backend service is not found", "500-spi-log-schema-not-found"))?;
+    let table_name = format!("{schema_name}.{GLOBAL_STORAGE_FLAG}_log_{tag}");
+    if common_pg::check_table_exit(&format!("log_{tag}"), &conn, ctx).await? {
+        upgrade_table(&conn, &schema_name, &table_name, tag, funs).await?;
+        return Ok((conn, table_name));
+    } else if !mgr {
+        return Err(funs.err().bad_request("item", "init", "The requested tag does not exist", "400-spi-log-tag-not-exist"));
+    }
+    conn.execute_one(
+        &format!(
+            r#"CREATE TABLE {table_name}
+(
+    {TABLE_CREATE_CONTENT}
+) PARTITION BY RANGE (ts)"#
+        ),
+        vec![],
     )
-    .await
+    .await?;
+    for (idx, (field_name, index_type)) in TABLE_INDEXES.iter().enumerate() {
+        conn.execute_one(&format!("CREATE INDEX idx_{schema_name}_{tag}_log_{idx} ON {table_name} USING {index_type}({field_name})"), vec![]).await?;
+    }
+    premake_partitions(&conn, &table_name, funs).await?;
+    UPGRADED_TABLES.write().await.insert(table_name.clone());
+    Ok((conn, table_name))
+}
+
+/// Add the columns of full-text search, integrity chain and live tail to the tables created by older versions.
+///
+/// The full-text vectors of the rows inserted before are backfilled when the column is added, so they can be found by keywords.
+/// The rows are not added to the integrity chain.
+async fn upgrade_table(conn: &TardisRelDBlConnection, schema_name: &str, table_name: &str, tag: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+    if UPGRADED_TABLES.read().await.contains(table_name) {
+        return Ok(());
+    }
+    let content_tsv_exists = conn
+        .count_by_sql(
+            "SELECT 1 FROM pg_attribute WHERE attrelid = to_regclass($1) AND attname = 'content_tsv' AND NOT attisdropped",
+            vec![Value::from(table_name)],
+        )
+        .await?
+        != 0;
+    for (column_name, column_type, idx) in UPGRADE_COLUMNS {
+        conn.execute_one(&format!("ALTER TABLE {table_name} ADD COLUMN IF NOT EXISTS {column_name} {column_type}"), vec![]).await?;
+        if let Some(idx) = idx {
+            let (field_name, index_type) = TABLE_INDEXES[idx];
+            conn.execute_one(
+                &format!("CREATE INDEX IF NOT EXISTS idx_{schema_name}_{tag}_log_{idx} ON {table_name} USING {index_type}({field_name})"),
+                vec![],
+            )
+            .await?;
+        }
+    }
+    if !content_tsv_exists {
+        conn.execute_one(
+            &format!(
+                "UPDATE {table_name} SET content_tsv = to_tsvector('{}', content) WHERE content_tsv IS NULL",
+                funs.conf::<LogConfig>().text_search_config
+            ),
+            vec![],
+        )
+        .await?;
+    }
+    UPGRADED_TABLES.write().await.insert(table_name.to_string());
+    Ok(())
+}
+
+/// Create the partitions of the current month and the following months
+pub async fn premake_partitions(conn: &TardisRelDBlConnection, table_name: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+    let (mut year, mut month) = (Utc::now().year(), Utc::now().month());
+    for _ in 0..=funs.conf::<LogConfig>().partition_premake_months {
+        ensure_partition(conn, table_name, year, month).await?;
+        (year, month) = next_month(year, month);
+    }
+    Ok(())
+}
+
+/// Make sure the partition covering `ts` exists before inserting
+pub async fn ensure_partition_by_ts(conn: &TardisRelDBlConnection, table_name: &str, ts: DateTime<Utc>) -> TardisResult<()> {
+    ensure_partition(conn, table_name, ts.year(), ts.month()).await
+}
+
+async fn ensure_partition(conn: &TardisRelDBlConnection, table_name: &str, year: i32, month: u32) -> TardisResult<()> {
+    let partition_name = format!("{table_name}_{}", partition_suffix(year, month));
+    if PARTITIONS.read().await.contains(&partition_name) {
+        return Ok(());
+    }
+    if is_partitioned(conn, table_name).await? {
+        let (next_year, next_month) = next_month(year, month);
+        let result = conn
+            .execute_one(
+                &format!(
+                    "CREATE TABLE IF NOT EXISTS {partition_name} PARTITION OF {table_name} FOR VALUES FROM ('{}') TO ('{}')",
+                    month_start(year, month),
+                    month_start(next_year, next_month)
+                ),
+                vec![],
+            )
+            .await;
+        // Another instance may have created the same partition concurrently
+        if let Err(e) = result {
+            if conn.count_by_sql("SELECT 1 WHERE to_regclass($1) IS NOT NULL", vec![Value::from(partition_name.as_str())]).await? == 0 {
+                return Err(e);
+            }
+        }
+    }
+    // For the tables that are not partitioned, the mark is also recorded to skip the check next time
+    PARTITIONS.write().await.insert(partition_name);
+    Ok(())
+}
+
+pub async fn forget_partition(partition_name: &str) {
+    PARTITIONS.write().await.remove(partition_name);
+}
+
+pub async fn is_partitioned(conn: &TardisRelDBlConnection, table_name: &str) -> TardisResult<bool> {
+    Ok(conn.count_by_sql("SELECT 1 FROM pg_partitioned_table WHERE partrelid = to_regclass($1)", vec![Value::from(table_name)]).await? != 0)
+}
+
+pub fn partition_suffix(year: i32, month: u32) -> String {
+    format!("p{year:04}{month:02}")
+}
+
+/// Parse the (year, month) from the partition name generated by [partition_suffix]
+pub fn parse_partition_suffix(partition_name: &str) -> Option<(i32, u32)> {
+    let suffix = partition_name.rsplit_once("_p")?.1;
+    if suffix.len() != 6 {
+        return None;
+    }
+    Some((suffix[..4].parse().ok()?, suffix[4..].parse().ok()?))
+}
+
+pub fn month_start(year: i32, month: u32) -> String {
+    format!("{year:04}-{month:02}-01 00:00:00+00")
+}
+
+pub fn next_month(year: i32, month: u32) -> (i32, u32) {
+    if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    }
 }
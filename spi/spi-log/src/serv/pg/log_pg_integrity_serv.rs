@@ -0,0 +1,394 @@
+use std::collections::HashMap;
+
+use bios_basic::spi::{
+    spi_constants::GLOBAL_STORAGE_FLAG,
+    spi_funs::{SpiBsInst, TypedSpiBsInst},
+    spi_initializer::common_pg,
+};
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    chrono::{DateTime, SecondsFormat, Utc},
+    crypto::crypto_sm2_4::TardisCryptoSm2PrivateKey,
+    db::{
+        reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
+        sea_orm::Value,
+    },
+    serde_json::{self, json},
+    TardisFuns, TardisFunsInst,
+};
+
+use crate::{
+    dto::log_integrity_dto::{LogIntegrityAddOrModifyReq, LogIntegrityBrokenResp, LogIntegrityResp, LogIntegrityVerifyReq, LogIntegrityVerifyResp},
+    log_config::LogConfig,
+};
+
+use super::log_pg_initializer;
+
+const DEFAULT_CHECKPOINT_INTERVAL: u32 = 1000;
+const VERIFY_BATCH_SIZE: u64 = 1000;
+// Not named `log_*` to avoid conflict with the tables of tags
+const INTEGRITY_TABLE_FLAG: &str = "integrity_log";
+const CHECKPOINT_TABLE_FLAG: &str = "checkpoint_log";
+
+/// Fields of an item covered by the hash
+pub struct IntegrityItem<'a> {
+    pub ts: DateTime<Utc>,
+    pub kind: &'a str,
+    pub key: &'a str,
+    pub op: &'a str,
+    pub content: &'a str,
+    pub owner: &'a str,
+    pub own_paths: &'a str,
+    pub ext: &'a serde_json::Value,
+    pub rel_key: &'a str,
+}
+
+/// Position of an item in the chain
+pub struct IntegrityLink {
+    pub seq: i64,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+async fn init_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, ctx: &TardisContext, mgr: bool) -> TardisResult<(TardisRelDBlConnection, String, String)> {
+    let (_, checkpoint_table_name) = common_pg::init_table_and_conn(
+        bs_inst,
+        ctx,
+        mgr,
+        None,
+        CHECKPOINT_TABLE_FLAG,
+        r#"tag character varying NOT NULL,
+    seq bigint NOT NULL,
+    hash character varying NOT NULL,
+    sign character varying NOT NULL,
+    create_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP"#,
+        vec![],
+        Some(vec!["tag", "seq"]),
+        None,
+    )
+    .await?;
+    let (conn, table_name) = common_pg::init_table_and_conn(
+        bs_inst,
+        ctx,
+        mgr,
+        None,
+        INTEGRITY_TABLE_FLAG,
+        r#"tag character varying NOT NULL,
+    checkpoint_interval integer NOT NULL,
+    last_seq bigint NOT NULL,
+    last_hash character varying NOT NULL,
+    create_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP"#,
+        vec![],
+        Some(vec!["tag"]),
+        None,
+    )
+    .await?;
+    Ok((conn, table_name, checkpoint_table_name))
+}
+
+fn get_private_key(funs: &TardisFunsInst) -> TardisResult<Option<TardisCryptoSm2PrivateKey>> {
+    funs.conf::<LogConfig>().integrity_sm2_private_key.as_deref().map(|pri_key| TardisFuns::crypto.sm2.new_private_key_from_str(pri_key)).transpose()
+}
+
+fn checkpoint_sign_data(tag: &str, seq: i64, hash: &str) -> String {
+    format!("{tag}:{seq}:{hash}")
+}
+
+/// Serialize json with the keys of objects sorted, so that the result is not affected by how jsonb stores the keys
+fn canonical_json(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut items = map.iter().collect::<Vec<_>>();
+            items.sort_by(|a, b| a.0.cmp(b.0));
+            format!(
+                "{{{}}}",
+                items.into_iter().map(|(key, value)| format!("{}:{}", serde_json::Value::from(key.as_str()), canonical_json(value))).collect::<Vec<_>>().join(",")
+            )
+        }
+        serde_json::Value::Array(array) => format!("[{}]", array.iter().map(canonical_json).collect::<Vec<_>>().join(",")),
+        _ => value.to_string(),
+    }
+}
+
+fn digest(prev_hash: &str, seq: i64, item: &IntegrityItem) -> TardisResult<String> {
+    let data = json!([
+        prev_hash,
+        seq,
+        // Same precision as the timestamp of PG
+        item.ts.to_rfc3339_opts(SecondsFormat::Micros, true),
+        item.kind,
+        item.key,
+        item.op,
+        item.content,
+        item.owner,
+        item.own_paths,
+        canonical_json(item.ext),
+        item.rel_key,
+    ]);
+    TardisFuns::crypto.digest.sm3(&data.to_string())
+}
+
+pub async fn add_or_modify(req: &LogIntegrityAddOrModifyReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (mut conn, table_name, _) = init_table_and_conn(bs_inst, ctx, true).await?;
+    conn.begin().await?;
+    // The chain can't be reset once enabled, only the checkpoint interval can be modified
+    conn.execute_one(
+        &format!(
+            r#"INSERT INTO {table_name} (tag, checkpoint_interval, last_seq, last_hash)
+VALUES ($1, $2, 0, '')
+ON CONFLICT (tag) DO UPDATE SET checkpoint_interval = EXCLUDED.checkpoint_interval"#
+        ),
+        vec![
+            Value::from(req.tag.as_str()),
+            Value::from(req.checkpoint_interval.unwrap_or(DEFAULT_CHECKPOINT_INTERVAL) as i32),
+        ],
+    )
+    .await?;
+    conn.commit().await?;
+    Ok(())
+}
+
+pub async fn find(_funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<LogIntegrityResp>> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    if !common_pg::check_table_exit(INTEGRITY_TABLE_FLAG, &bs_inst.0.conn(), ctx).await? {
+        return Ok(vec![]);
+    }
+    let (conn, table_name, _) = init_table_and_conn(bs_inst, ctx, false).await?;
+    let result = conn.query_all(&format!("SELECT tag, checkpoint_interval, last_seq, last_hash, create_time FROM {table_name} ORDER BY tag"), vec![]).await?;
+    result
+        .into_iter()
+        .map(|item| {
+            let checkpoint_interval: i32 = item.try_get("", "checkpoint_interval")?;
+            let last_seq: i64 = item.try_get("", "last_seq")?;
+            Ok(LogIntegrityResp {
+                tag: item.try_get("", "tag")?,
+                checkpoint_interval: checkpoint_interval as u32,
+                last_seq: last_seq as u64,
+                last_hash: item.try_get("", "last_hash")?,
+                create_time: item.try_get("", "create_time")?,
+            })
+        })
+        .collect()
+}
+
+/// Append the items in order to the chain of the tag if the integrity mode is enabled, and return the link of each item.
+///
+/// Must be called in the transaction inserting the items, the head of the chain is locked until the transaction ends.
+/// The head is read and written once for all items, and the checkpoints are inserted in one statement.
+pub async fn append(
+    conn: &TardisRelDBlConnection,
+    tag: &str,
+    items: &[IntegrityItem<'_>],
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<Option<Vec<IntegrityLink>>> {
+    if items.is_empty() || !common_pg::check_table_exit(INTEGRITY_TABLE_FLAG, conn, ctx).await? {
+        return Ok(None);
+    }
+    let schema_name = common_pg::get_schema_name_from_ext(inst.inst::<TardisRelDBClient>().1)
+        .ok_or_else(|| funs.err().internal_error("integrity", "append", "The schema of backend service is not found", "500-spi-log-schema-not-found"))?;
+    let table_name = format!("{schema_name}.{GLOBAL_STORAGE_FLAG}_{INTEGRITY_TABLE_FLAG}");
+    let Some(head) = conn
+        .query_one(
+            &format!("SELECT checkpoint_interval, last_seq, last_hash FROM {table_name} WHERE tag = $1 FOR UPDATE"),
+            vec![Value::from(tag)],
+        )
+        .await?
+    else {
+        return Ok(None);
+    };
+    let checkpoint_interval: i32 = head.try_get("", "checkpoint_interval")?;
+    let mut seq: i64 = head.try_get("", "last_seq")?;
+    let mut prev_hash: String = head.try_get("", "last_hash")?;
+    let pri_key = get_private_key(funs)?;
+    let mut links = Vec::with_capacity(items.len());
+    let mut checkpoint_fragments = vec![];
+    let mut checkpoint_vals = vec![];
+    for item in items {
+        seq += 1;
+        let hash = digest(&prev_hash, seq, item)?;
+        if seq % checkpoint_interval.max(1) as i64 == 0 {
+            if let Some(pri_key) = &pri_key {
+                let n = checkpoint_vals.len();
+                checkpoint_fragments.push(format!("(${}, ${}, ${}, ${})", n + 1, n + 2, n + 3, n + 4));
+                checkpoint_vals.extend([
+                    Value::from(tag),
+                    Value::from(seq),
+                    Value::from(hash.as_str()),
+                    Value::from(pri_key.sign(&checkpoint_sign_data(tag, seq, &hash))?),
+                ]);
+            }
+        }
+        links.push(IntegrityLink {
+            seq,
+            prev_hash: std::mem::replace(&mut prev_hash, hash.clone()),
+            hash,
+        });
+    }
+    if !checkpoint_fragments.is_empty() {
+        conn.execute_one(
+            &format!(
+                "INSERT INTO {schema_name}.{GLOBAL_STORAGE_FLAG}_{CHECKPOINT_TABLE_FLAG} (tag, seq, hash, sign) VALUES {}",
+                checkpoint_fragments.join(", ")
+            ),
+            checkpoint_vals,
+        )
+        .await?;
+    }
+    conn.execute_one(
+        &format!("UPDATE {table_name} SET last_seq = $1, last_hash = $2 WHERE tag = $3"),
+        vec![Value::from(seq), Value::from(prev_hash.as_str()), Value::from(tag)],
+    )
+    .await?;
+    Ok(Some(links))
+}
+
+/// Walk the chain of the items in the time range, and report the first broken link.
+///
+/// When `ts_end` is not specified, the chain is checked up to the head to detect the items removed from the end.
+pub async fn verify(req: &LogIntegrityVerifyReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<LogIntegrityVerifyResp> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let not_enabled = || funs.err().not_found("integrity", "verify", "The integrity mode of the tag is not enabled", "404-spi-log-integrity-not-enabled");
+    if !common_pg::check_table_exit(INTEGRITY_TABLE_FLAG, &bs_inst.0.conn(), ctx).await? {
+        return Err(not_enabled());
+    }
+    let (conn, integrity_table_name, checkpoint_table_name) = init_table_and_conn(bs_inst, ctx, false).await?;
+    let Some(head) = conn.query_one(&format!("SELECT last_seq FROM {integrity_table_name} WHERE tag = $1"), vec![Value::from(req.tag.as_str())]).await? else {
+        return Err(not_enabled());
+    };
+    let head_seq: i64 = head.try_get("", "last_seq")?;
+    let (_, table_name) = log_pg_initializer::init_table_and_conn(bs_inst, &req.tag, funs, ctx, false).await?;
+
+    let mut where_fragments = vec!["chain_seq IS NOT NULL".to_string()];
+    let mut sql_vals = vec![];
+    if let Some(ts_start) = req.ts_start {
+        sql_vals.push(Value::from(ts_start));
+        where_fragments.push(format!("ts >= ${}", sql_vals.len()));
+    }
+    if let Some(ts_end) = req.ts_end {
+        sql_vals.push(Value::from(ts_end));
+        where_fragments.push(format!("ts <= ${}", sql_vals.len()));
+    }
+    let range = conn
+        .query_one(
+            &format!("SELECT min(chain_seq) AS min_seq, max(chain_seq) AS max_seq FROM {table_name} WHERE {}", where_fragments.join(" AND ")),
+            sql_vals,
+        )
+        .await?;
+    let (min_seq, max_seq) = match range {
+        Some(range) => (range.try_get::<Option<i64>>("", "min_seq")?, range.try_get::<Option<i64>>("", "max_seq")?),
+        None => (None, None),
+    };
+    let Some(min_seq) = min_seq else {
+        return Ok(LogIntegrityVerifyResp::default());
+    };
+    let max_seq = if req.ts_end.is_none() { head_seq } else { max_seq.unwrap_or(min_seq) };
+
+    let pub_key = get_private_key(funs)?.map(|pri_key| TardisFuns::crypto.sm2.new_public_key(&pri_key)).transpose()?;
+    let mut checkpoints = conn
+        .query_all(
+            &format!("SELECT seq, hash, sign FROM {checkpoint_table_name} WHERE tag = $1 AND seq >= $2 AND seq <= $3"),
+            vec![Value::from(req.tag.as_str()), Value::from(min_seq), Value::from(max_seq)],
+        )
+        .await?
+        .into_iter()
+        .map(|item| Ok((item.try_get::<i64>("", "seq")?, (item.try_get::<String>("", "hash")?, item.try_get::<String>("", "sign")?))))
+        .collect::<TardisResult<HashMap<_, _>>>()?;
+
+    let mut resp = LogIntegrityVerifyResp::default();
+    let mut expected_seq = min_seq;
+    let mut prev_hash: Option<String> = None;
+    while expected_seq <= max_seq {
+        let result = conn
+            .query_all(
+                &format!(
+                    r#"SELECT chain_seq, prev_hash, hash, ts, kind, key, op, content, owner, own_paths, ext, rel_key
+FROM {table_name}
+WHERE chain_seq >= $1 AND chain_seq <= $2
+ORDER BY chain_seq
+LIMIT {VERIFY_BATCH_SIZE}"#
+                ),
+                vec![Value::from(expected_seq), Value::from(max_seq)],
+            )
+            .await?;
+        if result.is_empty() {
+            break;
+        }
+        for row in result {
+            let seq: i64 = row.try_get("", "chain_seq")?;
+            let key: String = row.try_get("", "key")?;
+            let ts: DateTime<Utc> = row.try_get("", "ts")?;
+            let broken = |reason: &str| LogIntegrityBrokenResp {
+                seq: seq as u64,
+                key: Some(key.clone()),
+                ts: Some(ts),
+                reason: reason.to_string(),
+            };
+            if seq < expected_seq {
+                resp.broken = Some(broken("The item is duplicated"));
+                return Ok(resp);
+            }
+            if seq > expected_seq {
+                resp.broken = Some(missing(expected_seq));
+                return Ok(resp);
+            }
+            let row_prev_hash: String = row.try_get("", "prev_hash")?;
+            let row_hash: String = row.try_get("", "hash")?;
+            if prev_hash.as_ref().map(|prev_hash| prev_hash != &row_prev_hash).unwrap_or(false) {
+                resp.broken = Some(broken("The previous hash does not match the hash of the previous item"));
+                return Ok(resp);
+            }
+            let ext: serde_json::Value = row.try_get("", "ext")?;
+            let item = IntegrityItem {
+                ts,
+                kind: &row.try_get::<String>("", "kind")?,
+                key: &key,
+                op: &row.try_get::<String>("", "op")?,
+                content: &row.try_get::<String>("", "content")?,
+                owner: &row.try_get::<String>("", "owner")?,
+                own_paths: &row.try_get::<String>("", "own_paths")?,
+                ext: &ext,
+                rel_key: &row.try_get::<String>("", "rel_key")?,
+            };
+            if digest(&row_prev_hash, seq, &item)? != row_hash {
+                resp.broken = Some(broken("The content of the item has been modified"));
+                return Ok(resp);
+            }
+            if let Some((checkpoint_hash, checkpoint_sign)) = checkpoints.remove(&seq) {
+                if checkpoint_hash != row_hash {
+                    resp.broken = Some(broken("The item does not match the signed checkpoint"));
+                    return Ok(resp);
+                }
+                match &pub_key {
+                    Some(pub_key) => {
+                        if !pub_key.verify(&checkpoint_sign_data(&req.tag, seq, &checkpoint_hash), &checkpoint_sign).unwrap_or(false) {
+                            resp.broken = Some(broken("The signature of the checkpoint is invalid"));
+                            return Ok(resp);
+                        }
+                        resp.verified_checkpoints += 1;
+                    }
+                    // The signature can't be verified without the key, so the checkpoint proves nothing
+                    None => resp.unverifiable_checkpoints += 1,
+                }
+            }
+            prev_hash = Some(row_hash);
+            expected_seq += 1;
+            resp.verified_items += 1;
+        }
+    }
+    if expected_seq <= max_seq {
+        resp.broken = Some(missing(expected_seq));
+    }
+    Ok(resp)
+}
+
+fn missing(seq: i64) -> LogIntegrityBrokenResp {
+    LogIntegrityBrokenResp {
+        seq: seq as u64,
+        key: None,
+        ts: None,
+        reason: "The item is missing".to_string(),
+    }
+}
@@ -1,59 +1,479 @@
+use std::collections::HashMap;
+
 use bios_basic::{basic_enumeration::BasicQueryOpKind, dto::BasicQueryCondInfo, helper::db_helper, spi::spi_funs::SpiBsInst};
 use tardis::{
-    basic::{dto::TardisContext, result::TardisResult},
-    db::{reldb_client::TardisRelDBClient, sea_orm::Value},
+    basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    chrono::{DateTime, Timelike, Utc},
+    db::{
+        reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
+        sea_orm::{QueryResult, Value},
+    },
+    futures::{
+        stream::{self, BoxStream},
+        StreamExt,
+    },
+    log::warn,
+    serde_json, tokio,
     web::web_resp::TardisPage,
     TardisFuns, TardisFunsInst,
 };
 
-use crate::dto::log_item_dto::{LogItemAddReq, LogItemFindReq, LogItemFindResp};
+use crate::{
+    dto::log_item_dto::{
+        LogItemAddReq, LogItemAggReq, LogItemAggResp, LogItemBatchAddErrorResp, LogItemExportFormatKind, LogItemExportReq, LogItemFilterReq, LogItemFindReq,
+        LogItemFindResp,
+    },
+    log_config::LogConfig,
+};
+
+use super::{
+    log_pg_initializer,
+    log_pg_integrity_serv::{self, IntegrityItem},
+    log_pg_tail_serv,
+};
+
+// Rows per insert statement, PG allows up to 65535 parameters
+const INSERT_BATCH_SIZE: usize = 1000;
+const EXPORT_CURSOR: &str = "log_export_cursor";
+const EXPORT_BATCH_SIZE: usize = 1000;
+const EXPORT_COLUMNS: [&str; 9] = ["ts", "kind", "key", "op", "owner", "own_paths", "rel_key", "content", "ext"];
+
+pub async fn add(add_req: &mut LogItemAddReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let item = package_add_item(add_req)?;
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (mut conn, table_name) = log_pg_initializer::init_table_and_conn(bs_inst, &add_req.tag, funs, ctx, true).await?;
+    log_pg_initializer::ensure_partition_by_ts(&conn, &table_name, item.ts).await?;
+    conn.begin().await?;
+    insert_items(&conn, &add_req.tag, &table_name, std::slice::from_ref(&item), funs, ctx, inst).await?;
+    conn.commit().await?;
+    log_pg_tail_serv::broadcast(&table_name, item).await;
+    Ok(())
+}
+
+/// Add items in a batch, items of the same tag are inserted in one transaction.
+///
+/// The errors are returned by the index of the item, a failed tag doesn't affect others.
+pub async fn add_items(
+    add_reqs: &mut [(u32, LogItemAddReq)],
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<Vec<LogItemBatchAddErrorResp>> {
+    let mut items_by_tag: Vec<(String, Vec<(u32, LogItemFindResp)>)> = Vec::new();
+    let mut errors = Vec::new();
+    for (index, add_req) in add_reqs.iter_mut() {
+        let item = match package_add_item(add_req) {
+            Ok(item) => item,
+            Err(e) => {
+                errors.push(LogItemBatchAddErrorResp::new(*index, &e));
+                continue;
+            }
+        };
+        match items_by_tag.iter_mut().find(|(tag, _)| tag == &add_req.tag) {
+            Some((_, items)) => items.push((*index, item)),
+            None => items_by_tag.push((add_req.tag.clone(), vec![(*index, item)])),
+        }
+    }
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    for (tag, items) in items_by_tag {
+        let (indexes, items): (Vec<_>, Vec<_>) = items.into_iter().unzip();
+        let result = async {
+            let (mut conn, table_name) = log_pg_initializer::init_table_and_conn(bs_inst, &tag, funs, ctx, true).await?;
+            for item in &items {
+                log_pg_initializer::ensure_partition_by_ts(&conn, &table_name, item.ts).await?;
+            }
+            conn.begin().await?;
+            insert_items(&conn, &tag, &table_name, &items, funs, ctx, inst).await?;
+            conn.commit().await?;
+            Ok::<_, TardisError>(table_name)
+        }
+        .await;
+        match result {
+            Ok(table_name) => {
+                for item in items {
+                    log_pg_tail_serv::broadcast(&table_name, item).await;
+                }
+            }
+            Err(e) => errors.extend(indexes.into_iter().map(|index| LogItemBatchAddErrorResp::new(index, &e))),
+        }
+    }
+    errors.sort_by_key(|error| error.index);
+    Ok(errors)
+}
+
+fn package_add_item(add_req: &mut LogItemAddReq) -> TardisResult<LogItemFindResp> {
+    // The partition is determined by ts, so it can't be left to the default value of the table.
+    // Truncated to the precision of PG so that the hash of the integrity chain can be recomputed from the stored value
+    let ts = *add_req.ts.get_or_insert_with(Utc::now);
+    let ts = ts.with_nanosecond(ts.nanosecond() / 1000 * 1000).unwrap_or(ts);
+    add_req.ts = Some(ts);
+    Ok(LogItemFindResp {
+        content: add_req.content.clone(),
+        kind: add_req.kind.as_ref().map(|kind| kind.to_string()).unwrap_or_default(),
+        ext: if let Some(ext) = &add_req.ext { ext.clone() } else { TardisFuns::json.str_to_json("{}")? },
+        owner: add_req.owner.clone().unwrap_or_default(),
+        own_paths: add_req.own_paths.clone().unwrap_or_default(),
+        key: add_req.key.as_ref().map(|key| key.to_string()).unwrap_or_default(),
+        op: add_req.op.clone().unwrap_or_default(),
+        rel_key: add_req.rel_key.as_ref().map(|rel_key| rel_key.to_string()).unwrap_or_default(),
+        ts,
+        highlight: None,
+    })
+}
+
+/// Append the items to the integrity chain and insert them with multi-row inserts, must be called in a transaction
+async fn insert_items(
+    conn: &TardisRelDBlConnection,
+    tag: &str,
+    table_name: &str,
+    items: &[LogItemFindResp],
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<()> {
+    let text_search_config = funs.conf::<LogConfig>().text_search_config.clone();
+    let integrity_items = items
+        .iter()
+        .map(|item| IntegrityItem {
+            ts: item.ts,
+            kind: &item.kind,
+            key: &item.key,
+            op: &item.op,
+            content: &item.content,
+            owner: &item.owner,
+            own_paths: &item.own_paths,
+            ext: &item.ext,
+            rel_key: &item.rel_key,
+        })
+        .collect::<Vec<_>>();
+    let mut links = log_pg_integrity_serv::append(conn, tag, &integrity_items, funs, ctx, inst).await?.map(|links| links.into_iter());
+    // Locates the items in the notifications of live tail
+    let ids = items.iter().map(|_| TardisFuns::field.nanoid()).collect::<Vec<_>>();
+    for (chunk, chunk_ids) in items.chunks(INSERT_BATCH_SIZE).zip(ids.chunks(INSERT_BATCH_SIZE)) {
+        let mut values_fragments = Vec::with_capacity(chunk.len());
+        let mut sql_vals = Vec::with_capacity(chunk.len() * 13);
+        for (item, id) in chunk.iter().zip(chunk_ids) {
+            let link = links.as_mut().and_then(|links| links.next());
+            let n = sql_vals.len();
+            values_fragments.push(format!(
+                "(${}, ${}, ${}, ${}, to_tsvector('{text_search_config}', ${}), ${}, ${}, ${}, ${}, ${}, ${}, ${}, ${}, ${})",
+                n + 1,
+                n + 2,
+                n + 3,
+                n + 4,
+                n + 4,
+                n + 5,
+                n + 6,
+                n + 7,
+                n + 8,
+                n + 9,
+                n + 10,
+                n + 11,
+                n + 12,
+                n + 13
+            ));
+            sql_vals.extend([
+                Value::from(item.kind.as_str()),
+                Value::from(item.key.as_str()),
+                Value::from(item.op.as_str()),
+                Value::from(item.content.as_str()),
+                Value::from(item.owner.as_str()),
+                Value::from(item.own_paths.as_str()),
+                Value::from(item.ext.clone()),
+                Value::from(item.rel_key.as_str()),
+                Value::from(item.ts),
+                Value::from(link.as_ref().map(|link| link.seq)),
+                Value::from(link.as_ref().map(|link| link.prev_hash.clone())),
+                Value::from(link.map(|link| link.hash)),
+                Value::from(id.as_str()),
+            ]);
+        }
+        conn.execute_one(
+            &format!(
+                r#"INSERT INTO {table_name} 
+    (kind, key, op, content, content_tsv, owner, own_paths, ext, rel_key, ts, chain_seq, prev_hash, hash, id)
+VALUES
+    {}
+	"#,
+                values_fragments.join(",\n    ")
+            ),
+            sql_vals,
+        )
+        .await?;
+    }
+    log_pg_tail_serv::notify(conn, table_name, &ids, items, funs).await
+}
+
+pub async fn find(find_req: &mut LogItemFindReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<TardisPage<LogItemFindResp>> {
+    let filter = &find_req.filter;
+    let (mut where_fragments, mut sql_vals) = package_filter(filter, funs)?;
+    let mut select_fragments = "".to_string();
+    let mut order_fragments = "ts DESC".to_string();
+    if let Some(query) = package_full_text_query(filter, &mut sql_vals, funs) {
+        // The content is escaped before being highlighted, so only the `<em>` tags of the highlight are HTML
+        let escaped_content = "replace(replace(replace(content, '&', '&amp;'), '<', '&lt;'), '>', '&gt;')";
+        select_fragments = format!(
+            ", ts_headline('{}', {escaped_content}, {query}, 'StartSel=<em>, StopSel=</em>, MaxFragments=3, MaxWords=20, MinWords=5') AS highlight",
+            funs.conf::<LogConfig>().text_search_config
+        );
+        where_fragments.push(format!("content_tsv @@ {query}"));
+        order_fragments = format!("ts_rank(content_tsv, {query}) DESC, ts DESC");
+    }
+    if where_fragments.is_empty() {
+        where_fragments.push("1 = 1".to_string());
+    }
+
+    sql_vals.push(Value::from(find_req.page_size));
+    sql_vals.push(Value::from((find_req.page_number - 1) * find_req.page_size as u32));
+    let page_fragments = format!("LIMIT ${} OFFSET ${}", sql_vals.len() - 1, sql_vals.len());
+
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, table_name) = log_pg_initializer::init_table_and_conn(bs_inst, &filter.tag, funs, ctx, false).await?;
+    let result = conn
+        .query_all(
+            format!(
+                r#"SELECT ts, key, op, content, kind, ext, owner, own_paths, rel_key, count(*) OVER() AS total{select_fragments}
+FROM {table_name}
+WHERE 
+    {}
+ORDER BY {order_fragments}
+{}"#,
+                where_fragments.join(" AND "),
+                page_fragments
+            )
+            .as_str(),
+            sql_vals,
+        )
+        .await?;
+
+    let mut total_size: i64 = 0;
+
+    let result = result
+        .into_iter()
+        .map(|item| {
+            if total_size == 0 {
+                total_size = item.try_get("", "total")?;
+            }
+            Ok(LogItemFindResp {
+                ts: item.try_get("", "ts")?,
+                key: item.try_get("", "key")?,
+                op: item.try_get("", "op")?,
+                ext: item.try_get("", "ext")?,
+                content: item.try_get("", "content")?,
+                rel_key: item.try_get("", "rel_key")?,
+                kind: item.try_get("", "kind")?,
+                owner: item.try_get("", "owner")?,
+                own_paths: item.try_get("", "own_paths")?,
+                highlight: if select_fragments.is_empty() { None } else { item.try_get("", "highlight")? },
+            })
+        })
+        .collect::<TardisResult<Vec<_>>>()?;
+
+    Ok(TardisPage {
+        page_size: find_req.page_size as u64,
+        page_number: find_req.page_number as u64,
+        total_size: total_size as u64,
+        records: result,
+    })
+}
 
-use super::log_pg_initializer;
+pub async fn agg(agg_req: &mut LogItemAggReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<LogItemAggResp>> {
+    let (mut where_fragments, mut sql_vals) = package_filter(&agg_req.filter, funs)?;
+    if let Some(query) = package_full_text_query(&agg_req.filter, &mut sql_vals, funs) {
+        where_fragments.push(format!("content_tsv @@ {query}"));
+    }
+    if where_fragments.is_empty() {
+        where_fragments.push("1 = 1".to_string());
+    }
 
-pub async fn add(add_req: &mut LogItemAddReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
-    let mut params = vec![
-        Value::from(add_req.kind.as_ref().unwrap_or(&"".into()).to_string()),
-        Value::from(add_req.key.as_ref().unwrap_or(&"".into()).to_string()),
-        Value::from(add_req.op.as_ref().unwrap_or(&"".to_string()).as_str()),
-        Value::from(add_req.content.as_str()),
-        Value::from(add_req.owner.as_ref().unwrap_or(&"".to_string()).as_str()),
-        Value::from(add_req.own_paths.as_ref().unwrap_or(&"".to_string()).as_str()),
-        Value::from(if let Some(ext) = &add_req.ext {
-            ext.clone()
-        } else {
-            TardisFuns::json.str_to_json("{}")?
-        }),
-        Value::from(add_req.rel_key.as_ref().unwrap_or(&"".into()).to_string()),
-    ];
-    if let Some(ts) = add_req.ts {
-        params.push(Value::from(ts));
+    let mut select_fragments = Vec::new();
+    let mut order_fragments = Vec::new();
+    if let Some(interval) = &agg_req.interval {
+        select_fragments.push(format!("date_trunc('{}', ts AT TIME ZONE 'UTC') AT TIME ZONE 'UTC' AS agg_ts", interval.to_sql()));
+        order_fragments.push("agg_ts ASC".to_string());
+    }
+    let group_by = agg_req.group_by.clone().unwrap_or_default();
+    for (idx, field) in group_by.iter().enumerate() {
+        select_fragments.push(format!("{} AS agg_group_{idx}", package_agg_field(field, funs)?));
     }
+    let group_fragments = (1..=select_fragments.len()).map(|idx| idx.to_string()).collect::<Vec<String>>().join(", ");
+    order_fragments.push("agg_count DESC".to_string());
+    select_fragments.push("count(*) AS agg_count".to_string());
+    sql_vals.push(Value::from(agg_req.limit.unwrap_or(1000)));
 
     let bs_inst = inst.inst::<TardisRelDBClient>();
-    let (mut conn, table_name) = log_pg_initializer::init_table_and_conn(bs_inst, &add_req.tag, ctx, true).await?;
+    let (conn, table_name) = log_pg_initializer::init_table_and_conn(bs_inst, &agg_req.filter.tag, funs, ctx, false).await?;
+    let result = conn
+        .query_all(
+            &format!(
+                r#"SELECT {}
+FROM {table_name}
+WHERE 
+    {}
+{}
+ORDER BY {}
+LIMIT ${}"#,
+                select_fragments.join(", "),
+                where_fragments.join(" AND "),
+                if group_fragments.is_empty() { "".to_string() } else { format!("GROUP BY {group_fragments}") },
+                order_fragments.join(", "),
+                sql_vals.len()
+            ),
+            sql_vals,
+        )
+        .await?;
+    result
+        .into_iter()
+        .map(|item| {
+            let count: i64 = item.try_get("", "agg_count")?;
+            Ok(LogItemAggResp {
+                ts: if agg_req.interval.is_some() { Some(item.try_get("", "agg_ts")?) } else { None },
+                group: group_by
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, field)| Ok((field.clone(), item.try_get("", &format!("agg_group_{idx}"))?)))
+                    .collect::<TardisResult<HashMap<_, _>>>()?,
+                count: count as u64,
+            })
+        })
+        .collect()
+}
+
+/// Export the items as a stream of chunks in the requested format, ordered by time.
+///
+/// The items are read through a cursor, so that the memory usage does not grow with the number of items.
+pub async fn export(
+    export_req: &LogItemExportReq,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<BoxStream<'static, TardisResult<String>>> {
+    let (mut where_fragments, mut sql_vals) = package_filter(&export_req.filter, funs)?;
+    if let Some(query) = package_full_text_query(&export_req.filter, &mut sql_vals, funs) {
+        where_fragments.push(format!("content_tsv @@ {query}"));
+    }
+    if where_fragments.is_empty() {
+        where_fragments.push("1 = 1".to_string());
+    }
+
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (mut conn, table_name) = log_pg_initializer::init_table_and_conn(bs_inst, &export_req.filter.tag, funs, ctx, false).await?;
+    // The cursor only lives in the transaction
     conn.begin().await?;
+    // A consumer that stops reading without dropping the stream must not hold the transaction forever
     conn.execute_one(
         &format!(
-            r#"INSERT INTO {table_name} 
-    (kind, key, op, content, owner, own_paths, ext, rel_key{})
-VALUES
-    ($1, $2, $3, $4, $5, $6, $7,$8{})
-	"#,
-            if add_req.ts.is_some() { ", ts" } else { "" },
-            if add_req.ts.is_some() { ", $9" } else { "" },
+            "SET LOCAL idle_in_transaction_session_timeout = {}",
+            funs.conf::<LogConfig>().export_idle_timeout_secs as u64 * 1000
         ),
-        params,
+        vec![],
     )
     .await?;
-    conn.commit().await?;
-    Ok(())
+    conn.execute_one(
+        &format!(
+            r#"DECLARE {EXPORT_CURSOR} NO SCROLL CURSOR FOR
+SELECT ts, key, op, content, kind, ext, owner, own_paths, rel_key
+FROM {table_name}
+WHERE 
+    {}
+ORDER BY ts DESC"#,
+            where_fragments.join(" AND ")
+        ),
+        sql_vals,
+    )
+    .await?;
+
+    let format = export_req.format;
+    let header = match format {
+        LogItemExportFormatKind::Csv => Some(format!("{}\n", EXPORT_COLUMNS.join(","))),
+        LogItemExportFormatKind::Jsonl => None,
+    };
+    let rows = stream::unfold(Some(ExportCursorConn(Some(conn))), move |cursor_conn| async move {
+        let mut cursor_conn = cursor_conn?;
+        let conn = cursor_conn.0.as_ref()?;
+        match conn.query_all(&format!("FETCH {EXPORT_BATCH_SIZE} FROM {EXPORT_CURSOR}"), vec![]).await {
+            Ok(result) if result.is_empty() => match cursor_conn.0.take()?.commit().await {
+                Ok(_) => None,
+                Err(e) => Some((Err(e), None)),
+            },
+            Ok(result) => {
+                let chunk = result.into_iter().map(|item| package_export_line(&item, format)).collect::<TardisResult<Vec<_>>>().map(|lines| lines.concat());
+                Some((chunk, Some(cursor_conn)))
+            }
+            // The transaction is rolled back when the connection is dropped
+            Err(e) => Some((Err(e), None)),
+        }
+    });
+    Ok(stream::iter(header.map(Ok)).chain(rows).boxed())
 }
 
-pub async fn find(find_req: &mut LogItemFindReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<TardisPage<LogItemFindResp>> {
+/// Connection holding the transaction of the export cursor.
+///
+/// It is rolled back in the background if the stream is dropped before finishing, e.g. the client disconnects.
+struct ExportCursorConn(Option<TardisRelDBlConnection>);
+
+impl Drop for ExportCursorConn {
+    fn drop(&mut self) {
+        if let Some(conn) = self.0.take() {
+            tokio::spawn(async move {
+                if let Err(e) = conn.rollback().await {
+                    warn!("[SPI-Log] Failed to roll back the transaction of the export cursor: {e:?}");
+                }
+            });
+        }
+    }
+}
+
+fn package_export_line(item: &QueryResult, format: LogItemExportFormatKind) -> TardisResult<String> {
+    let ts: DateTime<Utc> = item.try_get("", "ts")?;
+    let ext: serde_json::Value = item.try_get("", "ext")?;
+    let mut fields = vec![ts.to_rfc3339()];
+    for column in &EXPORT_COLUMNS[1..EXPORT_COLUMNS.len() - 1] {
+        fields.push(item.try_get("", column)?);
+    }
+    match format {
+        LogItemExportFormatKind::Csv => {
+            fields.push(ext.to_string());
+            Ok(format!("{}\n", fields.iter().map(|field| escape_csv(field)).collect::<Vec<_>>().join(",")))
+        }
+        LogItemExportFormatKind::Jsonl => {
+            let mut line = EXPORT_COLUMNS
+                .iter()
+                .zip(fields.into_iter().map(serde_json::Value::from))
+                .map(|(column, value)| (column.to_string(), value))
+                .collect::<serde_json::Map<_, _>>();
+            line.insert("ext".to_string(), ext);
+            Ok(format!("{}\n", serde_json::Value::Object(line)))
+        }
+    }
+}
+
+fn escape_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Convert the field to group by into the SQL expression
+fn package_agg_field(field: &str, funs: &TardisFunsInst) -> TardisResult<String> {
+    if let Some(path) = field.strip_prefix("ext.") {
+        // The path is embedded into SQL, so only simple names are allowed
+        if path.split('.').all(|item| !item.is_empty() && item.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')) {
+            return Ok(format!("ext #>> '{{{}}}'", path.replace('.', ",")));
+        }
+    } else if ["kind", "op", "owner", "key", "rel_key", "own_paths"].contains(&field) {
+        return Ok(field.to_string());
+    }
+    Err(funs.err().bad_request("item", "agg", &format!("The field [{field}] can't be grouped by"), "400-spi-log-agg-field-illegal"))
+}
+
+fn package_filter(filter: &LogItemFilterReq, funs: &TardisFunsInst) -> TardisResult<(Vec<String>, Vec<Value>)> {
     let mut where_fragments: Vec<String> = Vec::new();
     let mut sql_vals: Vec<Value> = vec![];
 
-    if let Some(kinds) = &find_req.kinds {
+    if let Some(kinds) = &filter.kinds {
         let place_holder = kinds
             .iter()
             .map(|kind| {
@@ -64,7 +484,7 @@ pub async fn find(find_req: &mut LogItemFindReq, funs: &TardisFunsInst, ctx: &Ta
             .join(",");
         where_fragments.push(format!("kind IN ({place_holder})"));
     }
-    if let Some(owners) = &find_req.owners {
+    if let Some(owners) = &filter.owners {
         let place_holder = owners
             .iter()
             .map(|owner| {
@@ -75,7 +495,7 @@ pub async fn find(find_req: &mut LogItemFindReq, funs: &TardisFunsInst, ctx: &Ta
             .join(",");
         where_fragments.push(format!("owner IN ({place_holder})"));
     }
-    if let Some(keys) = &find_req.keys {
+    if let Some(keys) = &filter.keys {
         let place_holder = keys
             .iter()
             .map(|key| {
@@ -86,7 +506,7 @@ pub async fn find(find_req: &mut LogItemFindReq, funs: &TardisFunsInst, ctx: &Ta
             .join(",");
         where_fragments.push(format!("key IN ({place_holder})"));
     }
-    if let Some(ops) = &find_req.ops {
+    if let Some(ops) = &filter.ops {
         let place_holder = ops
             .iter()
             .map(|op| {
@@ -97,7 +517,7 @@ pub async fn find(find_req: &mut LogItemFindReq, funs: &TardisFunsInst, ctx: &Ta
             .join(",");
         where_fragments.push(format!("op IN ({place_holder})"));
     }
-    if let Some(rel_keys) = &find_req.rel_keys {
+    if let Some(rel_keys) = &filter.rel_keys {
         let place_holder = rel_keys
             .iter()
             .map(|rel_key| {
@@ -108,15 +528,15 @@ pub async fn find(find_req: &mut LogItemFindReq, funs: &TardisFunsInst, ctx: &Ta
             .join(",");
         where_fragments.push(format!("rel_key IN ({place_holder})"));
     }
-    if let Some(own_paths) = &find_req.own_paths {
+    if let Some(own_paths) = &filter.own_paths {
         sql_vals.push(Value::from(format!("{}%", own_paths)));
         where_fragments.push(format!("own_paths like ${}", sql_vals.len()));
     }
-    if let Some(ts_start) = find_req.ts_start {
+    if let Some(ts_start) = filter.ts_start {
         sql_vals.push(Value::from(ts_start));
         where_fragments.push(format!("ts >= ${}", sql_vals.len()));
     }
-    if let Some(ts_end) = find_req.ts_end {
+    if let Some(ts_end) = filter.ts_end {
         sql_vals.push(Value::from(ts_end));
         where_fragments.push(format!("ts <= ${}", sql_vals.len()));
     }
@@ -129,7 +549,7 @@ pub async fn find(find_req: &mut LogItemFindReq, funs: &TardisFunsInst, ctx: &Ta
         ))
     };
     let err_op_in_without_value = || Err(funs.err().bad_request("item", "log", "Request item using 'IN' operator show hava a value", "400-spi-item-op-in-without-value"));
-    if let Some(ext) = &find_req.ext {
+    if let Some(ext) = &filter.ext {
         for ext_item in ext {
             let value = db_helper::json_to_sea_orm_value(&ext_item.value, ext_item.op == BasicQueryOpKind::Like);
             let Some(mut value) = value else {
@@ -185,7 +605,7 @@ pub async fn find(find_req: &mut LogItemFindReq, funs: &TardisFunsInst, ctx: &Ta
             }
         }
     }
-    if let Some(ext_or) = &find_req.ext_or {
+    if let Some(ext_or) = &filter.ext_or {
         let mut or_fragments = vec![];
         for ext_or_item in ext_or {
             let value = db_helper::json_to_sea_orm_value(&ext_or_item.value, ext_or_item.op == BasicQueryOpKind::Like);
@@ -249,59 +669,12 @@ pub async fn find(find_req: &mut LogItemFindReq, funs: &TardisFunsInst, ctx: &Ta
         }
         where_fragments.push(format!(" ( {} ) ", or_fragments.join(" OR ")));
     }
-    if where_fragments.is_empty() {
-        where_fragments.push("1 = 1".to_string());
-    }
-
-    sql_vals.push(Value::from(find_req.page_size));
-    sql_vals.push(Value::from((find_req.page_number - 1) * find_req.page_size as u32));
-    let page_fragments = format!("LIMIT ${} OFFSET ${}", sql_vals.len() - 1, sql_vals.len());
-
-    let bs_inst = inst.inst::<TardisRelDBClient>();
-    let (conn, table_name) = log_pg_initializer::init_table_and_conn(bs_inst, &find_req.tag, ctx, false).await?;
-    let result = conn
-        .query_all(
-            format!(
-                r#"SELECT ts, key, op, content, kind, ext, owner, own_paths, rel_key, count(*) OVER() AS total
-FROM {table_name}
-WHERE 
-    {}
-ORDER BY ts DESC
-{}"#,
-                where_fragments.join(" AND "),
-                page_fragments
-            )
-            .as_str(),
-            sql_vals,
-        )
-        .await?;
-
-    let mut total_size: i64 = 0;
-
-    let result = result
-        .into_iter()
-        .map(|item| {
-            if total_size == 0 {
-                total_size = item.try_get("", "total")?;
-            }
-            Ok(LogItemFindResp {
-                ts: item.try_get("", "ts")?,
-                key: item.try_get("", "key")?,
-                op: item.try_get("", "op")?,
-                ext: item.try_get("", "ext")?,
-                content: item.try_get("", "content")?,
-                rel_key: item.try_get("", "rel_key")?,
-                kind: item.try_get("", "kind")?,
-                owner: item.try_get("", "owner")?,
-                own_paths: item.try_get("", "own_paths")?,
-            })
-        })
-        .collect::<TardisResult<Vec<_>>>()?;
+    Ok((where_fragments, sql_vals))
+}
 
-    Ok(TardisPage {
-        page_size: find_req.page_size as u64,
-        page_number: find_req.page_number as u64,
-        total_size: total_size as u64,
-        records: result,
-    })
+/// Return the tsquery expression of the full-text search if `q` is specified
+fn package_full_text_query(filter: &LogItemFilterReq, sql_vals: &mut Vec<Value>, funs: &TardisFunsInst) -> Option<String> {
+    let q = filter.q.as_ref().map(|q| q.trim()).filter(|q| !q.is_empty())?;
+    sql_vals.push(Value::from(q));
+    Some(format!("websearch_to_tsquery('{}', ${})", funs.conf::<LogConfig>().text_search_config, sql_vals.len()))
 }
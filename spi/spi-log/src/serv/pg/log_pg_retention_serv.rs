@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+
+use bios_basic::spi::{
+    spi_constants::GLOBAL_STORAGE_FLAG,
+    spi_funs::{SpiBsInst, TypedSpiBsInst},
+    spi_initializer::common_pg,
+};
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    chrono::{DateTime, Duration, TimeZone, Utc},
+    db::{
+        reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
+        sea_orm::Value,
+    },
+    log::info,
+    TardisFunsInst,
+};
+
+use crate::dto::log_retention_dto::{LogRetentionAddOrModifyReq, LogRetentionPurgeResp, LogRetentionResp};
+
+use super::log_pg_initializer;
+
+// The policy for all tags is stored with this tag
+const ALL_TAGS_FLAG: &str = "*";
+const BYTES_PER_MB: i64 = 1024 * 1024;
+
+struct LogPartition {
+    table_name: String,
+    partition_name: String,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    size: i64,
+}
+
+async fn init_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, ctx: &TardisContext) -> TardisResult<(TardisRelDBlConnection, String)> {
+    // Not named `log_*` to avoid conflict with the tables of tags
+    common_pg::init_table_and_conn(
+        bs_inst,
+        ctx,
+        true,
+        None,
+        "retention_log",
+        r#"tag character varying NOT NULL,
+    max_days integer NOT NULL,
+    max_size_mb bigint NOT NULL,
+    update_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP"#,
+        vec![],
+        Some(vec!["tag"]),
+        Some("update_time"),
+    )
+    .await
+}
+
+async fn find_policies(conn: &TardisRelDBlConnection, table_name: &str) -> TardisResult<Vec<LogRetentionResp>> {
+    let result = conn.query_all(&format!("SELECT tag, max_days, max_size_mb, update_time FROM {table_name} ORDER BY tag"), vec![]).await?;
+    result
+        .into_iter()
+        .map(|item| {
+            let tag: String = item.try_get("", "tag")?;
+            let max_days: i32 = item.try_get("", "max_days")?;
+            let max_size_mb: i64 = item.try_get("", "max_size_mb")?;
+            Ok(LogRetentionResp {
+                tag: if tag == ALL_TAGS_FLAG { None } else { Some(tag) },
+                max_days: max_days as u32,
+                max_size_mb: max_size_mb as u64,
+                update_time: item.try_get("", "update_time")?,
+            })
+        })
+        .collect()
+}
+
+pub async fn add_or_modify(req: &LogRetentionAddOrModifyReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (mut conn, table_name) = init_table_and_conn(bs_inst, ctx).await?;
+    let tag = req.tag.as_deref().unwrap_or(ALL_TAGS_FLAG);
+    let existing = find_policies(&conn, &table_name).await?.into_iter().find(|policy| policy.tag.as_deref().unwrap_or(ALL_TAGS_FLAG) == tag);
+    let max_days = req.max_days.or_else(|| existing.as_ref().map(|policy| policy.max_days)).unwrap_or(0);
+    let max_size_mb = req.max_size_mb.or_else(|| existing.as_ref().map(|policy| policy.max_size_mb)).unwrap_or(0);
+    conn.begin().await?;
+    conn.execute_one(
+        &format!(
+            r#"INSERT INTO {table_name} (tag, max_days, max_size_mb)
+VALUES ($1, $2, $3)
+ON CONFLICT (tag) DO UPDATE SET max_days = EXCLUDED.max_days, max_size_mb = EXCLUDED.max_size_mb"#
+        ),
+        vec![Value::from(tag), Value::from(max_days as i32), Value::from(max_size_mb as i64)],
+    )
+    .await?;
+    conn.commit().await?;
+    Ok(())
+}
+
+pub async fn find(_funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<LogRetentionResp>> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, table_name) = init_table_and_conn(bs_inst, ctx).await?;
+    find_policies(&conn, &table_name).await
+}
+
+pub async fn delete(tag: Option<String>, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (mut conn, table_name) = init_table_and_conn(bs_inst, ctx).await?;
+    conn.begin().await?;
+    conn.execute_one(&format!("DELETE FROM {table_name} WHERE tag = $1"), vec![Value::from(tag.as_deref().unwrap_or(ALL_TAGS_FLAG))]).await?;
+    conn.commit().await?;
+    Ok(())
+}
+
+/// Apply the retention policies of all tags, and create the partitions of the following months.
+///
+/// Expired partitions are dropped as a whole, the remaining expired rows are deleted.
+/// When the size limit is exceeded, the oldest partitions are dropped, except the partition of the current month.
+pub async fn purge(funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<LogRetentionPurgeResp> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let schema_name = common_pg::get_schema_name_from_ext(bs_inst.1)
+        .ok_or_else(|| funs.err().internal_error("retention", "purge", "The schema of backend service is not found", "500-spi-log-schema-not-found"))?;
+    let (conn, policy_table_name) = init_table_and_conn(bs_inst, ctx).await?;
+    let policies = find_policies(&conn, &policy_table_name)
+        .await?
+        .into_iter()
+        .map(|policy| (policy.tag.clone().unwrap_or_else(|| ALL_TAGS_FLAG.to_string()), policy))
+        .collect::<HashMap<_, _>>();
+    let default_policy = policies.get(ALL_TAGS_FLAG);
+
+    let now = Utc::now();
+    let mut resp = LogRetentionPurgeResp::default();
+    let mut all_partitions = Vec::new();
+    for (tag, table_name) in find_log_tables(&conn, &schema_name).await? {
+        let policy = policies.get(&tag);
+        let max_days = policy
+            .map(|policy| policy.max_days)
+            .filter(|max_days| *max_days > 0)
+            .or_else(|| default_policy.map(|policy| policy.max_days).filter(|max_days| *max_days > 0));
+        let cutoff = max_days.map(|max_days| now - Duration::days(max_days as i64));
+        if !log_pg_initializer::is_partitioned(&conn, &table_name).await? {
+            if let Some(cutoff) = cutoff {
+                resp.deleted_rows += conn.execute_one(&format!("DELETE FROM {table_name} WHERE ts < $1"), vec![Value::from(cutoff)]).await?.rows_affected();
+            }
+            continue;
+        }
+        log_pg_initializer::premake_partitions(&conn, &table_name, funs).await?;
+        let mut partitions = find_partitions(&conn, &schema_name, &table_name).await?;
+        if let Some(cutoff) = cutoff {
+            for partition in partitions.iter().filter(|partition| partition.end <= cutoff) {
+                drop_partition(&conn, partition, &mut resp).await?;
+            }
+            partitions.retain(|partition| partition.end > cutoff);
+            resp.deleted_rows += conn.execute_one(&format!("DELETE FROM {table_name} WHERE ts < $1"), vec![Value::from(cutoff)]).await?.rows_affected();
+        }
+        if let Some(max_size_mb) = policy.map(|policy| policy.max_size_mb).filter(|max_size_mb| *max_size_mb > 0) {
+            drop_oldest_partitions(&conn, &mut partitions, max_size_mb as i64 * BYTES_PER_MB, now, &mut resp).await?;
+        }
+        all_partitions.extend(partitions);
+    }
+    if let Some(max_size_mb) = default_policy.map(|policy| policy.max_size_mb).filter(|max_size_mb| *max_size_mb > 0) {
+        all_partitions.sort_by_key(|partition| partition.start);
+        drop_oldest_partitions(&conn, &mut all_partitions, max_size_mb as i64 * BYTES_PER_MB, now, &mut resp).await?;
+    }
+    info!(
+        "[SPI-Log] Retention purged in schema [{schema_name}], dropped partitions: {:?}, deleted rows: {}",
+        resp.dropped_partitions, resp.deleted_rows
+    );
+    Ok(resp)
+}
+
+/// Return (tag, table name) of all log tables in the schema
+async fn find_log_tables(conn: &TardisRelDBlConnection, schema_name: &str) -> TardisResult<Vec<(String, String)>> {
+    let table_prefix = format!("{GLOBAL_STORAGE_FLAG}_log_");
+    let result = conn
+        .query_all(
+            r#"SELECT c.relname AS name
+FROM pg_class c
+JOIN pg_namespace n ON n.oid = c.relnamespace
+WHERE n.nspname = $1 AND starts_with(c.relname, $2) AND c.relkind IN ('r', 'p') AND NOT c.relispartition"#,
+            vec![Value::from(schema_name), Value::from(table_prefix.as_str())],
+        )
+        .await?;
+    result
+        .into_iter()
+        .map(|item| {
+            let name: String = item.try_get("", "name")?;
+            Ok((name.strip_prefix(&table_prefix).unwrap_or(&name).to_string(), format!("{schema_name}.{name}")))
+        })
+        .collect()
+}
+
+/// Return the partitions of the table ordered by time
+async fn find_partitions(conn: &TardisRelDBlConnection, schema_name: &str, table_name: &str) -> TardisResult<Vec<LogPartition>> {
+    let result = conn
+        .query_all(
+            r#"SELECT c.relname AS name, pg_total_relation_size(c.oid) AS size
+FROM pg_inherits i
+JOIN pg_class c ON c.oid = i.inhrelid
+WHERE i.inhparent = to_regclass($1)"#,
+            vec![Value::from(table_name)],
+        )
+        .await?;
+    let mut partitions = Vec::with_capacity(result.len());
+    for item in result {
+        let name: String = item.try_get("", "name")?;
+        // Ignore the partitions not created by this service
+        let Some((year, month)) = log_pg_initializer::parse_partition_suffix(&name) else {
+            continue;
+        };
+        let (next_year, next_month) = log_pg_initializer::next_month(year, month);
+        let (Some(start), Some(end)) = (
+            Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0).single(),
+            Utc.with_ymd_and_hms(next_year, next_month, 1, 0, 0, 0).single(),
+        ) else {
+            continue;
+        };
+        partitions.push(LogPartition {
+            table_name: table_name.to_string(),
+            partition_name: format!("{schema_name}.{name}"),
+            start,
+            end,
+            size: item.try_get("", "size")?,
+        });
+    }
+    partitions.sort_by_key(|partition| partition.start);
+    Ok(partitions)
+}
+
+async fn drop_oldest_partitions(
+    conn: &TardisRelDBlConnection,
+    partitions: &mut Vec<LogPartition>,
+    max_size: i64,
+    now: DateTime<Utc>,
+    resp: &mut LogRetentionPurgeResp,
+) -> TardisResult<()> {
+    let mut total_size = partitions.iter().map(|partition| partition.size).sum::<i64>();
+    let mut dropped = 0;
+    for partition in partitions.iter() {
+        // The partition being written is never dropped
+        if total_size <= max_size || partition.end > now {
+            break;
+        }
+        drop_partition(conn, partition, resp).await?;
+        total_size -= partition.size;
+        dropped += 1;
+    }
+    partitions.drain(..dropped);
+    Ok(())
+}
+
+async fn drop_partition(conn: &TardisRelDBlConnection, partition: &LogPartition, resp: &mut LogRetentionPurgeResp) -> TardisResult<()> {
+    conn.execute_one(&format!("DROP TABLE IF EXISTS {}", partition.partition_name), vec![]).await?;
+    log_pg_initializer::forget_partition(&partition.partition_name).await;
+    info!("[SPI-Log] Dropped partition [{}] of table [{}]", partition.partition_name, partition.table_name);
+    resp.dropped_partitions.push(partition.partition_name.clone());
+    Ok(())
+}
@@ -0,0 +1,295 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use bios_basic::spi::spi_funs::SpiBsInst;
+use lazy_static::lazy_static;
+use tardis::{
+    basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    db::{
+        reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
+        sea_orm::{
+            sqlx::{
+                self,
+                postgres::{PgListener, PgRow},
+                Row,
+            },
+            Value,
+        },
+    },
+    futures::{SinkExt, StreamExt},
+    log::{info, warn},
+    serde_json,
+    tokio::{
+        self,
+        sync::{
+            broadcast::{self, error::RecvError},
+            RwLock,
+        },
+    },
+    web::poem::web::websocket::{BoxWebSocketUpgraded, Message, WebSocket},
+    TardisFuns, TardisFunsInst,
+};
+
+use crate::{
+    dto::log_item_dto::{LogItemFilterReq, LogItemFindResp},
+    log_constants,
+};
+
+use super::log_pg_initializer;
+
+const CHANNEL_CAPACITY: usize = 1024;
+// Cache key prefix of the tables having subscribers on any node
+const SUBSCRIBED_CACHE_KEY_PREFIX: &str = "spi-log:tail:subscribed:";
+// The subscription mark is refreshed while the table has subscribers on this node, and expires after the last one leaves
+const SUBSCRIBED_EXP_SECS: usize = 60;
+const SUBSCRIBED_REFRESH_SECS: u64 = 20;
+// NOTIFY payload is limited to 8000 bytes by default, leave some room for the other fields
+const NOTIFY_ITEM_MAX_LEN: usize = 7000;
+// Ids of the items recently pushed from the notifications, a notification sent again is skipped
+const DELIVERED_IDS_CAPACITY: usize = 4096;
+
+lazy_static! {
+    // Identifies this process, the notifications sent by itself have been broadcast locally
+    static ref NODE_ID: String = TardisFuns::field.nanoid();
+    // Senders of newly added items, by table name, created by the first subscriber of the table
+    static ref SENDERS: RwLock<HashMap<String, broadcast::Sender<LogItemFindResp>>> = RwLock::new(HashMap::new());
+    // Connection uris that are being listened on
+    static ref LISTENERS: RwLock<HashSet<String>> = RwLock::new(HashSet::new());
+}
+
+/// Notify other nodes of the newly added items, they are only delivered when the transaction is committed.
+///
+/// NOTIFY serializes the committing transactions, so it is skipped when no node subscribes the table.
+/// The item is sent in the notification if it fits the payload limit, otherwise only its id is sent and the item is fetched by the listener.
+pub async fn notify(conn: &TardisRelDBlConnection, table_name: &str, ids: &[String], items: &[LogItemFindResp], funs: &TardisFunsInst) -> TardisResult<()> {
+    if !funs.cache().exists(&format!("{SUBSCRIBED_CACHE_KEY_PREFIX}{table_name}")).await? {
+        return Ok(());
+    }
+    let payloads = ids
+        .iter()
+        .zip(items)
+        .map(|(id, item)| {
+            let item_json = TardisFuns::json.obj_to_json(item)?;
+            let payload = if item_json.to_string().len() <= NOTIFY_ITEM_MAX_LEN {
+                serde_json::json!({
+                    "node": NODE_ID.as_str(),
+                    "table": table_name,
+                    "id": id,
+                    "item": item_json,
+                })
+            } else {
+                serde_json::json!({
+                    "node": NODE_ID.as_str(),
+                    "table": table_name,
+                    "id": id,
+                    "ts": item.ts,
+                })
+            };
+            Ok(payload.to_string())
+        })
+        .collect::<TardisResult<Vec<_>>>()?;
+    conn.execute_one(
+        "SELECT pg_notify($1, payload) FROM unnest($2::text[]) AS payload",
+        vec![Value::from(log_constants::TAIL_NOTIFY_CHANNEL), Value::from(payloads)],
+    )
+    .await?;
+    Ok(())
+}
+
+/// Push the newly added item to the subscribers of this node
+pub async fn broadcast(table_name: &str, item: LogItemFindResp) {
+    if let Some(sender) = SENDERS.read().await.get(table_name) {
+        // An error means there are no subscribers now, the sender is kept for the later ones
+        let _ = sender.send(item);
+    }
+}
+
+pub async fn tail(tail_req: &LogItemFilterReq, websocket: WebSocket, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<BoxWebSocketUpgraded> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (_, table_name) = log_pg_initializer::init_table_and_conn(bs_inst, &tail_req.tag, funs, ctx, false).await?;
+    let conn_uri = bs_inst.1.get(log_constants::CONN_URI_FLAG).ok_or_else(|| {
+        funs.err().internal_error(
+            "item",
+            "tail",
+            "The connection uri of backend service is not found",
+            "500-spi-log-conn-uri-not-found",
+        )
+    })?;
+    start_listener(conn_uri).await?;
+    let mut receiver = {
+        let mut senders = SENDERS.write().await;
+        if !senders.contains_key(&table_name) {
+            mark_subscribed(&table_name, funs).await?;
+        }
+        senders.entry(table_name.clone()).or_insert_with(|| broadcast::channel(CHANNEL_CAPACITY).0).subscribe()
+    };
+    let tail_req = tail_req.clone();
+    Ok(websocket
+        .on_upgrade(move |socket| async move {
+            let (mut sink, mut stream) = socket.split();
+            loop {
+                tokio::select! {
+                    item = receiver.recv() => {
+                        match item {
+                            Ok(item) => {
+                                match tail_req.is_match(&item) {
+                                    Ok(true) => {}
+                                    Ok(false) => continue,
+                                    Err(e) => {
+                                        warn!("[SPI-Log] Live tail filter error: {e:?}");
+                                        break;
+                                    }
+                                }
+                                if sink.send(Message::Text(TardisFuns::json.obj_to_string(&item).unwrap_or_default())).await.is_err() {
+                                    break;
+                                }
+                            }
+                            Err(RecvError::Lagged(skipped)) => warn!("[SPI-Log] Live tail subscriber is lagging, {skipped} items are skipped"),
+                            Err(RecvError::Closed) => break,
+                        }
+                    }
+                    client_message = stream.next() => {
+                        match client_message {
+                            Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            drop(receiver);
+            let mut senders = SENDERS.write().await;
+            if senders.get(&table_name).map(|sender| sender.receiver_count() == 0).unwrap_or(false) {
+                senders.remove(&table_name);
+            }
+        })
+        .boxed())
+}
+
+/// Mark the table as subscribed for the nodes adding items, and keep the mark until the table has no subscribers on this node
+async fn mark_subscribed(table_name: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+    let cache_key = format!("{SUBSCRIBED_CACHE_KEY_PREFIX}{table_name}");
+    let cache_client = funs.cache();
+    cache_client.set_ex(&cache_key, NODE_ID.as_str(), SUBSCRIBED_EXP_SECS).await?;
+    let table_name = table_name.to_string();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(SUBSCRIBED_REFRESH_SECS));
+        // The first tick completes immediately
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            if !SENDERS.read().await.contains_key(&table_name) {
+                break;
+            }
+            if let Err(e) = cache_client.set_ex(&cache_key, NODE_ID.as_str(), SUBSCRIBED_EXP_SECS).await {
+                warn!("[SPI-Log] Live tail refresh subscription error: {e:?}");
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Listen on the notifications of the items added by other nodes, one listener per database.
+///
+/// The listener exits on error and is started again by the next subscriber.
+async fn start_listener(conn_uri: &str) -> TardisResult<()> {
+    if LISTENERS.read().await.contains(conn_uri) {
+        return Ok(());
+    }
+    let mut listeners = LISTENERS.write().await;
+    if listeners.contains(conn_uri) {
+        return Ok(());
+    }
+    let mut listener = PgListener::connect(conn_uri)
+        .await
+        .map_err(|e| TardisError::internal_error(&format!("[SPI-Log] Connect listener error: {e}"), "500-spi-log-tail-listen-error"))?;
+    listener
+        .listen(log_constants::TAIL_NOTIFY_CHANNEL)
+        .await
+        .map_err(|e| TardisError::internal_error(&format!("[SPI-Log] Listen error: {e}"), "500-spi-log-tail-listen-error"))?;
+    listeners.insert(conn_uri.to_string());
+    let conn_uri = conn_uri.to_string();
+    tokio::spawn(async move {
+        info!("[SPI-Log] Live tail listener started");
+        let mut delivered_ids = VecDeque::with_capacity(DELIVERED_IDS_CAPACITY);
+        loop {
+            let notification = match listener.recv().await {
+                Ok(notification) => notification,
+                Err(e) => {
+                    warn!("[SPI-Log] Live tail listener error: {e}");
+                    break;
+                }
+            };
+            let Ok(payload) = serde_json::from_str::<serde_json::Value>(notification.payload()) else {
+                warn!("[SPI-Log] Live tail notification is illegal: {}", notification.payload());
+                continue;
+            };
+            let (Some(node), Some(table_name), Some(id)) = (payload["node"].as_str(), payload["table"].as_str(), payload["id"].as_str()) else {
+                continue;
+            };
+            if node == NODE_ID.as_str() || !SENDERS.read().await.contains_key(table_name) {
+                continue;
+            }
+            let delivered_id = format!("{table_name}:{id}");
+            if delivered_ids.contains(&delivered_id) {
+                continue;
+            }
+            let item = if payload["item"].is_object() {
+                match serde_json::from_value::<LogItemFindResp>(payload["item"].clone()) {
+                    Ok(item) => item,
+                    Err(e) => {
+                        warn!("[SPI-Log] Live tail decode item error: {e}");
+                        continue;
+                    }
+                }
+            } else {
+                // The table name is only sent by this service, but it is embedded into SQL, so check it anyway
+                if !table_name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.') {
+                    continue;
+                }
+                let row = match sqlx::query(&format!(
+                    "SELECT ts, key, op, content, kind, ext, owner, own_paths, rel_key FROM {table_name} WHERE ts = $1::timestamptz AND id = $2"
+                ))
+                .bind(payload["ts"].as_str().unwrap_or_default())
+                .bind(id)
+                .fetch_optional(&mut listener)
+                .await
+                {
+                    Ok(Some(row)) => row,
+                    Ok(None) => continue,
+                    Err(e) => {
+                        warn!("[SPI-Log] Live tail fetch item error: {e}");
+                        continue;
+                    }
+                };
+                match package_item(&row) {
+                    Ok(item) => item,
+                    Err(e) => {
+                        warn!("[SPI-Log] Live tail decode item error: {e}");
+                        continue;
+                    }
+                }
+            };
+            if delivered_ids.len() == DELIVERED_IDS_CAPACITY {
+                delivered_ids.pop_front();
+            }
+            delivered_ids.push_back(delivered_id);
+            broadcast(table_name, item).await;
+        }
+        LISTENERS.write().await.remove(&conn_uri);
+    });
+    Ok(())
+}
+
+fn package_item(row: &PgRow) -> Result<LogItemFindResp, sqlx::Error> {
+    Ok(LogItemFindResp {
+        ts: row.try_get("ts")?,
+        key: row.try_get("key")?,
+        op: row.try_get("op")?,
+        content: row.try_get("content")?,
+        kind: row.try_get("kind")?,
+        ext: row.try_get("ext")?,
+        owner: row.try_get("owner")?,
+        own_paths: row.try_get("own_paths")?,
+        rel_key: row.try_get("rel_key")?,
+        highlight: None,
+    })
+}
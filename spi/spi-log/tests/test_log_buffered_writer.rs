@@ -0,0 +1,100 @@
+use std::time::Duration;
+
+use bios_basic::test::test_http_client::TestHttpClient;
+use bios_sdk_invoke::clients::spi_log_client::{LogItemAddReq, SpiLogBufferedWriter};
+use bios_spi_log::dto::log_item_dto::LogItemFindResp;
+use bios_spi_log::log_constants::DOMAIN_CODE;
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::serde_json::json;
+use tardis::tokio::time::sleep;
+use tardis::web::web_resp::TardisPage;
+
+pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
+    let ctx = TardisContext {
+        own_paths: "t1/app001".to_string(),
+        ak: "".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: "app001".to_string(),
+        ..Default::default()
+    };
+    client.set_auth(&ctx)?;
+    let add_req = |tag: &str, key: &str| LogItemAddReq {
+        tag: tag.to_string(),
+        content: "buffered".to_string(),
+        kind: None,
+        ext: None,
+        key: Some(key.to_string()),
+        op: None,
+        rel_key: None,
+        ts: None,
+        owner: None,
+        own_paths: None,
+    };
+    // Created outside of the flushing task, and flushed when full
+    let writer = SpiLogBufferedWriter::new(DOMAIN_CODE, ctx.clone(), 3, Duration::from_secs(3600));
+    writer.write(add_req("buffered", "b001")).await?;
+    writer.write(add_req("buffered", "b002")).await?;
+    writer.flush().await?;
+    assert_eq!(count_items(client).await, 2);
+    for key in ["b003", "b004", "b005"] {
+        writer.write(add_req("buffered", key)).await?;
+    }
+    let mut total_size = 0;
+    for _ in 0..50 {
+        total_size = count_items(client).await;
+        if total_size == 5 {
+            break;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(total_size, 5);
+
+    // Failures are returned by the explicit flush
+    writer.write(add_req("buffered", "b006")).await?;
+    writer.write(add_req("Illegal Tag", "b007")).await?;
+    let error = writer.flush().await.unwrap_err();
+    assert_eq!(error.code, "400-spi-log-writer-items-discarded");
+    assert_eq!(count_items(client).await, 6);
+
+    // Failures of the flushes in the background are returned by the next call
+    for key in ["b008", "b009"] {
+        writer.write(add_req("buffered", key)).await?;
+    }
+    writer.write(add_req("Illegal Tag", "b010")).await?;
+    // Flushes after the background flush, which has failed
+    let error = writer.flush().await.unwrap_err();
+    assert_eq!(error.code, "400-spi-log-writer-items-discarded");
+    writer.flush().await?;
+    assert_eq!(count_items(client).await, 8);
+
+    // The buffer is flushed when the writer is dropped
+    writer.write(add_req("buffered", "b011")).await?;
+    drop(writer);
+    let mut total_size = 0;
+    for _ in 0..50 {
+        total_size = count_items(client).await;
+        if total_size == 9 {
+            break;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(total_size, 9);
+
+    Ok(())
+}
+
+async fn count_items(client: &TestHttpClient) -> u64 {
+    let result: TardisPage<LogItemFindResp> = client
+        .put(
+            "/ci/item",
+            &json!({
+                "tag":"buffered",
+                "page_number":1,
+                "page_size":10
+            }),
+        )
+        .await;
+    result.total_size
+}
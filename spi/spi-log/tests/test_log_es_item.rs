@@ -0,0 +1,192 @@
+use std::time::Duration;
+
+use bios_basic::test::test_http_client::TestHttpClient;
+use bios_spi_log::dto::log_item_dto::{LogItemBatchAddResp, LogItemFindResp};
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::serde_json::json;
+use tardis::tokio::time::sleep;
+use tardis::web::web_resp::{TardisPage, TardisResp, Void};
+
+pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
+    client.set_auth(&TardisContext {
+        own_paths: "t1/app002".to_string(),
+        ak: "".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: "app002".to_string(),
+        ..Default::default()
+    })?;
+
+    let _: Void = client
+        .post(
+            "/ci/item",
+            &json!({
+                "tag":"audit",
+                "content": "account [001] login",
+                "key": "001",
+                "op":"login",
+                "ts":"2023-05-31T23:00:00.000Z",
+                "own_paths":"t1/app002",
+                "ext": {"ip":"10.0.0.1","status":"ok","cost":10}
+            }),
+        )
+        .await;
+    let batch_result: LogItemBatchAddResp = client
+        .post(
+            "/ci/item/batch",
+            &json!([
+                {
+                    "tag":"audit", "content":"account [001] logout", "key":"001", "op":"logout", "ts":"2023-06-01T01:00:00.000Z",
+                    "ext": {"ip":"10.0.0.1","status":"ok","cost":20}
+                },
+                {
+                    "tag":"audit", "content":"account [002] login failed", "key":"002", "op":"login", "ts":"2023-06-02T01:00:00.000Z",
+                    "ext": {"ip":"10.0.0.2","status":"error","cost":30}
+                },
+                {"tag":"audit", "content":"account [003] login", "key":"003", "op":"login", "ext": "illegal"}
+            ]),
+        )
+        .await;
+    assert_eq!(batch_result.succeed, 2);
+    assert_eq!(batch_result.errors[0].index, 2);
+    // Wait for the refresh of ES
+    sleep(Duration::from_millis(1500)).await;
+
+    // Items in indexes of different months
+    let find_result: TardisPage<LogItemFindResp> = client
+        .put(
+            "/ci/item/find",
+            &json!({
+                "tag":"audit",
+                "page_number":1,
+                "page_size":10
+            }),
+        )
+        .await;
+    assert_eq!(find_result.total_size, 3);
+    assert_eq!(find_result.records[0].key, "002");
+    assert_eq!(find_result.records[2].op, "login");
+    assert_eq!(find_result.records[2].ext["ip"], "10.0.0.1");
+
+    let find_result: TardisPage<LogItemFindResp> = client
+        .put(
+            "/ci/item/find",
+            &json!({
+                "tag":"audit",
+                "ops":["login"],
+                "ts_start":"2023-06-01T00:00:00.000Z",
+                "page_number":1,
+                "page_size":10
+            }),
+        )
+        .await;
+    assert_eq!(find_result.total_size, 1);
+    assert_eq!(find_result.records[0].key, "002");
+
+    let find_result: TardisPage<LogItemFindResp> = client
+        .put(
+            "/ci/item/find",
+            &json!({
+                "tag":"audit",
+                "own_paths":"t1",
+                "page_number":1,
+                "page_size":10
+            }),
+        )
+        .await;
+    assert_eq!(find_result.total_size, 1);
+
+    // ext conditions
+    let find_result: TardisPage<LogItemFindResp> = client
+        .put(
+            "/ci/item/find",
+            &json!({
+                "tag":"audit",
+                "ext":[{"field":"ip","op":"=","value":"10.0.0.1"},{"field":"status","op":"!=","value":"error"}],
+                "page_number":1,
+                "page_size":10
+            }),
+        )
+        .await;
+    assert_eq!(find_result.total_size, 2);
+    let find_result: TardisPage<LogItemFindResp> = client
+        .put(
+            "/ci/item/find",
+            &json!({
+                "tag":"audit",
+                "ext":[{"field":"cost","op":"=","value":30}],
+                "page_number":1,
+                "page_size":10
+            }),
+        )
+        .await;
+    assert_eq!(find_result.total_size, 1);
+    let find_result: TardisPage<LogItemFindResp> = client
+        .put(
+            "/ci/item/find",
+            &json!({
+                "tag":"audit",
+                "ext_or":[{"field":"status","op":"=","value":"error"},{"field":"ip","op":"in","value":["10.0.0.3","10.0.0.1"]}],
+                "page_number":1,
+                "page_size":10
+            }),
+        )
+        .await;
+    assert_eq!(find_result.total_size, 3);
+    let find_result: TardisPage<LogItemFindResp> = client
+        .put(
+            "/ci/item/find",
+            &json!({
+                "tag":"audit",
+                "ext":[{"field":"ip","op":"like","value":"0.0.2"}],
+                "page_number":1,
+                "page_size":10
+            }),
+        )
+        .await;
+    assert_eq!(find_result.total_size, 1);
+
+    // Full-text search
+    let find_result: TardisPage<LogItemFindResp> = client
+        .put(
+            "/ci/item/find",
+            &json!({
+                "tag":"audit",
+                "q":"login -failed",
+                "page_number":1,
+                "page_size":10
+            }),
+        )
+        .await;
+    assert_eq!(find_result.total_size, 1);
+    assert_eq!(find_result.records[0].key, "001");
+
+    let find_result: TardisResp<TardisPage<LogItemFindResp>> = client
+        .put_resp(
+            "/ci/item/find",
+            &json!({
+                "tag":"not_exist",
+                "page_number":1,
+                "page_size":10
+            }),
+        )
+        .await;
+    assert_eq!(find_result.code, "400-spi-log-item-find");
+
+    // Values of ext are compared as strings, so the range operators don't accept numbers
+    let find_result: TardisResp<TardisPage<LogItemFindResp>> = client
+        .put_resp(
+            "/ci/item/find",
+            &json!({
+                "tag":"audit",
+                "ext":[{"field":"cost","op":">","value":9}],
+                "page_number":1,
+                "page_size":10
+            }),
+        )
+        .await;
+    assert_eq!(find_result.code, "400-spi-log-item-find");
+
+    Ok(())
+}
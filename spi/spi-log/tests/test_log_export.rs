@@ -0,0 +1,143 @@
+use std::time::Duration;
+
+use bios_basic::test::test_http_client::TestHttpClient;
+use bios_sdk_invoke::clients::spi_object_client::SpiObjectClient;
+use bios_spi_log::dto::log_item_dto::LogItemExportObjResp;
+use bios_spi_log::log_constants::DOMAIN_CODE;
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::serde_json::{self, json, Value};
+use tardis::tokio::time::sleep;
+use tardis::web::web_resp::{TardisResp, Void};
+use tardis::TardisFuns;
+
+pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
+    client.set_auth(&TardisContext {
+        own_paths: "t1/app001".to_string(),
+        ak: "".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: "app001".to_string(),
+        ..Default::default()
+    })?;
+
+    for (key, content, ts) in [
+        ("e001", "created", "2022-10-01T10:00:00.000Z"),
+        ("e002", r#"modified "title", "desc""#, "2022-10-02T10:00:00.000Z"),
+        ("e003", "deleted\nby admin", "2022-10-03T10:00:00.000Z"),
+    ] {
+        let _: Void = client
+            .post(
+                "/ci/item",
+                &json!({
+                    "tag":"export",
+                    "key": key,
+                    "content": content,
+                    "op":"modify",
+                    "ts": ts,
+                    "ext": {"source":"web"}
+                }),
+            )
+            .await;
+    }
+
+    // CSV
+    let csv = client
+        .put_to_str(
+            "/ci/item/export",
+            &json!({
+                "tag":"export",
+                "format":"csv"
+            }),
+        )
+        .await;
+    let mut lines = csv.lines();
+    assert_eq!(lines.next().unwrap(), "ts,kind,key,op,owner,own_paths,rel_key,content,ext");
+    assert!(lines.next().unwrap().contains(",e003,modify,"));
+    assert!(csv.contains(r#""modified ""title"", ""desc""""#));
+    assert!(csv.contains("\"deleted\nby admin\""));
+    assert!(csv.contains(r#""{""source"":""web""}""#));
+
+    let csv = client
+        .put_to_str(
+            "/ci/item/export",
+            &json!({
+                "tag":"export",
+                "keys":["e001"],
+                "format":"csv"
+            }),
+        )
+        .await;
+    assert_eq!(csv.lines().count(), 2);
+
+    // JSON lines
+    let jsonl = client
+        .put_to_str(
+            "/ci/item/export",
+            &json!({
+                "tag":"export",
+                "format":"jsonl"
+            }),
+        )
+        .await;
+    let items = jsonl.lines().map(|line| serde_json::from_str::<Value>(line).unwrap()).collect::<Vec<_>>();
+    assert_eq!(items.len(), 3);
+    assert_eq!(items[0]["key"], "e003");
+    assert_eq!(items[1]["content"], r#"modified "title", "desc""#);
+    assert_eq!(items[2]["key"], "e001");
+    assert_eq!(items[2]["ext"]["source"], "web");
+
+    // Object storage
+    let ctx = TardisContext {
+        own_paths: "t1/app001".to_string(),
+        ak: "".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: "app001".to_string(),
+        ..Default::default()
+    };
+    let funs = TardisFuns::inst(DOMAIN_CODE.to_string(), None);
+    let view_url = SpiObjectClient::presign_obj_url("view", "spi-log/export/test.csv", 300, true, &funs, &ctx).await?.unwrap();
+    assert!(view_url.contains("spi-log/export/test.csv"));
+
+    let resp: LogItemExportObjResp = client
+        .put(
+            "/ci/item/export/obj",
+            &json!({
+                "tag":"export",
+                "format":"csv"
+            }),
+        )
+        .await;
+    assert!(resp.object_path.starts_with("spi-log/export/export/"));
+    assert!(resp.object_path.ends_with(".csv"));
+    let task_id = resp.task_id.unwrap();
+    let mut finished = false;
+    for _ in 0..50 {
+        finished = client.get(&format!("/ci/item/export/task/{task_id}")).await;
+        if finished {
+            break;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+    assert!(finished);
+    let csv = TardisFuns::web_client().get_to_str(&resp.url, None).await?.body.unwrap();
+    let mut lines = csv.lines();
+    assert_eq!(lines.next().unwrap(), "ts,kind,key,op,owner,own_paths,rel_key,content,ext");
+    assert!(lines.next().unwrap().contains(",e003,modify,"));
+    assert!(csv.contains("\"deleted\nby admin\""));
+
+    // The task is only visible to its own paths
+    client.set_auth(&TardisContext {
+        own_paths: "t1/app002".to_string(),
+        ak: "".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: "app002".to_string(),
+        ..Default::default()
+    })?;
+    let result: TardisResp<bool> = client.get_resp(&format!("/ci/item/export/task/{task_id}")).await;
+    assert_eq!(result.code, "404-spi-log-item-export");
+
+    Ok(())
+}
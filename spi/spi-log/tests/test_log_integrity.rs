@@ -0,0 +1,232 @@
+use bios_basic::spi::spi_initializer::common_pg;
+use bios_basic::test::test_http_client::TestHttpClient;
+use bios_spi_log::dto::log_integrity_dto::{LogIntegrityResp, LogIntegrityVerifyResp};
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::chrono::{DateTime, SecondsFormat, Utc};
+use tardis::db::sea_orm::Value;
+use tardis::serde_json::{self, json};
+use tardis::web::web_resp::Void;
+use tardis::TardisFuns;
+
+pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
+    let ctx = TardisContext {
+        own_paths: "t1/app001".to_string(),
+        ak: "".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: "app001".to_string(),
+        ..Default::default()
+    };
+    client.set_auth(&ctx)?;
+
+    let _: Void = client
+        .put(
+            "/ci/integrity",
+            &json!({
+                "tag":"audit_chain",
+                "checkpoint_interval":2
+            }),
+        )
+        .await;
+    for idx in 0..5 {
+        let _: Void = client
+            .post(
+                "/ci/item",
+                &json!({
+                    "tag":"audit_chain",
+                    "key": format!("00{idx}"),
+                    "content": format!("账号[{idx}]登录系统"),
+                    "op":"login",
+                    "ext": {"b": idx, "a": {"z": true, "y": [1, 2.5]}},
+                    "ts": format!("2022-09-2{idx}T10:00:00.123456789Z")
+                }),
+            )
+            .await;
+    }
+    let integrities: Vec<LogIntegrityResp> = client.get("/ci/integrity").await;
+    assert_eq!(integrities.len(), 1);
+    assert_eq!(integrities[0].tag, "audit_chain");
+    assert_eq!(integrities[0].last_seq, 5);
+
+    let verify_resp: LogIntegrityVerifyResp = client
+        .put(
+            "/ci/integrity/verify",
+            &json!({
+                "tag":"audit_chain"
+            }),
+        )
+        .await;
+    assert_eq!(verify_resp.verified_items, 5);
+    // Checkpoints are signed by the key configured in the test config
+    assert_eq!(verify_resp.verified_checkpoints, 2);
+    assert_eq!(verify_resp.unverifiable_checkpoints, 0);
+    assert!(verify_resp.broken.is_none());
+
+    // Tamper with the content of an item
+    let table_name = format!("{}.starsys_log_audit_chain", common_pg::get_schema_name_from_context(&ctx));
+    TardisFuns::reldb().conn().execute_one(&format!("UPDATE {table_name} SET content = 'modified' WHERE key = '002'"), vec![]).await?;
+    let verify_resp: LogIntegrityVerifyResp = client
+        .put(
+            "/ci/integrity/verify",
+            &json!({
+                "tag":"audit_chain"
+            }),
+        )
+        .await;
+    assert_eq!(verify_resp.verified_items, 2);
+    let broken = verify_resp.broken.unwrap();
+    assert_eq!(broken.seq, 3);
+    assert_eq!(broken.key.as_deref(), Some("002"));
+
+    // Items out of the range are not checked
+    let verify_resp: LogIntegrityVerifyResp = client
+        .put(
+            "/ci/integrity/verify",
+            &json!({
+                "tag":"audit_chain",
+                "ts_start":"2022-09-23T00:00:00Z",
+                "ts_end":"2022-09-25T00:00:00Z"
+            }),
+        )
+        .await;
+    assert_eq!(verify_resp.verified_items, 2);
+    assert!(verify_resp.broken.is_none());
+
+    // Delete the last item
+    TardisFuns::reldb().conn().execute_one(&format!("DELETE FROM {table_name} WHERE key = '004'"), vec![]).await?;
+    let verify_resp: LogIntegrityVerifyResp = client
+        .put(
+            "/ci/integrity/verify",
+            &json!({
+                "tag":"audit_chain",
+                "ts_start":"2022-09-23T00:00:00Z"
+            }),
+        )
+        .await;
+    let broken = verify_resp.broken.unwrap();
+    assert_eq!(broken.seq, 5);
+    assert!(broken.key.is_none());
+
+    test_rewritten_chain(client, &ctx).await?;
+    Ok(())
+}
+
+/// Rewrite the chain with the hashes recomputed, which can only be detected by the signatures of the checkpoints
+async fn test_rewritten_chain(client: &mut TestHttpClient, ctx: &TardisContext) -> TardisResult<()> {
+    let _: Void = client
+        .put(
+            "/ci/integrity",
+            &json!({
+                "tag":"audit_signed",
+                "checkpoint_interval":2
+            }),
+        )
+        .await;
+    for idx in 0..4 {
+        let _: Void = client
+            .post(
+                "/ci/item",
+                &json!({
+                    "tag":"audit_signed",
+                    "key": format!("00{idx}"),
+                    "content": format!("账号[{idx}]登录系统"),
+                    "op":"login",
+                    "ext": {"b": idx, "a": {"z": true, "y": [1, 2.5]}},
+                    "ts": format!("2022-10-0{}T10:00:00.123456Z", idx + 1)
+                }),
+            )
+            .await;
+    }
+    let verify_resp: LogIntegrityVerifyResp = client
+        .put(
+            "/ci/integrity/verify",
+            &json!({
+                "tag":"audit_signed"
+            }),
+        )
+        .await;
+    assert_eq!(verify_resp.verified_items, 4);
+    assert_eq!(verify_resp.verified_checkpoints, 2);
+    assert!(verify_resp.broken.is_none());
+
+    // Modify the first item and recompute the hashes of the whole chain, including the checkpoints, but they can't be signed again
+    let schema_name = common_pg::get_schema_name_from_context(ctx);
+    let table_name = format!("{schema_name}.starsys_log_audit_signed");
+    let conn = TardisFuns::reldb().conn();
+    conn.execute_one(&format!("UPDATE {table_name} SET content = 'modified' WHERE key = '000'"), vec![]).await?;
+    let rows = conn
+        .query_all(
+            &format!("SELECT chain_seq, ts, kind, key, op, content, owner, own_paths, ext, rel_key FROM {table_name} ORDER BY chain_seq"),
+            vec![],
+        )
+        .await?;
+    let mut prev_hash = "".to_string();
+    for row in rows {
+        let seq: i64 = row.try_get("", "chain_seq")?;
+        let ts: DateTime<Utc> = row.try_get("", "ts")?;
+        let ext: serde_json::Value = row.try_get("", "ext")?;
+        let data = json!([
+            prev_hash,
+            seq,
+            ts.to_rfc3339_opts(SecondsFormat::Micros, true),
+            row.try_get::<String>("", "kind")?,
+            row.try_get::<String>("", "key")?,
+            row.try_get::<String>("", "op")?,
+            row.try_get::<String>("", "content")?,
+            row.try_get::<String>("", "owner")?,
+            row.try_get::<String>("", "own_paths")?,
+            canonical_json(&ext),
+            row.try_get::<String>("", "rel_key")?,
+        ]);
+        let hash = TardisFuns::crypto.digest.sm3(&data.to_string())?;
+        conn.execute_one(
+            &format!("UPDATE {table_name} SET prev_hash = $1, hash = $2 WHERE chain_seq = $3"),
+            vec![Value::from(prev_hash.as_str()), Value::from(hash.as_str()), Value::from(seq)],
+        )
+        .await?;
+        conn.execute_one(
+            &format!("UPDATE {schema_name}.starsys_checkpoint_log SET hash = $1 WHERE tag = 'audit_signed' AND seq = $2"),
+            vec![Value::from(hash.as_str()), Value::from(seq)],
+        )
+        .await?;
+        prev_hash = hash;
+    }
+    conn.execute_one(
+        &format!("UPDATE {schema_name}.starsys_integrity_log SET last_hash = $1 WHERE tag = 'audit_signed'"),
+        vec![Value::from(prev_hash.as_str())],
+    )
+    .await?;
+
+    let verify_resp: LogIntegrityVerifyResp = client
+        .put(
+            "/ci/integrity/verify",
+            &json!({
+                "tag":"audit_signed"
+            }),
+        )
+        .await;
+    assert_eq!(verify_resp.verified_items, 1);
+    assert_eq!(verify_resp.verified_checkpoints, 0);
+    let broken = verify_resp.broken.unwrap();
+    assert_eq!(broken.seq, 2);
+    assert_eq!(broken.key.as_deref(), Some("001"));
+    assert_eq!(broken.reason, "The signature of the checkpoint is invalid");
+    Ok(())
+}
+
+/// The same serialization as the integrity chain, with the keys of objects sorted
+fn canonical_json(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut items = map.iter().collect::<Vec<_>>();
+            items.sort_by(|a, b| a.0.cmp(b.0));
+            format!(
+                "{{{}}}",
+                items.into_iter().map(|(key, value)| format!("{}:{}", serde_json::Value::from(key.as_str()), canonical_json(value))).collect::<Vec<_>>().join(",")
+            )
+        }
+        serde_json::Value::Array(array) => format!("[{}]", array.iter().map(canonical_json).collect::<Vec<_>>().join(",")),
+        _ => value.to_string(),
+    }
+}
@@ -1,5 +1,5 @@
 use bios_basic::test::test_http_client::TestHttpClient;
-use bios_spi_log::dto::log_item_dto::LogItemFindResp;
+use bios_spi_log::dto::log_item_dto::{LogItemAggResp, LogItemBatchAddResp, LogItemFindResp};
 use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
 use tardis::serde_json::json;
@@ -96,7 +96,7 @@ pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
             }),
         )
         .await;
-    assert!(find_result.code.starts_with("400"));
+    assert_eq!(find_result.code, "400-spi-log-item-init");
 
     let find_result: TardisPage<LogItemFindResp> = client
         .put(
@@ -240,5 +240,243 @@ pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
     assert_eq!(find_result.total_size, 1);
     assert_eq!(find_result.records[0].key, "001");
     assert_eq!(find_result.records[0].op, "init");
+
+    // Full-text search
+    let _: Void = client
+        .post(
+            "/ci/item",
+            &json!({
+                "tag":"ops",
+                "key": "001",
+                "content": "Database connection timeout when syncing orders",
+                "op":"sync",
+                "ts":"2022-09-26T10:00:00.000Z"
+            }),
+        )
+        .await;
+    let _: Void = client
+        .post(
+            "/ci/item",
+            &json!({
+                "tag":"ops",
+                "key": "002",
+                "content": "Connection refused by payment gateway, connection retried",
+                "op":"pay",
+                "ts":"2022-09-26T11:00:00.000Z"
+            }),
+        )
+        .await;
+    let _: Void = client
+        .post(
+            "/ci/item",
+            &json!({
+                "tag":"ops",
+                "key": "003",
+                "content": "Orders synced successfully",
+                "op":"sync",
+                "ts":"2022-09-26T12:00:00.000Z"
+            }),
+        )
+        .await;
+
+    let find_result: TardisPage<LogItemFindResp> = client
+        .put(
+            "/ci/item/find",
+            &json!({
+                "tag":"ops",
+                "q":"connection",
+                "page_number":1,
+                "page_size":10
+            }),
+        )
+        .await;
+    assert_eq!(find_result.total_size, 2);
+    // Ranked by relevance rather than time
+    assert_eq!(find_result.records[0].key, "002");
+    assert!(find_result.records[0].highlight.as_ref().unwrap().contains("<em>Connection</em>"));
+
+    // The content is escaped in the highlight
+    let _: Void = client
+        .post(
+            "/ci/item",
+            &json!({
+                "tag":"ops_escape",
+                "key": "001",
+                "content": "<img src=x onerror=alert(1)> connection lost",
+                "op":"sync",
+                "ts":"2022-09-26T10:00:00.000Z"
+            }),
+        )
+        .await;
+    let find_result: TardisPage<LogItemFindResp> = client
+        .put(
+            "/ci/item/find",
+            &json!({
+                "tag":"ops_escape",
+                "q":"connection",
+                "page_number":1,
+                "page_size":10
+            }),
+        )
+        .await;
+    let highlight = find_result.records[0].highlight.as_ref().unwrap();
+    assert!(highlight.contains("<em>connection</em>"));
+    assert!(!highlight.contains("<img"));
+
+    let find_result: TardisPage<LogItemFindResp> = client
+        .put(
+            "/ci/item/find",
+            &json!({
+                "tag":"ops",
+                "q":"\"connection timeout\"",
+                "page_number":1,
+                "page_size":10
+            }),
+        )
+        .await;
+    assert_eq!(find_result.total_size, 1);
+    assert_eq!(find_result.records[0].key, "001");
+
+    let find_result: TardisPage<LogItemFindResp> = client
+        .put(
+            "/ci/item/find",
+            &json!({
+                "tag":"ops",
+                "q":"connection -payment",
+                "ops":["sync"],
+                "page_number":1,
+                "page_size":10
+            }),
+        )
+        .await;
+    assert_eq!(find_result.total_size, 1);
+    assert_eq!(find_result.records[0].key, "001");
+
+    let find_result: TardisPage<LogItemFindResp> = client
+        .put(
+            "/ci/item/find",
+            &json!({
+                "tag":"ops",
+                "page_number":1,
+                "page_size":10
+            }),
+        )
+        .await;
+    assert_eq!(find_result.total_size, 3);
+    assert_eq!(find_result.records[0].key, "003");
+    assert!(find_result.records[0].highlight.is_none());
+    
+    // Aggregation
+    let agg_result: Vec<LogItemAggResp> = client
+        .put(
+            "/ci/item/agg",
+            &json!({
+                "tag":"feed"
+            }),
+        )
+        .await;
+    assert_eq!(agg_result.len(), 1);
+    assert_eq!(agg_result[0].count, 3);
+
+    let agg_result: Vec<LogItemAggResp> = client
+        .put(
+            "/ci/item/agg",
+            &json!({
+                "tag":"feed",
+                "group_by":["op"]
+            }),
+        )
+        .await;
+    assert_eq!(agg_result.len(), 2);
+    assert_eq!(agg_result[0].group.get("op").unwrap().as_deref(), Some("init"));
+    assert_eq!(agg_result[0].count, 2);
+    assert_eq!(agg_result[1].group.get("op").unwrap().as_deref(), Some("modify"));
+    assert_eq!(agg_result[1].count, 1);
+
+    let agg_result: Vec<LogItemAggResp> = client
+        .put(
+            "/ci/item/agg",
+            &json!({
+                "tag":"feed",
+                "keys":["001"],
+                "interval":"day"
+            }),
+        )
+        .await;
+    assert_eq!(agg_result.len(), 2);
+    assert_eq!(agg_result[0].ts.unwrap().to_rfc3339(), "2022-09-26T00:00:00+00:00");
+    assert_eq!(agg_result[0].count, 1);
+    assert_eq!(agg_result[1].ts.unwrap().to_rfc3339(), "2022-09-27T00:00:00+00:00");
+    assert_eq!(agg_result[1].count, 1);
+
+    let agg_result: Vec<LogItemAggResp> = client
+        .put(
+            "/ci/item/agg",
+            &json!({
+                "tag":"project",
+                "group_by":["kind", "ext.status", "ext.not_exist"]
+            }),
+        )
+        .await;
+    assert_eq!(agg_result.len(), 1);
+    assert_eq!(agg_result[0].group.get("kind").unwrap().as_deref(), Some("req"));
+    assert_eq!(agg_result[0].group.get("ext.status").unwrap().as_deref(), Some("1"));
+    assert_eq!(agg_result[0].group.get("ext.not_exist").unwrap().as_deref(), None);
+
+    let agg_result: TardisResp<Vec<LogItemAggResp>> = client
+        .put_resp(
+            "/ci/item/agg",
+            &json!({
+                "tag":"project",
+                "group_by":["content"]
+            }),
+        )
+        .await;
+    assert!(agg_result.code.starts_with("400"));
+
+    // Batch add
+    let batch_result: LogItemBatchAddResp = client
+        .post(
+            "/ci/item/batch",
+            &json!([
+                {"tag":"batch", "content":"login succeed", "key":"u001", "op":"login"},
+                {"tag":"Batch-Illegal", "content":"login succeed"},
+                {"tag":"batch", "content":"logout succeed", "key":"u001", "op":"logout"},
+                {"tag":"batch", "content":"x"},
+                {"tag":"batch_other", "content":"login failed", "key":"u002", "op":"login"}
+            ]),
+        )
+        .await;
+    assert_eq!(batch_result.succeed, 3);
+    assert_eq!(batch_result.errors.iter().map(|error| error.index).collect::<Vec<_>>(), vec![1, 3]);
+    assert!(batch_result.errors.iter().all(|error| error.code.starts_with("400")));
+    let find_result: TardisPage<LogItemFindResp> = client
+        .put(
+            "/ci/item/find",
+            &json!({
+                "tag":"batch",
+                "keys":["u001"],
+                "page_number":1,
+                "page_size":10
+            }),
+        )
+        .await;
+    assert_eq!(find_result.total_size, 2);
+    let find_result: TardisPage<LogItemFindResp> = client
+        .put(
+            "/ci/item/find",
+            &json!({
+                "tag":"batch_other",
+                "page_number":1,
+                "page_size":10
+            }),
+        )
+        .await;
+    assert_eq!(find_result.total_size, 1);
+
+    let batch_result: TardisResp<LogItemBatchAddResp> = client
+        .post_resp("/ci/item/batch", &(0..1001).map(|i| json!({"tag":"batch", "content":format!("item {i}")})).collect::<Vec<_>>())
+        .await;
+    assert!(batch_result.code.starts_with("400"));
     Ok(())
 }
@@ -0,0 +1,81 @@
+use bios_basic::test::test_http_client::TestHttpClient;
+use bios_spi_log::dto::log_item_dto::LogItemFindResp;
+use bios_spi_log::dto::log_retention_dto::{LogRetentionPurgeResp, LogRetentionResp};
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::serde_json::json;
+use tardis::web::web_resp::{TardisPage, Void};
+
+pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
+    client.set_auth(&TardisContext {
+        own_paths: "t1/app001".to_string(),
+        ak: "".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: "app001".to_string(),
+        ..Default::default()
+    })?;
+
+    let _: Void = client
+        .put(
+            "/ci/retention",
+            &json!({
+                "tag":"feed",
+                "max_days":30
+            }),
+        )
+        .await;
+    let _: Void = client
+        .put(
+            "/ci/retention",
+            &json!({
+                "max_size_mb":1024
+            }),
+        )
+        .await;
+    let policies: Vec<LogRetentionResp> = client.get("/ci/retention").await;
+    assert_eq!(policies.len(), 2);
+    let feed_policy = policies.iter().find(|policy| policy.tag.as_deref() == Some("feed")).unwrap();
+    assert_eq!(feed_policy.max_days, 30);
+    assert_eq!(feed_policy.max_size_mb, 0);
+    let all_tags_policy = policies.iter().find(|policy| policy.tag.is_none()).unwrap();
+    assert_eq!(all_tags_policy.max_days, 0);
+    assert_eq!(all_tags_policy.max_size_mb, 1024);
+
+    // The logs of feed were written in 2022
+    let purge_result: LogRetentionPurgeResp = client.put("/ci/retention/purge", &Void {}).await;
+    assert!(purge_result.dropped_partitions.iter().any(|partition| partition.ends_with("_log_feed_p202209")));
+    let find_result: TardisPage<LogItemFindResp> = client
+        .put(
+            "/ci/item/find",
+            &json!({
+                "tag":"feed",
+                "page_number":1,
+                "page_size":10
+            }),
+        )
+        .await;
+    assert_eq!(find_result.total_size, 0);
+    // Purged by the schedule service, nothing left to purge
+    let purge_result: LogRetentionPurgeResp = client.get("/ci/retention/purge").await;
+    assert!(purge_result.dropped_partitions.is_empty());
+    // Tags without policy are not affected
+    let find_result: TardisPage<LogItemFindResp> = client
+        .put(
+            "/ci/item/find",
+            &json!({
+                "tag":"project",
+                "page_number":1,
+                "page_size":10
+            }),
+        )
+        .await;
+    assert_eq!(find_result.total_size, 1);
+
+    client.delete("/ci/retention?tag=feed").await;
+    let policies: Vec<LogRetentionResp> = client.get("/ci/retention").await;
+    assert_eq!(policies.len(), 1);
+    assert!(policies[0].tag.is_none());
+
+    Ok(())
+}
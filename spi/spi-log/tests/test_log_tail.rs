@@ -0,0 +1,219 @@
+use std::time::Duration;
+
+use bios_basic::test::test_http_client::TestHttpClient;
+use bios_basic::{basic_enumeration::BasicQueryOpKind, dto::BasicQueryCondInfo};
+use bios_spi_log::dto::log_item_dto::{LogItemFilterReq, LogItemFindResp};
+use tardis::basic::dto::TardisContext;
+use tardis::basic::field::TrimString;
+use tardis::basic::result::TardisResult;
+use tardis::chrono::Utc;
+use tardis::db::sea_orm::Value;
+use tardis::futures::{SinkExt, StreamExt};
+use tardis::serde_json::json;
+use tardis::tokio::time::{sleep, timeout};
+use tardis::web::tokio_tungstenite::tungstenite::Message;
+use tardis::web::web_resp::Void;
+use tardis::TardisFuns;
+
+pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
+    let item = LogItemFindResp {
+        content: "task executed".to_string(),
+        kind: "task".to_string(),
+        ext: json!({"status":"ok","cost":120}),
+        owner: "app001".to_string(),
+        own_paths: "t1/app001".to_string(),
+        key: "job001".to_string(),
+        op: "exec".to_string(),
+        rel_key: "".to_string(),
+        ts: Utc::now(),
+        highlight: None,
+    };
+
+    assert!(LogItemFilterReq {
+        tag: "schedule".to_string(),
+        ..Default::default()
+    }
+    .is_match(&item)?);
+    assert!(LogItemFilterReq {
+        tag: "schedule".to_string(),
+        kinds: Some(vec![TrimString("task".to_string()), TrimString("flow".to_string())]),
+        keys: Some(vec![TrimString("job001".to_string())]),
+        own_paths: Some("t1".to_string()),
+        ..Default::default()
+    }
+    .is_match(&item)?);
+    assert!(!LogItemFilterReq {
+        tag: "schedule".to_string(),
+        keys: Some(vec![TrimString("job002".to_string())]),
+        ..Default::default()
+    }
+    .is_match(&item)?);
+    assert!(!LogItemFilterReq {
+        tag: "schedule".to_string(),
+        own_paths: Some("t2".to_string()),
+        ..Default::default()
+    }
+    .is_match(&item)?);
+
+    // ext conditions
+    assert!(LogItemFilterReq {
+        tag: "schedule".to_string(),
+        ext: Some(vec![
+            BasicQueryCondInfo {
+                field: "status".to_string(),
+                op: BasicQueryOpKind::Eq,
+                value: json!("ok"),
+            },
+            BasicQueryCondInfo {
+                field: "cost".to_string(),
+                op: BasicQueryOpKind::Gt,
+                value: json!(100),
+            },
+        ]),
+        ..Default::default()
+    }
+    .is_match(&item)?);
+    assert!(!LogItemFilterReq {
+        tag: "schedule".to_string(),
+        ext: Some(vec![
+            BasicQueryCondInfo {
+                field: "status".to_string(),
+                op: BasicQueryOpKind::Eq,
+                value: json!("ok"),
+            },
+            BasicQueryCondInfo {
+                field: "cost".to_string(),
+                op: BasicQueryOpKind::Gt,
+                value: json!(200),
+            },
+        ]),
+        ..Default::default()
+    }
+    .is_match(&item)?);
+    assert!(LogItemFilterReq {
+        tag: "schedule".to_string(),
+        ext_or: Some(vec![
+            BasicQueryCondInfo {
+                field: "status".to_string(),
+                op: BasicQueryOpKind::Eq,
+                value: json!("error"),
+            },
+            BasicQueryCondInfo {
+                field: "cost".to_string(),
+                op: BasicQueryOpKind::Gt,
+                value: json!(100),
+            },
+        ]),
+        ..Default::default()
+    }
+    .is_match(&item)?);
+
+    // Live tail
+    client.set_auth(&TardisContext {
+        own_paths: "t1/app001".to_string(),
+        ak: "".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: "app001".to_string(),
+        ..Default::default()
+    })?;
+    let _: Void = client
+        .post(
+            "/ci/item",
+            &json!({
+                "tag":"tail",
+                "key":"job000",
+                "op":"exec",
+                "content":"created",
+            }),
+        )
+        .await;
+    let mut socket = client.ws_connect("/ci/item/tail/tail?keys=job001,job002&ext=%5B%7B%22field%22%3A%22status%22%2C%22op%22%3A%22Eq%22%2C%22value%22%3A%22ok%22%7D%5D").await;
+    sleep(Duration::from_millis(100)).await;
+    let table_name: String = TardisFuns::reldb()
+        .conn()
+        .query_one("SELECT schemaname || '.' || tablename AS table_name FROM pg_tables WHERE tablename LIKE '%\\_log\\_tail'", vec![])
+        .await?
+        .unwrap()
+        .try_get("", "table_name")?;
+    // The nodes adding items know that the table is subscribed
+    assert!(TardisFuns::cache().exists(&format!("spi-log:tail:subscribed:{table_name}")).await?);
+
+    for (key, status) in [("job001", "error"), ("job003", "ok"), ("job001", "ok")] {
+        let _: Void = client
+            .post(
+                "/ci/item",
+                &json!({
+                    "tag":"tail",
+                    "key":key,
+                    "op":"exec",
+                    "content":"task executed",
+                    "ext":{"status":status},
+                }),
+            )
+            .await;
+    }
+    let Message::Text(message) = timeout(Duration::from_secs(5), socket.next()).await.unwrap().unwrap().unwrap() else {
+        panic!("unexpected message");
+    };
+    let item = TardisFuns::json.str_to_obj::<LogItemFindResp>(&message)?;
+    assert_eq!(item.key, "job001");
+    assert_eq!(item.ext["status"], "ok");
+
+    // Items added by other nodes are pushed through PG NOTIFY, by id if they are too large to be sent in the notification
+    let ts = Utc::now();
+    TardisFuns::reldb()
+        .conn()
+        .execute_one(
+            &format!(
+                r#"INSERT INTO {table_name} (ts, key, op, content, kind, owner, own_paths, ext, rel_key, id)
+VALUES ($1, 'job002', 'exec', 'from other node', '', 'app001', 't1/app001', $2, '', 'other001')"#
+            ),
+            vec![Value::from(ts), Value::from(json!({"status":"ok"}))],
+        )
+        .await?;
+    let locator = json!({
+        "node": "other",
+        "table": table_name,
+        "id": "other001",
+        "ts": ts,
+    })
+    .to_string();
+    let inline = json!({
+        "node": "other",
+        "table": table_name,
+        "id": "other002",
+        "item": {
+            "content": "sent in notification",
+            "kind": "",
+            "ext": {"status":"ok"},
+            "owner": "app001",
+            "own_paths": "t1/app001",
+            "key": "job001",
+            "op": "exec",
+            "rel_key": "",
+            "ts": Utc::now(),
+        },
+    })
+    .to_string();
+    // The notification sent again is skipped
+    for payload in [&locator, &locator, &inline] {
+        TardisFuns::reldb().conn().execute_one("SELECT pg_notify('spi_log_tail', $1)", vec![Value::from(payload.as_str())]).await?;
+    }
+    let Message::Text(message) = timeout(Duration::from_secs(5), socket.next()).await.unwrap().unwrap().unwrap() else {
+        panic!("unexpected message");
+    };
+    let item = TardisFuns::json.str_to_obj::<LogItemFindResp>(&message)?;
+    assert_eq!(item.key, "job002");
+    assert_eq!(item.content, "from other node");
+    let Message::Text(message) = timeout(Duration::from_secs(5), socket.next()).await.unwrap().unwrap().unwrap() else {
+        panic!("unexpected message");
+    };
+    let item = TardisFuns::json.str_to_obj::<LogItemFindResp>(&message)?;
+    assert_eq!(item.key, "job001");
+    assert_eq!(item.content, "sent in notification");
+
+    socket.send(Message::Close(None)).await.unwrap();
+
+    Ok(())
+}
@@ -3,9 +3,12 @@ use tardis::web::context_extractor::TardisContextExtractor;
 use tardis::web::poem_openapi;
 use tardis::web::poem_openapi::param::Path;
 use tardis::web::poem_openapi::payload::Json;
-use tardis::web::web_resp::{TardisApiResult, TardisPage, TardisResp, Void};
+use tardis::web::web_resp::{TardisApiResult, TardisResp, Void};
 
-use crate::dto::search_item_dto::{SearchItemAddReq, SearchItemModifyReq, SearchItemSearchReq, SearchItemSearchResp};
+use crate::dto::search_item_dto::{
+    SearchItemAddReq, SearchItemBulkReq, SearchItemBulkResp, SearchItemModifyReq, SearchItemReindexProgressResp, SearchItemSearchPageResp, SearchItemSearchReq,
+    SearchItemSuggestReq, SearchItemSuggestResp,
+};
 use crate::serv::search_item_serv;
 
 #[derive(Clone)]
@@ -39,10 +42,50 @@ impl SearchCiItemApi {
     }
 
     /// Search Items
+    ///
+    /// The aggregations are computed over all matching records when requested.
     #[oai(path = "/search", method = "put")]
-    async fn search(&self, mut search_req: Json<SearchItemSearchReq>, ctx: TardisContextExtractor) -> TardisApiResult<TardisPage<SearchItemSearchResp>> {
+    async fn search(&self, mut search_req: Json<SearchItemSearchReq>, ctx: TardisContextExtractor) -> TardisApiResult<SearchItemSearchPageResp> {
         let funs = crate::get_tardis_inst();
         let resp = search_item_serv::search(&mut search_req.0, &funs, &ctx.0).await?;
         TardisResp::ok(resp)
     }
+
+    /// Suggest Items
+    ///
+    /// Return the completions and corrections of the input over the titles visible to the search context.
+    #[oai(path = "/suggest", method = "put")]
+    async fn suggest(&self, mut suggest_req: Json<SearchItemSuggestReq>, ctx: TardisContextExtractor) -> TardisApiResult<SearchItemSuggestResp> {
+        let funs = crate::get_tardis_inst();
+        let resp = search_item_serv::suggest(&mut suggest_req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Bulk Upsert And Delete Items
+    ///
+    /// The failed items are returned without affecting the others.
+    #[oai(path = "/bulk", method = "put")]
+    async fn bulk(&self, mut bulk_req: Json<SearchItemBulkReq>, ctx: TardisContextExtractor) -> TardisApiResult<SearchItemBulkResp> {
+        let funs = crate::get_tardis_inst();
+        let resp = search_item_serv::bulk(&mut bulk_req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Reindex Tag
+    ///
+    /// Rebuild the tag by an asynchronous task, the task id is returned to get the progress.
+    #[oai(path = "/reindex/:tag", method = "put")]
+    async fn reindex(&self, tag: Path<String>, ctx: TardisContextExtractor) -> TardisApiResult<i64> {
+        let funs = crate::get_tardis_inst();
+        let task_id = search_item_serv::reindex(&tag.0, &funs, &ctx.0).await?;
+        TardisResp::accepted(task_id)
+    }
+
+    /// Get Reindex Progress
+    #[oai(path = "/reindex/task/:task_id", method = "get")]
+    async fn get_reindex_progress(&self, task_id: Path<i64>, ctx: TardisContextExtractor) -> TardisApiResult<SearchItemReindexProgressResp> {
+        let funs = crate::get_tardis_inst();
+        let resp = search_item_serv::get_reindex_progress(task_id.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
 }
@@ -3,10 +3,10 @@ use std::collections::HashMap;
 use bios_basic::dto::BasicQueryCondInfo;
 use serde::{Deserialize, Serialize};
 use tardis::{
-    basic::field::TrimString,
+    basic::{error::TardisError, field::TrimString},
     chrono::{DateTime, Utc},
     serde_json::{self, Value},
-    web::poem_openapi,
+    web::{poem_openapi, web_resp::TardisPage},
     TardisFuns,
 };
 
@@ -30,6 +30,8 @@ pub struct SearchItemAddReq {
     pub update_time: Option<DateTime<Utc>>,
     pub ext: Option<Value>,
     pub visit_keys: Option<SearchItemVisitKeysReq>,
+    // Embedding used by the similarity search, its dimension must be the one declared for the tag
+    pub vector: Option<Vec<f32>>,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
@@ -50,6 +52,7 @@ pub struct SearchItemModifyReq {
     // Overwrites the original content when it is true
     pub ext_override: Option<bool>,
     pub visit_keys: Option<SearchItemVisitKeysReq>,
+    pub vector: Option<Vec<f32>>,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
@@ -105,6 +108,10 @@ pub struct SearchItemSearchReq {
     // When the record set is very large, it will seriously affect the performance, it is not recommended to use.
     pub sort: Option<Vec<SearchItemSearchSortReq>>,
     pub page: SearchItemSearchPageReq,
+    // Facets computed over all matching records, not just the current page
+    pub aggs: Option<Vec<SearchItemSearchAggReq>>,
+    // Highlight the matched fragments of title and content, only valid when `query.q` is specified
+    pub highlight: Option<SearchItemSearchHighlightReq>,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
@@ -159,6 +166,29 @@ pub struct SearchItemQueryReq {
     pub update_time_end: Option<DateTime<Utc>>,
     // Extended filtering conditions
     pub ext: Option<Vec<BasicQueryCondInfo>>,
+    // Similarity search by the vector, combined with `q` when both are specified
+    pub knn: Option<SearchItemQueryKnnReq>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct SearchItemQueryKnnReq {
+    pub vector: Vec<f32>,
+    // Number of the nearest items matched, default is 10
+    #[oai(validator(minimum(value = "1"), maximum(value = "1000")))]
+    pub k: Option<u16>,
+    // Weight of the similarity in the score, the rest is the weight of `q`, default is 0.5
+    #[oai(validator(minimum(value = "0"), maximum(value = "1")))]
+    pub weight: Option<f32>,
+}
+
+impl SearchItemQueryKnnReq {
+    pub fn k(&self) -> u16 {
+        self.k.unwrap_or(10)
+    }
+
+    pub fn weight(&self) -> f32 {
+        self.weight.unwrap_or(0.5)
+    }
 }
 
 #[derive(poem_openapi::Enum, Serialize, Deserialize, Debug)]
@@ -204,6 +234,115 @@ pub struct SearchItemSearchPageReq {
     pub fetch_total: bool,
 }
 
+/// Highlighting of the search result.
+///
+/// The content of the items indexed before highlighting was supported by PostgreSQL is not stored,
+/// so their content is not highlighted until they are modified or added again.
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Default)]
+pub struct SearchItemSearchHighlightReq {
+    // Default is `<em>`.
+    // The tags are passed as the options of `ts_headline` by PG, so they are limited to the characters of simple HTML tags
+    #[oai(validator(pattern = r"^[a-zA-Z0-9<>/=' _\-:;.#]{1,64}$"))]
+    pub pre_tag: Option<String>,
+    // Default is `</em>`, limited to the same characters as `pre_tag`
+    #[oai(validator(pattern = r"^[a-zA-Z0-9<>/=' _\-:;.#]{1,64}$"))]
+    pub post_tag: Option<String>,
+    // Size of the content fragments, counted in characters by ES and in words by PG, defaults to the backend's own
+    #[oai(validator(minimum(value = "2")))]
+    pub fragment_size: Option<u16>,
+}
+
+impl SearchItemSearchHighlightReq {
+    pub fn pre_tag(&self) -> &str {
+        self.pre_tag.as_deref().unwrap_or("<em>")
+    }
+
+    pub fn post_tag(&self) -> &str {
+        self.post_tag.as_deref().unwrap_or("</em>")
+    }
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct SearchItemSearchAggReq {
+    // Name of the aggregation in the response, defaults to the field
+    pub name: Option<String>,
+    // One of `kind`, `owner`, `own_paths`, `create_time`, `update_time`, or a field of ext
+    #[oai(validator(pattern = r"^[a-zA-Z0-9_\-.]+$"))]
+    pub field: String,
+    // Group by the time interval, only valid for `create_time` and `update_time`
+    pub interval: Option<SearchItemSearchAggIntervalKind>,
+    // Maximum number of buckets, ordered by count, default is 10, ignored by time intervals
+    #[oai(validator(minimum(value = "1"), maximum(value = "1000")))]
+    pub size: Option<u16>,
+}
+
+impl SearchItemSearchAggReq {
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.field)
+    }
+}
+
+#[derive(poem_openapi::Enum, Serialize, Deserialize, Debug)]
+pub enum SearchItemSearchAggIntervalKind {
+    #[oai(rename = "day")]
+    Day,
+    #[oai(rename = "week")]
+    Week,
+    #[oai(rename = "month")]
+    Month,
+    #[oai(rename = "year")]
+    Year,
+}
+
+impl SearchItemSearchAggIntervalKind {
+    pub fn to_sql(&self) -> &str {
+        match self {
+            SearchItemSearchAggIntervalKind::Day => "day",
+            SearchItemSearchAggIntervalKind::Week => "week",
+            SearchItemSearchAggIntervalKind::Month => "month",
+            SearchItemSearchAggIntervalKind::Year => "year",
+        }
+    }
+}
+
+/// Page of the search result.
+///
+/// The fields of [`TardisPage`] are kept, so the responses can still be read as `TardisPage<SearchItemSearchResp>`,
+/// and the callers of the service can convert it by `into()`.
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct SearchItemSearchPageResp {
+    pub page_size: u64,
+    pub page_number: u64,
+    pub total_size: u64,
+    pub records: Vec<SearchItemSearchResp>,
+    // Same order as the request, only present when aggregations are requested
+    pub aggs: Option<Vec<SearchItemSearchAggResp>>,
+}
+
+impl From<SearchItemSearchPageResp> for TardisPage<SearchItemSearchResp> {
+    fn from(page: SearchItemSearchPageResp) -> Self {
+        TardisPage {
+            page_size: page.page_size,
+            page_number: page.page_number,
+            total_size: page.total_size,
+            records: page.records,
+        }
+    }
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct SearchItemSearchAggResp {
+    pub name: String,
+    pub buckets: Vec<SearchItemSearchAggBucketResp>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct SearchItemSearchAggBucketResp {
+    // Time intervals are formatted as RFC 3339 in UTC, e.g. `2022-09-01T00:00:00Z`
+    pub key: String,
+    pub count: u64,
+}
+
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
 pub struct SearchItemSearchResp {
     #[oai(validator(min_length = "2"))]
@@ -221,4 +360,91 @@ pub struct SearchItemSearchResp {
     pub ext: Value,
     pub rank_title: f32,
     pub rank_content: f32,
+    // Only present when highlighting is requested and the field is matched
+    pub title_highlight: Option<String>,
+    // Matched fragments separated by ` ... `
+    pub content_highlight: Option<String>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Default)]
+pub struct SearchItemBulkReq {
+    // Items to be added, or to overwrite the existing ones with the same key
+    pub upserts: Option<Vec<SearchItemAddReq>>,
+    pub deletes: Option<Vec<SearchItemBulkDeleteReq>>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct SearchItemBulkDeleteReq {
+    #[oai(validator(pattern = r"^[a-z0-9-_]+$"))]
+    pub tag: String,
+    pub key: TrimString,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct SearchItemBulkResp {
+    pub succeed: u32,
+    pub errors: Vec<SearchItemBulkErrorResp>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct SearchItemBulkErrorResp {
+    pub tag: String,
+    pub key: String,
+    pub code: String,
+    pub msg: String,
+}
+
+impl SearchItemBulkErrorResp {
+    pub fn new(tag: &str, key: &str, error: &TardisError) -> Self {
+        SearchItemBulkErrorResp {
+            tag: tag.to_string(),
+            key: key.to_string(),
+            code: error.code.clone(),
+            msg: error.message.clone(),
+        }
+    }
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct SearchItemReindexProgressResp {
+    pub finished: bool,
+    // Number of items to be copied, unknown before the copy is started
+    pub total: Option<u64>,
+    pub done: u64,
+    // Present when the reindex is failed, the original table or index is kept
+    pub error: Option<String>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct SearchItemSuggestReq {
+    #[oai(validator(pattern = r"^[a-z0-9-_]+$"))]
+    pub tag: String,
+    // Search context for record permission filtering, same as searching
+    pub ctx: SearchItemSearchCtxReq,
+    // The input to be completed or corrected
+    #[oai(validator(min_length = "1"))]
+    pub q: String,
+    // Maximum number of completions and corrections each, 10 by default
+    #[oai(validator(minimum(value = "1"), maximum(value = "100")))]
+    pub size: Option<u16>,
+}
+
+impl SearchItemSuggestReq {
+    pub fn size(&self) -> u16 {
+        self.size.unwrap_or(10)
+    }
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct SearchItemSuggestResp {
+    // Items whose title starts with the input, or contains a phrase starting with it when suggested by query in ES
+    pub completions: Vec<SearchItemSuggestCompletionResp>,
+    // Words of the titles similar to the words of the input, i.e. "did you mean"
+    pub corrections: Vec<String>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct SearchItemSuggestCompletionResp {
+    pub key: String,
+    pub title: String,
 }
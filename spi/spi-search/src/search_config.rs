@@ -2,8 +2,24 @@ use bios_basic::rbum::rbum_config::RbumConfig;
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 
-#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(default)]
 pub struct SearchConfig {
     pub rbum: RbumConfig,
+    // Maximum number of items upserted and deleted in one bulk request
+    pub bulk_max_items: usize,
+    pub cache_key_async_task_status: String,
+    // Seconds the progress of a reindex task is kept after it is finished
+    pub reindex_progress_exp_secs: u64,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        SearchConfig {
+            rbum: Default::default(),
+            bulk_max_items: 1000,
+            cache_key_async_task_status: "spi-search:cache:task:status".to_string(),
+            reindex_progress_exp_secs: 3600,
+        }
+    }
 }
@@ -1 +1,10 @@
 pub const DOMAIN_CODE: &str = "spi-search";
+pub(crate) const CONN_URI_FLAG: &str = "__conn_uri__";
+// Text search configuration of PG, suffixed by the tag for the configuration of a tag
+pub(crate) const TEXT_SEARCH_CONFIG_FLAG: &str = "__text_search_config__";
+// Schema of the pg_trgm extension of PG, absent when the extension is not available
+pub(crate) const TRGM_SCHEMA_FLAG: &str = "__trgm_schema__";
+// Dimension of the vectors of PG and ES, suffixed by the tag, absent when the vectors of the tag are not enabled
+pub(crate) const VECTOR_DIM_FLAG: &str = "__vector_dim__";
+// Schema of the vector extension of PG, absent when the extension is not available
+pub(crate) const VECTOR_SCHEMA_FLAG: &str = "__vector_schema__";
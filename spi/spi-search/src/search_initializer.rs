@@ -1,11 +1,22 @@
+use std::collections::HashMap;
+
 use bios_basic::spi::{api::spi_ci_bs_api, dto::spi_bs_dto::SpiBsCertResp, spi_constants, spi_funs::SpiBsInst, spi_initializer};
 use tardis::{
-    basic::{dto::TardisContext, result::TardisResult},
+    basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    serde_json::Value,
     web::web_server::TardisWebServer,
     TardisFuns, TardisFunsInst,
 };
 
-use crate::{api::ci::search_ci_item_api, search_config::SearchConfig, search_constants::DOMAIN_CODE, serv};
+use crate::{
+    api::ci::search_ci_item_api,
+    search_config::SearchConfig,
+    search_constants::{self, DOMAIN_CODE},
+    serv,
+};
+
+// Limited by the indexes of pgvector
+const MAX_VECTOR_DIM: u64 = 2000;
 
 pub async fn init(web_server: &TardisWebServer) -> TardisResult<()> {
     let mut funs = crate::get_tardis_inst();
@@ -33,13 +44,50 @@ async fn init_api(web_server: &TardisWebServer) -> TardisResult<()> {
 pub async fn init_fun(bs_cert: SpiBsCertResp, ctx: &TardisContext, mgr: bool) -> TardisResult<SpiBsInst> {
     match bs_cert.kind_code.as_str() {
         #[cfg(feature = "spi-pg")]
-        spi_constants::SPI_PG_KIND_CODE => spi_initializer::common_pg::init(&bs_cert, ctx, mgr).await,
+        spi_constants::SPI_PG_KIND_CODE => serv::pg::search_pg_initializer::init(&bs_cert, ctx, mgr).await,
         #[cfg(feature = "spi-es")]
         spi_constants::SPI_ES_KIND_CODE => serv::es::search_es_initializer::init(&bs_cert, ctx, mgr).await,
         _ => Err(bs_cert.bs_not_implemented())?,
     }
 }
 
+/// The vectors are enabled by declaring their dimensions by tag in the ext of the backend service, e.g. `{"tag_vector_dims":{"doc":768}}`.
+pub(crate) fn init_vector_dims(bs_ext: &Value, ext: &mut HashMap<String, String>) -> TardisResult<()> {
+    if let Some(tag_vector_dims) = bs_ext.get("tag_vector_dims").and_then(|dims| dims.as_object()) {
+        for (tag, dim) in tag_vector_dims {
+            let dim = dim.as_u64().filter(|dim| (1..=MAX_VECTOR_DIM).contains(dim)).ok_or_else(|| {
+                TardisError::bad_request(
+                    &format!("The vector dimension of tag [{tag}] must be an integer between 1 and {MAX_VECTOR_DIM}"),
+                    "400-spi-search-vector-dim-illegal",
+                )
+            })?;
+            ext.insert(format!("{}{tag}", search_constants::VECTOR_DIM_FLAG), dim.to_string());
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn get_vector_dim(ext: &HashMap<String, String>, tag: &str) -> Option<usize> {
+    ext.get(&format!("{}{tag}", search_constants::VECTOR_DIM_FLAG)).and_then(|dim| dim.parse().ok())
+}
+
+/// Check the vector of an item or a query against the dimension declared for the tag.
+pub(crate) fn check_vector(tag: &str, vector: &[f32], ext: &HashMap<String, String>) -> TardisResult<()> {
+    let Some(dim) = get_vector_dim(ext, tag) else {
+        return Err(TardisError::bad_request(
+            &format!("The vectors are not enabled for tag [{tag}]"),
+            "400-spi-search-vector-not-supported",
+        ));
+    };
+    if vector.len() != dim || vector.iter().any(|value| !value.is_finite()) {
+        return Err(TardisError::bad_request(
+            &format!("The vector of tag [{tag}] must be {dim} finite numbers"),
+            "400-spi-search-vector-dim-illegal",
+        ));
+    }
+    Ok(())
+}
+
 #[inline]
 pub(crate) fn get_tardis_inst() -> TardisFunsInst {
     TardisFuns::inst_with_db_conn(DOMAIN_CODE.to_string(), None)
@@ -4,11 +4,20 @@ use bios_basic::spi::{dto::spi_bs_dto::SpiBsCertResp, spi_funs::SpiBsInst, spi_i
 use tardis::{
     basic::{dto::TardisContext, result::TardisResult},
     search::search_client::TardisSearchClient,
+    TardisFuns,
 };
 
+use crate::{search_constants, search_initializer};
+
+/// The vectors of the tags are enabled by `tag_vector_dims` in the ext of the backend service, e.g. `{"tag_vector_dims":{"doc":768}}`.
 pub async fn init(bs_cert: &SpiBsCertResp, ctx: &TardisContext, _mgr: bool) -> TardisResult<SpiBsInst> {
     let client = TardisSearchClient::init(&bs_cert.conn_uri, 60)?;
     let mut ext = HashMap::new();
+    // The aggregations and highlights are not supported by the client, they are requested over HTTP directly
+    ext.insert(search_constants::CONN_URI_FLAG.to_string(), bs_cert.conn_uri.clone());
+    if !bs_cert.ext.trim().is_empty() {
+        search_initializer::init_vector_dims(&TardisFuns::json.str_to_json(&bs_cert.ext)?, &mut ext)?;
+    }
     if !bs_cert.private {
         let key_prefix = spi_initializer::common::get_isolation_flag_from_context(ctx);
         spi_initializer::common::set_isolation_flag_to_ext(&key_prefix, &mut ext);
@@ -1,24 +1,46 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
 
 use bios_basic::{
     basic_enumeration::BasicQueryOpKind,
     spi::{spi_funs::SpiBsInst, spi_initializer::common},
 };
+use lazy_static::lazy_static;
 use tardis::{
     basic::{dto::TardisContext, result::TardisResult},
+    chrono::Utc,
     search::search_client::TardisSearchClient,
     serde_json::{self, json},
-    web::web_resp::TardisPage,
+    tokio::sync::RwLock,
+    web::web_client::TardisHttpResponse,
     TardisFuns, TardisFunsInst,
 };
 
-use crate::dto::search_item_dto::{
-    SearchItemAddReq, SearchItemModifyReq, SearchItemQueryReq, SearchItemSearchCtxReq, SearchItemSearchPageReq, SearchItemSearchQScopeKind, SearchItemSearchReq,
-    SearchItemSearchResp,
+use crate::{
+    dto::search_item_dto::{
+        SearchItemAddReq, SearchItemBulkErrorResp, SearchItemBulkReq, SearchItemModifyReq, SearchItemQueryReq, SearchItemSearchAggBucketResp, SearchItemSearchAggReq,
+        SearchItemSearchAggResp, SearchItemSearchCtxReq, SearchItemSearchHighlightReq, SearchItemSearchPageReq, SearchItemSearchPageResp, SearchItemSearchQScopeKind,
+        SearchItemSearchReq, SearchItemSearchResp, SearchItemSuggestCompletionResp, SearchItemSuggestReq, SearchItemSuggestResp,
+    },
+    search_config::SearchConfig,
+    search_constants, search_initializer,
+    serv::search_item_serv,
 };
 
 use super::search_es_initializer;
 
+// Expiration of the marks of a running reindex, refreshed while it runs, so that they are removed if the node exits during the reindex
+const REINDEX_MARK_EXPIRE_SEC: usize = 300;
+// Number of keys deleted from the new index in one request after the reindex
+const REINDEX_DELETE_BATCH_SIZE: usize = 1000;
+
+lazy_static! {
+    // Indexes known to have the vector field, the index is removed when it is replaced by a reindex on this node
+    static ref VECTOR_MAPPED_INDEXES: RwLock<HashSet<String>> = RwLock::new(HashSet::new());
+}
+
 fn format_index(req_index: &str, ext: &HashMap<String, String>) -> String {
     if let Some(key_prefix) = common::get_isolation_flag_from_ext(ext) {
         format!("{key_prefix}{req_index}")
@@ -27,17 +49,17 @@ fn format_index(req_index: &str, ext: &HashMap<String, String>) -> String {
     }
 }
 
-fn gen_data_mappings() -> String {
-    r#"{
+fn gen_data_mappings(vector_dim: Option<usize>) -> String {
+    let mut mappings = json!({
         "mappings": {
             "properties": {
                 "tag":{"type": "keyword"},
                 "kind":{"type": "keyword"},
                 "key":{"type": "keyword"},
-                "title":{"type": "text"},
+                "title":{"type": "text", "fields": {"suggest": {"type": "completion"}}},
                 "content":{"type": "text"},
                 "owner":{"type": "keyword"},
-                "own_paths":{"type": "text"},
+                "own_paths":{"type": "text", "fields": {"keyword": {"type": "keyword"}}},
                 "create_time":{"type": "date"},
                 "update_time":{"type": "date"},
                 "ext":{"type": "object"},
@@ -52,17 +74,63 @@ fn gen_data_mappings() -> String {
                 }
             }
         }
-    }"#
-    .to_string()
+    });
+    if let Some(vector_dim) = vector_dim {
+        mappings["mappings"]["properties"]["vector"] = gen_vector_mapping(vector_dim);
+    }
+    mappings.to_string()
+}
+
+fn gen_vector_mapping(vector_dim: usize) -> serde_json::Value {
+    json!({"type": "dense_vector", "dims": vector_dim, "index": true, "similarity": "cosine"})
+}
+
+/// Add the vector field to the indexes created before the vectors of the tag are enabled.
+///
+/// The dimension of the field can't be changed once added, the request is rejected by ES in that case.
+async fn init_vector_mapping(index: &str, tag: &str, ext: &HashMap<String, String>, funs: &TardisFunsInst) -> TardisResult<()> {
+    let Some(vector_dim) = search_initializer::get_vector_dim(ext, tag) else {
+        return Ok(());
+    };
+    if VECTOR_MAPPED_INDEXES.read().await.contains(index) {
+        return Ok(());
+    }
+    let conn_uri = get_conn_uri(ext, funs)?;
+    let resp = funs
+        .web_client()
+        .put_str_to_str(
+            &format!("{conn_uri}/{index}/_mapping"),
+            &json!({"properties": {"vector": gen_vector_mapping(vector_dim)}}).to_string(),
+            Some(vec![("Content-Type".to_string(), "application/json".to_string())]),
+        )
+        .await?;
+    parse_http_resp(resp, "init_vector_mapping", funs)?;
+    VECTOR_MAPPED_INDEXES.write().await.insert(index.to_string());
+    Ok(())
+}
+
+async fn check_vector_mapped(index: &str, conn_uri: &str, funs: &TardisFunsInst) -> TardisResult<bool> {
+    if VECTOR_MAPPED_INDEXES.read().await.contains(index) {
+        return Ok(true);
+    }
+    let mapped = check_field_mapped("vector", conn_uri, index, funs).await?;
+    if mapped {
+        VECTOR_MAPPED_INDEXES.write().await.insert(index.to_string());
+    }
+    Ok(mapped)
 }
 
 pub async fn add(add_req: &mut SearchItemAddReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
     let (client, ext, _) = inst.inst::<TardisSearchClient>();
     let index = format_index(&add_req.tag, ext);
 
-    if search_es_initializer::init_index(client, &index, Some(&gen_data_mappings())).await.is_err() {
+    if search_es_initializer::init_index(client, &index, Some(&gen_data_mappings(search_initializer::get_vector_dim(ext, &add_req.tag)))).await.is_err() {
         return Err(funs.err().bad_request("search_es_item_serv", "add", "index not exist", "400-search-index-not-exist"));
     }
+    if let Some(vector) = &add_req.vector {
+        search_initializer::check_vector(&add_req.tag, vector, ext)?;
+        init_vector_mapping(&index, &add_req.tag, ext, funs).await?;
+    }
     if !search(
         &mut SearchItemSearchReq {
             tag: add_req.tag.clone(),
@@ -85,6 +153,8 @@ pub async fn add(add_req: &mut SearchItemAddReq, funs: &TardisFunsInst, ctx: &Ta
                 size: 1,
                 fetch_total: false,
             },
+            aggs: None,
+            highlight: None,
         },
         funs,
         ctx,
@@ -129,6 +199,8 @@ pub async fn modify(tag: &str, key: &str, modify_req: &mut SearchItemModifyReq,
             size: 1,
             fetch_total: false,
         },
+        aggs: None,
+        highlight: None,
     })?;
     let mut search_result = client.raw_search(&index, &q, Some(1), Some(0), None).await?;
     if search_result.hits.hits.is_empty() {
@@ -170,6 +242,11 @@ pub async fn modify(tag: &str, key: &str, modify_req: &mut SearchItemModifyReq,
             }
         }
     }
+    if let Some(vector) = &modify_req.vector {
+        search_initializer::check_vector(tag, vector, ext)?;
+        init_vector_mapping(&index, tag, ext, funs).await?;
+        query.insert("vector".to_string(), json!(vector).to_string());
+    }
     if let Some(visit_keys) = &modify_req.visit_keys {
         if let Some(accounts) = &visit_keys.accounts {
             query.insert("visit_keys.accounts".to_string(), json!(accounts.clone()).to_string());
@@ -219,25 +296,35 @@ pub async fn delete(tag: &str, key: &str, funs: &TardisFunsInst, _ctx: &TardisCo
             size: 1,
             fetch_total: false,
         },
+        aggs: None,
+        highlight: None,
     })?;
     client.delete_by_query(&index, &q).await?;
+    record_reindex_deletes(&index, &[key.to_string()], funs).await?;
 
     Ok(())
 }
 
-pub async fn search(search_req: &mut SearchItemSearchReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<TardisPage<SearchItemSearchResp>> {
-    let q = gen_query_dsl(search_req)?;
+pub async fn search(search_req: &mut SearchItemSearchReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<SearchItemSearchPageResp> {
+    let (client, ext, _) = inst.inst::<TardisSearchClient>();
+    let index = format_index(&search_req.tag, ext);
+    if !client.check_index_exist(&index).await? {
+        return Err(funs.err().bad_request("search_es_item_serv", "add", "index not exist", "400-search-index-not-exist"));
+    }
+    let q = if let Some(knn) = &search_req.query.knn {
+        search_initializer::check_vector(&search_req.tag, &knn.vector, ext)?;
+        // Not mapped on the read path, an index without the vector field has no vectors to match
+        let vector_mapped = check_vector_mapped(&index, get_conn_uri(ext, funs)?, funs).await?;
+        gen_hybrid_query_dsl(search_req, vector_mapped)?
+    } else {
+        gen_query_dsl(search_req)?
+    };
     let mut track_scores = None;
     if let Some(sorts) = &search_req.sort {
         if sorts.iter().any(|sort| sort.field == "rank_title" || sort.field == "rank_content") {
             track_scores = Some(true);
         }
     }
-    let (client, ext, _) = inst.inst::<TardisSearchClient>();
-    let index = format_index(&search_req.tag, ext);
-    if !client.check_index_exist(&index).await? {
-        return Err(funs.err().bad_request("search_es_item_serv", "add", "index not exist", "400-search-index-not-exist"));
-    }
 
     let result = client
         .raw_search(
@@ -253,7 +340,7 @@ pub async fn search(search_req: &mut SearchItemSearchReq, funs: &TardisFunsInst,
     if search_req.page.fetch_total && total_size == 0 {
         total_size = result.hits.total.value as i64;
     }
-    let records = result
+    let mut records = result
         .hits
         .hits
         .iter()
@@ -270,64 +357,666 @@ pub async fn search(search_req: &mut SearchItemSearchReq, funs: &TardisFunsInst,
                     ext: item.ext.unwrap_or_default(),
                     rank_title: raw_item._score.unwrap_or_default(),
                     rank_content: raw_item._score.unwrap_or_default(),
+                    title_highlight: None,
+                    content_highlight: None,
                 })
             } else {
                 Err(funs.err().format_error("search_es_item_serv", "search", "search result format error", "500-result-format-error"))
             }
         })
         .collect::<Result<Vec<_>, _>>()?;
-    Ok(TardisPage {
+    if let (Some(highlight), Some(_)) = (&search_req.highlight, &search_req.query.q) {
+        if !records.is_empty() {
+            let ids = result.hits.hits.iter().map(|raw_item| raw_item._id.clone()).collect::<Vec<_>>();
+            let mut highlights = search_highlights(highlight, &q, &ids, &index, ext, funs).await?;
+            for (record, id) in records.iter_mut().zip(ids.iter()) {
+                if let Some((title_highlight, content_highlight)) = highlights.remove(id) {
+                    record.title_highlight = title_highlight;
+                    record.content_highlight = content_highlight;
+                }
+            }
+        }
+    }
+    let aggs = if let Some(aggs) = &search_req.aggs {
+        Some(search_aggs(aggs, &q, &index, ext, funs).await?)
+    } else {
+        None
+    };
+    Ok(SearchItemSearchPageResp {
         page_size: search_req.page.size as u64,
         page_number: search_req.page.number as u64,
         total_size: total_size as u64,
         records,
+        aggs,
     })
 }
 
-fn gen_query_dsl(search_req: &SearchItemSearchReq) -> TardisResult<String> {
-    let mut must_q = vec![];
-    let mut must_not_q = vec![];
-    let mut should_q = vec![];
-    let mut filter_q = vec![];
-    let mut sort_q = vec![];
+/// Aggregate over all the records matched by the query, requested separately since the hits are paged.
+async fn search_aggs(
+    aggs: &[SearchItemSearchAggReq],
+    q: &str,
+    index: &str,
+    ext: &HashMap<String, String>,
+    funs: &TardisFunsInst,
+) -> TardisResult<Vec<SearchItemSearchAggResp>> {
+    let conn_uri = get_conn_uri(ext, funs)?;
+    let mut aggs_q = serde_json::Map::new();
+    for (idx, agg) in aggs.iter().enumerate() {
+        let terms_size = agg.size.unwrap_or(10);
+        let terms_order = json!([{"_count": "desc"}, {"_key": "asc"}]);
+        let agg_q = match (agg.field.to_lowercase().as_str(), &agg.interval) {
+            (field @ ("create_time" | "update_time"), Some(interval)) => json!({
+                "date_histogram": {
+                    "field": field,
+                    "calendar_interval": interval.to_sql(),
+                    "min_doc_count": 1,
+                    "format": "yyyy-MM-dd'T'HH:mm:ss'Z'"
+                }
+            }),
+            (_, Some(_)) => {
+                return Err(funs.err().bad_request(
+                    "search_es_item_serv",
+                    "search",
+                    &format!("The interval is not supported by the aggregation field=[{}]", agg.field),
+                    "400-spi-search-agg-interval-not-supported",
+                ))
+            }
+            (field @ ("kind" | "owner"), None) => json!({"terms": {"field": field, "size": terms_size, "order": terms_order}}),
+            ("own_paths", None) => {
+                // Indexes created before the keyword sub-field was introduced would get no buckets
+                if !check_field_mapped("own_paths.keyword", conn_uri, index, funs).await? {
+                    return Err(funs.err().conflict(
+                        "search_es_item_serv",
+                        "search",
+                        "The index doesn't support aggregating by own_paths since it was created by an earlier version, reindex the tag to enable it",
+                        "409-spi-search-agg-field-not-mapped",
+                    ));
+                }
+                json!({"terms": {"field": "own_paths.keyword", "size": terms_size, "order": terms_order}})
+            }
+            (_, None) => {
+                let field = get_ext_agg_field(&agg.field, conn_uri, index, funs).await?;
+                json!({"terms": {"field": field, "size": terms_size, "order": terms_order}})
+            }
+        };
+        // The names are specified by the client, so they are not used as the keys of ES
+        aggs_q.insert(format!("agg{idx}"), agg_q);
+    }
+    let mut agg_q = TardisFuns::json.str_to_json(q)?;
+    if let Some(agg_q) = agg_q.as_object_mut() {
+        agg_q.remove("sort");
+        agg_q.insert("size".to_string(), json!(0));
+        agg_q.insert("aggs".to_string(), serde_json::Value::Object(aggs_q));
+    }
+    let result = raw_search_by_http(&agg_q, conn_uri, index, funs).await?;
+    Ok(aggs
+        .iter()
+        .enumerate()
+        .map(|(idx, agg)| SearchItemSearchAggResp {
+            name: agg.name().to_string(),
+            buckets: result["aggregations"][format!("agg{idx}")]["buckets"]
+                .as_array()
+                .map(|buckets| {
+                    buckets
+                        .iter()
+                        .map(|bucket| SearchItemSearchAggBucketResp {
+                            key: match (&bucket["key_as_string"], &bucket["key"]) {
+                                (serde_json::Value::String(key), _) | (_, serde_json::Value::String(key)) => key.clone(),
+                                (_, key) => key.to_string(),
+                            },
+                            count: bucket["doc_count"].as_u64().unwrap_or_default(),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        })
+        .collect())
+}
 
-    // ctx
+/// Highlight the records of the current page by their ids, requested separately since the hits of the client carry no highlights.
+///
+/// Returns the title and content highlights by id.
+async fn search_highlights(
+    highlight: &SearchItemSearchHighlightReq,
+    q: &str,
+    ids: &[String],
+    index: &str,
+    ext: &HashMap<String, String>,
+    funs: &TardisFunsInst,
+) -> TardisResult<HashMap<String, (Option<String>, Option<String>)>> {
+    let conn_uri = get_conn_uri(ext, funs)?;
+    let query_q = TardisFuns::json.str_to_json(q)?["query"].take();
+    let mut content_highlight_q = json!({"number_of_fragments": 3});
+    if let Some(fragment_size) = highlight.fragment_size {
+        content_highlight_q["fragment_size"] = json!(fragment_size);
+    }
+    let highlight_q = json!({
+        "query": {
+            "bool": {
+                "must": [query_q],
+                "filter": [{"ids": {"values": ids}}]
+            }
+        },
+        "size": ids.len(),
+        "_source": false,
+        "highlight": {
+            "pre_tags": [highlight.pre_tag()],
+            "post_tags": [highlight.post_tag()],
+            "fields": {
+                // The whole title is returned, while the content is cut into fragments
+                "title": {"number_of_fragments": 0},
+                "content": content_highlight_q
+            }
+        }
+    });
+    let result = raw_search_by_http(&highlight_q, conn_uri, index, funs).await?;
+    let join_fragments = |fragments: &serde_json::Value| {
+        fragments.as_array().map(|fragments| fragments.iter().filter_map(|fragment| fragment.as_str()).collect::<Vec<_>>().join(" ... "))
+    };
+    Ok(result["hits"]["hits"]
+        .as_array()
+        .map(|hits| {
+            hits.iter()
+                .filter_map(|hit| {
+                    let id = hit["_id"].as_str()?;
+                    Some((
+                        id.to_string(),
+                        (join_fragments(&hit["highlight"]["title"]), join_fragments(&hit["highlight"]["content"])),
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// Completions are suggested by the completion suggester of `title.suggest` when all the items are visible, i.e. the search context is empty.
+/// Since the suggester can't be filtered, they are suggested by a phrase prefix query of `title` filtered by the search context otherwise,
+/// as well as for the indexes created before the completion field is added.
+///
+/// Corrections are suggested by the term suggester of `title`, and checked against the visible items afterwards.
+pub async fn suggest(suggest_req: &mut SearchItemSuggestReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<SearchItemSuggestResp> {
+    let (client, ext, _) = inst.inst::<TardisSearchClient>();
+    let index = format_index(&suggest_req.tag, ext);
+    if !client.check_index_exist(&index).await? {
+        return Err(funs.err().bad_request("search_es_item_serv", "suggest", "index not exist", "400-search-index-not-exist"));
+    }
+    let conn_uri = get_conn_uri(ext, funs)?;
+    let size = suggest_req.size() as usize;
+    let ctx_q = gen_ctx_query_dsl(&suggest_req.ctx);
+    let by_suggester = ctx_q.is_empty() && check_field_mapped("title.suggest", conn_uri, &index, funs).await?;
+    let ctx_q = if suggest_req.ctx.cond_by_or.unwrap_or(false) && !ctx_q.is_empty() {
+        json!({"bool": {"should": ctx_q, "minimum_should_match": 1}})
+    } else {
+        json!({"bool": {"must": ctx_q}})
+    };
+
+    let mut suggesters = json!({
+        "correction": {
+            "text": suggest_req.q,
+            "term": {"field": "title", "size": size, "suggest_mode": "always"}
+        }
+    });
+    if by_suggester {
+        suggesters["completion"] = json!({
+            "prefix": suggest_req.q,
+            "completion": {"field": "title.suggest", "size": size}
+        });
+    }
+    let suggest_q = json!({
+        "size": 0,
+        "_source": ["key", "title"],
+        "suggest": suggesters
+    });
+    let result = raw_search_by_http(&suggest_q, conn_uri, &index, funs).await?;
+
+    let completion_sources = if by_suggester {
+        result["suggest"]["completion"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .flat_map(|completion| completion["options"].as_array().into_iter().flatten())
+            .map(|option| option["_source"].clone())
+            .collect::<Vec<_>>()
+    } else {
+        let completion_q = json!({
+            "query": {"bool": {"must": [{"match_phrase_prefix": {"title": suggest_req.q}}], "filter": [ctx_q]}},
+            "size": size,
+            "_source": ["key", "title"]
+        });
+        let completion_result = raw_search_by_http(&completion_q, conn_uri, &index, funs).await?;
+        completion_result["hits"]["hits"].as_array().into_iter().flatten().map(|hit| hit["_source"].clone()).collect::<Vec<_>>()
+    };
+    let completions = completion_sources
+        .iter()
+        .filter_map(|source| {
+            Some(SearchItemSuggestCompletionResp {
+                key: source["key"].as_str()?.to_string(),
+                title: source["title"].as_str()?.to_string(),
+            })
+        })
+        .take(size)
+        .collect();
+
+    let mut correction_options = result["suggest"]["correction"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|correction| correction["options"].as_array().into_iter().flatten())
+        .filter_map(|option| Some((option["text"].as_str()?.to_string(), option["score"].as_f64().unwrap_or_default())))
+        .collect::<Vec<_>>();
+    correction_options.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    let mut corrections: Vec<String> = vec![];
+    for (text, _) in correction_options {
+        if !corrections.contains(&text) {
+            corrections.push(text);
+        }
+    }
+    if !corrections.is_empty() {
+        // Count the visible items containing each word
+        let filters = corrections.iter().map(|correction| (correction.clone(), json!({"match": {"title": correction}}))).collect::<serde_json::Map<_, _>>();
+        let visible_q = json!({
+            "query": ctx_q,
+            "size": 0,
+            "aggs": {"corrections": {"filters": {"filters": filters}}}
+        });
+        let visible_result = raw_search_by_http(&visible_q, conn_uri, &index, funs).await?;
+        let buckets = &visible_result["aggregations"]["corrections"]["buckets"];
+        corrections.retain(|correction| buckets[correction]["doc_count"].as_u64().unwrap_or(0) > 0);
+        corrections.truncate(size);
+    }
+    Ok(SearchItemSuggestResp { completions, corrections })
+}
+
+pub async fn bulk_items(bulk_req: &mut SearchItemBulkReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<SearchItemBulkErrorResp>> {
+    let (client, ext, _) = inst.inst::<TardisSearchClient>();
+    let conn_uri = get_conn_uri(ext, funs)?;
+    let mut errors = Vec::new();
+    let mut upserts: HashMap<&str, Vec<&SearchItemAddReq>> = HashMap::new();
+    for add_req in bulk_req.upserts.iter().flatten() {
+        if let Some(vector) = &add_req.vector {
+            if let Err(e) = search_initializer::check_vector(&add_req.tag, vector, ext) {
+                errors.push(SearchItemBulkErrorResp::new(&add_req.tag, &add_req.key.to_string(), &e));
+                continue;
+            }
+        }
+        let add_reqs = upserts.entry(add_req.tag.as_str()).or_default();
+        // Keep the last one when a key occurs more than once, same as the PG backend
+        add_reqs.retain(|exist_req| exist_req.key.to_string() != add_req.key.to_string());
+        add_reqs.push(add_req);
+    }
+    let mut deletes: HashMap<&str, Vec<String>> = HashMap::new();
+    for delete_req in bulk_req.deletes.iter().flatten() {
+        deletes.entry(delete_req.tag.as_str()).or_default().push(delete_req.key.to_string());
+    }
+
+    // Lines of the bulk body, and the tag and key of each action to report its error
+    let mut actions = Vec::new();
+    let mut action_items: Vec<(&str, String)> = Vec::new();
+    let mut deleted_keys: HashMap<&str, Vec<String>> = HashMap::new();
+    for (tag, add_reqs) in upserts {
+        let index = format_index(tag, ext);
+        let keys = add_reqs.iter().map(|add_req| add_req.key.to_string()).collect::<Vec<String>>();
+        let mut init_result = search_es_initializer::init_index(client, &index, Some(&gen_data_mappings(search_initializer::get_vector_dim(ext, tag)))).await;
+        if init_result.is_ok() && add_reqs.iter().any(|add_req| add_req.vector.is_some()) {
+            init_result = init_vector_mapping(&index, tag, ext, funs).await;
+        }
+        let ids = match init_result {
+            Ok(_) => get_ids_by_keys(&keys, conn_uri, &index, funs).await,
+            Err(e) => Err(e),
+        };
+        match ids {
+            Ok(ids) => {
+                for (add_req, key) in add_reqs.into_iter().zip(keys) {
+                    // The existing document is overwritten, otherwise the id is generated like `add`
+                    if let Some(id) = ids.get(&key).and_then(|ids| ids.first()) {
+                        actions.push(json!({"index": {"_index": index, "_id": id}}).to_string());
+                    } else {
+                        actions.push(json!({"index": {"_index": index}}).to_string());
+                    }
+                    actions.push(TardisFuns::json.obj_to_string(add_req)?);
+                    action_items.push((tag, key));
+                }
+            }
+            Err(e) => errors.extend(keys.iter().map(|key| SearchItemBulkErrorResp::new(tag, key, &e))),
+        }
+    }
+    for (tag, keys) in deletes {
+        let index = format_index(tag, ext);
+        let ids = match client.check_index_exist(&index).await {
+            Ok(true) => get_ids_by_keys(&keys, conn_uri, &index, funs).await,
+            Ok(false) => Err(funs.err().bad_request("search_es_item_serv", "bulk_items", "index not exist", "400-search-index-not-exist")),
+            Err(e) => Err(e),
+        };
+        match ids {
+            Ok(ids) => {
+                for key in keys {
+                    for id in ids.get(&key).into_iter().flatten() {
+                        actions.push(json!({"delete": {"_index": index, "_id": id}}).to_string());
+                        action_items.push((tag, key.clone()));
+                    }
+                    deleted_keys.entry(tag).or_default().push(key);
+                }
+            }
+            Err(e) => errors.extend(keys.iter().map(|key| SearchItemBulkErrorResp::new(tag, key, &e))),
+        }
+    }
+    if actions.is_empty() {
+        return Ok(errors);
+    }
+
+    let resp = funs
+        .web_client()
+        .post_str_to_str(
+            &format!("{conn_uri}/_bulk?refresh=true"),
+            &format!("{}\n", actions.join("\n")),
+            Some(vec![("Content-Type".to_string(), "application/x-ndjson".to_string())]),
+        )
+        .await?;
+    let result = parse_http_resp(resp, "bulk_items", funs)?;
+    if result["errors"].as_bool().unwrap_or(false) {
+        for (item, (tag, key)) in result["items"].as_array().into_iter().flatten().zip(action_items) {
+            // Each item is like `{"index": {"status": 400, "error": {...}}}`
+            let Some(action_result) = item.as_object().and_then(|item| item.values().next()) else {
+                continue;
+            };
+            if let Some(error) = action_result.get("error") {
+                errors.push(SearchItemBulkErrorResp {
+                    tag: tag.to_string(),
+                    key,
+                    code: format!("{}-spi-search-bulk-item-error", action_result["status"].as_u64().unwrap_or(500)),
+                    msg: error.to_string(),
+                });
+            }
+        }
+    }
+    for (tag, keys) in deleted_keys {
+        let keys = keys.into_iter().filter(|key| !errors.iter().any(|error| error.tag == tag && &error.key == key)).collect::<Vec<_>>();
+        record_reindex_deletes(&format_index(tag, ext), &keys, funs).await?;
+    }
+    Ok(errors)
+}
+
+/// Returns the ids of the documents by key, there should be only one document for each key.
+async fn get_ids_by_keys(keys: &[String], conn_uri: &str, index: &str, funs: &TardisFunsInst) -> TardisResult<HashMap<String, Vec<String>>> {
+    let q = json!({
+        "query": {"terms": {"key": keys}},
+        "size": keys.len(),
+        "_source": ["key"]
+    });
+    let result = raw_search_by_http(&q, conn_uri, index, funs).await?;
+    let mut ids: HashMap<String, Vec<String>> = HashMap::new();
+    for hit in result["hits"]["hits"].as_array().into_iter().flatten() {
+        if let (Some(id), Some(key)) = (hit["_id"].as_str(), hit["_source"]["key"].as_str()) {
+            ids.entry(key.to_string()).or_default().push(id.to_string());
+        }
+    }
+    Ok(ids)
+}
+
+/// Copy the documents into a new index by `_reindex`, then point the tag to the new index by an alias.
+///
+/// The original index is blocked for writing during the final catch-up copy and the swap, so the writes are rejected in the meantime.
+/// The keys deleted during the reindex are recorded in the cache and deleted from the new index before the swap.
+pub async fn reindex_items(tag: &str, task_id: i64, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let (client, ext, _) = inst.inst::<TardisSearchClient>();
+    let index = format_index(tag, ext);
+    if !client.check_index_exist(&index).await? {
+        return Err(funs.err().bad_request("search_es_item_serv", "reindex_items", "index not exist", "400-search-index-not-exist"));
+    }
+    let conn_uri = get_conn_uri(ext, funs)?;
+    let new_index = format!("{index}-reindex-{}", Utc::now().timestamp_millis());
+    client.create_index(&new_index, Some(&gen_data_mappings(search_initializer::get_vector_dim(ext, tag)))).await?;
+    // Marked before the copy starts, so that the documents deleted after they are copied are always recorded
+    funs.cache().set_ex(&reindex_mark_key(&index, funs), &new_index, REINDEX_MARK_EXPIRE_SEC).await?;
+    let result = reindex_and_swap(&index, &new_index, task_id, conn_uri, funs).await;
+    VECTOR_MAPPED_INDEXES.write().await.remove(&index);
+    let _ = funs.cache().del(&reindex_mark_key(&index, funs)).await;
+    let _ = funs.cache().del(&reindex_deletes_key(&index, funs)).await;
+    if result.is_err() {
+        // The original index is kept, restore its writes and drop the incomplete new index
+        let _ = set_index_write_block(&index, false, conn_uri, funs).await;
+        let _ = funs.web_client().delete_to_void(&format!("{conn_uri}/{new_index}"), None).await;
+    }
+    result
+}
+
+async fn reindex_and_swap(index: &str, new_index: &str, task_id: i64, conn_uri: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+    // The documents changed since copied have greater versions, which are copied again in the catch-up
+    let reindex_q = json!({
+        "source": {"index": index},
+        "dest": {"index": new_index, "version_type": "external"},
+        "conflicts": "proceed"
+    });
+    let resp = funs
+        .web_client()
+        .post_str_to_str(
+            &format!("{conn_uri}/_reindex?wait_for_completion=false"),
+            &reindex_q.to_string(),
+            Some(vec![("Content-Type".to_string(), "application/json".to_string())]),
+        )
+        .await?;
+    let reindex_task_id = parse_http_resp(resp, "reindex_items", funs)?["task"].as_str().map(|reindex_task_id| reindex_task_id.to_string()).unwrap_or_default();
+    loop {
+        tardis::tokio::time::sleep(Duration::from_secs(1)).await;
+        let resp = funs.web_client().get_to_str(&format!("{conn_uri}/_tasks/{reindex_task_id}"), None).await?;
+        let reindex_task = parse_http_resp(resp, "reindex_items", funs)?;
+        let status = &reindex_task["task"]["status"];
+        funs.cache().expire(&reindex_mark_key(index, funs), REINDEX_MARK_EXPIRE_SEC).await?;
+        funs.cache().expire(&reindex_deletes_key(index, funs), REINDEX_MARK_EXPIRE_SEC).await?;
+        search_item_serv::set_reindex_progress(
+            task_id,
+            status["total"].as_u64(),
+            status["created"].as_u64().unwrap_or_default() + status["updated"].as_u64().unwrap_or_default(),
+            funs,
+        )
+        .await?;
+        if reindex_task["completed"].as_bool().unwrap_or(false) {
+            if let Some(error) = reindex_task.get("error").or_else(|| reindex_task["response"]["failures"].as_array().and_then(|failures| failures.first())) {
+                return Err(funs.err().internal_error("search_es_item_serv", "reindex_items", &format!("reindex error: {error}"), "500-spi-search-reindex-error"));
+            }
+            break;
+        }
+    }
+
+    let origin_indexes = get_concrete_indexes(index, conn_uri, funs).await?;
+    set_index_write_block(index, true, conn_uri, funs).await?;
+    let resp = funs
+        .web_client()
+        .post_str_to_str(
+            &format!("{conn_uri}/_reindex?refresh=true"),
+            &reindex_q.to_string(),
+            Some(vec![("Content-Type".to_string(), "application/json".to_string())]),
+        )
+        .await?;
+    let catch_up_result = parse_http_resp(resp, "reindex_items", funs)?;
+    if let Some(failure) = catch_up_result["failures"].as_array().and_then(|failures| failures.first()) {
+        return Err(funs.err().internal_error("search_es_item_serv", "reindex_items", &format!("reindex error: {failure}"), "500-spi-search-reindex-error"));
+    }
+    // No more deletes are recorded since the original index is blocked for writing
+    let deleted_keys = funs.cache().hgetall(&reindex_deletes_key(index, funs)).await?.into_keys().collect::<Vec<_>>();
+    for keys in deleted_keys.chunks(REINDEX_DELETE_BATCH_SIZE) {
+        let resp = funs
+            .web_client()
+            .post_str_to_str(
+                &format!("{conn_uri}/{new_index}/_delete_by_query?refresh=true"),
+                &json!({"query": {"terms": {"key": keys}}}).to_string(),
+                Some(vec![("Content-Type".to_string(), "application/json".to_string())]),
+            )
+            .await?;
+        parse_http_resp(resp, "reindex_items", funs)?;
+    }
+    // The original indexes are removed in the same request, which also works when the tag is not an alias yet
+    let mut actions = vec![json!({"add": {"index": new_index, "alias": index}})];
+    actions.extend(origin_indexes.iter().map(|origin_index| json!({"remove_index": {"index": origin_index}})));
+    let resp = funs
+        .web_client()
+        .post_str_to_str(
+            &format!("{conn_uri}/_aliases"),
+            &json!({ "actions": actions }).to_string(),
+            Some(vec![("Content-Type".to_string(), "application/json".to_string())]),
+        )
+        .await?;
+    parse_http_resp(resp, "reindex_items", funs)?;
+    Ok(())
+}
+
+fn reindex_mark_key(index: &str, funs: &TardisFunsInst) -> String {
+    format!("{}:reindexing:{index}", funs.conf::<SearchConfig>().cache_key_async_task_status)
+}
+
+fn reindex_deletes_key(index: &str, funs: &TardisFunsInst) -> String {
+    format!("{}:reindex-deletes:{index}", funs.conf::<SearchConfig>().cache_key_async_task_status)
+}
+
+/// Record the deleted keys while the index is being reindexed, called after the documents are deleted from the index.
+async fn record_reindex_deletes(index: &str, keys: &[String], funs: &TardisFunsInst) -> TardisResult<()> {
+    if keys.is_empty() || !funs.cache().exists(&reindex_mark_key(index, funs)).await? {
+        return Ok(());
+    }
+    let deletes_key = reindex_deletes_key(index, funs);
+    for key in keys {
+        funs.cache().hset(&deletes_key, key, "").await?;
+    }
+    Ok(())
+}
+
+/// Returns the indexes the alias points to, or the index itself when it is not an alias.
+async fn get_concrete_indexes(index: &str, conn_uri: &str, funs: &TardisFunsInst) -> TardisResult<Vec<String>> {
+    let resp = funs.web_client().get_to_str(&format!("{conn_uri}/_alias/{index}"), None).await?;
+    match resp.body {
+        Some(body) if resp.code == 200 => Ok(TardisFuns::json.str_to_json(&body)?.as_object().map(|indexes| indexes.keys().cloned().collect()).unwrap_or_default()),
+        _ => Ok(vec![index.to_string()]),
+    }
+}
+
+async fn set_index_write_block(index: &str, block: bool, conn_uri: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+    let resp = funs
+        .web_client()
+        .put_str_to_str(
+            &format!("{conn_uri}/{index}/_settings"),
+            &json!({"index.blocks.write": block}).to_string(),
+            Some(vec![("Content-Type".to_string(), "application/json".to_string())]),
+        )
+        .await?;
+    parse_http_resp(resp, "reindex_items", funs)?;
+    Ok(())
+}
+
+fn parse_http_resp(resp: TardisHttpResponse<String>, op: &str, funs: &TardisFunsInst) -> TardisResult<serde_json::Value> {
+    let body = resp.body.unwrap_or_default();
+    if resp.code != 200 {
+        return Err(funs.err().internal_error("search_es_item_serv", op, &format!("request error: {body}"), "500-spi-search-request-error"));
+    }
+    TardisFuns::json.str_to_json(&body)
+}
+
+fn get_conn_uri<'a>(ext: &'a HashMap<String, String>, funs: &TardisFunsInst) -> TardisResult<&'a str> {
+    Ok(ext
+        .get(search_constants::CONN_URI_FLAG)
+        .ok_or_else(|| {
+            funs.err().internal_error(
+                "search_es_item_serv",
+                "search",
+                "The connection uri of backend service is not found",
+                "500-spi-search-conn-uri-not-found",
+            )
+        })?
+        .trim_end_matches('/'))
+}
+
+async fn raw_search_by_http(body: &serde_json::Value, conn_uri: &str, index: &str, funs: &TardisFunsInst) -> TardisResult<serde_json::Value> {
+    let resp = funs
+        .web_client()
+        .post_str_to_str(
+            &format!("{conn_uri}/{index}/_search"),
+            &body.to_string(),
+            Some(vec![("Content-Type".to_string(), "application/json".to_string())]),
+        )
+        .await?;
+    let body = resp.body.unwrap_or_default();
+    if resp.code != 200 {
+        return Err(funs.err().internal_error("search_es_item_serv", "search", &format!("search error: {body}"), "500-spi-search-request-error"));
+    }
+    TardisFuns::json.str_to_json(&body)
+}
+
+/// The ext is dynamically mapped, strings are mapped as text with a keyword sub-field, which is the one can be aggregated.
+/// Check whether the field is mapped by all the indexes behind the name
+async fn check_field_mapped(field: &str, conn_uri: &str, index: &str, funs: &TardisFunsInst) -> TardisResult<bool> {
+    let resp = funs.web_client().get_to_str(&format!("{conn_uri}/{index}/_mapping/field/{field}"), None).await?;
+    let mapping = match resp.body {
+        Some(body) if resp.code == 200 => TardisFuns::json.str_to_json(&body)?,
+        _ => return Ok(false),
+    };
+    Ok(mapping.as_object().map(|index_mappings| !index_mappings.is_empty() && index_mappings.values().all(|index_mapping| index_mapping["mappings"].get(field).is_some())).unwrap_or(false))
+}
+
+async fn get_ext_agg_field(field: &str, conn_uri: &str, index: &str, funs: &TardisFunsInst) -> TardisResult<String> {
+    let field = format!("ext.{field}");
+    let resp = funs.web_client().get_to_str(&format!("{conn_uri}/{index}/_mapping/field/{field}"), None).await?;
+    let mapping = match resp.body {
+        Some(body) if resp.code == 200 => TardisFuns::json.str_to_json(&body)?,
+        _ => serde_json::Value::Null,
+    };
+    let is_text = mapping
+        .as_object()
+        .into_iter()
+        .flat_map(|index_mappings| index_mappings.values())
+        .filter_map(|index_mapping| index_mapping["mappings"][&field]["mapping"].as_object())
+        .flat_map(|field_mapping| field_mapping.values())
+        .any(|field_mapping| field_mapping["type"] == "text");
+    Ok(if is_text { format!("{field}.keyword") } else { field })
+}
+
+fn gen_ctx_query_dsl(req_ctx: &SearchItemSearchCtxReq) -> Vec<serde_json::Value> {
     let mut ctx_q = vec![];
-    if let Some(accounts) = &search_req.ctx.accounts {
+    if let Some(accounts) = &req_ctx.accounts {
         ctx_q.push(json!({
             "terms": {
                 "visit_keys.accounts": accounts
             }
         }));
     }
-    if let Some(apps) = &search_req.ctx.apps {
+    if let Some(apps) = &req_ctx.apps {
         ctx_q.push(json!({
             "terms": {
                 "visit_keys.apps": apps
             }
         }));
     }
-    if let Some(tenants) = &search_req.ctx.tenants {
+    if let Some(tenants) = &req_ctx.tenants {
         ctx_q.push(json!({
             "terms": {
                 "visit_keys.tenants": tenants
             }
         }));
     }
-    if let Some(roles) = &search_req.ctx.roles {
+    if let Some(roles) = &req_ctx.roles {
         ctx_q.push(json!({
             "terms": {
                 "visit_keys.roles": roles
             }
         }));
     }
-    if let Some(groups) = &search_req.ctx.groups {
+    if let Some(groups) = &req_ctx.groups {
         ctx_q.push(json!({
             "terms": {
                 "visit_keys.groups": groups
             }
         }));
     }
+    ctx_q
+}
+
+fn gen_query_dsl(search_req: &SearchItemSearchReq) -> TardisResult<String> {
+    let mut must_q = vec![];
+    let mut must_not_q = vec![];
+    let mut should_q = vec![];
+    let mut filter_q = vec![];
+    let mut sort_q = vec![];
+
+    // ctx
+    let mut ctx_q = gen_ctx_query_dsl(&search_req.ctx);
     if search_req.ctx.cond_by_or.unwrap_or(false) {
         should_q.append(&mut ctx_q);
     } else {
@@ -598,6 +1287,44 @@ fn gen_query_dsl(search_req: &SearchItemSearchReq) -> TardisResult<String> {
     Ok(q.to_string())
 }
 
+/// The items matching `q` and the nearest items matching the other conditions are both returned,
+/// the scores are blended by the weight of the knn, i.e. `(1 - weight) * score of q + weight * similarity`.
+///
+/// Only the items matching `q` are returned when the vector field is not mapped, since no item has a vector then.
+fn gen_hybrid_query_dsl(search_req: &mut SearchItemSearchReq, vector_mapped: bool) -> TardisResult<String> {
+    let mut q = TardisFuns::json.str_to_json(&gen_query_dsl(search_req)?)?;
+    if !vector_mapped {
+        if search_req.query.q.is_none() {
+            q["query"] = json!({"match_none": {}});
+        }
+        return Ok(q.to_string());
+    }
+    let keyword_q = search_req.query.q.take();
+    let filter_q = TardisFuns::json.str_to_json(&gen_query_dsl(search_req)?)?["query"].take();
+    search_req.query.q = keyword_q;
+    let Some(knn) = &search_req.query.knn else {
+        return Ok(q.to_string());
+    };
+    let weight = knn.weight();
+    q["knn"] = json!({
+        "field": "vector",
+        "query_vector": knn.vector,
+        "k": knn.k(),
+        "num_candidates": (knn.k() as u32 * 10).clamp(100, 10000),
+        "filter": filter_q,
+        "boost": weight
+    });
+    if search_req.query.q.is_some() {
+        q["query"]["bool"]["boost"] = json!(1.0 - weight);
+    } else if let Some(q) = q.as_object_mut() {
+        q.remove("query");
+    }
+    if search_req.sort.is_none() {
+        q["sort"] = json!([{"_score": {"order": "desc"}}]);
+    }
+    Ok(q.to_string())
+}
+
 fn merge(a: &mut serde_json::Value, b: serde_json::Value) {
     match (a, b) {
         (a @ &mut serde_json::Value::Object(_), serde_json::Value::Object(b)) => {
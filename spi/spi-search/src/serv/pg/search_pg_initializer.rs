@@ -1,41 +1,322 @@
-use bios_basic::spi::{spi_funs::TypedSpiBsInst, spi_initializer};
+use std::collections::{HashMap, HashSet};
+
+use bios_basic::spi::{
+    dto::spi_bs_dto::SpiBsCertResp,
+    spi_constants::GLOBAL_STORAGE_FLAG,
+    spi_funs::{SpiBsInst, TypedSpiBsInst},
+    spi_initializer,
+};
+use lazy_static::lazy_static;
 use tardis::{
-    basic::{dto::TardisContext, result::TardisResult},
-    db::reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
+    basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    db::{
+        reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
+        sea_orm::Value,
+    },
+    log::warn,
+    tokio::sync::RwLock,
+    TardisFuns,
 };
 
-pub async fn init_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, tag: &str, ctx: &TardisContext, mgr: bool) -> TardisResult<(TardisRelDBlConnection, String)> {
-    spi_initializer::common_pg::init_table_and_conn(
-        bs_inst,
-        ctx,
-        mgr,
-        Some(tag),
-        "search",
-        r#"kind character varying NOT NULL,
+use crate::{search_constants, search_initializer};
+
+// Used when not specified, requires the zhparser extension
+const DEFAULT_TEXT_SEARCH_CONFIG: &str = "chinese_zh";
+// Built-in configuration used when the default one is not available
+const FALLBACK_TEXT_SEARCH_CONFIG: &str = "pg_catalog.simple";
+
+lazy_static! {
+    // Tables known to have the columns added after the first version
+    static ref UPGRADED_TABLES: RwLock<HashSet<String>> = RwLock::new(HashSet::new());
+}
+
+/// The text search configuration is specified by the ext of the backend service, e.g.
+/// `{"text_search_config":"english","tag_text_search_configs":{"feed_zh":"chinese_zh"}}`,
+/// the latter overrides the former for the tags in it, so that the tags can be in different languages.
+///
+/// The value is one of `simple`, `english`, `chinese_zh`, or the name of a custom configuration.
+/// The initialization fails if a specified configuration is not available in the database,
+/// only the default one (`chinese_zh` when not specified) falls back to `simple`.
+/// Changing the configuration of a tag that has items makes them unmatched until they are modified.
+///
+/// The vectors of the tags declared by `tag_vector_dims` require the pgvector extension.
+pub async fn init(bs_cert: &SpiBsCertResp, ctx: &TardisContext, mgr: bool) -> TardisResult<SpiBsInst> {
+    let mut inst = spi_initializer::common_pg::init(bs_cert, ctx, mgr).await?;
+    let bs_ext = TardisFuns::json.str_to_json(&bs_cert.ext)?;
+    let mut inst_ext = HashMap::new();
+    {
+        let conn = inst.inst::<TardisRelDBClient>().0.conn();
+        let text_search_config = match bs_ext.get("text_search_config") {
+            Some(text_search_config) => {
+                let text_search_config = text_search_config
+                    .as_str()
+                    .ok_or_else(|| TardisError::bad_request("The text search configuration must be a string", "400-spi-search-text-search-config-illegal"))?;
+                check_text_search_config(&conn, text_search_config, false).await?
+            }
+            None => check_text_search_config(&conn, DEFAULT_TEXT_SEARCH_CONFIG, true).await?,
+        };
+        inst_ext.insert(search_constants::TEXT_SEARCH_CONFIG_FLAG.to_string(), text_search_config);
+        if let Some(tag_text_search_configs) = bs_ext.get("tag_text_search_configs").and_then(|configs| configs.as_object()) {
+            for (tag, text_search_config) in tag_text_search_configs {
+                let text_search_config = text_search_config.as_str().ok_or_else(|| {
+                    TardisError::bad_request(
+                        &format!("The text search configuration of tag [{tag}] must be a string"),
+                        "400-spi-search-text-search-config-illegal",
+                    )
+                })?;
+                inst_ext.insert(
+                    format!("{}{tag}", search_constants::TEXT_SEARCH_CONFIG_FLAG),
+                    check_text_search_config(&conn, text_search_config, false).await?,
+                );
+            }
+        }
+        if let Some(trgm_schema) = check_extension(&conn, "pg_trgm").await? {
+            inst_ext.insert(search_constants::TRGM_SCHEMA_FLAG.to_string(), trgm_schema);
+        }
+        search_initializer::init_vector_dims(&bs_ext, &mut inst_ext)?;
+        if inst_ext.keys().any(|key| key.starts_with(search_constants::VECTOR_DIM_FLAG)) {
+            if let Some(vector_schema) = check_extension(&conn, "vector").await? {
+                inst_ext.insert(search_constants::VECTOR_SCHEMA_FLAG.to_string(), vector_schema);
+            }
+        }
+    }
+    inst.ext.extend(inst_ext);
+    Ok(inst)
+}
+
+/// Install the extension if it is not installed, returns its schema.
+///
+/// The extensions require the privilege to be installed, the features depending on them are not available without it,
+/// i.e. the corrections of the suggestions for `pg_trgm` and the vectors for `vector` (pgvector).
+async fn check_extension(conn: &TardisRelDBlConnection, extname: &str) -> TardisResult<Option<String>> {
+    if let Some(schema) = get_installed_extension_schema(conn, extname).await? {
+        return Ok(Some(schema));
+    }
+    if let Err(e) = conn.execute_one(&format!("CREATE EXTENSION IF NOT EXISTS {extname} SCHEMA public"), vec![]).await {
+        warn!("[SPI-Search] The {extname} extension is not available, the features depending on it are disabled: {e:?}");
+        return Ok(None);
+    }
+    get_installed_extension_schema(conn, extname).await
+}
+
+async fn get_installed_extension_schema(conn: &TardisRelDBlConnection, extname: &str) -> TardisResult<Option<String>> {
+    let result = conn.query_one("SELECT extnamespace::regnamespace::text AS schema_name FROM pg_extension WHERE extname = $1", vec![Value::from(extname)]).await?;
+    Ok(match result {
+        Some(result) => Some(result.try_get("", "schema_name")?),
+        None => None,
+    })
+}
+
+pub fn get_trgm_schema(ext: &HashMap<String, String>) -> Option<&str> {
+    ext.get(search_constants::TRGM_SCHEMA_FLAG).map(|trgm_schema| trgm_schema.as_str())
+}
+
+/// Returns the schema of the vector extension when the vectors of the tag are enabled and the extension is available.
+pub fn get_vector_schema<'a>(ext: &'a HashMap<String, String>, tag: &str) -> Option<&'a str> {
+    search_initializer::get_vector_dim(ext, tag)?;
+    ext.get(search_constants::VECTOR_SCHEMA_FLAG).map(|vector_schema| vector_schema.as_str())
+}
+
+/// Check the vector against the dimension of the tag, returns the schema of the vector extension.
+pub fn check_vector<'a>(tag: &str, vector: &[f32], ext: &'a HashMap<String, String>) -> TardisResult<&'a str> {
+    search_initializer::check_vector(tag, vector, ext)?;
+    get_vector_schema(ext, tag).ok_or_else(|| TardisError::bad_request("The vector extension of PG is not available", "400-spi-search-vector-not-supported"))
+}
+
+/// The literal of the vector type, passed as a text parameter and cast to the type in SQL.
+pub fn vector_to_sql(vector: &[f32]) -> String {
+    format!("[{}]", vector.iter().map(|value| value.to_string()).collect::<Vec<_>>().join(","))
+}
+
+/// Resolve the name of the text search configuration and check that it is available in the database.
+///
+/// An unavailable configuration is an error unless `fallback` is set, then `simple` is used instead,
+/// e.g. the default `chinese_zh` without the zhparser extension installed.
+async fn check_text_search_config(conn: &TardisRelDBlConnection, name: &str, fallback: bool) -> TardisResult<String> {
+    let text_search_config = match name {
+        "simple" => "pg_catalog.simple",
+        "english" => "pg_catalog.english",
+        "chinese_zh" => "public.chinese_zh",
+        // It is embedded into SQL, so only `[schema.]name` is accepted
+        custom if custom.split('.').count() <= 2 && custom.split('.').all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')) => custom,
+        _ => {
+            return Err(TardisError::bad_request(
+                &format!("The text search configuration [{name}] is illegal"),
+                "400-spi-search-text-search-config-illegal",
+            ))
+        }
+    };
+    if conn.count_by_sql("SELECT 1 WHERE to_regconfig($1) IS NOT NULL", vec![Value::from(text_search_config)]).await? == 0 {
+        if !fallback {
+            return Err(TardisError::bad_request(
+                &format!("The text search configuration [{name}] is not available in the database"),
+                "400-spi-search-text-search-config-not-available",
+            ));
+        }
+        warn!("[SPI-Search] The text search configuration [{text_search_config}] is not available, fall back to [{FALLBACK_TEXT_SEARCH_CONFIG}]");
+        return Ok(FALLBACK_TEXT_SEARCH_CONFIG.to_string());
+    }
+    Ok(text_search_config.to_string())
+}
+
+pub fn get_text_search_config<'a>(ext: &'a HashMap<String, String>, tag: &str) -> &'a str {
+    ext.get(&format!("{}{tag}", search_constants::TEXT_SEARCH_CONFIG_FLAG))
+        .or_else(|| ext.get(search_constants::TEXT_SEARCH_CONFIG_FLAG))
+        .map(|text_search_config| text_search_config.as_str())
+        .unwrap_or(FALLBACK_TEXT_SEARCH_CONFIG)
+}
+
+const TABLE_CREATE_CONTENT: &str = r#"kind character varying NOT NULL,
     key character varying NOT NULL PRIMARY KEY,
     title character varying NOT NULL,
     title_tsv tsvector,
+    content text NOT NULL DEFAULT '',
     content_tsv tsvector,
     owner character varying NOT NULL,
     own_paths character varying NOT NULL,
     create_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
     update_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
     ext jsonb NOT NULL,
-    visit_keys jsonb"#,
-        vec![
-            ("kind", "btree"),
-            ("key", "btree"),
-            ("title_tsv", "gin"),
-            ("content_tsv", "gin"),
-            ("ext", "gin"),
-            ("owner", "btree"),
-            ("own_paths", "btree"),
-            ("create_time", "btree"),
-            ("update_time", "btree"),
-            ("visit_keys", "gin"),
-        ],
+    visit_keys jsonb"#;
+const TABLE_INDEXES: [(&str, &str); 10] = [
+    ("kind", "btree"),
+    ("key", "btree"),
+    ("title_tsv", "gin"),
+    ("content_tsv", "gin"),
+    ("ext", "gin"),
+    ("owner", "btree"),
+    ("own_paths", "btree"),
+    ("create_time", "btree"),
+    ("update_time", "btree"),
+    ("visit_keys", "gin"),
+];
+// The shadow table of a tag is a table of the tag with this suffix
+const REINDEX_TAG_SUFFIX: &str = "__reindex";
+
+pub async fn init_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, tag: &str, ctx: &TardisContext, mgr: bool) -> TardisResult<(TardisRelDBlConnection, String)> {
+    let (conn, table_name) = do_init_table_and_conn(bs_inst, tag, ctx, mgr).await?;
+    upgrade_table(&conn, &table_name, tag, bs_inst.1).await?;
+    Ok((conn, table_name))
+}
+
+async fn do_init_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, tag: &str, ctx: &TardisContext, mgr: bool) -> TardisResult<(TardisRelDBlConnection, String)> {
+    spi_initializer::common_pg::init_table_and_conn(
+        bs_inst,
+        ctx,
+        mgr,
+        Some(tag),
+        "search",
+        TABLE_CREATE_CONTENT,
+        TABLE_INDEXES.to_vec(),
         None,
         Some("update_time"),
     )
     .await
 }
+
+/// Create the empty shadow table of the tag to be filled by the reindex, the one left by a failed reindex is dropped.
+///
+/// The returned connection holds an advisory lock of the shadow table in its transaction until it is dropped,
+/// so that a concurrent reindex of the tag is rejected instead of dropping the shadow table being filled.
+pub async fn init_reindex_table(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, tag: &str, ctx: &TardisContext) -> TardisResult<(TardisRelDBlConnection, String)> {
+    let schema_name = get_schema_name(bs_inst)?;
+    let mut lock_conn = bs_inst.0.conn();
+    lock_conn.begin().await?;
+    let locked = lock_conn
+        .query_one(
+            "SELECT pg_try_advisory_xact_lock(hashtext($1)) AS locked",
+            vec![Value::from(format!("{schema_name}.{GLOBAL_STORAGE_FLAG}_search_{tag}{REINDEX_TAG_SUFFIX}"))],
+        )
+        .await?
+        .map(|result| result.try_get::<bool>("", "locked"))
+        .transpose()?
+        .unwrap_or(false);
+    if !locked {
+        return Err(TardisError::conflict(&format!("The tag [{tag}] is being reindexed"), "409-spi-search-reindex-running"));
+    }
+    bs_inst
+        .0
+        .conn()
+        .execute_one(&format!("DROP TABLE IF EXISTS {schema_name}.{GLOBAL_STORAGE_FLAG}_search_{tag}{REINDEX_TAG_SUFFIX}"), vec![])
+        .await?;
+    let (conn, shadow_table_name) = do_init_table_and_conn(bs_inst, &format!("{tag}{REINDEX_TAG_SUFFIX}"), ctx, true).await?;
+    // Not cached, since the shadow table is created again by every reindex
+    do_upgrade_table(&conn, &shadow_table_name, tag, bs_inst.1).await?;
+    Ok((lock_conn, shadow_table_name))
+}
+
+/// Replace the table of the tag by its shadow table within the transaction of the connection.
+///
+/// The indexes are renamed as well, since their names are derived from the tag when created.
+pub async fn swap_reindex_table(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, tag: &str, conn: &TardisRelDBlConnection) -> TardisResult<()> {
+    let schema_name = get_schema_name(bs_inst)?;
+    let shadow_tag = format!("{tag}{REINDEX_TAG_SUFFIX}");
+    conn.execute_one(&format!("DROP TABLE {schema_name}.{GLOBAL_STORAGE_FLAG}_search_{tag}"), vec![]).await?;
+    conn.execute_one(
+        &format!("ALTER TABLE {schema_name}.{GLOBAL_STORAGE_FLAG}_search_{shadow_tag} RENAME TO {GLOBAL_STORAGE_FLAG}_search_{tag}"),
+        vec![],
+    )
+    .await?;
+    conn.execute_one(
+        &format!("ALTER INDEX {schema_name}.{GLOBAL_STORAGE_FLAG}_search_{shadow_tag}_pkey RENAME TO {GLOBAL_STORAGE_FLAG}_search_{tag}_pkey"),
+        vec![],
+    )
+    .await?;
+    for index_suffix in ["title_trgm", "vector"] {
+        conn.execute_one(
+            &format!("ALTER INDEX IF EXISTS {schema_name}.{GLOBAL_STORAGE_FLAG}_search_{shadow_tag}_{index_suffix} RENAME TO {GLOBAL_STORAGE_FLAG}_search_{tag}_{index_suffix}"),
+            vec![],
+        )
+        .await?;
+    }
+    for idx in 0..TABLE_INDEXES.len() {
+        conn.execute_one(
+            &format!("ALTER INDEX {schema_name}.idx_{schema_name}_{shadow_tag}_search_{idx} RENAME TO idx_{schema_name}_{tag}_search_{idx}"),
+            vec![],
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+fn get_schema_name(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>) -> TardisResult<String> {
+    spi_initializer::common_pg::get_schema_name_from_ext(bs_inst.1)
+        .ok_or_else(|| TardisError::internal_error("The schema of backend service is not found", "500-spi-search-schema-not-found"))
+}
+
+/// Add the raw content used by highlighting and the trigram index of title used by suggestions to the tables created by older versions,
+/// and the vector column to the tables of the tags whose vectors are enabled.
+///
+/// The raw content of the rows inserted before was not stored and can't be recovered from `content_tsv`,
+/// so their content is left empty and not highlighted until modified.
+/// The dimension of the column is not changed once added, so the vectors of a tag must be dropped before changing its dimension.
+async fn upgrade_table(conn: &TardisRelDBlConnection, table_name: &str, tag: &str, ext: &HashMap<String, String>) -> TardisResult<()> {
+    if UPGRADED_TABLES.read().await.contains(table_name) {
+        return Ok(());
+    }
+    do_upgrade_table(conn, table_name, tag, ext).await?;
+    UPGRADED_TABLES.write().await.insert(table_name.to_string());
+    Ok(())
+}
+
+async fn do_upgrade_table(conn: &TardisRelDBlConnection, table_name: &str, tag: &str, ext: &HashMap<String, String>) -> TardisResult<()> {
+    conn.execute_one(&format!("ALTER TABLE {table_name} ADD COLUMN IF NOT EXISTS content text NOT NULL DEFAULT ''"), vec![]).await?;
+    // The indexes are created in the schema of the table
+    let index_name_prefix = table_name.rsplit('.').next().unwrap_or(table_name);
+    if let Some(trgm_schema) = get_trgm_schema(ext) {
+        conn.execute_one(
+            &format!("CREATE INDEX IF NOT EXISTS {index_name_prefix}_title_trgm ON {table_name} USING gin(title {trgm_schema}.gin_trgm_ops)"),
+            vec![],
+        )
+        .await?;
+    }
+    if let (Some(vector_schema), Some(vector_dim)) = (get_vector_schema(ext, tag), search_initializer::get_vector_dim(ext, tag)) {
+        conn.execute_one(&format!("ALTER TABLE {table_name} ADD COLUMN IF NOT EXISTS vector {vector_schema}.vector({vector_dim})"), vec![]).await?;
+        conn.execute_one(
+            &format!("CREATE INDEX IF NOT EXISTS {index_name_prefix}_vector ON {table_name} USING hnsw(vector {vector_schema}.vector_cosine_ops)"),
+            vec![],
+        )
+        .await?;
+    }
+    Ok(())
+}
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use bios_basic::{basic_enumeration::BasicQueryOpKind, dto::BasicQueryCondInfo, helper::db_helper, spi::spi_funs::SpiBsInst};
 use tardis::{
     basic::{dto::TardisContext, result::TardisResult},
@@ -7,14 +9,25 @@ use tardis::{
         sea_orm::Value,
     },
     serde_json,
-    web::web_resp::TardisPage,
     TardisFuns, TardisFunsInst,
 };
 
-use crate::dto::search_item_dto::{SearchItemAddReq, SearchItemModifyReq, SearchItemSearchQScopeKind, SearchItemSearchReq, SearchItemSearchResp};
+use crate::{
+    dto::search_item_dto::{
+        SearchItemAddReq, SearchItemBulkErrorResp, SearchItemBulkReq, SearchItemModifyReq, SearchItemSearchAggBucketResp, SearchItemSearchAggReq, SearchItemSearchAggResp,
+        SearchItemSearchCtxReq, SearchItemSearchPageResp, SearchItemSearchQScopeKind, SearchItemSearchReq, SearchItemSearchResp, SearchItemSuggestCompletionResp,
+        SearchItemSuggestReq, SearchItemSuggestResp,
+    },
+    serv::search_item_serv,
+};
 
 use super::search_pg_initializer;
 
+// Maximum number of titles split into the words for the corrections of each input word
+const SUGGEST_CORRECTION_CANDIDATES: usize = 100;
+// Number of items copied in one statement when reindexing
+const REINDEX_BATCH_SIZE: u32 = 500;
+
 pub async fn add(add_req: &mut SearchItemAddReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
     let mut params = Vec::new();
     params.push(Value::from(add_req.kind.to_string()));
@@ -36,15 +49,24 @@ pub async fn add(add_req: &mut SearchItemAddReq, _funs: &TardisFunsInst, ctx: &T
     };
 
     let bs_inst = inst.inst::<TardisRelDBClient>();
+    let mut vector_fragments = ("", "".to_string());
+    if let Some(vector) = &add_req.vector {
+        let vector_schema = search_pg_initializer::check_vector(&add_req.tag, vector, bs_inst.1)?;
+        params.push(Value::from(search_pg_initializer::vector_to_sql(vector)));
+        vector_fragments = (", vector", format!(", ${}::{vector_schema}.vector", params.len()));
+    }
+    let text_search_config = search_pg_initializer::get_text_search_config(bs_inst.1, &add_req.tag);
     let (mut conn, table_name) = search_pg_initializer::init_table_and_conn(bs_inst, &add_req.tag, ctx, true).await?;
     conn.begin().await?;
     conn.execute_one(
         &format!(
             r#"INSERT INTO {table_name} 
-    (kind, key, title, title_tsv, content_tsv, owner, own_paths, create_time, update_time, ext, visit_keys)
+    (kind, key, title, title_tsv, content, content_tsv, owner, own_paths, create_time, update_time, ext, visit_keys{})
 VALUES
-    ($1, $2, $3, to_tsvector('public.chinese_zh', $4), to_tsvector('public.chinese_zh', $5), $6, $7, $8, $9, $10, {})"#,
+    ($1, $2, $3, to_tsvector('{text_search_config}', $4), $5, to_tsvector('{text_search_config}', $5), $6, $7, $8, $9, $10, {}{})"#,
+            vector_fragments.0,
             if add_req.visit_keys.is_some() { "$11" } else { "null" },
+            vector_fragments.1,
         ),
         params,
     )
@@ -55,6 +77,7 @@ VALUES
 
 pub async fn modify(tag: &str, key: &str, modify_req: &mut SearchItemModifyReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
     let bs_inst = inst.inst::<TardisRelDBClient>();
+    let text_search_config = search_pg_initializer::get_text_search_config(bs_inst.1, tag);
     let (mut conn, table_name) = search_pg_initializer::init_table_and_conn(bs_inst, tag, ctx, true).await?;
 
     let mut params = Vec::new();
@@ -68,11 +91,12 @@ pub async fn modify(tag: &str, key: &str, modify_req: &mut SearchItemModifyReq,
     };
     if let Some(title) = &modify_req.title {
         sql_sets.push(format!("title = ${}", params.len() + 1));
-        sql_sets.push(format!("title_tsv = to_tsvector('public.chinese_zh', ${})", params.len() + 1));
+        sql_sets.push(format!("title_tsv = to_tsvector('{text_search_config}', ${})", params.len() + 1));
         params.push(Value::from(title));
     };
     if let Some(content) = &modify_req.content {
-        sql_sets.push(format!("content_tsv = to_tsvector('public.chinese_zh', ${})", params.len() + 1));
+        sql_sets.push(format!("content = ${}", params.len() + 1));
+        sql_sets.push(format!("content_tsv = to_tsvector('{text_search_config}', ${})", params.len() + 1));
         params.push(Value::from(content));
     };
     if let Some(owner) = &modify_req.owner {
@@ -106,6 +130,11 @@ pub async fn modify(tag: &str, key: &str, modify_req: &mut SearchItemModifyReq,
         sql_sets.push(format!("visit_keys = ${}", params.len() + 1));
         params.push(Value::from(visit_keys.to_sql()));
     };
+    if let Some(vector) = &modify_req.vector {
+        let vector_schema = search_pg_initializer::check_vector(tag, vector, bs_inst.1)?;
+        sql_sets.push(format!("vector = ${}::{vector_schema}.vector", params.len() + 1));
+        params.push(Value::from(search_pg_initializer::vector_to_sql(vector)));
+    };
 
     conn.begin().await?;
     conn.execute_one(
@@ -140,8 +169,14 @@ async fn get_ext(tag: &str, key: &str, table_name: &str, conn: &TardisRelDBlConn
     Ok(result.try_get("", "ext")?)
 }
 
-pub async fn search(search_req: &mut SearchItemSearchReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<TardisPage<SearchItemSearchResp>> {
-    let select_fragments;
+pub async fn search(search_req: &mut SearchItemSearchReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<SearchItemSearchPageResp> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let text_search_config = search_pg_initializer::get_text_search_config(bs_inst.1, &search_req.tag);
+    let (conn, table_name) = search_pg_initializer::init_table_and_conn(bs_inst, &search_req.tag, ctx, false).await?;
+    // The expressions of rank_title and rank_content
+    let mut rank_fragments = ("0::float4", "0::float4");
+    let mut where_q_fragment = None;
+    let mut highlight_fragments = ", NULL::text AS title_highlight, NULL::text AS content_highlight".to_string();
     let mut from_fragments = "".to_string();
     let mut where_fragments: Vec<String> = vec!["1=1".to_string()];
     let mut order_fragments: Vec<String> = Vec::new();
@@ -161,49 +196,41 @@ pub async fn search(search_req: &mut SearchItemSearchReq, funs: &TardisFunsInst,
             })
             .collect::<String>();
         sql_vals.push(Value::from(q.as_str()));
-        from_fragments = format!(", to_tsquery('public.chinese_zh', ${}) AS query", sql_vals.len());
+        from_fragments = format!(", to_tsquery('{text_search_config}', ${}) AS query", sql_vals.len());
         match search_req.query.q_scope.as_ref().unwrap_or(&SearchItemSearchQScopeKind::Title) {
             SearchItemSearchQScopeKind::Title => {
-                select_fragments = ", COALESCE(ts_rank(title_tsv, query), 0::float4) AS rank_title, 0::float4 AS rank_content".to_string();
-                where_fragments.push("(query @@ title_tsv)".to_string());
+                rank_fragments = ("COALESCE(ts_rank(title_tsv, query), 0::float4)", "0::float4");
+                where_q_fragment = Some("(query @@ title_tsv)");
             }
             SearchItemSearchQScopeKind::Content => {
-                select_fragments = ", 0::float4 AS rank_title, COALESCE(ts_rank(content_tsv, query), 0::float4) AS rank_content".to_string();
-                where_fragments.push("(query @@ content_tsv)".to_string());
+                rank_fragments = ("0::float4", "COALESCE(ts_rank(content_tsv, query), 0::float4)");
+                where_q_fragment = Some("(query @@ content_tsv)");
             }
             SearchItemSearchQScopeKind::TitleContent => {
-                select_fragments = ", COALESCE(ts_rank(title_tsv, query), 0::float4) AS rank_title, COALESCE(ts_rank(content_tsv, query), 0::float4) AS rank_content".to_string();
-                where_fragments.push("(query @@ title_tsv OR query @@ content_tsv)".to_string());
+                rank_fragments = ("COALESCE(ts_rank(title_tsv, query), 0::float4)", "COALESCE(ts_rank(content_tsv, query), 0::float4)");
+                where_q_fragment = Some("(query @@ title_tsv OR query @@ content_tsv)");
             }
         }
-    } else {
-        select_fragments = ", 0::float4 AS rank_title, 0::float4 AS rank_content".to_string();
+        if let Some(highlight) = &search_req.highlight {
+            // The whole title is returned, while the content is cut into fragments
+            sql_vals.push(Value::from(format!(r#"StartSel="{}", StopSel="{}", HighlightAll=true"#, highlight.pre_tag(), highlight.post_tag())));
+            let title_options_idx = sql_vals.len();
+            let mut content_options = format!(r#"StartSel="{}", StopSel="{}", MaxFragments=3"#, highlight.pre_tag(), highlight.post_tag());
+            if let Some(fragment_size) = highlight.fragment_size {
+                content_options.push_str(&format!(", MaxWords={fragment_size}, MinWords={}", fragment_size / 2));
+            }
+            sql_vals.push(Value::from(content_options));
+            highlight_fragments = format!(
+                r#", CASE WHEN query @@ title_tsv THEN ts_headline('{text_search_config}', title, query, ${title_options_idx}) END AS title_highlight,
+    CASE WHEN query @@ content_tsv AND content <> '' THEN ts_headline('{text_search_config}', content, query, ${}) END AS content_highlight"#,
+                sql_vals.len()
+            );
+        }
     }
 
     // Add visit_keys filter
-    let req_ctx = search_req.ctx.to_sql();
-    if !req_ctx.is_empty() {
-        let mut where_visit_keys_fragments = Vec::new();
-        for (scope_key, scope_values) in req_ctx {
-            if scope_values.is_empty() {
-                continue;
-            }
-            if scope_values.len() == 1 {
-                where_visit_keys_fragments.push(format!("visit_keys -> '{scope_key}' ? ${}", sql_vals.len() + 1));
-            } else {
-                where_visit_keys_fragments.push(format!(
-                    "visit_keys -> '{scope_key}' ?| array[{}]",
-                    (0..scope_values.len()).map(|idx| format!("${}", sql_vals.len() + idx + 1)).collect::<Vec<String>>().join(", ")
-                ));
-            }
-            for scope_value in scope_values {
-                sql_vals.push(Value::from(scope_value));
-            }
-        }
-        where_fragments.push(format!(
-            "(visit_keys IS NULL OR ({}))",
-            where_visit_keys_fragments.join(if search_req.ctx.cond_by_or.unwrap_or(false) { " OR " } else { " AND " })
-        ));
+    if let Some(where_visit_keys_fragment) = gen_visit_keys_where_fragment(&search_req.ctx, &mut sql_vals) {
+        where_fragments.push(where_visit_keys_fragment);
     }
 
     if let Some(kinds) = &search_req.query.kinds {
@@ -336,6 +363,31 @@ pub async fn search(search_req: &mut SearchItemSearchReq, funs: &TardisFunsInst,
         }
     }
 
+    if let Some(knn) = &search_req.query.knn {
+        let vector_schema = search_pg_initializer::check_vector(&search_req.tag, &knn.vector, bs_inst.1)?;
+        sql_vals.push(Value::from(search_pg_initializer::vector_to_sql(&knn.vector)));
+        let distance_fragment = format!("vector OPERATOR({vector_schema}.<=>) ${}::{vector_schema}.vector", sql_vals.len());
+        sql_vals.push(Value::from(knn.k() as u32));
+        // The nearest items are the ones matching the conditions other than `q`, and the similarity is scaled to [0, 1] like ES
+        from_fragments = format!(
+            r#" LEFT JOIN (
+    SELECT key AS knn_key, 1 - ({distance_fragment}) / 2 AS rank_vector
+    FROM {table_name}
+    WHERE {} AND vector IS NOT NULL
+    ORDER BY {distance_fragment}
+    LIMIT ${}
+) AS knn ON knn.knn_key = key{from_fragments}"#,
+            where_fragments.join(" AND "),
+            sql_vals.len()
+        );
+        where_fragments.push(match where_q_fragment {
+            Some(where_q_fragment) => format!("({where_q_fragment} OR knn.knn_key IS NOT NULL)"),
+            None => "knn.knn_key IS NOT NULL".to_string(),
+        });
+    } else if let Some(where_q_fragment) = where_q_fragment {
+        where_fragments.push(where_q_fragment.to_string());
+    }
+
     if let Some(sort) = &search_req.sort {
         for sort_item in sort {
             if sort_item.field.to_lowercase() == "key"
@@ -352,23 +404,34 @@ pub async fn search(search_req: &mut SearchItemSearchReq, funs: &TardisFunsInst,
         }
     }
 
+    // Aggregations share the conditions but not the paging
+    let agg_sql_vals = sql_vals.clone();
+    if let (Some(knn), None) = (&search_req.query.knn, &search_req.sort) {
+        // Ordered by the blended score when not specified
+        sql_vals.push(Value::from(knn.weight()));
+        order_fragments.push(format!(
+            "(1 - ${0}) * ({} + {}) + ${0} * COALESCE(knn.rank_vector, 0) DESC",
+            sql_vals.len(),
+            rank_fragments.0,
+            rank_fragments.1
+        ));
+    }
     sql_vals.push(Value::from(search_req.page.size));
     sql_vals.push(Value::from((search_req.page.number - 1) * search_req.page.size as u32));
     let page_fragments = format!("LIMIT ${} OFFSET ${}", sql_vals.len() - 1, sql_vals.len());
 
-    let bs_inst = inst.inst::<TardisRelDBClient>();
-    let (conn, table_name) = search_pg_initializer::init_table_and_conn(bs_inst, &search_req.tag, ctx, false).await?;
     let result = conn
         .query_all(
             format!(
-                r#"SELECT kind, key, title, owner, own_paths, create_time, update_time, ext{}{}
+                r#"SELECT kind, key, title, owner, own_paths, create_time, update_time, ext{}{}{}
 FROM {table_name}{}
 WHERE 
     {}
     {}
 {}"#,
                 if search_req.page.fetch_total { ", count(*) OVER() AS total" } else { "" },
-                select_fragments,
+                format!(", {} AS rank_title, {} AS rank_content", rank_fragments.0, rank_fragments.1),
+                highlight_fragments,
                 from_fragments,
                 where_fragments.join(" AND "),
                 if order_fragments.is_empty() {
@@ -401,18 +464,402 @@ WHERE
                 ext: item.try_get("", "ext")?,
                 rank_title: item.try_get("", "rank_title")?,
                 rank_content: item.try_get("", "rank_content")?,
+                title_highlight: item.try_get("", "title_highlight")?,
+                content_highlight: item.try_get("", "content_highlight")?,
             })
         })
         .collect::<TardisResult<Vec<SearchItemSearchResp>>>()?;
 
-    Ok(TardisPage {
+    let aggs = if let Some(aggs) = &search_req.aggs {
+        let mut agg_resps = Vec::with_capacity(aggs.len());
+        for agg in aggs {
+            agg_resps.push(search_agg(agg, &conn, &table_name, &from_fragments, &where_fragments, agg_sql_vals.clone(), funs).await?);
+        }
+        Some(agg_resps)
+    } else {
+        None
+    };
+
+    Ok(SearchItemSearchPageResp {
         page_size: search_req.page.size as u64,
         page_number: search_req.page.number as u64,
         total_size: total_size as u64,
         records: result,
+        aggs,
     })
 }
 
+/// Group the matching records by the field.
+///
+/// Arrays in ext are expanded, so that each element is counted like the terms aggregation of Elasticsearch.
+async fn search_agg(
+    agg: &SearchItemSearchAggReq,
+    conn: &TardisRelDBlConnection,
+    table_name: &str,
+    from_fragments: &str,
+    where_fragments: &[String],
+    mut sql_vals: Vec<Value>,
+    funs: &TardisFunsInst,
+) -> TardisResult<SearchItemSearchAggResp> {
+    let (key_fragments, order_fragments) = match (agg.field.to_lowercase().as_str(), &agg.interval) {
+        (field @ ("create_time" | "update_time"), Some(interval)) => (
+            format!(
+                r#"to_char(date_trunc('{}', {field} AT TIME ZONE 'UTC'), 'YYYY-MM-DD"T"HH24:MI:SS"Z"')"#,
+                interval.to_sql()
+            ),
+            "agg_key ASC".to_string(),
+        ),
+        (_, Some(_)) => {
+            return Err(funs.err().bad_request(
+                "item",
+                "search",
+                &format!("The interval is not supported by the aggregation field=[{}]", agg.field),
+                "400-spi-search-agg-interval-not-supported",
+            ))
+        }
+        (field @ ("kind" | "owner" | "own_paths"), None) => (field.to_string(), "agg_count DESC, agg_key ASC".to_string()),
+        (_, None) => {
+            sql_vals.push(Value::from(agg.field.as_str()));
+            (
+                format!(
+                    "jsonb_array_elements_text(CASE jsonb_typeof(ext -> ${0}) WHEN 'array' THEN ext -> ${0} ELSE jsonb_build_array(ext -> ${0}) END)",
+                    sql_vals.len()
+                ),
+                "agg_count DESC, agg_key ASC".to_string(),
+            )
+        }
+    };
+    let page_fragments = if agg.interval.is_none() {
+        sql_vals.push(Value::from(agg.size.unwrap_or(10)));
+        format!("LIMIT ${}", sql_vals.len())
+    } else {
+        "".to_string()
+    };
+    let result = conn
+        .query_all(
+            &format!(
+                r#"SELECT agg_key, count(*) AS agg_count
+FROM (
+    SELECT {key_fragments} AS agg_key
+    FROM {table_name}{from_fragments}
+    WHERE
+        {}
+) AS agg
+WHERE agg_key IS NOT NULL
+GROUP BY agg_key
+ORDER BY {order_fragments}
+{page_fragments}"#,
+                where_fragments.join(" AND "),
+            ),
+            sql_vals,
+        )
+        .await?;
+    let buckets = result
+        .into_iter()
+        .map(|item| {
+            Ok(SearchItemSearchAggBucketResp {
+                key: item.try_get("", "agg_key")?,
+                count: item.try_get::<i64>("", "agg_count")? as u64,
+            })
+        })
+        .collect::<TardisResult<Vec<_>>>()?;
+    Ok(SearchItemSearchAggResp {
+        name: agg.name().to_string(),
+        buckets,
+    })
+}
+
+fn gen_visit_keys_where_fragment(req_ctx: &SearchItemSearchCtxReq, sql_vals: &mut Vec<Value>) -> Option<String> {
+    let cond_by_or = req_ctx.cond_by_or.unwrap_or(false);
+    let req_ctx = req_ctx.to_sql();
+    if req_ctx.is_empty() {
+        return None;
+    }
+    let mut where_visit_keys_fragments = Vec::new();
+    for (scope_key, scope_values) in req_ctx {
+        if scope_values.is_empty() {
+            continue;
+        }
+        if scope_values.len() == 1 {
+            where_visit_keys_fragments.push(format!("visit_keys -> '{scope_key}' ? ${}", sql_vals.len() + 1));
+        } else {
+            where_visit_keys_fragments.push(format!(
+                "visit_keys -> '{scope_key}' ?| array[{}]",
+                (0..scope_values.len()).map(|idx| format!("${}", sql_vals.len() + idx + 1)).collect::<Vec<String>>().join(", ")
+            ));
+        }
+        for scope_value in scope_values {
+            sql_vals.push(Value::from(scope_value));
+        }
+    }
+    Some(format!(
+        "(visit_keys IS NULL OR ({}))",
+        where_visit_keys_fragments.join(if cond_by_or { " OR " } else { " AND " })
+    ))
+}
+
+/// Completions are the titles starting with the input, corrections are the words of the titles similar to the words of the input by pg_trgm,
+/// which are empty when the extension is not available.
+///
+/// The words are taken from the visible titles containing a word similar to each input word (`<%`), which are found by the trigram index of title.
+pub async fn suggest(suggest_req: &mut SearchItemSuggestReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<SearchItemSuggestResp> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let trgm_schema = search_pg_initializer::get_trgm_schema(bs_inst.1);
+    let (conn, table_name) = search_pg_initializer::init_table_and_conn(bs_inst, &suggest_req.tag, ctx, false).await?;
+
+    let mut sql_vals = vec![Value::from(format!(
+        "{}%",
+        suggest_req.q.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+    ))];
+    let where_visit_keys_fragment = gen_visit_keys_where_fragment(&suggest_req.ctx, &mut sql_vals).unwrap_or_else(|| "1=1".to_string());
+    sql_vals.push(Value::from(suggest_req.size() as u32));
+    let completions = conn
+        .query_all(
+            &format!(
+                r#"SELECT key, title
+FROM {table_name}
+WHERE title ILIKE $1 AND {where_visit_keys_fragment}
+ORDER BY length(title), title
+LIMIT ${}"#,
+                sql_vals.len()
+            ),
+            sql_vals,
+        )
+        .await?
+        .into_iter()
+        .map(|item| {
+            Ok(SearchItemSuggestCompletionResp {
+                key: item.try_get("", "key")?,
+                title: item.try_get("", "title")?,
+            })
+        })
+        .collect::<TardisResult<Vec<_>>>()?;
+
+    let Some(trgm_schema) = trgm_schema else {
+        return Ok(SearchItemSuggestResp { completions, corrections: vec![] });
+    };
+    let mut sql_vals = vec![Value::from(suggest_req.q.as_str())];
+    let where_visit_keys_fragment = gen_visit_keys_where_fragment(&suggest_req.ctx, &mut sql_vals).unwrap_or_else(|| "1=1".to_string());
+    sql_vals.push(Value::from(suggest_req.size() as u32));
+    let corrections = conn
+        .query_all(
+            &format!(
+                r#"WITH input_words AS (
+    SELECT DISTINCT input_word FROM regexp_split_to_table(lower($1), '\s+') AS input_word WHERE input_word <> ''
+), title_words AS (
+    SELECT DISTINCT title_word
+    FROM input_words, LATERAL (
+        SELECT title FROM {table_name}
+        WHERE input_word OPERATOR({trgm_schema}.<%) title AND {where_visit_keys_fragment}
+        LIMIT {SUGGEST_CORRECTION_CANDIDATES}
+    ) AS matched, regexp_split_to_table(lower(matched.title), '\s+') AS title_word
+)
+SELECT title_word, MAX({trgm_schema}.similarity(title_word, input_word)) AS score
+FROM title_words, input_words
+WHERE title_word OPERATOR({trgm_schema}.%) input_word AND title_word <> input_word
+GROUP BY title_word
+ORDER BY score DESC, title_word
+LIMIT ${}"#,
+                sql_vals.len()
+            ),
+            sql_vals,
+        )
+        .await?
+        .into_iter()
+        .map(|item| item.try_get("", "title_word"))
+        .collect::<Result<Vec<String>, _>>()?;
+    Ok(SearchItemSuggestResp { completions, corrections })
+}
+
+pub async fn bulk_items(bulk_req: &mut SearchItemBulkReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<SearchItemBulkErrorResp>> {
+    let mut errors = Vec::new();
+    let mut upserts: HashMap<&str, Vec<&SearchItemAddReq>> = HashMap::new();
+    for add_req in bulk_req.upserts.iter().flatten() {
+        if let Some(vector) = &add_req.vector {
+            if let Err(e) = search_pg_initializer::check_vector(&add_req.tag, vector, inst.inst::<TardisRelDBClient>().1) {
+                errors.push(SearchItemBulkErrorResp::new(&add_req.tag, &add_req.key.to_string(), &e));
+                continue;
+            }
+        }
+        let add_reqs = upserts.entry(add_req.tag.as_str()).or_default();
+        // A row can't be upserted twice in one statement, so the last one wins
+        add_reqs.retain(|exist_req| exist_req.key.to_string() != add_req.key.to_string());
+        add_reqs.push(add_req);
+    }
+    for (tag, add_reqs) in upserts {
+        if let Err(e) = bulk_upsert(tag, &add_reqs, ctx, inst).await {
+            errors.extend(add_reqs.iter().map(|add_req| SearchItemBulkErrorResp::new(tag, &add_req.key.to_string(), &e)));
+        }
+    }
+    let mut deletes: HashMap<&str, Vec<String>> = HashMap::new();
+    for delete_req in bulk_req.deletes.iter().flatten() {
+        deletes.entry(delete_req.tag.as_str()).or_default().push(delete_req.key.to_string());
+    }
+    for (tag, keys) in deletes {
+        if let Err(e) = bulk_delete(tag, &keys, ctx, inst).await {
+            errors.extend(keys.iter().map(|key| SearchItemBulkErrorResp::new(tag, key, &e)));
+        }
+    }
+    Ok(errors)
+}
+
+async fn bulk_upsert(tag: &str, add_reqs: &[&SearchItemAddReq], ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let mut params = Vec::new();
+    let mut sql_values = Vec::new();
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let text_search_config = search_pg_initializer::get_text_search_config(bs_inst.1, tag);
+    // The column only exists when the vectors of the tag are enabled
+    let vector_schema = search_pg_initializer::get_vector_schema(bs_inst.1, tag);
+    for add_req in add_reqs {
+        let idx = params.len();
+        params.push(Value::from(add_req.kind.to_string()));
+        params.push(Value::from(add_req.key.to_string()));
+        params.push(Value::from(add_req.title.as_str()));
+        params.push(Value::from(add_req.content.as_str()));
+        params.push(Value::from(add_req.owner.as_ref().unwrap_or(&"".to_string()).as_str()));
+        params.push(Value::from(add_req.own_paths.as_ref().unwrap_or(&"".to_string()).as_str()));
+        params.push(Value::from(if let Some(create_time) = add_req.create_time { create_time } else { Utc::now() }));
+        params.push(Value::from(if let Some(update_time) = add_req.update_time { update_time } else { Utc::now() }));
+        params.push(Value::from(if let Some(ext) = &add_req.ext {
+            ext.clone()
+        } else {
+            TardisFuns::json.str_to_json("{}")?
+        }));
+        params.push(Value::from(add_req.visit_keys.as_ref().map(|visit_keys| visit_keys.to_sql())));
+        let vector_fragment = if let Some(vector_schema) = vector_schema {
+            params.push(Value::from(add_req.vector.as_ref().map(|vector| search_pg_initializer::vector_to_sql(vector))));
+            format!(", ${}::{vector_schema}.vector", idx + 11)
+        } else {
+            "".to_string()
+        };
+        sql_values.push(format!(
+            "(${}, ${}, ${}, to_tsvector('{text_search_config}', ${}), ${}, to_tsvector('{text_search_config}', ${}), ${}, ${}, ${}, ${}, ${}, ${}{vector_fragment})",
+            idx + 1,
+            idx + 2,
+            idx + 3,
+            idx + 3,
+            idx + 4,
+            idx + 4,
+            idx + 5,
+            idx + 6,
+            idx + 7,
+            idx + 8,
+            idx + 9,
+            idx + 10
+        ));
+    }
+
+    let (mut conn, table_name) = search_pg_initializer::init_table_and_conn(bs_inst, tag, ctx, true).await?;
+    conn.begin().await?;
+    conn.execute_one(
+        &format!(
+            r#"INSERT INTO {table_name}
+    (kind, key, title, title_tsv, content, content_tsv, owner, own_paths, create_time, update_time, ext, visit_keys{})
+VALUES
+    {}
+ON CONFLICT (key) DO UPDATE SET
+    kind = EXCLUDED.kind, title = EXCLUDED.title, title_tsv = EXCLUDED.title_tsv, content = EXCLUDED.content, content_tsv = EXCLUDED.content_tsv,
+    owner = EXCLUDED.owner, own_paths = EXCLUDED.own_paths, create_time = EXCLUDED.create_time, update_time = EXCLUDED.update_time,
+    ext = EXCLUDED.ext, visit_keys = EXCLUDED.visit_keys{}"#,
+            if vector_schema.is_some() { ", vector" } else { "" },
+            sql_values.join(",\n    "),
+            if vector_schema.is_some() { ", vector = EXCLUDED.vector" } else { "" },
+        ),
+        params,
+    )
+    .await?;
+    conn.commit().await?;
+    Ok(())
+}
+
+async fn bulk_delete(tag: &str, keys: &[String], ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (mut conn, table_name) = search_pg_initializer::init_table_and_conn(bs_inst, tag, ctx, false).await?;
+    conn.begin().await?;
+    conn.execute_one(
+        &format!(
+            "DELETE FROM {table_name} WHERE key IN ({})",
+            (1..=keys.len()).map(|idx| format!("${idx}")).collect::<Vec<String>>().join(", ")
+        ),
+        keys.iter().map(|key| Value::from(key.as_str())).collect(),
+    )
+    .await?;
+    conn.commit().await?;
+    Ok(())
+}
+
+/// Copy the items into the shadow table in batches, then catch up with the changes made during the copy and swap the tables,
+/// writes to the tag are blocked while swapping.
+///
+/// Only one reindex of a tag runs at a time, the others fail with a conflict error.
+pub async fn reindex_items(tag: &str, task_id: i64, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let text_search_config = search_pg_initializer::get_text_search_config(bs_inst.1, tag);
+    let (mut conn, table_name) = search_pg_initializer::init_table_and_conn(bs_inst, tag, ctx, false).await?;
+    let (lock_conn, shadow_table_name) = search_pg_initializer::init_reindex_table(bs_inst, tag, ctx).await?;
+    let vector_fragment = if search_pg_initializer::get_vector_schema(bs_inst.1, tag).is_some() { ", vector" } else { "" };
+    // The rows inserted before the content is stored keep their content_tsv
+    let copy_sql = format!(
+        r#"INSERT INTO {shadow_table_name}
+    (kind, key, title, title_tsv, content, content_tsv, owner, own_paths, create_time, update_time, ext, visit_keys{vector_fragment})
+SELECT kind, key, title, to_tsvector('{text_search_config}', title), content,
+    CASE WHEN content = '' THEN content_tsv ELSE to_tsvector('{text_search_config}', content) END,
+    owner, own_paths, create_time, update_time, ext, visit_keys{vector_fragment}
+FROM {table_name} AS origin"#
+    );
+
+    let total = conn.count_by_sql(&format!("SELECT 1 FROM {table_name}"), vec![]).await?;
+    let mut done = 0;
+    search_item_serv::set_reindex_progress(task_id, Some(total), done, funs).await?;
+    let mut last_key = "".to_string();
+    loop {
+        let result = conn
+            .query_one(
+                &format!(
+                    r#"WITH copied AS (
+{copy_sql}
+WHERE key > $1
+ORDER BY key
+LIMIT {REINDEX_BATCH_SIZE}
+RETURNING key
+)
+SELECT COUNT(*) AS count, MAX(key) AS last_key FROM copied"#
+                ),
+                vec![Value::from(last_key.as_str())],
+            )
+            .await?
+            .ok_or_else(|| funs.err().internal_error("item", "reindex", "failed to copy the items", "500-spi-search-reindex-error"))?;
+        let count: i64 = result.try_get("", "count")?;
+        let Some(key) = result.try_get::<Option<String>>("", "last_key")? else {
+            break;
+        };
+        last_key = key;
+        done += count as u64;
+        search_item_serv::set_reindex_progress(task_id, Some(total), done, funs).await?;
+    }
+
+    conn.begin().await?;
+    conn.execute_one(&format!("LOCK TABLE {table_name} IN EXCLUSIVE MODE"), vec![]).await?;
+    conn.execute_one(
+        &format!(
+            r#"DELETE FROM {shadow_table_name} AS shadow
+WHERE NOT EXISTS (SELECT 1 FROM {table_name} AS origin WHERE origin.key = shadow.key AND origin.update_time = shadow.update_time)"#
+        ),
+        vec![],
+    )
+    .await?;
+    conn.execute_one(
+        &format!("{copy_sql}\nWHERE NOT EXISTS (SELECT 1 FROM {shadow_table_name} AS shadow WHERE shadow.key = origin.key)"),
+        vec![],
+    )
+    .await?;
+    search_pg_initializer::swap_reindex_table(bs_inst, tag, &conn).await?;
+    conn.commit().await?;
+    lock_conn.rollback().await?;
+    search_item_serv::set_reindex_progress(task_id, Some(total), done.max(total), funs).await?;
+    Ok(())
+}
+
 fn merge(a: &mut serde_json::Value, b: serde_json::Value) {
     match (a, b) {
         (a @ &mut serde_json::Value::Object(_), serde_json::Value::Object(b)) => {
@@ -1,15 +1,27 @@
+use bios_basic::process::task_processor::TaskProcessor;
 use bios_basic::spi::spi_constants;
 use bios_basic::spi::spi_funs::SpiBsInstExtractor;
 use bios_basic::spi_dispatch_service;
 
+use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
-use tardis::web::web_resp::TardisPage;
+use tardis::log::warn;
+use tardis::serde_json::json;
+use tardis::tokio::{self, time::Duration};
+use tardis::TardisFunsInst;
 
-use crate::dto::search_item_dto::{SearchItemAddReq, SearchItemModifyReq, SearchItemSearchReq, SearchItemSearchResp};
+use crate::dto::search_item_dto::{
+    SearchItemAddReq, SearchItemBulkErrorResp, SearchItemBulkReq, SearchItemBulkResp, SearchItemModifyReq, SearchItemReindexProgressResp, SearchItemSearchPageResp,
+    SearchItemSearchReq, SearchItemSuggestReq, SearchItemSuggestResp,
+};
+use crate::search_config::SearchConfig;
 use crate::search_initializer;
 
 #[cfg(feature = "spi-es")]
 use super::es;
+
+// Cache key infix of the owner of a reindex task
+const REINDEX_TASK_OWNER_FLAG: &str = ":reindex_owner:";
 #[cfg(feature = "spi-pg")]
 use super::pg;
 
@@ -26,6 +38,98 @@ spi_dispatch_service! {
         add(add_req: &mut SearchItemAddReq) -> TardisResult<()>;
         modify(tag: &str, key: &str, modify_req: &mut SearchItemModifyReq) -> TardisResult<()>;
         delete(tag: &str, key: &str) -> TardisResult<()>;
-        search(search_req: &mut SearchItemSearchReq) -> TardisResult<TardisPage<SearchItemSearchResp>>;
+        search(search_req: &mut SearchItemSearchReq) -> TardisResult<SearchItemSearchPageResp>;
+        suggest(suggest_req: &mut SearchItemSuggestReq) -> TardisResult<SearchItemSuggestResp>;
+        bulk_items(bulk_req: &mut SearchItemBulkReq) -> TardisResult<Vec<SearchItemBulkErrorResp>>;
+        reindex_items(tag: &str, task_id: i64) -> TardisResult<()>;
+    }
+}
+
+/// Upsert and delete items in bulk, the failed items are reported without affecting the others.
+pub async fn bulk(bulk_req: &mut SearchItemBulkReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<SearchItemBulkResp> {
+    let total = bulk_req.upserts.as_ref().map(|upserts| upserts.len()).unwrap_or(0) + bulk_req.deletes.as_ref().map(|deletes| deletes.len()).unwrap_or(0);
+    let max_items = funs.conf::<SearchConfig>().bulk_max_items;
+    if total > max_items {
+        return Err(funs.err().bad_request(
+            "item",
+            "bulk",
+            &format!("The number of items exceeds the limit {max_items}"),
+            "400-spi-search-bulk-too-many-items",
+        ));
+    }
+    if total == 0 {
+        return Ok(SearchItemBulkResp { succeed: 0, errors: vec![] });
+    }
+    let errors = bulk_items(bulk_req, funs, ctx).await?;
+    Ok(SearchItemBulkResp {
+        succeed: (total - errors.len()) as u32,
+        errors,
+    })
+}
+
+/// Rebuild the tag in the background, e.g. after the mappings or the text search configuration are changed.
+///
+/// The items are copied into a shadow table (PG) or index (ES) which then replaces the original one atomically.
+/// The progress is only visible to the same own_paths, and removed after `reindex_progress_exp_secs` once the task is finished.
+pub async fn reindex(tag: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<i64> {
+    let task_tag = tag.to_string();
+    let task_ctx = ctx.clone();
+    let conf = funs.conf::<SearchConfig>();
+    let task_id = TaskProcessor::execute_task_with_process(
+        &conf.cache_key_async_task_status,
+        move |task_id| async move {
+            let funs = crate::get_tardis_inst();
+            let conf = funs.conf::<SearchConfig>();
+            let result = reindex_items(&task_tag, task_id, &funs, &task_ctx).await;
+            if let Err(e) = &result {
+                TaskProcessor::set_process_data(&conf.cache_key_async_task_status, task_id, json!({"error": e.message}), funs.cache()).await?;
+            }
+            funs.cache()
+                .set_ex(
+                    &format!("{}{REINDEX_TASK_OWNER_FLAG}{task_id}", conf.cache_key_async_task_status),
+                    &task_ctx.own_paths,
+                    conf.reindex_progress_exp_secs as usize,
+                )
+                .await?;
+            let cache_key = conf.cache_key_async_task_status.clone();
+            let progress_exp_secs = conf.reindex_progress_exp_secs;
+            let cache_client = funs.cache();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_secs(progress_exp_secs)).await;
+                if let Err(e) = TaskProcessor::delete_process_data(&cache_key, task_id, cache_client).await {
+                    warn!("[SPI-Search] Remove the progress of reindex task [{task_id}] error: {e:?}");
+                }
+            });
+            result
+        },
+        funs,
+    )
+    .await?;
+    // Expires with the progress once the task is finished, not overwritten if the task is already finished
+    funs.cache().set_nx(&format!("{}{REINDEX_TASK_OWNER_FLAG}{task_id}", conf.cache_key_async_task_status), &ctx.own_paths).await?;
+    Ok(task_id)
+}
+
+pub async fn set_reindex_progress(task_id: i64, total: Option<u64>, done: u64, funs: &TardisFunsInst) -> TardisResult<()> {
+    TaskProcessor::set_process_data(
+        &funs.conf::<SearchConfig>().cache_key_async_task_status,
+        task_id,
+        json!({"total": total, "done": done}),
+        funs.cache(),
+    )
+    .await
+}
+
+pub async fn get_reindex_progress(task_id: i64, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<SearchItemReindexProgressResp> {
+    let conf = funs.conf::<SearchConfig>();
+    if funs.cache().get(&format!("{}{REINDEX_TASK_OWNER_FLAG}{task_id}", conf.cache_key_async_task_status)).await?.as_deref() != Some(ctx.own_paths.as_str()) {
+        return Err(funs.err().not_found("item", "reindex", "The reindex task is not found or expired", "404-spi-search-reindex-task-not-exist"));
     }
+    let progress = TaskProcessor::get_process_data(&conf.cache_key_async_task_status, task_id, funs.cache()).await?.unwrap_or_default();
+    Ok(SearchItemReindexProgressResp {
+        finished: TaskProcessor::check_status(&conf.cache_key_async_task_status, task_id, funs.cache()).await?,
+        total: progress["total"].as_u64(),
+        done: progress["done"].as_u64().unwrap_or_default(),
+        error: progress["error"].as_str().map(|error| error.to_string()),
+    })
 }
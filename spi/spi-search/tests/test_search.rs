@@ -6,16 +6,19 @@ use bios_basic::rbum::serv::rbum_kind_serv::RbumKindServ;
 use bios_basic::spi::dto::spi_bs_dto::SpiBsAddReq;
 use bios_basic::spi::spi_constants;
 use bios_basic::test::test_http_client::TestHttpClient;
+use bios_spi_search::dto::search_item_dto::SearchItemSearchPageResp;
 use bios_spi_search::search_constants::DOMAIN_CODE;
 use bios_spi_search::search_initializer;
 use tardis::basic::dto::TardisContext;
 use tardis::basic::field::TrimString;
 use tardis::basic::result::TardisResult;
+use tardis::serde_json::json;
 use tardis::tokio::time::sleep;
-use tardis::web::web_resp::Void;
+use tardis::web::web_resp::{TardisResp, Void};
 use tardis::{testcontainers, tokio, TardisFuns};
 mod init_search_container;
 mod test_search_item;
+mod test_search_pg_item;
 
 #[tokio::test]
 async fn test_search() -> TardisResult<()> {
@@ -60,7 +63,7 @@ async fn init_data() -> TardisResult<()> {
             "/ci/manage/bs",
             &SpiBsAddReq {
                 name: TrimString("test-spi".to_string()),
-                kind_id: TrimString(kind_id),
+                kind_id: TrimString(kind_id.clone()),
                 conn_uri: env::var("TARDIS_FW.ES.URL").unwrap(),
                 ak: TrimString("".to_string()),
                 sk: TrimString("".to_string()),
@@ -75,5 +78,124 @@ async fn init_data() -> TardisResult<()> {
 
     test_search_item::test(&mut client).await?;
 
+    // Vectors are enabled by another backend service, so that the tags of the one above are not changed
+    client.set_auth(&ctx)?;
+    let bs_id: String = client
+        .post(
+            "/ci/manage/bs",
+            &SpiBsAddReq {
+                name: TrimString("test-spi-vector".to_string()),
+                kind_id: TrimString(kind_id),
+                conn_uri: env::var("TARDIS_FW.ES.URL").unwrap(),
+                ak: TrimString("".to_string()),
+                sk: TrimString("".to_string()),
+                ext: r#"{"max_connections":20,"min_connections":10,"tag_vector_dims":{"vec":3,"vec_legacy":3}}"#.to_string(),
+                private: false,
+                disabled: None,
+            },
+        )
+        .await;
+    let _: Void = client.put(&format!("/ci/manage/bs/{}/rel/app004", bs_id), &Void {}).await;
+
+    test_search_item::test_vector(&mut client).await?;
+
+    // PostgreSQL backend
+    client.set_auth(&ctx)?;
+    let kind_id = RbumKindServ::get_rbum_kind_id_by_code(spi_constants::SPI_PG_KIND_CODE, &funs).await?.unwrap();
+    let bs_id: String = client
+        .post(
+            "/ci/manage/bs",
+            &SpiBsAddReq {
+                name: TrimString("test-spi-pg".to_string()),
+                kind_id: TrimString(kind_id.clone()),
+                conn_uri: env::var("TARDIS_FW.DB.URL").unwrap(),
+                ak: TrimString("".to_string()),
+                sk: TrimString("".to_string()),
+                ext: r#"{"max_connections":20,"min_connections":10,"text_search_config":"english","tag_text_search_configs":{"doc_zh":"chinese_zh"}}"#.to_string(),
+                private: false,
+                disabled: None,
+            },
+        )
+        .await;
+    let _: Void = client.put(&format!("/ci/manage/bs/{}/rel/app002", bs_id), &Void {}).await;
+
+    test_search_pg_item::test(&mut client).await?;
+
+    // Vectors of PostgreSQL, with and without the pgvector extension
+    client.set_auth(&ctx)?;
+    let bs_id: String = client
+        .post(
+            "/ci/manage/bs",
+            &SpiBsAddReq {
+                name: TrimString("test-spi-pg-vector".to_string()),
+                kind_id: TrimString(kind_id.clone()),
+                conn_uri: env::var("PGVECTOR_URL").unwrap(),
+                ak: TrimString("".to_string()),
+                sk: TrimString("".to_string()),
+                ext: r#"{"max_connections":20,"min_connections":10,"text_search_config":"simple","tag_vector_dims":{"vec":3}}"#.to_string(),
+                private: false,
+                disabled: None,
+            },
+        )
+        .await;
+    let _: Void = client.put(&format!("/ci/manage/bs/{}/rel/app005", bs_id), &Void {}).await;
+    let bs_id: String = client
+        .post(
+            "/ci/manage/bs",
+            &SpiBsAddReq {
+                name: TrimString("test-spi-pg-no-vector".to_string()),
+                kind_id: TrimString(kind_id.clone()),
+                conn_uri: env::var("TARDIS_FW.DB.URL").unwrap(),
+                ak: TrimString("".to_string()),
+                sk: TrimString("".to_string()),
+                ext: r#"{"max_connections":20,"min_connections":10,"text_search_config":"english","tag_vector_dims":{"vec":3}}"#.to_string(),
+                private: false,
+                disabled: None,
+            },
+        )
+        .await;
+    let _: Void = client.put(&format!("/ci/manage/bs/{}/rel/app006", bs_id), &Void {}).await;
+
+    test_search_pg_item::test_vector(&mut client).await?;
+
+    // A specified text search configuration that is not available fails the initialization
+    client.set_auth(&ctx)?;
+    let bs_id: String = client
+        .post(
+            "/ci/manage/bs",
+            &SpiBsAddReq {
+                name: TrimString("test-spi-pg-illegal".to_string()),
+                kind_id: TrimString(kind_id),
+                conn_uri: env::var("TARDIS_FW.DB.URL").unwrap(),
+                ak: TrimString("".to_string()),
+                sk: TrimString("".to_string()),
+                ext: r#"{"max_connections":20,"min_connections":10,"text_search_config":"public.not_exist"}"#.to_string(),
+                private: false,
+                disabled: None,
+            },
+        )
+        .await;
+    let _: Void = client.put(&format!("/ci/manage/bs/{}/rel/app003", bs_id), &Void {}).await;
+    client.set_auth(&TardisContext {
+        own_paths: "t1/app003".to_string(),
+        ak: "".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: "app003".to_string(),
+        ..Default::default()
+    })?;
+    let search_result: TardisResp<SearchItemSearchPageResp> = client
+        .put_resp(
+            "/ci/item/search",
+            &json!({
+                "tag":"doc",
+                "ctx":{},
+                "query":{},
+                "page":{"number":1,"size":10}
+            }),
+        )
+        .await;
+    assert_eq!(search_result.code, "400-spi-search-text-search-config-not-available");
+
     Ok(())
 }
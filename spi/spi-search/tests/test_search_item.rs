@@ -1,10 +1,13 @@
+use std::env;
+
 use bios_basic::test::test_http_client::TestHttpClient;
-use bios_spi_search::dto::search_item_dto::SearchItemSearchResp;
+use bios_spi_search::dto::search_item_dto::{SearchItemBulkResp, SearchItemReindexProgressResp, SearchItemSearchPageResp, SearchItemSearchResp, SearchItemSuggestResp};
 use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
 use tardis::serde_json::json;
 use tardis::tokio::time::sleep;
 use tardis::web::web_resp::{TardisPage, TardisResp, Void};
+use tardis::TardisFuns;
 
 pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
     client.set_auth(&TardisContext {
@@ -499,6 +502,106 @@ pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
     assert_eq!(search_result.total_size, 2);
     assert_eq!(search_result.records[0].key, "002");
 
+    // Highlight
+    let search_result: TardisPage<SearchItemSearchResp> = client
+        .put(
+            "/ci/item/search",
+            &json!({
+                "tag":"feed",
+                "ctx":{
+                    "apps":["003"]
+                },
+                "query":{
+                    "q": "新增"
+                },
+                "page":{"number":1,"size":10,"fetch_total":true},
+                "highlight":{"pre_tag":"<b>","post_tag":"</b>"}
+            }),
+        )
+        .await;
+    assert_eq!(search_result.total_size, 2);
+    assert!(search_result.records.iter().all(|record| record.title_highlight.as_ref().unwrap().contains("<b>")));
+    assert!(search_result.records.iter().all(|record| record.content_highlight.is_none()));
+    let search_result: TardisPage<SearchItemSearchResp> = client
+        .put(
+            "/ci/item/search",
+            &json!({
+                "tag":"feed",
+                "ctx":{},
+                "query":{},
+                "page":{"number":1,"size":10,"fetch_total":true},
+                "highlight":{}
+            }),
+        )
+        .await;
+    assert!(search_result.records.iter().all(|record| record.title_highlight.is_none()));
+
+    // Aggregations
+    let search_result: TardisResp<SearchItemSearchPageResp> = client
+        .put_resp(
+            "/ci/item/search",
+            &json!({
+                "tag":"feed",
+                "ctx":{},
+                "query":{},
+                "page":{"number":1,"size":1,"fetch_total":true},
+                "aggs":[{"field":"kind","interval":"month"}]
+            }),
+        )
+        .await;
+    assert!(search_result.code.starts_with("400"));
+
+    let search_result: SearchItemSearchPageResp = client
+        .put(
+            "/ci/item/search",
+            &json!({
+                "tag":"feed",
+                "ctx":{},
+                "query":{},
+                "page":{"number":1,"size":1,"fetch_total":true},
+                "aggs":[
+                    {"field":"kind"},
+                    {"name":"version","field":"version"},
+                    {"field":"rel_accounts","size":2},
+                    {"name":"month","field":"create_time","interval":"month"}
+                ]
+            }),
+        )
+        .await;
+    assert_eq!(search_result.total_size, 3);
+    assert_eq!(search_result.records.len(), 1);
+    let aggs = search_result.aggs.unwrap();
+    assert_eq!(aggs.len(), 4);
+    assert_eq!(aggs[0].name, "kind");
+    assert_eq!(aggs[0].buckets.iter().map(|bucket| (bucket.key.as_str(), bucket.count)).collect::<Vec<_>>(), vec![("req", 2), ("task", 1)]);
+    assert_eq!(aggs[1].name, "version");
+    assert_eq!(aggs[1].buckets.iter().map(|bucket| (bucket.key.as_str(), bucket.count)).collect::<Vec<_>>(), vec![("1.3", 2), ("1.x", 1)]);
+    assert_eq!(aggs[2].name, "rel_accounts");
+    assert_eq!(aggs[2].buckets.iter().map(|bucket| (bucket.key.as_str(), bucket.count)).collect::<Vec<_>>(), vec![("acc01", 2), ("acc03", 2)]);
+    assert_eq!(aggs[3].name, "month");
+    assert_eq!(
+        aggs[3].buckets.iter().map(|bucket| (bucket.key.as_str(), bucket.count)).collect::<Vec<_>>(),
+        vec![("2022-08-01T00:00:00Z", 1), ("2022-09-01T00:00:00Z", 2)]
+    );
+
+    // Aggregations over the filtered records
+    let search_result: SearchItemSearchPageResp = client
+        .put(
+            "/ci/item/search",
+            &json!({
+                "tag":"feed",
+                "ctx":{},
+                "query":{
+                    "owners":["account002"]
+                },
+                "page":{"number":1,"size":10,"fetch_total":false},
+                "aggs":[{"field":"kind"}]
+            }),
+        )
+        .await;
+    let aggs = search_result.aggs.unwrap();
+    assert_eq!(aggs[0].buckets.iter().map(|bucket| (bucket.key.as_str(), bucket.count)).collect::<Vec<_>>(), vec![("req", 1), ("task", 1)]);
+
     // Delete
     let search_result: TardisPage<SearchItemSearchResp> = client
         .put(
@@ -532,5 +635,410 @@ pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
         .await;
     assert_eq!(search_result.total_size, 0);
 
+    // Bulk
+    let bulk_result: SearchItemBulkResp = client
+        .put(
+            "/ci/item/bulk",
+            &json!({
+                "upserts":[
+                    {
+                        "tag":"bulk",
+                        "kind": "req",
+                        "key": "001",
+                        "title": "全局账号",
+                        "content": "账号登录",
+                        "owner":"account001",
+                        "own_paths":"t001"
+                    },
+                    {
+                        "tag":"bulk",
+                        "kind": "req",
+                        "key": "002",
+                        "title": "全局应用",
+                        "content": "应用管理",
+                        "owner":"account001",
+                        "own_paths":"t001"
+                    }
+                ]
+            }),
+        )
+        .await;
+    assert_eq!(bulk_result.succeed, 2);
+    let bulk_result: SearchItemBulkResp = client
+        .put(
+            "/ci/item/bulk",
+            &json!({
+                "upserts":[
+                    {
+                        "tag":"bulk",
+                        "kind": "req",
+                        "key": "001",
+                        "title": "全局账号修改",
+                        "content": "账号登录",
+                        "owner":"account001",
+                        "own_paths":"t001"
+                    }
+                ],
+                "deletes":[{"tag":"bulk","key":"002"}]
+            }),
+        )
+        .await;
+    assert_eq!(bulk_result.succeed, 2);
+    assert!(bulk_result.errors.is_empty());
+    let search_result: SearchItemSearchPageResp = client
+        .put(
+            "/ci/item/search",
+            &json!({
+                "tag":"bulk",
+                "ctx":{},
+                "query":{},
+                "page":{"number":1,"size":10,"fetch_total":true}
+            }),
+        )
+        .await;
+    assert_eq!(search_result.total_size, 1);
+    assert_eq!(search_result.records[0].title, "全局账号修改");
+
+    // Reindex, the item deleted during the reindex is not restored by it
+    let _: Void = client
+        .put(
+            "/ci/item",
+            &json!({
+                "tag":"bulk",
+                "kind": "req",
+                "key": "002",
+                "title": "Deleted while reindexing",
+                "owner":"account001",
+                "own_paths":"t001",
+                "visit_keys":{"accounts":["acc02"]}
+            }),
+        )
+        .await;
+    sleep(std::time::Duration::from_secs(1)).await;
+    let task_id: i64 = client.put("/ci/item/reindex/bulk", &json!({})).await;
+    client.delete(&format!("/ci/item/{}/{}", "bulk", "002")).await;
+    loop {
+        sleep(std::time::Duration::from_secs(1)).await;
+        let progress: SearchItemReindexProgressResp = client.get(&format!("/ci/item/reindex/task/{task_id}")).await;
+        assert!(progress.error.is_none());
+        if progress.finished {
+            break;
+        }
+    }
+    let search_result: SearchItemSearchPageResp = client
+        .put(
+            "/ci/item/search",
+            &json!({
+                "tag":"bulk",
+                "ctx":{},
+                "query":{},
+                "page":{"number":1,"size":10,"fetch_total":true}
+            }),
+        )
+        .await;
+    assert_eq!(search_result.total_size, 1);
+    assert_eq!(search_result.records[0].key, "001");
+
+    // Suggest
+    let _: Void = client
+        .put(
+            "/ci/item",
+            &json!({
+                "tag":"bulk",
+                "kind": "req",
+                "key": "003",
+                "title": "Running the benches",
+                "content": "Benches are run nightly.",
+                "owner":"account001",
+                "own_paths":"t001",
+                "visit_keys":{"accounts":["acc02"]}
+            }),
+        )
+        .await;
+    sleep(std::time::Duration::from_secs(1)).await;
+    let suggest_result: SearchItemSuggestResp = client
+        .put(
+            "/ci/item/suggest",
+            &json!({
+                "tag":"bulk",
+                "ctx":{},
+                "q": "全局"
+            }),
+        )
+        .await;
+    assert_eq!(suggest_result.completions.len(), 1);
+    assert_eq!(suggest_result.completions[0].title, "全局账号修改");
+    let suggest_result: SearchItemSuggestResp = client
+        .put(
+            "/ci/item/suggest",
+            &json!({
+                "tag":"bulk",
+                "ctx":{"accounts":["acc02"]},
+                "q": "runn"
+            }),
+        )
+        .await;
+    assert_eq!(suggest_result.completions.len(), 1);
+    assert_eq!(suggest_result.completions[0].key, "003");
+    let suggest_result: SearchItemSuggestResp = client
+        .put(
+            "/ci/item/suggest",
+            &json!({
+                "tag":"bulk",
+                "ctx":{"accounts":["acc01"]},
+                "q": "runn"
+            }),
+        )
+        .await;
+    assert!(suggest_result.completions.is_empty());
+    let suggest_result: SearchItemSuggestResp = client
+        .put(
+            "/ci/item/suggest",
+            &json!({
+                "tag":"bulk",
+                "ctx":{},
+                "q": "benchs"
+            }),
+        )
+        .await;
+    assert_eq!(suggest_result.corrections[0], "benches");
+    // The invisible items don't take the places of the visible ones
+    for i in 0..6 {
+        let _: Void = client
+            .put(
+                "/ci/item",
+                &json!({
+                    "tag":"bulk",
+                    "kind": "req",
+                    "key": format!("1{i:02}"),
+                    "title": format!("Running the tests {i}"),
+                    "owner":"account001",
+                    "own_paths":"t001",
+                    "visit_keys":{"accounts":["acc01"]}
+                }),
+            )
+            .await;
+    }
+    sleep(std::time::Duration::from_secs(1)).await;
+    let suggest_result: SearchItemSuggestResp = client
+        .put(
+            "/ci/item/suggest",
+            &json!({
+                "tag":"bulk",
+                "ctx":{"accounts":["acc02"]},
+                "q": "running",
+                "size": 1
+            }),
+        )
+        .await;
+    assert_eq!(suggest_result.completions.len(), 1);
+    assert_eq!(suggest_result.completions[0].key, "003");
+
+    // The indexes created without the completion field are suggested by query
+    let resp = TardisFuns::web_client()
+        .put_str_to_str(
+            &format!("{}/suggest_legacy", env::var("TARDIS_FW.ES.URL").unwrap()),
+            &json!({"mappings": {"properties": {"key": {"type": "keyword"}, "title": {"type": "text"}}}}).to_string(),
+            Some(vec![("Content-Type".to_string(), "application/json".to_string())]),
+        )
+        .await?;
+    assert_eq!(resp.code, 200);
+    let _: Void = client
+        .put(
+            "/ci/item",
+            &json!({
+                "tag":"suggest_legacy",
+                "kind": "req",
+                "key": "001",
+                "title": "Running the legacy index",
+                "owner":"account001",
+                "own_paths":"t001"
+            }),
+        )
+        .await;
+    sleep(std::time::Duration::from_secs(1)).await;
+    let suggest_result: SearchItemSuggestResp = client
+        .put(
+            "/ci/item/suggest",
+            &json!({
+                "tag":"suggest_legacy",
+                "ctx":{},
+                "q": "runn"
+            }),
+        )
+        .await;
+    assert_eq!(suggest_result.completions.len(), 1);
+    assert_eq!(suggest_result.completions[0].key, "001");
+
+    Ok(())
+}
+
+pub async fn test_vector(client: &mut TestHttpClient) -> TardisResult<()> {
+    client.set_auth(&TardisContext {
+        own_paths: "t1/app004".to_string(),
+        ak: "".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: "app004".to_string(),
+        ..Default::default()
+    })?;
+
+    let add_result: TardisResp<Void> = client
+        .put_resp(
+            "/ci/item",
+            &json!({
+                "tag":"bulk",
+                "kind": "doc",
+                "key": "v00",
+                "title": "Vectors are not enabled",
+                "content": "",
+                "vector": [1.0, 0.0, 0.0]
+            }),
+        )
+        .await;
+    assert_eq!(add_result.code, "400-spi-search-vector-not-supported");
+    let add_result: TardisResp<Void> = client
+        .put_resp(
+            "/ci/item",
+            &json!({
+                "tag":"vec",
+                "kind": "doc",
+                "key": "v00",
+                "title": "The dimension is illegal",
+                "content": "",
+                "vector": [1.0, 0.0]
+            }),
+        )
+        .await;
+    assert_eq!(add_result.code, "400-spi-search-vector-dim-illegal");
+    let bulk_result: SearchItemBulkResp = client
+        .put(
+            "/ci/item/bulk",
+            &json!({
+                "upserts":[
+                    {"tag":"vec", "kind": "doc", "key": "v01", "title": "Rust vector search", "content": "", "vector": [1.0, 0.0, 0.0]},
+                    {"tag":"vec", "kind": "doc", "key": "v02", "title": "Database tuning", "content": "", "vector": [0.0, 1.0, 0.0]},
+                    {"tag":"vec", "kind": "doc", "key": "v03", "title": "Rust async runtime", "content": "", "vector": [0.0, 0.0, 1.0]},
+                    {"tag":"vec", "kind": "note", "key": "v04", "title": "Database backup", "content": "", "vector": [0.1, 1.0, 0.0]}
+                ]
+            }),
+        )
+        .await;
+    assert_eq!(bulk_result.succeed, 4);
+    let search_result: SearchItemSearchPageResp = client
+        .put(
+            "/ci/item/search",
+            &json!({
+                "tag":"vec",
+                "ctx":{},
+                "query":{"knn":{"vector":[0.9, 0.1, 0.0],"k":1}},
+                "page":{"number":1,"size":10,"fetch_total":true}
+            }),
+        )
+        .await;
+    assert_eq!(search_result.total_size, 1);
+    assert_eq!(search_result.records[0].key, "v01");
+    // The nearest items are filtered by the conditions
+    let search_result: SearchItemSearchPageResp = client
+        .put(
+            "/ci/item/search",
+            &json!({
+                "tag":"vec",
+                "ctx":{},
+                "query":{"kinds":["doc"],"knn":{"vector":[0.1, 1.0, 0.0],"k":1}},
+                "page":{"number":1,"size":10,"fetch_total":true}
+            }),
+        )
+        .await;
+    assert_eq!(search_result.total_size, 1);
+    assert_eq!(search_result.records[0].key, "v02");
+    // Hybrid, the items matching `q` and the nearest ones
+    let _: Void = client
+        .put(
+            "/ci/item/vec/v03",
+            &json!({
+                "vector": [0.0, 0.6, 0.8]
+            }),
+        )
+        .await;
+    sleep(std::time::Duration::from_secs(1)).await;
+    let search_result: SearchItemSearchPageResp = client
+        .put(
+            "/ci/item/search",
+            &json!({
+                "tag":"vec",
+                "ctx":{},
+                "query":{"q":"rust","kinds":["doc"],"knn":{"vector":[0.0, 1.0, 0.0],"k":1,"weight":1.0}},
+                "page":{"number":1,"size":10,"fetch_total":true}
+            }),
+        )
+        .await;
+    assert_eq!(search_result.total_size, 3);
+    assert_eq!(search_result.records[0].key, "v02");
+    let search_result: SearchItemSearchPageResp = client
+        .put(
+            "/ci/item/search",
+            &json!({
+                "tag":"vec",
+                "ctx":{},
+                "query":{"q":"rust","kinds":["doc"],"knn":{"vector":[0.0, 1.0, 0.0],"k":2,"weight":0.95}},
+                "page":{"number":1,"size":10,"fetch_total":true}
+            }),
+        )
+        .await;
+    assert_eq!(search_result.total_size, 3);
+    assert_eq!(search_result.records[0].key, "v02");
+    assert_eq!(search_result.records[1].key, "v03");
+
+    // The index created without the vector field is searched by `q` only, and not mapped by searching
+    let resp = TardisFuns::web_client()
+        .put_str_to_str(
+            &format!("{}/vec_legacy", env::var("TARDIS_FW.ES.URL").unwrap()),
+            &json!({"mappings": {"properties": {"key": {"type": "keyword"}, "kind": {"type": "keyword"}, "title": {"type": "text"}}}}).to_string(),
+            Some(vec![("Content-Type".to_string(), "application/json".to_string())]),
+        )
+        .await?;
+    assert_eq!(resp.code, 200);
+    let _: Void = client
+        .put(
+            "/ci/item",
+            &json!({
+                "tag":"vec_legacy",
+                "kind": "doc",
+                "key": "l01",
+                "title": "Rust legacy index",
+                "content": ""
+            }),
+        )
+        .await;
+    sleep(std::time::Duration::from_secs(1)).await;
+    let search_result: SearchItemSearchPageResp = client
+        .put(
+            "/ci/item/search",
+            &json!({
+                "tag":"vec_legacy",
+                "ctx":{},
+                "query":{"q":"rust","knn":{"vector":[1.0, 0.0, 0.0],"k":1}},
+                "page":{"number":1,"size":10,"fetch_total":true}
+            }),
+        )
+        .await;
+    assert_eq!(search_result.total_size, 1);
+    assert_eq!(search_result.records[0].key, "l01");
+    let search_result: SearchItemSearchPageResp = client
+        .put(
+            "/ci/item/search",
+            &json!({
+                "tag":"vec_legacy",
+                "ctx":{},
+                "query":{"knn":{"vector":[1.0, 0.0, 0.0],"k":1}},
+                "page":{"number":1,"size":10,"fetch_total":true}
+            }),
+        )
+        .await;
+    assert_eq!(search_result.total_size, 0);
+    let mapping = TardisFuns::web_client().get_to_str(&format!("{}/vec_legacy/_mapping/field/vector", env::var("TARDIS_FW.ES.URL").unwrap()), None).await?;
+    assert!(!mapping.body.unwrap_or_default().contains("dense_vector"));
+
     Ok(())
 }
@@ -0,0 +1,541 @@
+use bios_basic::test::test_http_client::TestHttpClient;
+use bios_spi_search::dto::search_item_dto::{SearchItemBulkResp, SearchItemReindexProgressResp, SearchItemSearchPageResp, SearchItemSuggestResp};
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::chrono::{Datelike, Utc};
+use tardis::serde_json::json;
+use tardis::tokio::time::sleep;
+use tardis::web::web_resp::{TardisResp, Void};
+
+pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
+    client.set_auth(&TardisContext {
+        own_paths: "t1/app002".to_string(),
+        ak: "".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: "app002".to_string(),
+        ..Default::default()
+    })?;
+
+    let _: Void = client
+        .put(
+            "/ci/item",
+            &json!({
+                "tag":"doc",
+                "kind": "req",
+                "key": "001",
+                "title": "Running the tests",
+                "content": "The runner runs all tests of the workspace in parallel.",
+                "owner":"account001",
+                "own_paths":"t001",
+                "ext":{"version":"1.0"}
+            }),
+        )
+        .await;
+    let _: Void = client
+        .put(
+            "/ci/item",
+            &json!({
+                "tag":"doc",
+                "kind": "task",
+                "key": "002",
+                "title": "Writing the docs",
+                "content": "Docs are written in markdown.",
+                "owner":"account002",
+                "own_paths":"t001",
+                "ext":{"version":"1.0"}
+            }),
+        )
+        .await;
+    let _: Void = client
+        .put(
+            "/ci/item",
+            &json!({
+                "tag":"doc_zh",
+                "kind": "req",
+                "key": "001",
+                "title": "新增全局账号逻辑",
+                "content": "账号登录",
+                "owner":"account001",
+                "own_paths":"t001"
+            }),
+        )
+        .await;
+
+    // Stemmed by the english configuration of the backend service
+    let search_result: SearchItemSearchPageResp = client
+        .put(
+            "/ci/item/search",
+            &json!({
+                "tag":"doc",
+                "ctx":{},
+                "query":{
+                    "q": "run",
+                    "q_scope": "title_content"
+                },
+                "page":{"number":1,"size":10,"fetch_total":true},
+                "highlight":{}
+            }),
+        )
+        .await;
+    assert_eq!(search_result.total_size, 1);
+    assert_eq!(search_result.records[0].key, "001");
+    assert!(search_result.records[0].title_highlight.as_ref().unwrap().contains("<em>Running</em>"));
+    assert!(search_result.records[0].content_highlight.as_ref().unwrap().contains("<em>runs</em>"));
+
+    let search_result: SearchItemSearchPageResp = client
+        .put(
+            "/ci/item/search",
+            &json!({
+                "tag":"doc",
+                "ctx":{},
+                "query":{
+                    "q": "markdown",
+                    "q_scope": "title_content"
+                },
+                "page":{"number":1,"size":10,"fetch_total":true},
+                "highlight":{"pre_tag":"<mark class='hit'>","post_tag":"</mark>","fragment_size":4}
+            }),
+        )
+        .await;
+    assert_eq!(search_result.total_size, 1);
+    assert_eq!(search_result.records[0].key, "002");
+    assert!(search_result.records[0].title_highlight.is_none());
+    assert!(search_result.records[0].content_highlight.as_ref().unwrap().contains("<mark class='hit'>markdown</mark>"));
+
+    // The tags are passed as the options of ts_headline, so the separators of options are rejected
+    let search_result: TardisResp<SearchItemSearchPageResp> = client
+        .put_resp(
+            "/ci/item/search",
+            &json!({
+                "tag":"doc",
+                "ctx":{},
+                "query":{
+                    "q": "markdown"
+                },
+                "page":{"number":1,"size":10,"fetch_total":true},
+                "highlight":{"pre_tag":"<b>\", MaxFragments=100, StartSel=\"<b>"}
+            }),
+        )
+        .await;
+    assert!(search_result.code.starts_with("400"));
+
+    // Segmented by the chinese configuration of the tag
+    let search_result: SearchItemSearchPageResp = client
+        .put(
+            "/ci/item/search",
+            &json!({
+                "tag":"doc_zh",
+                "ctx":{},
+                "query":{
+                    "q": "账号"
+                },
+                "page":{"number":1,"size":10,"fetch_total":true}
+            }),
+        )
+        .await;
+    assert_eq!(search_result.total_size, 1);
+
+    // Aggregations
+    let search_result: SearchItemSearchPageResp = client
+        .put(
+            "/ci/item/search",
+            &json!({
+                "tag":"doc",
+                "ctx":{},
+                "query":{},
+                "page":{"number":1,"size":1,"fetch_total":true},
+                "aggs":[{"field":"kind"},{"field":"version"}]
+            }),
+        )
+        .await;
+    assert_eq!(search_result.total_size, 2);
+    let aggs = search_result.aggs.unwrap();
+    assert_eq!(aggs[0].buckets.iter().map(|bucket| (bucket.key.as_str(), bucket.count)).collect::<Vec<_>>(), vec![("req", 1), ("task", 1)]);
+    assert_eq!(aggs[1].buckets.iter().map(|bucket| (bucket.key.as_str(), bucket.count)).collect::<Vec<_>>(), vec![("1.0", 2)]);
+
+    let search_result: SearchItemSearchPageResp = client
+        .put(
+            "/ci/item/search",
+            &json!({
+                "tag":"doc",
+                "ctx":{},
+                "query":{},
+                "page":{"number":1,"size":10,"fetch_total":false},
+                "aggs":[
+                    {"field":"own_paths"},
+                    {"name":"top_owner","field":"owner","size":1},
+                    {"name":"year","field":"create_time","interval":"year"}
+                ]
+            }),
+        )
+        .await;
+    let aggs = search_result.aggs.unwrap();
+    assert_eq!(aggs.len(), 3);
+    assert_eq!(aggs[0].name, "own_paths");
+    assert_eq!(aggs[0].buckets.iter().map(|bucket| (bucket.key.as_str(), bucket.count)).collect::<Vec<_>>(), vec![("t001", 2)]);
+    assert_eq!(aggs[1].name, "top_owner");
+    assert_eq!(aggs[1].buckets.iter().map(|bucket| (bucket.key.as_str(), bucket.count)).collect::<Vec<_>>(), vec![("account001", 1)]);
+    assert_eq!(aggs[2].name, "year");
+    assert_eq!(aggs[2].buckets.len(), 1);
+    assert_eq!(aggs[2].buckets[0].key, format!("{}-01-01T00:00:00Z", Utc::now().year()));
+    assert_eq!(aggs[2].buckets[0].count, 2);
+
+    // Aggregations over the filtered records
+    let search_result: SearchItemSearchPageResp = client
+        .put(
+            "/ci/item/search",
+            &json!({
+                "tag":"doc",
+                "ctx":{},
+                "query":{
+                    "owners":["account002"]
+                },
+                "page":{"number":1,"size":10,"fetch_total":false},
+                "aggs":[{"field":"kind"}]
+            }),
+        )
+        .await;
+    let aggs = search_result.aggs.unwrap();
+    assert_eq!(aggs[0].buckets.iter().map(|bucket| (bucket.key.as_str(), bucket.count)).collect::<Vec<_>>(), vec![("task", 1)]);
+
+    // Intervals only apply to the time fields
+    let search_result: TardisResp<SearchItemSearchPageResp> = client
+        .put_resp(
+            "/ci/item/search",
+            &json!({
+                "tag":"doc",
+                "ctx":{},
+                "query":{},
+                "page":{"number":1,"size":1,"fetch_total":false},
+                "aggs":[{"field":"kind","interval":"month"}]
+            }),
+        )
+        .await;
+    assert_eq!(search_result.code, "400-spi-search-item-search");
+
+    // Bulk
+    let bulk_result: SearchItemBulkResp = client
+        .put(
+            "/ci/item/bulk",
+            &json!({
+                "upserts":[
+                    {
+                        "tag":"doc",
+                        "kind": "task",
+                        "key": "002",
+                        "title": "Writing the guides",
+                        "content": "Guides are written in markdown.",
+                        "owner":"account002",
+                        "own_paths":"t001",
+                        "ext":{"version":"1.1"}
+                    },
+                    {
+                        "tag":"doc",
+                        "kind": "req",
+                        "key": "003",
+                        "title": "Running the benches",
+                        "content": "Benches are run nightly.",
+                        "owner":"account001",
+                        "own_paths":"t001",
+                        "ext":{"version":"1.1"}
+                    }
+                ],
+                "deletes":[{"tag":"doc","key":"001"},{"tag":"doc_missing","key":"001"}]
+            }),
+        )
+        .await;
+    assert_eq!(bulk_result.succeed, 3);
+    assert_eq!(bulk_result.errors.len(), 1);
+    assert_eq!(bulk_result.errors[0].tag, "doc_missing");
+    let search_result: SearchItemSearchPageResp = client
+        .put(
+            "/ci/item/search",
+            &json!({
+                "tag":"doc",
+                "ctx":{},
+                "query":{},
+                "sort":[{"field":"key","order":"asc"}],
+                "page":{"number":1,"size":10,"fetch_total":true}
+            }),
+        )
+        .await;
+    assert_eq!(search_result.total_size, 2);
+    assert_eq!(search_result.records[0].key, "002");
+    assert_eq!(search_result.records[0].title, "Writing the guides");
+    assert_eq!(search_result.records[1].key, "003");
+
+    // Reindex
+    let task_id: i64 = client.put("/ci/item/reindex/doc", &json!({})).await;
+    loop {
+        sleep(std::time::Duration::from_millis(500)).await;
+        let progress: SearchItemReindexProgressResp = client.get(&format!("/ci/item/reindex/task/{task_id}")).await;
+        assert!(progress.error.is_none());
+        if progress.finished {
+            assert_eq!(progress.total, Some(2));
+            assert_eq!(progress.done, 2);
+            break;
+        }
+    }
+    // The progress is only visible to the own_paths that started the task
+    client.set_auth(&TardisContext {
+        own_paths: "t1/app002/other".to_string(),
+        ak: "".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: "app002".to_string(),
+        ..Default::default()
+    })?;
+    let progress: TardisResp<SearchItemReindexProgressResp> = client.get_resp(&format!("/ci/item/reindex/task/{task_id}")).await;
+    assert_eq!(progress.code, "404-spi-search-item-reindex");
+    client.set_auth(&TardisContext {
+        own_paths: "t1/app002".to_string(),
+        ak: "".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: "app002".to_string(),
+        ..Default::default()
+    })?;
+    let search_result: SearchItemSearchPageResp = client
+        .put(
+            "/ci/item/search",
+            &json!({
+                "tag":"doc",
+                "ctx":{},
+                "query":{
+                    "q": "run",
+                    "q_scope": "title_content"
+                },
+                "page":{"number":1,"size":10,"fetch_total":true}
+            }),
+        )
+        .await;
+    assert_eq!(search_result.total_size, 1);
+    assert_eq!(search_result.records[0].key, "003");
+
+    // Suggest
+    let _: Void = client
+        .put(
+            "/ci/item",
+            &json!({
+                "tag":"doc",
+                "kind": "req",
+                "key": "004",
+                "title": "Running the docs site",
+                "content": "The site is built from markdown.",
+                "owner":"account001",
+                "own_paths":"t001",
+                "visit_keys":{"accounts":["acc02"]}
+            }),
+        )
+        .await;
+    let suggest_result: SearchItemSuggestResp = client
+        .put(
+            "/ci/item/suggest",
+            &json!({
+                "tag":"doc",
+                "ctx":{},
+                "q": "runn"
+            }),
+        )
+        .await;
+    assert_eq!(suggest_result.completions.iter().map(|completion| completion.key.as_str()).collect::<Vec<_>>(), vec!["003", "004"]);
+    let suggest_result: SearchItemSuggestResp = client
+        .put(
+            "/ci/item/suggest",
+            &json!({
+                "tag":"doc",
+                "ctx":{"accounts":["acc01"]},
+                "q": "runn"
+            }),
+        )
+        .await;
+    assert_eq!(suggest_result.completions.len(), 1);
+    assert_eq!(suggest_result.completions[0].title, "Running the benches");
+    let suggest_result: SearchItemSuggestResp = client
+        .put(
+            "/ci/item/suggest",
+            &json!({
+                "tag":"doc",
+                "ctx":{},
+                "q": "benchs"
+            }),
+        )
+        .await;
+    assert!(suggest_result.completions.is_empty());
+    assert_eq!(suggest_result.corrections[0], "benches");
+
+    Ok(())
+}
+
+pub async fn test_vector(client: &mut TestHttpClient) -> TardisResult<()> {
+    client.set_auth(&TardisContext {
+        own_paths: "t1/app005".to_string(),
+        ak: "".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: "app005".to_string(),
+        ..Default::default()
+    })?;
+
+    let bulk_result: SearchItemBulkResp = client
+        .put(
+            "/ci/item/bulk",
+            &json!({
+                "upserts":[
+                    {"tag":"vec", "kind": "doc", "key": "v01", "title": "Rust vector search", "content": "", "vector": [1.0, 0.0, 0.0]},
+                    {"tag":"vec", "kind": "doc", "key": "v02", "title": "Database tuning", "content": "", "vector": [0.0, 1.0, 0.0]},
+                    {"tag":"vec", "kind": "doc", "key": "v03", "title": "Rust async runtime", "content": "", "vector": [0.0, 0.0, 1.0]},
+                    {"tag":"vec", "kind": "note", "key": "v04", "title": "Database backup", "content": "", "vector": [0.1, 1.0, 0.0]},
+                    {"tag":"vec", "kind": "doc", "key": "v05", "title": "Rust without vector", "content": ""},
+                    {"tag":"vec", "kind": "doc", "key": "v06", "title": "The dimension is illegal", "content": "", "vector": [1.0, 0.0]}
+                ]
+            }),
+        )
+        .await;
+    assert_eq!(bulk_result.succeed, 5);
+    assert_eq!(bulk_result.errors.len(), 1);
+    assert_eq!(bulk_result.errors[0].key, "v06");
+    assert_eq!(bulk_result.errors[0].code, "400-spi-search-vector-dim-illegal");
+
+    let search_result: SearchItemSearchPageResp = client
+        .put(
+            "/ci/item/search",
+            &json!({
+                "tag":"vec",
+                "ctx":{},
+                "query":{"knn":{"vector":[0.9, 0.1, 0.0],"k":1}},
+                "page":{"number":1,"size":10,"fetch_total":true}
+            }),
+        )
+        .await;
+    assert_eq!(search_result.total_size, 1);
+    assert_eq!(search_result.records[0].key, "v01");
+    // The nearest items are filtered by the conditions
+    let search_result: SearchItemSearchPageResp = client
+        .put(
+            "/ci/item/search",
+            &json!({
+                "tag":"vec",
+                "ctx":{},
+                "query":{"kinds":["doc"],"knn":{"vector":[0.1, 1.0, 0.0],"k":1}},
+                "page":{"number":1,"size":10,"fetch_total":true}
+            }),
+        )
+        .await;
+    assert_eq!(search_result.total_size, 1);
+    assert_eq!(search_result.records[0].key, "v02");
+    // Hybrid, the items matching `q` are joined with the nearest ones, including the ones without vectors
+    let _: Void = client
+        .put(
+            "/ci/item/vec/v03",
+            &json!({
+                "vector": [0.0, 0.6, 0.8]
+            }),
+        )
+        .await;
+    let search_result: SearchItemSearchPageResp = client
+        .put(
+            "/ci/item/search",
+            &json!({
+                "tag":"vec",
+                "ctx":{},
+                "query":{"q":"rust","kinds":["doc"],"knn":{"vector":[0.0, 1.0, 0.0],"k":1,"weight":1.0}},
+                "page":{"number":1,"size":10,"fetch_total":true}
+            }),
+        )
+        .await;
+    assert_eq!(search_result.total_size, 4);
+    assert_eq!(search_result.records[0].key, "v02");
+    assert!(search_result.records.iter().any(|record| record.key == "v05"));
+    // Ordered by the blended score
+    let search_result: SearchItemSearchPageResp = client
+        .put(
+            "/ci/item/search",
+            &json!({
+                "tag":"vec",
+                "ctx":{},
+                "query":{"q":"rust","kinds":["doc"],"knn":{"vector":[0.0, 1.0, 0.0],"k":2,"weight":0.95}},
+                "page":{"number":1,"size":10,"fetch_total":true}
+            }),
+        )
+        .await;
+    assert_eq!(search_result.total_size, 4);
+    assert_eq!(search_result.records[0].key, "v02");
+    assert_eq!(search_result.records[1].key, "v03");
+
+    // The vectors are kept by reindexing
+    let task_id: i64 = client.put("/ci/item/reindex/vec", &json!({})).await;
+    loop {
+        sleep(std::time::Duration::from_millis(500)).await;
+        let progress: SearchItemReindexProgressResp = client.get(&format!("/ci/item/reindex/task/{task_id}")).await;
+        assert!(progress.error.is_none());
+        if progress.finished {
+            assert_eq!(progress.done, 5);
+            break;
+        }
+    }
+    let search_result: SearchItemSearchPageResp = client
+        .put(
+            "/ci/item/search",
+            &json!({
+                "tag":"vec",
+                "ctx":{},
+                "query":{"knn":{"vector":[0.9, 0.1, 0.0],"k":1}},
+                "page":{"number":1,"size":10,"fetch_total":true}
+            }),
+        )
+        .await;
+    assert_eq!(search_result.total_size, 1);
+    assert_eq!(search_result.records[0].key, "v01");
+
+    // The vectors are rejected without the pgvector extension
+    client.set_auth(&TardisContext {
+        own_paths: "t1/app006".to_string(),
+        ak: "".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: "app006".to_string(),
+        ..Default::default()
+    })?;
+    let _: Void = client
+        .put(
+            "/ci/item",
+            &json!({
+                "tag":"vec",
+                "kind": "doc",
+                "key": "v00",
+                "title": "Rust without vector",
+                "content": ""
+            }),
+        )
+        .await;
+    let add_result: TardisResp<Void> = client
+        .put_resp(
+            "/ci/item",
+            &json!({
+                "tag":"vec",
+                "kind": "doc",
+                "key": "v01",
+                "title": "Rust vector search",
+                "content": "",
+                "vector": [1.0, 0.0, 0.0]
+            }),
+        )
+        .await;
+    assert_eq!(add_result.code, "400-spi-search-vector-not-supported");
+    let search_result: TardisResp<SearchItemSearchPageResp> = client
+        .put_resp(
+            "/ci/item/search",
+            &json!({
+                "tag":"vec",
+                "ctx":{},
+                "query":{"knn":{"vector":[1.0, 0.0, 0.0],"k":1}},
+                "page":{"number":1,"size":10}
+            }),
+        )
+        .await;
+    assert_eq!(search_result.code, "400-spi-search-vector-not-supported");
+
+    Ok(())
+}
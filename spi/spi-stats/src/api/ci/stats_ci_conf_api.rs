@@ -6,8 +6,9 @@ use tardis::web::poem_openapi::payload::Json;
 use tardis::web::web_resp::{TardisApiResult, TardisPage, TardisResp, Void};
 
 use crate::dto::stats_conf_dto::{
-    StatsConfDimAddReq, StatsConfDimInfoResp, StatsConfDimModifyReq, StatsConfFactAddReq, StatsConfFactColAddReq, StatsConfFactColInfoResp, StatsConfFactColModifyReq,
-    StatsConfFactInfoResp, StatsConfFactModifyReq,
+    StatsConfDashboardAddReq, StatsConfDashboardInfoResp, StatsConfDashboardModifyReq, StatsConfDimAddReq, StatsConfDimInfoResp, StatsConfDimModifyReq, StatsConfFactAddReq,
+    StatsConfFactColAddReq, StatsConfFactColInfoResp, StatsConfFactColModifyReq, StatsConfFactInfoResp, StatsConfFactModifyReq, StatsConfFactRollupAddReq,
+    StatsConfFactRollupInfoResp, StatsConfSavedQueryAddReq, StatsConfSavedQueryInfoResp, StatsConfSavedQueryModifyReq,
 };
 use crate::serv::stats_conf_serv;
 
@@ -158,6 +159,137 @@ impl StatsCiConfApi {
         TardisResp::ok(resp)
     }
 
+    /// Add Fact Rollup Configuration
+    ///
+    /// If the fact is online, the rollup instance is created and backfilled
+    #[oai(path = "/fact/:fact_key/rollup", method = "put")]
+    async fn fact_rollup_add(&self, fact_key: Path<String>, add_req: Json<StatsConfFactRollupAddReq>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        stats_conf_serv::fact_rollup_add(&fact_key.0, &add_req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Delete Fact Rollup Configuration
+    #[oai(path = "/fact/:fact_key/rollup/:fact_rollup_key", method = "delete")]
+    async fn fact_rollup_delete(&self, fact_key: Path<String>, fact_rollup_key: Path<String>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        stats_conf_serv::fact_rollup_delete(&fact_key.0, &fact_rollup_key.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Find Fact Rollup Configurations
+    #[oai(path = "/fact/:fact_key/rollup", method = "get")]
+    async fn fact_rollup_paginate(
+        &self,
+        fact_key: Path<String>,
+        key: Query<Option<String>>,
+        page_number: Query<u32>,
+        page_size: Query<u32>,
+        desc_by_create: Query<Option<bool>>,
+        desc_by_update: Query<Option<bool>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<TardisPage<StatsConfFactRollupInfoResp>> {
+        let funs = crate::get_tardis_inst();
+        let resp = stats_conf_serv::fact_rollup_paginate(fact_key.0, key.0, page_number.0, page_size.0, desc_by_create.0, desc_by_update.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Refresh Fact Rollup Instance
+    ///
+    /// Rebuild the whole rollup instance, can be called periodically by the schedule service
+    #[oai(path = "/fact/:fact_key/rollup/:fact_rollup_key/refresh", method = "put")]
+    async fn fact_rollup_refresh(&self, fact_key: Path<String>, fact_rollup_key: Path<String>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        stats_conf_serv::fact_rollup_refresh(&fact_key.0, &fact_rollup_key.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Add Saved Query Configuration
+    ///
+    /// The saved query belongs to the tenant/app of the current context
+    #[oai(path = "/saved_query", method = "put")]
+    async fn saved_query_add(&self, add_req: Json<StatsConfSavedQueryAddReq>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        stats_conf_serv::saved_query_add(&add_req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Modify Saved Query Configuration
+    #[oai(path = "/saved_query/:saved_query_key", method = "patch")]
+    async fn saved_query_modify(&self, saved_query_key: Path<String>, modify_req: Json<StatsConfSavedQueryModifyReq>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        stats_conf_serv::saved_query_modify(&saved_query_key.0, &modify_req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Delete Saved Query Configuration
+    #[oai(path = "/saved_query/:saved_query_key", method = "delete")]
+    async fn saved_query_delete(&self, saved_query_key: Path<String>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        stats_conf_serv::saved_query_delete(&saved_query_key.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Find Saved Query Configurations
+    #[oai(path = "/saved_query", method = "get")]
+    async fn saved_query_paginate(
+        &self,
+        key: Query<Option<String>>,
+        show_name: Query<Option<String>>,
+        page_number: Query<u32>,
+        page_size: Query<u32>,
+        desc_by_create: Query<Option<bool>>,
+        desc_by_update: Query<Option<bool>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<TardisPage<StatsConfSavedQueryInfoResp>> {
+        let funs = crate::get_tardis_inst();
+        let resp = stats_conf_serv::saved_query_paginate(key.0, show_name.0, page_number.0, page_size.0, desc_by_create.0, desc_by_update.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Add Dashboard Configuration
+    ///
+    /// The dashboard belongs to the tenant/app of the current context
+    #[oai(path = "/dashboard", method = "put")]
+    async fn dashboard_add(&self, add_req: Json<StatsConfDashboardAddReq>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        stats_conf_serv::dashboard_add(&add_req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Modify Dashboard Configuration
+    #[oai(path = "/dashboard/:dashboard_key", method = "patch")]
+    async fn dashboard_modify(&self, dashboard_key: Path<String>, modify_req: Json<StatsConfDashboardModifyReq>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        stats_conf_serv::dashboard_modify(&dashboard_key.0, &modify_req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Delete Dashboard Configuration
+    #[oai(path = "/dashboard/:dashboard_key", method = "delete")]
+    async fn dashboard_delete(&self, dashboard_key: Path<String>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        stats_conf_serv::dashboard_delete(&dashboard_key.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Find Dashboard Configurations
+    #[oai(path = "/dashboard", method = "get")]
+    async fn dashboard_paginate(
+        &self,
+        key: Query<Option<String>>,
+        show_name: Query<Option<String>>,
+        page_number: Query<u32>,
+        page_size: Query<u32>,
+        desc_by_create: Query<Option<bool>>,
+        desc_by_update: Query<Option<bool>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<TardisPage<StatsConfDashboardInfoResp>> {
+        let funs = crate::get_tardis_inst();
+        let resp = stats_conf_serv::dashboard_paginate(key.0, show_name.0, page_number.0, page_size.0, desc_by_create.0, desc_by_update.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
     /// Online dimension configuration
     #[oai(path = "/dim/:dim_key/online", method = "put")]
     async fn dim_online(&self, dim_key: Path<String>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
@@ -1,10 +1,15 @@
 use tardis::web::context_extractor::TardisContextExtractor;
+use tardis::TardisFuns;
 
+use tardis::web::poem;
+use tardis::web::poem::web::websocket::{BoxWebSocketUpgraded, WebSocket};
 use tardis::web::poem_openapi;
+use tardis::web::poem_openapi::param::{Path, Query};
 use tardis::web::poem_openapi::payload::Json;
+use tardis::web::poem_openapi::types::ParseFromJSON;
 use tardis::web::web_resp::{TardisApiResult, TardisResp};
 
-use crate::dto::stats_query_dto::{StatsQueryMetricsReq, StatsQueryMetricsResp};
+use crate::dto::stats_query_dto::{StatsQueryMetricsReq, StatsQueryMetricsResp, StatsQuerySavedMetricsReq};
 use crate::serv::stats_metric_serv;
 
 #[derive(Clone)]
@@ -20,4 +25,31 @@ impl StatsCiMetricApi {
         let resp = stats_metric_serv::query_metrics(&query_req.0, &funs, &ctx.0).await?;
         TardisResp::ok(resp)
     }
+
+    /// Query Metrics By Saved Query
+    ///
+    /// The `${<param name>}` placeholders of the saved query are replaced by the parameters
+    #[oai(path = "/saved/:saved_query_key", method = "put")]
+    async fn query_saved_metrics(
+        &self,
+        saved_query_key: Path<String>,
+        query_req: Json<StatsQuerySavedMetricsReq>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<StatsQueryMetricsResp> {
+        let funs = crate::get_tardis_inst();
+        let resp = stats_metric_serv::query_saved_metrics(&saved_query_key.0, &query_req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Stream Metrics
+    ///
+    /// Push the metrics through websocket, first the current result and then the updated results when the fact records change.
+    /// `query` is the json of the query request, the changes are collected for `debounce_ms` before pushing.
+    #[oai(path = "/stream", method = "get")]
+    async fn stream(&self, query: Query<String>, debounce_ms: Query<Option<u64>>, websocket: WebSocket, ctx: TardisContextExtractor) -> poem::Result<BoxWebSocketUpgraded> {
+        let funs = crate::get_tardis_inst();
+        let query_req = StatsQueryMetricsReq::parse_from_json(Some(TardisFuns::json.str_to_json(&query.0)?))
+            .map_err(|e| funs.err().bad_request("metric", "stream", &format!("The query is illegal: {}", e.message()), "400-spi-stats-invalid-request"))?;
+        Ok(stats_metric_serv::stream(query_req, debounce_ms.0, websocket, &funs, &ctx.0).await?)
+    }
 }
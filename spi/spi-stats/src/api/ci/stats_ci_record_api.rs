@@ -7,7 +7,7 @@ use tardis::web::poem_openapi::param::{Path, Query};
 use tardis::web::poem_openapi::payload::Json;
 use tardis::web::web_resp::{TardisApiResult, TardisPage, TardisResp, Void};
 
-use crate::dto::stats_record_dto::{StatsDimRecordAddReq, StatsDimRecordDeleteReq, StatsFactRecordLoadReq, StatsFactRecordsLoadReq};
+use crate::dto::stats_record_dto::{StatsDimRecordAddReq, StatsDimRecordDeleteReq, StatsFactRecordLoadReq, StatsFactRecordsLoadReq, StatsRecordImportReq, StatsRecordImportResp};
 use crate::serv::stats_record_serv;
 
 #[derive(Clone)]
@@ -46,6 +46,16 @@ impl StatsCiRecordApi {
         TardisResp::ok(Void {})
     }
 
+    /// Import Fact Records
+    ///
+    /// The columns are `key`, `own_paths`, `ct` and the fact columns, the illegal records are reported and the others are loaded
+    #[oai(path = "/fact/:fact_key/batch/import", method = "put")]
+    async fn fact_records_import(&self, fact_key: Path<String>, import_req: Json<StatsRecordImportReq>, ctx: TardisContextExtractor) -> TardisApiResult<StatsRecordImportResp> {
+        let funs = crate::get_tardis_inst();
+        let resp = stats_record_serv::import_fact_records(&fact_key.0, import_req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
     /// Delete Fact Records
     #[oai(path = "/fact/:fact_key/batch/remove", method = "put")]
     async fn fact_records_delete(&self, fact_key: Path<String>, delete_req: Json<Vec<String>>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
@@ -86,6 +96,16 @@ impl StatsCiRecordApi {
         TardisResp::ok(Void {})
     }
 
+    /// Import Dimension Records
+    ///
+    /// The columns are `key`, `show_name` and `parent_key`, the existing records are updated and the illegal records are reported
+    #[oai(path = "/dim/:dim_key/batch/import", method = "put")]
+    async fn dim_records_import(&self, dim_key: Path<String>, import_req: Json<StatsRecordImportReq>, ctx: TardisContextExtractor) -> TardisApiResult<StatsRecordImportResp> {
+        let funs = crate::get_tardis_inst();
+        let resp = stats_record_serv::import_dim_records(&dim_key.0, import_req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
     /// Find Dimension Records
     #[oai(path = "/dim/:dim_key", method = "get")]
     async fn dim_record_paginate(
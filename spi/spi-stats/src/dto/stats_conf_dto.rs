@@ -1,11 +1,14 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 use tardis::{
     chrono::{DateTime, Utc},
     db::sea_orm,
+    serde_json::Value,
     web::poem_openapi,
 };
 
-use crate::stats_enumeration::{StatsDataTypeKind, StatsFactColKind};
+use crate::stats_enumeration::{StatsDataTypeKind, StatsFactColKind, StatsQueryTimeWindowKind};
 
 /// Add Dimension Configuration Request Object
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
@@ -217,3 +220,141 @@ pub struct StatsConfFactColInfoResp {
     pub create_time: DateTime<Utc>,
     pub update_time: DateTime<Utc>,
 }
+
+/// Add Fact Rollup Configuration Request Object
+///
+/// A rollup is a materialized pre-aggregation of the fact records,
+/// grouped by the specified dimensions and the time window of the create time.
+/// The queries are answered from it transparently, except those deduplicating the records or with records deleted in the time range.
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct StatsConfFactRollupAddReq {
+    /// The primary key or encoding passed in from the external system
+    #[oai(validator(pattern = r"^[a-z0-9_]+$"))]
+    pub key: String,
+    /// Dimension column keys of the fact to group by
+    pub dims: Vec<String>,
+    /// Time window of the create time, only `hour` and `date` are supported
+    pub time_window: StatsQueryTimeWindowKind,
+    /// Measure column keys of the fact to aggregate, only numeric measures are supported.
+    /// The record count (`_count`) is always aggregated.
+    pub measures: Vec<String>,
+    pub remark: Option<String>,
+}
+
+/// Fact Rollup Configuration Response Object
+#[derive(poem_openapi::Object, sea_orm::FromQueryResult, Serialize, Deserialize, Debug)]
+pub struct StatsConfFactRollupInfoResp {
+    /// The primary key or encoding passed in from the external system
+    pub key: String,
+    /// Dimension column keys of the fact to group by
+    pub dims: Vec<String>,
+    /// Time window of the create time
+    pub time_window: StatsQueryTimeWindowKind,
+    /// Measure column keys of the fact to aggregate
+    pub measures: Vec<String>,
+    pub remark: Option<String>,
+    pub create_time: DateTime<Utc>,
+    pub update_time: DateTime<Utc>,
+}
+
+/// Add Saved Query Configuration Request Object
+///
+/// The saved query belongs to the tenant/app of the current context.
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct StatsConfSavedQueryAddReq {
+    /// The primary key or encoding passed in from the external system
+    #[oai(validator(pattern = r"^[a-z0-9_]+$"))]
+    pub key: String,
+    /// The name of the saved query
+    #[oai(validator(min_length = "2"))]
+    pub show_name: String,
+    /// Query template in the format of [`crate::dto::stats_query_dto::StatsQueryMetricsReq`].
+    ///
+    /// A string value in the format of `${<param name>}`, e.g. `"start_time":"${start_time}"`, is a placeholder,
+    /// which is replaced by the parameter value when executing, the `from` field can't be a placeholder.
+    /// The template is checked with dummy values of the placeholders when saving.
+    pub query: Value,
+    pub remark: Option<String>,
+}
+
+/// Modify Saved Query Configuration Request Object
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct StatsConfSavedQueryModifyReq {
+    /// The name of the saved query
+    #[oai(validator(min_length = "2"))]
+    pub show_name: Option<String>,
+    /// Query template, see [`StatsConfSavedQueryAddReq::query`]
+    pub query: Option<Value>,
+    pub remark: Option<String>,
+}
+
+/// Saved Query Configuration Response Object
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct StatsConfSavedQueryInfoResp {
+    /// The primary key or encoding passed in from the external system
+    pub key: String,
+    /// The name of the saved query
+    pub show_name: String,
+    /// Query template
+    pub query: Value,
+    pub owner: String,
+    pub own_paths: String,
+    pub remark: Option<String>,
+    pub create_time: DateTime<Utc>,
+    pub update_time: DateTime<Utc>,
+}
+
+/// Dashboard Item Object
+#[derive(poem_openapi::Object, Serialize, Deserialize, Clone, Debug)]
+pub struct StatsConfDashboardItem {
+    /// The key of the saved query
+    pub saved_query_key: String,
+    /// Parameters of the saved query for this item, not parsed by the service,
+    /// the client passes them when executing the saved query, see [`crate::dto::stats_query_dto::StatsQuerySavedMetricsReq`]
+    pub params: Option<HashMap<String, Value>>,
+    /// Layout metadata, e.g. `{"x":0,"y":0,"w":6,"h":4,"chart":"line"}`, not parsed by the service
+    pub layout: Option<Value>,
+}
+
+/// Add Dashboard Configuration Request Object
+///
+/// The dashboard belongs to the tenant/app of the current context.
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct StatsConfDashboardAddReq {
+    /// The primary key or encoding passed in from the external system
+    #[oai(validator(pattern = r"^[a-z0-9_]+$"))]
+    pub key: String,
+    /// The name of the dashboard
+    #[oai(validator(min_length = "2"))]
+    pub show_name: String,
+    /// Saved queries of the dashboard, in display order
+    pub items: Vec<StatsConfDashboardItem>,
+    pub remark: Option<String>,
+}
+
+/// Modify Dashboard Configuration Request Object
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct StatsConfDashboardModifyReq {
+    /// The name of the dashboard
+    #[oai(validator(min_length = "2"))]
+    pub show_name: Option<String>,
+    /// Saved queries of the dashboard, in display order
+    pub items: Option<Vec<StatsConfDashboardItem>>,
+    pub remark: Option<String>,
+}
+
+/// Dashboard Configuration Response Object
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct StatsConfDashboardInfoResp {
+    /// The primary key or encoding passed in from the external system
+    pub key: String,
+    /// The name of the dashboard
+    pub show_name: String,
+    /// Saved queries of the dashboard
+    pub items: Vec<StatsConfDashboardItem>,
+    pub owner: String,
+    pub own_paths: String,
+    pub remark: Option<String>,
+    pub create_time: DateTime<Utc>,
+    pub update_time: DateTime<Utc>,
+}
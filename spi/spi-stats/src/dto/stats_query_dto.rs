@@ -27,6 +27,7 @@ pub struct StatsQueryMetricsReq {
     /// Ignore group rollup
     /// If true or null, the group rollup will not be counted
     /// If false, the group rollup will be counted
+    /// Overridden by `ignore_group_rollup` of each group
     pub ignore_group_rollup: Option<bool>,
     /// Filter conditions, two-dimensional array, OR between groups, AND within groups
     #[oai(rename = "where")]
@@ -51,7 +52,11 @@ pub struct StatsQueryMetricsSelectReq {
     /// Measure column key
     pub code: String,
     /// Aggregate function
+    ///
+    /// The `growth` function requires exactly one group with time window, compared with the previous period of the same other groups.
     pub fun: StatsQueryAggFunKind,
+    /// Related measure column key, required by the `ratio` function as the denominator
+    pub rel_code: Option<String>,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
@@ -60,6 +65,13 @@ pub struct StatsQueryDimensionGroupReq {
     pub code: String,
     /// Time window function
     pub time_window: Option<StatsQueryTimeWindowKind>,
+    /// Hierarchy level of the dimension to group by, starting from 0, e.g. 1 for the city of the address dimension of country-province-city.
+    /// Only valid for the stable dimensions with a hierarchy, the records above the level are grouped into `""`.
+    /// Group by the same dimension with successive levels to drill down from one level to the next.
+    pub hierarchy: Option<u8>,
+    /// Ignore the rollup of this level, i.e. the `ROLLUP` group among the groups of this level.
+    /// Defaults to `ignore_group_rollup` of the request
+    pub ignore_group_rollup: Option<bool>,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
@@ -79,6 +91,8 @@ pub struct StatsQueryMetricsOrderReq {
     /// Measure column key
     pub code: String,
     pub fun: StatsQueryAggFunKind,
+    /// Related measure column key, required by the `ratio` function as the denominator
+    pub rel_code: Option<String>,
     /// Sort direction
     pub asc: bool,
 }
@@ -89,6 +103,8 @@ pub struct StatsQueryDimensionGroupOrderReq {
     pub code: String,
     /// Time window function
     pub time_window: Option<StatsQueryTimeWindowKind>,
+    /// Hierarchy level of the dimension
+    pub hierarchy: Option<u8>,
     /// Sort direction
     pub asc: bool,
 }
@@ -106,7 +122,11 @@ pub struct StatsQueryMetricsHavingReq {
     /// Measure Column key
     pub code: String,
     /// Aggregate function
+    ///
+    /// The `growth` function is not supported
     pub fun: StatsQueryAggFunKind,
+    /// Related measure column key, required by the `ratio` function as the denominator
+    pub rel_code: Option<String>,
     /// Operator
     pub op: BasicQueryOpKind,
     /// Value
@@ -122,7 +142,8 @@ pub struct StatsQueryMetricsResp {
     ///
     /// key = alias name, value = show name
     ///
-    /// The format of the alias: `field name__<function name>`
+    /// The format of the alias: `field name__<function name>`, `field name__ratio__<related field name>` for the `ratio` function,
+    /// or `field name__hierarchy<level>` for the hierarchy levels of dimensions
     pub show_names: HashMap<String, String>,
     /// Group
     ///
@@ -237,3 +258,10 @@ pub struct StatsQueryMetricsResp {
     /// ```
     pub group: Value,
 }
+
+/// Execute Saved Query Request
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct StatsQuerySavedMetricsReq {
+    /// Parameters to replace the `${<param name>}` placeholders of the saved query
+    pub params: Option<HashMap<String, Value>>,
+}
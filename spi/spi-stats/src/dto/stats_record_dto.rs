@@ -50,3 +50,53 @@ pub struct StatsDimRecordDeleteReq {
     /// Primary key
     pub key: Value,
 }
+
+#[derive(poem_openapi::Enum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[oai(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum StatsRecordImportFormatKind {
+    // The first line is the header of column names
+    Csv,
+    // One json object per line
+    Jsonl,
+}
+
+/// Import Records Request Object
+///
+/// The size of the file is limited by `import_max_size` of the config, 10 MiB by default
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct StatsRecordImportReq {
+    pub format: StatsRecordImportFormatKind,
+    /// Content of the file, either this or `object_path` is required
+    pub content: Option<String>,
+    /// Path of the file in spi-object
+    pub object_path: Option<String>,
+    /// Whether the file in spi-object is private, default is true
+    pub private: Option<bool>,
+    /// Only validate the records without importing them
+    pub dry_run: Option<bool>,
+}
+
+/// Import Records Response Object
+///
+/// The counts are the ones to be imported when it is a dry run
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Default)]
+pub struct StatsRecordImportResp {
+    /// Number of the records in the file
+    pub total: u64,
+    /// Number of the records added
+    pub added: u64,
+    /// Number of the existing records updated, only for dimension records
+    pub updated: u64,
+    /// The illegal records, which are not imported
+    pub errors: Vec<StatsRecordImportErrorResp>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct StatsRecordImportErrorResp {
+    /// Line number in the file, starting from 1
+    pub line: u64,
+    /// Primary key of the record if present
+    pub key: Option<String>,
+    pub message: String,
+}
@@ -1,6 +1,10 @@
+pub mod stats_pg_conf_dashboard_serv;
 pub mod stats_pg_conf_dim_serv;
 pub mod stats_pg_conf_fact_col_serv;
+pub mod stats_pg_conf_fact_rollup_serv;
 pub mod stats_pg_conf_fact_serv;
+pub mod stats_pg_conf_saved_query_serv;
 pub mod stats_pg_initializer;
 pub mod stats_pg_metric_serv;
+pub mod stats_pg_metric_stream_serv;
 pub(crate) mod stats_pg_record_serv;
@@ -0,0 +1,222 @@
+use bios_basic::spi::{
+    spi_funs::SpiBsInst,
+    spi_initializer::common_pg::{self, package_table_name},
+};
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    db::{
+        reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
+        sea_orm::Value,
+    },
+    serde_json,
+    web::web_resp::TardisPage,
+    TardisFuns, TardisFunsInst,
+};
+
+use crate::dto::stats_conf_dto::{StatsConfDashboardAddReq, StatsConfDashboardInfoResp, StatsConfDashboardItem, StatsConfDashboardModifyReq};
+
+use super::{stats_pg_conf_saved_query_serv, stats_pg_initializer};
+
+pub(crate) async fn add(add_req: &StatsConfDashboardAddReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (mut conn, table_name) = stats_pg_initializer::init_conf_dashboard_table_and_conn(bs_inst, ctx, true).await?;
+    conn.begin().await?;
+    if get(&add_req.key, &conn, ctx).await?.is_some() {
+        return Err(funs.err().conflict(
+            "dashboard_conf",
+            "add",
+            "The dashboard config already exists, please delete it and then add it.",
+            "409-spi-stats-dashboard-conf-exist",
+        ));
+    }
+    check_items(&add_req.items, "add", &conn, funs, ctx).await?;
+    let params = vec![
+        Value::from(add_req.key.to_string()),
+        Value::from(add_req.show_name.clone()),
+        Value::from(TardisFuns::json.obj_to_json(&add_req.items)?),
+        Value::from(ctx.owner.clone()),
+        Value::from(ctx.own_paths.clone()),
+        Value::from(add_req.remark.as_ref().unwrap_or(&"".to_string()).as_str()),
+    ];
+    conn.execute_one(
+        &format!(
+            r#"INSERT INTO {table_name}
+(key, show_name, items, owner, own_paths, remark)
+VALUES
+($1, $2, $3, $4, $5, $6)
+"#,
+        ),
+        params,
+    )
+    .await?;
+    conn.commit().await?;
+    Ok(())
+}
+
+pub(crate) async fn modify(dashboard_key: &str, modify_req: &StatsConfDashboardModifyReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (mut conn, table_name) = stats_pg_initializer::init_conf_dashboard_table_and_conn(bs_inst, ctx, true).await?;
+    conn.begin().await?;
+    if get(dashboard_key, &conn, ctx).await?.is_none() {
+        return Err(funs.err().not_found("dashboard_conf", "modify", "The dashboard config does not exist.", "404-spi-stats-dashboard-conf-not-exist"));
+    }
+    let mut sql_sets = vec![];
+    let mut params = vec![Value::from(dashboard_key.to_string()), Value::from(ctx.own_paths.clone())];
+    if let Some(show_name) = &modify_req.show_name {
+        sql_sets.push(format!("show_name = ${}", params.len() + 1));
+        params.push(Value::from(show_name.to_string()));
+    }
+    if let Some(items) = &modify_req.items {
+        check_items(items, "modify", &conn, funs, ctx).await?;
+        sql_sets.push(format!("items = ${}", params.len() + 1));
+        params.push(Value::from(TardisFuns::json.obj_to_json(items)?));
+    }
+    if let Some(remark) = &modify_req.remark {
+        sql_sets.push(format!("remark = ${}", params.len() + 1));
+        params.push(Value::from(remark.to_string()));
+    }
+    if sql_sets.is_empty() {
+        return Ok(());
+    }
+    conn.execute_one(
+        &format!(
+            r#"UPDATE {table_name}
+SET {}
+WHERE key = $1 AND own_paths = $2
+"#,
+            sql_sets.join(",")
+        ),
+        params,
+    )
+    .await?;
+    conn.commit().await?;
+    Ok(())
+}
+
+pub(crate) async fn delete(dashboard_key: &str, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (mut conn, table_name) = stats_pg_initializer::init_conf_dashboard_table_and_conn(bs_inst, ctx, true).await?;
+    conn.begin().await?;
+    conn.execute_one(
+        &format!("DELETE FROM {table_name} WHERE key = $1 AND own_paths = $2"),
+        vec![Value::from(dashboard_key), Value::from(ctx.own_paths.clone())],
+    )
+    .await?;
+    conn.commit().await?;
+    Ok(())
+}
+
+pub(crate) async fn paginate(
+    dashboard_key: Option<String>,
+    show_name: Option<String>,
+    page_number: u32,
+    page_size: u32,
+    desc_by_create: Option<bool>,
+    desc_by_update: Option<bool>,
+    _funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<TardisPage<StatsConfDashboardInfoResp>> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, _) = stats_pg_initializer::init_conf_dashboard_table_and_conn(bs_inst, ctx, true).await?;
+
+    do_paginate(dashboard_key, show_name, page_number, page_size, desc_by_create, desc_by_update, &conn, ctx).await
+}
+
+async fn get(dashboard_key: &str, conn: &TardisRelDBlConnection, ctx: &TardisContext) -> TardisResult<Option<StatsConfDashboardInfoResp>> {
+    do_paginate(Some(dashboard_key.to_string()), None, 1, 1, None, None, conn, ctx).await.map(|page| page.records.into_iter().next())
+}
+
+async fn do_paginate(
+    dashboard_key: Option<String>,
+    show_name: Option<String>,
+    page_number: u32,
+    page_size: u32,
+    desc_by_create: Option<bool>,
+    desc_by_update: Option<bool>,
+    conn: &TardisRelDBlConnection,
+    ctx: &TardisContext,
+) -> TardisResult<TardisPage<StatsConfDashboardInfoResp>> {
+    let table_name = package_table_name("stats_conf_dashboard", ctx);
+    let mut sql_where = vec!["own_paths = $1".to_string()];
+    let mut sql_order = vec![];
+    let mut params: Vec<Value> = vec![Value::from(ctx.own_paths.clone()), Value::from(page_size), Value::from((page_number - 1) * page_size)];
+    if let Some(dashboard_key) = &dashboard_key {
+        sql_where.push(format!("key = ${}", params.len() + 1));
+        params.push(Value::from(dashboard_key.to_string()));
+    }
+    if let Some(show_name) = &show_name {
+        sql_where.push(format!("show_name LIKE ${}", params.len() + 1));
+        params.push(Value::from(format!("%{show_name}%")));
+    }
+    if let Some(desc_by_create) = desc_by_create {
+        sql_order.push(format!("create_time {}", if desc_by_create { "DESC" } else { "ASC" }));
+    }
+    if let Some(desc_by_update) = desc_by_update {
+        sql_order.push(format!("update_time {}", if desc_by_update { "DESC" } else { "ASC" }));
+    }
+
+    let result = conn
+        .query_all(
+            &format!(
+                r#"SELECT key, show_name, items, owner, own_paths, remark, create_time, update_time, count(*) OVER() AS total
+FROM {table_name}
+WHERE
+    {}
+    {}
+LIMIT $2 OFFSET $3
+"#,
+                sql_where.join(" AND "),
+                if sql_order.is_empty() {
+                    "".to_string()
+                } else {
+                    format!("ORDER BY {}", sql_order.join(","))
+                }
+            ),
+            params,
+        )
+        .await?;
+
+    let mut total_size: i64 = 0;
+    let result = result
+        .into_iter()
+        .map(|item| {
+            if total_size == 0 {
+                total_size = item.try_get("", "total")?;
+            }
+            Ok(StatsConfDashboardInfoResp {
+                key: item.try_get("", "key")?,
+                show_name: item.try_get("", "show_name")?,
+                items: TardisFuns::json.json_to_obj(item.try_get::<serde_json::Value>("", "items")?)?,
+                owner: item.try_get("", "owner")?,
+                own_paths: item.try_get("", "own_paths")?,
+                remark: item.try_get("", "remark")?,
+                create_time: item.try_get("", "create_time")?,
+                update_time: item.try_get("", "update_time")?,
+            })
+        })
+        .collect::<TardisResult<_>>()?;
+    Ok(TardisPage {
+        page_size: page_size as u64,
+        page_number: page_number as u64,
+        total_size: total_size as u64,
+        records: result,
+    })
+}
+
+/// The saved queries of the items must exist in the same tenant/app.
+async fn check_items(items: &[StatsConfDashboardItem], op: &str, conn: &TardisRelDBlConnection, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    // The lazy loading mechanism may cause the saved query table to not be created
+    let saved_query_table_exist = common_pg::check_table_exit("stats_conf_saved_query", conn, ctx).await?;
+    for item in items {
+        if !saved_query_table_exist || stats_pg_conf_saved_query_serv::get(&item.saved_query_key, conn, ctx).await?.is_none() {
+            return Err(funs.err().not_found(
+                "dashboard_conf",
+                op,
+                &format!("The saved query [{}] of the dashboard does not exist.", item.saved_query_key),
+                "404-spi-stats-saved-query-conf-not-exist",
+            ));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,486 @@
+use bios_basic::spi::{
+    spi_funs::SpiBsInst,
+    spi_initializer::common_pg::{self, package_table_name},
+};
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    db::{
+        reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
+        sea_orm::Value,
+    },
+    web::web_resp::TardisPage,
+    TardisFunsInst,
+};
+
+use crate::{
+    dto::stats_conf_dto::{StatsConfFactColInfoResp, StatsConfFactRollupAddReq, StatsConfFactRollupInfoResp},
+    stats_enumeration::{StatsDataTypeKind, StatsFactColKind},
+};
+
+use super::{stats_pg_conf_dim_serv, stats_pg_conf_fact_col_serv, stats_pg_conf_fact_serv, stats_pg_initializer};
+
+/// The virtual measure of the record count, always aggregated by the rollups.
+const COUNT_MEASURE_KEY: &str = "_count";
+
+pub(crate) async fn add(fact_conf_key: &str, add_req: &StatsConfFactRollupAddReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (mut conn, table_name) = stats_pg_initializer::init_conf_fact_rollup_table_and_conn(bs_inst, ctx, true).await?;
+    conn.begin().await?;
+    if stats_pg_conf_fact_serv::get(fact_conf_key, &conn, ctx).await?.is_none() {
+        return Err(funs.err().not_found("fact_rollup_conf", "add", "The fact config does not exist.", "404-spi-stats-fact-conf-not-exist"));
+    }
+    if conn
+        .count_by_sql(
+            &format!("SELECT 1 FROM {table_name} WHERE key = $1 AND rel_conf_fact_key = $2"),
+            vec![Value::from(&add_req.key), Value::from(fact_conf_key)],
+        )
+        .await?
+        != 0
+    {
+        return Err(funs.err().conflict(
+            "fact_rollup_conf",
+            "add",
+            "The fact rollup config already exists, please delete it and then add it.",
+            "409-spi-stats-fact-rollup-conf-exist",
+        ));
+    }
+    if add_req.time_window.to_rollup_trunc_unit().is_none() {
+        return Err(funs.err().bad_request(
+            "fact_rollup_conf",
+            "add",
+            "The time window of the fact rollup only supports hour and date.",
+            "400-spi-stats-fact-rollup-time-window-not-legal",
+        ));
+    }
+    let fact_col_conf_set = stats_pg_conf_fact_col_serv::find_by_fact_conf_key(fact_conf_key, &conn, ctx, inst).await?;
+    for (idx, dim) in add_req.dims.iter().enumerate() {
+        if add_req.dims[..idx].contains(dim) || !fact_col_conf_set.iter().any(|col| &col.key == dim && col.kind == StatsFactColKind::Dimension) {
+            return Err(funs.err().bad_request(
+                "fact_rollup_conf",
+                "add",
+                &format!("The dimension [{dim}] of the fact rollup is not legal."),
+                "400-spi-stats-fact-rollup-dim-not-legal",
+            ));
+        }
+    }
+    for (idx, measure) in add_req.measures.iter().enumerate() {
+        if add_req.measures[..idx].contains(measure)
+            || !fact_col_conf_set
+                .iter()
+                .any(|col| &col.key == measure && col.kind == StatsFactColKind::Measure && col.mes_data_type.as_ref().is_some_and(StatsDataTypeKind::is_numeric))
+        {
+            return Err(funs.err().bad_request(
+                "fact_rollup_conf",
+                "add",
+                &format!("The measure [{measure}] of the fact rollup is not legal, only numeric measures are supported."),
+                "400-spi-stats-fact-rollup-measure-not-legal",
+            ));
+        }
+    }
+    let params = vec![
+        Value::from(add_req.key.to_string()),
+        Value::from(add_req.dims.clone()),
+        Value::from(add_req.time_window.to_string()),
+        Value::from(add_req.measures.clone()),
+        Value::from(fact_conf_key.to_string()),
+        Value::from(add_req.remark.as_ref().unwrap_or(&"".to_string()).as_str()),
+    ];
+    conn.execute_one(
+        &format!(
+            r#"INSERT INTO {table_name}
+(key, dims, time_window, measures, rel_conf_fact_key, remark)
+VALUES
+($1, $2, $3, $4, $5, $6)
+"#,
+        ),
+        params,
+    )
+    .await?;
+    // Backfill the rollup of the online fact
+    if stats_pg_conf_fact_serv::online(fact_conf_key, &conn, ctx).await? {
+        let rollup_conf = get(fact_conf_key, &add_req.key, &conn, ctx).await?.ok_or_else(|| {
+            funs.err().internal_error(
+                "fact_rollup_conf",
+                "add",
+                &format!("Fail to get fact rollup config by key [{}]", add_req.key),
+                "500-spi-stats-internal-error",
+            )
+        })?;
+        create_inst_table(fact_conf_key, &rollup_conf, &fact_col_conf_set, &conn, funs, ctx, inst).await?;
+        do_refresh_inst(fact_conf_key, &rollup_conf, &fact_col_conf_set, None, &conn, ctx).await?;
+    }
+    conn.commit().await?;
+    Ok(())
+}
+
+pub(crate) async fn delete(fact_conf_key: &str, rollup_conf_key: &str, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (mut conn, table_name) = stats_pg_initializer::init_conf_fact_rollup_table_and_conn(bs_inst, ctx, true).await?;
+    conn.begin().await?;
+    conn.execute_one(
+        &format!("DELETE FROM {table_name} WHERE key = $1 AND rel_conf_fact_key = $2"),
+        vec![Value::from(rollup_conf_key), Value::from(fact_conf_key)],
+    )
+    .await?;
+    drop_inst_table(fact_conf_key, rollup_conf_key, &conn, ctx).await?;
+    conn.commit().await?;
+    Ok(())
+}
+
+/// Rebuild the whole rollup instance from the fact records.
+///
+/// Incremental updates are applied when the fact records are loaded and the rollup is rebuilt when they are cleaned,
+/// the deleted records are kept in the rollup. This can be called by the schedule service to correct the rollup.
+pub(crate) async fn refresh(fact_conf_key: &str, rollup_conf_key: &str, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (mut conn, _) = stats_pg_initializer::init_conf_fact_rollup_table_and_conn(bs_inst, ctx, true).await?;
+    conn.begin().await?;
+    if !stats_pg_conf_fact_serv::online(fact_conf_key, &conn, ctx).await? {
+        return Err(funs.err().conflict("fact_rollup_inst", "refresh", "The fact config not online.", "409-spi-stats-fact-conf-not-online"));
+    }
+    let rollup_conf = get(fact_conf_key, rollup_conf_key, &conn, ctx)
+        .await?
+        .ok_or_else(|| funs.err().not_found("fact_rollup_inst", "refresh", "The fact rollup config does not exist.", "404-spi-stats-fact-rollup-conf-not-exist"))?;
+    let fact_col_conf_set = stats_pg_conf_fact_col_serv::find_by_fact_conf_key(fact_conf_key, &conn, ctx, inst).await?;
+    do_refresh_inst(fact_conf_key, &rollup_conf, &fact_col_conf_set, None, &conn, ctx).await?;
+    conn.commit().await?;
+    Ok(())
+}
+
+pub(crate) async fn paginate(
+    fact_conf_key: String,
+    rollup_conf_key: Option<String>,
+    page_number: u32,
+    page_size: u32,
+    desc_by_create: Option<bool>,
+    desc_by_update: Option<bool>,
+    _funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<TardisPage<StatsConfFactRollupInfoResp>> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, _) = stats_pg_initializer::init_conf_fact_rollup_table_and_conn(bs_inst, ctx, true).await?;
+
+    do_paginate(fact_conf_key, rollup_conf_key, page_number, page_size, desc_by_create, desc_by_update, &conn, ctx).await
+}
+
+async fn get(fact_conf_key: &str, rollup_conf_key: &str, conn: &TardisRelDBlConnection, ctx: &TardisContext) -> TardisResult<Option<StatsConfFactRollupInfoResp>> {
+    do_paginate(fact_conf_key.to_string(), Some(rollup_conf_key.to_string()), 1, 1, None, None, conn, ctx).await.map(|page| page.records.into_iter().next())
+}
+
+pub(in crate::serv::pg) async fn find_by_fact_conf_key(fact_conf_key: &str, conn: &TardisRelDBlConnection, ctx: &TardisContext) -> TardisResult<Vec<StatsConfFactRollupInfoResp>> {
+    if !common_pg::check_table_exit("stats_conf_fact_rollup", conn, ctx).await? {
+        return Ok(vec![]);
+    }
+    do_paginate(fact_conf_key.to_string(), None, 1, u32::MAX, None, None, conn, ctx).await.map(|page| page.records)
+}
+
+async fn do_paginate(
+    fact_conf_key: String,
+    rollup_conf_key: Option<String>,
+    page_number: u32,
+    page_size: u32,
+    desc_by_create: Option<bool>,
+    desc_by_update: Option<bool>,
+    conn: &TardisRelDBlConnection,
+    ctx: &TardisContext,
+) -> TardisResult<TardisPage<StatsConfFactRollupInfoResp>> {
+    let table_name = package_table_name("stats_conf_fact_rollup", ctx);
+    let mut sql_where = vec!["rel_conf_fact_key = $1".to_string()];
+    let mut sql_order = vec![];
+    let mut params: Vec<Value> = vec![Value::from(fact_conf_key), Value::from(page_size), Value::from((page_number - 1) * page_size)];
+    if let Some(rollup_conf_key) = &rollup_conf_key {
+        sql_where.push(format!("key = ${}", params.len() + 1));
+        params.push(Value::from(rollup_conf_key.to_string()));
+    }
+    if let Some(desc_by_create) = desc_by_create {
+        sql_order.push(format!("create_time {}", if desc_by_create { "DESC" } else { "ASC" }));
+    }
+    if let Some(desc_by_update) = desc_by_update {
+        sql_order.push(format!("update_time {}", if desc_by_update { "DESC" } else { "ASC" }));
+    }
+
+    let result = conn
+        .query_all(
+            &format!(
+                r#"SELECT key, dims, time_window, measures, remark, create_time, update_time, count(*) OVER() AS total
+FROM {table_name}
+WHERE
+    {}
+    {}
+LIMIT $2 OFFSET $3
+"#,
+                sql_where.join(" AND "),
+                if sql_order.is_empty() {
+                    "".to_string()
+                } else {
+                    format!("ORDER BY {}", sql_order.join(","))
+                }
+            ),
+            params,
+        )
+        .await?;
+
+    let mut total_size: i64 = 0;
+    let result = result
+        .into_iter()
+        .map(|item| {
+            if total_size == 0 {
+                total_size = item.try_get("", "total")?;
+            }
+            Ok(StatsConfFactRollupInfoResp {
+                key: item.try_get("", "key")?,
+                dims: item.try_get("", "dims")?,
+                time_window: item.try_get("", "time_window")?,
+                measures: item.try_get("", "measures")?,
+                remark: item.try_get("", "remark")?,
+                create_time: item.try_get("", "create_time")?,
+                update_time: item.try_get("", "update_time")?,
+            })
+        })
+        .collect::<TardisResult<_>>()?;
+    Ok(TardisPage {
+        page_size: page_size as u64,
+        page_number: page_number as u64,
+        total_size: total_size as u64,
+        records: result,
+    })
+}
+
+/// Rollup instance table name: `starsys_stats_inst_fact_<fact key>_rollup_<rollup key>`
+pub(in crate::serv::pg) fn package_inst_table_name(fact_conf_key: &str, rollup_conf_key: &str, ctx: &TardisContext) -> String {
+    package_table_name(&format!("stats_inst_fact_{fact_conf_key}_rollup_{rollup_conf_key}"), ctx)
+}
+
+/// Create the rollup instance tables of the fact, called when the fact goes online.
+pub(in crate::serv::pg) async fn create_inst_tables(
+    fact_conf_key: &str,
+    fact_col_conf_set: &[StatsConfFactColInfoResp],
+    conn: &TardisRelDBlConnection,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<()> {
+    for rollup_conf in find_by_fact_conf_key(fact_conf_key, conn, ctx).await? {
+        create_inst_table(fact_conf_key, &rollup_conf, fact_col_conf_set, conn, funs, ctx, inst).await?;
+    }
+    Ok(())
+}
+
+/// Delete the rollup configs and instance tables of the fact, called when the fact is deleted.
+pub(in crate::serv::pg) async fn delete_by_fact_conf_key(fact_conf_key: &str, conn: &TardisRelDBlConnection, ctx: &TardisContext) -> TardisResult<()> {
+    for rollup_conf in find_by_fact_conf_key(fact_conf_key, conn, ctx).await? {
+        drop_inst_table(fact_conf_key, &rollup_conf.key, conn, ctx).await?;
+    }
+    if common_pg::check_table_exit("stats_conf_fact_rollup", conn, ctx).await? {
+        conn.execute_one(
+            &format!("DELETE FROM {} WHERE rel_conf_fact_key = $1", package_table_name("stats_conf_fact_rollup", ctx)),
+            vec![Value::from(fact_conf_key)],
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Update the rollup instances of the fact.
+///
+/// The records of `loaded_fact_record_keys` loaded in the current transaction are added incrementally, all the buckets are rebuilt if `None`.
+pub(in crate::serv::pg) async fn refresh_inst(
+    fact_conf_key: &str,
+    loaded_fact_record_keys: Option<&[String]>,
+    conn: &TardisRelDBlConnection,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<()> {
+    let rollup_conf_set = find_by_fact_conf_key(fact_conf_key, conn, ctx).await?;
+    if rollup_conf_set.is_empty() {
+        return Ok(());
+    }
+    let fact_col_conf_set = stats_pg_conf_fact_col_serv::find_by_fact_conf_key(fact_conf_key, conn, ctx, inst).await?;
+    for rollup_conf in &rollup_conf_set {
+        do_refresh_inst(fact_conf_key, rollup_conf, &fact_col_conf_set, loaded_fact_record_keys, conn, ctx).await?;
+    }
+    Ok(())
+}
+
+/// Create rollup instance table.
+///
+/// The table fields are:
+/// - own_paths                                 data owner, used for data permission control
+/// - ct                                        the start of the time window bucket of the create time
+/// - [xxx,xxx,xxx,...]                         the dimensions of the rollup
+/// - [xxx__sum,xxx__count,xxx__max,xxx__min]   the aggregations of each measure and `_count`
+///
+/// The primary key is the bucket, i.e. `own_paths`, `ct` and the dimensions.
+///
+/// # Examples
+/// ```
+/// CREATE TABLE spi617070303031.starsys_stats_inst_fact_req_rollup_daily (
+///  own_paths character varying NOT NULL,
+///  ct timestamp with time zone NOT NULL,
+///  status character varying NOT NULL,
+///  act_hours__sum bigint NOT NULL,
+///  act_hours__count bigint NOT NULL,
+///  act_hours__max integer NOT NULL,
+///  act_hours__min integer NOT NULL,
+///  _count__sum bigint NOT NULL,
+///  _count__count bigint NOT NULL,
+///  _count__max integer NOT NULL,
+///  _count__min integer NOT NULL,
+///  PRIMARY KEY (own_paths, ct, status)
+/// )
+/// ```
+async fn create_inst_table(
+    fact_conf_key: &str,
+    rollup_conf: &StatsConfFactRollupInfoResp,
+    fact_col_conf_set: &[StatsConfFactColInfoResp],
+    conn: &TardisRelDBlConnection,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<()> {
+    let mut sql = vec![];
+    let mut index = vec![];
+    sql.push("own_paths character varying NOT NULL".to_string());
+    index.push(("own_paths".to_string(), "btree"));
+    sql.push("ct timestamp with time zone NOT NULL".to_string());
+    index.push(("ct".to_string(), "btree"));
+    for dim in &rollup_conf.dims {
+        let Some(dim_conf) = (match fact_col_conf_set.iter().find(|col| &col.key == dim).and_then(|col| col.dim_rel_conf_dim_key.as_ref()) {
+            Some(dim_conf_key) => stats_pg_conf_dim_serv::get(dim_conf_key, conn, ctx, inst).await?,
+            None => None,
+        }) else {
+            return Err(funs.err().conflict(
+                "fact_rollup_inst",
+                "create",
+                &format!("Fail to get dimension config of the rollup dimension [{dim}]"),
+                "409-spi-stats-fail-to-get-dim-config",
+            ));
+        };
+        if fact_col_conf_set.iter().any(|col| &col.key == dim && col.dim_multi_values.unwrap_or(false)) {
+            sql.push(format!("{dim} {}[] NOT NULL", dim_conf.data_type.to_pg_data_type()));
+            index.push((dim.clone(), "gin"));
+        } else {
+            sql.push(format!("{dim} {} NOT NULL", dim_conf.data_type.to_pg_data_type()));
+            index.push((dim.clone(), "btree"));
+        }
+    }
+    for (measure, mes_data_type) in package_measures(rollup_conf, fact_col_conf_set) {
+        let sum_data_type = if mes_data_type == StatsDataTypeKind::Int { "bigint" } else { mes_data_type.to_pg_data_type() };
+        sql.push(format!("{measure}__sum {sum_data_type} NOT NULL"));
+        sql.push(format!("{measure}__count bigint NOT NULL"));
+        sql.push(format!("{measure}__max {} NOT NULL", mes_data_type.to_pg_data_type()));
+        sql.push(format!("{measure}__min {} NOT NULL", mes_data_type.to_pg_data_type()));
+    }
+    common_pg::init_table(
+        conn,
+        Some(&format!("{fact_conf_key}_rollup_{}", rollup_conf.key)),
+        "stats_inst_fact",
+        sql.join(",\r\n").as_str(),
+        index.iter().map(|(field, index_type)| (field.as_str(), *index_type)).collect(),
+        Some(["own_paths", "ct"].into_iter().chain(rollup_conf.dims.iter().map(String::as_str)).collect()),
+        None,
+        ctx,
+    )
+    .await
+}
+
+async fn drop_inst_table(fact_conf_key: &str, rollup_conf_key: &str, conn: &TardisRelDBlConnection, ctx: &TardisContext) -> TardisResult<()> {
+    if common_pg::check_table_exit(&format!("stats_inst_fact_{fact_conf_key}_rollup_{rollup_conf_key}"), conn, ctx).await? {
+        conn.execute_one(&format!("DROP TABLE {}", package_inst_table_name(fact_conf_key, rollup_conf_key, ctx)), vec![]).await?;
+    }
+    Ok(())
+}
+
+/// Update the rollup instance.
+///
+/// The rollup aggregates all the fact records, the deleted records are not excluded and the records are not deduplicated,
+/// the queries that need them are answered from the fact records, see `stats_pg_metric_serv::match_rollup`.
+///
+/// If `loaded_fact_record_keys` is specified, the records of the keys loaded in the current transaction are added to their buckets,
+/// otherwise the whole rollup instance is rebuilt.
+/// The rollup instance is locked until the end of the transaction, so the concurrent updates are applied one by one.
+async fn do_refresh_inst(
+    fact_conf_key: &str,
+    rollup_conf: &StatsConfFactRollupInfoResp,
+    fact_col_conf_set: &[StatsConfFactColInfoResp],
+    loaded_fact_record_keys: Option<&[String]>,
+    conn: &TardisRelDBlConnection,
+    ctx: &TardisContext,
+) -> TardisResult<()> {
+    let fact_inst_table_name = package_table_name(&format!("stats_inst_fact_{fact_conf_key}"), ctx);
+    let rollup_inst_table_name = package_inst_table_name(fact_conf_key, &rollup_conf.key, ctx);
+    let trunc_unit = rollup_conf.time_window.to_rollup_trunc_unit().unwrap_or("day");
+
+    let mut sql_fields = vec!["own_paths".to_string(), "ct".to_string()];
+    let sql_bucket = format!("timezone('UTC', date_trunc('{trunc_unit}', timezone('UTC', fact.ct)))");
+    let mut sql_groups = vec!["fact.own_paths".to_string(), sql_bucket];
+    for dim in &rollup_conf.dims {
+        sql_fields.push(dim.to_string());
+        sql_groups.push(format!("fact.{dim}"));
+    }
+    let sql_bucket_fields = sql_fields.join(",");
+    let mut sql_selects = sql_groups.clone();
+    let mut sql_updates = vec![];
+    for (measure, _) in package_measures(rollup_conf, fact_col_conf_set) {
+        sql_fields.extend([format!("{measure}__sum"), format!("{measure}__count"), format!("{measure}__max"), format!("{measure}__min")]);
+        sql_selects.extend([
+            format!("sum(fact.{measure})"),
+            format!("count(fact.{measure})"),
+            format!("max(fact.{measure})"),
+            format!("min(fact.{measure})"),
+        ]);
+        sql_updates.extend([
+            format!("{measure}__sum = rollup.{measure}__sum + excluded.{measure}__sum"),
+            format!("{measure}__count = rollup.{measure}__count + excluded.{measure}__count"),
+            format!("{measure}__max = GREATEST(rollup.{measure}__max, excluded.{measure}__max)"),
+            format!("{measure}__min = LEAST(rollup.{measure}__min, excluded.{measure}__min)"),
+        ]);
+    }
+
+    conn.execute_one("SELECT pg_advisory_xact_lock(hashtext($1))", vec![Value::from(rollup_inst_table_name.as_str())]).await?;
+    let (sql_part_wheres, sql_part_conflict, params) = if let Some(loaded_fact_record_keys) = loaded_fact_record_keys {
+        // The fact records are only appended, so the records of the keys inserted by the current transaction are the loaded ones
+        (
+            "AND fact.key = ANY($1) AND fact.xmin = (txid_current() % 4294967296)::text::xid".to_string(),
+            format!("ON CONFLICT ({sql_bucket_fields}) DO UPDATE SET {}", sql_updates.join(",")),
+            vec![Value::from(loaded_fact_record_keys.to_vec())],
+        )
+    } else {
+        conn.execute_one(&format!("DELETE FROM {rollup_inst_table_name}"), vec![]).await?;
+        ("".to_string(), "".to_string(), vec![])
+    };
+    conn.execute_one(
+        &format!(
+            r#"INSERT INTO {rollup_inst_table_name} AS rollup
+({})
+SELECT {}
+FROM (
+    SELECT fact.*, 1 AS {COUNT_MEASURE_KEY}
+    FROM {fact_inst_table_name} fact
+    WHERE 1 = 1
+        {sql_part_wheres}
+) fact
+GROUP BY {}
+{sql_part_conflict}"#,
+            sql_fields.join(","),
+            sql_selects.join(","),
+            sql_groups.join(","),
+        ),
+        params,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Measures of the rollup with their data types, including `_count`.
+fn package_measures(rollup_conf: &StatsConfFactRollupInfoResp, fact_col_conf_set: &[StatsConfFactColInfoResp]) -> Vec<(String, StatsDataTypeKind)> {
+    rollup_conf
+        .measures
+        .iter()
+        .filter_map(|measure| {
+            fact_col_conf_set.iter().find(|col| &col.key == measure).and_then(|col| col.mes_data_type.clone()).map(|mes_data_type| (measure.clone(), mes_data_type))
+        })
+        .chain(std::iter::once((COUNT_MEASURE_KEY.to_string(), StatsDataTypeKind::Int)))
+        .collect()
+}
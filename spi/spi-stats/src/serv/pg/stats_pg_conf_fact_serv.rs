@@ -18,7 +18,7 @@ use crate::{
     stats_enumeration::{StatsDataTypeKind, StatsFactColKind},
 };
 
-use super::{stats_pg_conf_dim_serv, stats_pg_conf_fact_col_serv, stats_pg_initializer};
+use super::{stats_pg_conf_dim_serv, stats_pg_conf_fact_col_serv, stats_pg_conf_fact_rollup_serv, stats_pg_initializer};
 
 pub async fn online(fact_conf_key: &str, conn: &TardisRelDBlConnection, ctx: &TardisContext) -> TardisResult<bool> {
     common_pg::check_table_exit(&format!("stats_inst_fact_{fact_conf_key}"), conn, ctx).await
@@ -116,6 +116,7 @@ pub(crate) async fn delete(fact_conf_key: &str, _funs: &TardisFunsInst, ctx: &Ta
         conn.execute_one(&format!("DROP TABLE {}{fact_conf_key}", package_table_name("stats_inst_fact_", ctx)), vec![]).await?;
         conn.execute_one(&format!("DROP TABLE {}{fact_conf_key}_del", package_table_name("stats_inst_fact_", ctx)), vec![]).await?;
     }
+    stats_pg_conf_fact_rollup_serv::delete_by_fact_conf_key(fact_conf_key, &conn, ctx).await?;
     conn.commit().await?;
     Ok(())
 }
@@ -274,6 +275,7 @@ pub(crate) async fn create_inst(fact_conf_key: &str, funs: &TardisFunsInst, ctx:
         ));
     }
     create_inst_table(&fact_conf, &fact_col_conf, &conn, funs, ctx, inst).await?;
+    stats_pg_conf_fact_rollup_serv::create_inst_tables(&fact_conf.key, &fact_col_conf, &conn, funs, ctx, inst).await?;
     conn.commit().await?;
     Ok(())
 }
@@ -0,0 +1,331 @@
+use std::collections::HashMap;
+
+use bios_basic::spi::{
+    spi_funs::SpiBsInst,
+    spi_initializer::common_pg::{self, package_table_name},
+};
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    db::{
+        reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
+        sea_orm::Value,
+    },
+    serde_json::{self, json},
+    web::{poem_openapi::types::ParseFromJSON, web_resp::TardisPage},
+    TardisFunsInst,
+};
+
+use crate::dto::{
+    stats_conf_dto::{StatsConfSavedQueryAddReq, StatsConfSavedQueryInfoResp, StatsConfSavedQueryModifyReq},
+    stats_query_dto::{StatsQueryMetricsReq, StatsQueryMetricsResp, StatsQuerySavedMetricsReq},
+};
+
+use super::{stats_pg_conf_fact_serv, stats_pg_initializer, stats_pg_metric_serv};
+
+pub(crate) async fn add(add_req: &StatsConfSavedQueryAddReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (mut conn, table_name) = stats_pg_initializer::init_conf_saved_query_table_and_conn(bs_inst, ctx, true).await?;
+    conn.begin().await?;
+    if get(&add_req.key, &conn, ctx).await?.is_some() {
+        return Err(funs.err().conflict(
+            "saved_query_conf",
+            "add",
+            "The saved query config already exists, please delete it and then add it.",
+            "409-spi-stats-saved-query-conf-exist",
+        ));
+    }
+    check_query(&add_req.query, "add", &conn, funs, ctx).await?;
+    let params = vec![
+        Value::from(add_req.key.to_string()),
+        Value::from(add_req.show_name.clone()),
+        Value::from(add_req.query.clone()),
+        Value::from(ctx.owner.clone()),
+        Value::from(ctx.own_paths.clone()),
+        Value::from(add_req.remark.as_ref().unwrap_or(&"".to_string()).as_str()),
+    ];
+    conn.execute_one(
+        &format!(
+            r#"INSERT INTO {table_name}
+(key, show_name, query, owner, own_paths, remark)
+VALUES
+($1, $2, $3, $4, $5, $6)
+"#,
+        ),
+        params,
+    )
+    .await?;
+    conn.commit().await?;
+    Ok(())
+}
+
+pub(crate) async fn modify(saved_query_key: &str, modify_req: &StatsConfSavedQueryModifyReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (mut conn, table_name) = stats_pg_initializer::init_conf_saved_query_table_and_conn(bs_inst, ctx, true).await?;
+    conn.begin().await?;
+    if get(saved_query_key, &conn, ctx).await?.is_none() {
+        return Err(funs.err().not_found("saved_query_conf", "modify", "The saved query config does not exist.", "404-spi-stats-saved-query-conf-not-exist"));
+    }
+    let mut sql_sets = vec![];
+    let mut params = vec![Value::from(saved_query_key.to_string()), Value::from(ctx.own_paths.clone())];
+    if let Some(show_name) = &modify_req.show_name {
+        sql_sets.push(format!("show_name = ${}", params.len() + 1));
+        params.push(Value::from(show_name.to_string()));
+    }
+    if let Some(query) = &modify_req.query {
+        check_query(query, "modify", &conn, funs, ctx).await?;
+        sql_sets.push(format!("query = ${}", params.len() + 1));
+        params.push(Value::from(query.clone()));
+    }
+    if let Some(remark) = &modify_req.remark {
+        sql_sets.push(format!("remark = ${}", params.len() + 1));
+        params.push(Value::from(remark.to_string()));
+    }
+    if sql_sets.is_empty() {
+        return Ok(());
+    }
+    conn.execute_one(
+        &format!(
+            r#"UPDATE {table_name}
+SET {}
+WHERE key = $1 AND own_paths = $2
+"#,
+            sql_sets.join(",")
+        ),
+        params,
+    )
+    .await?;
+    conn.commit().await?;
+    Ok(())
+}
+
+pub(crate) async fn delete(saved_query_key: &str, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (mut conn, table_name) = stats_pg_initializer::init_conf_saved_query_table_and_conn(bs_inst, ctx, true).await?;
+    conn.begin().await?;
+    // The lazy loading mechanism may cause the dashboard table to not be created
+    if common_pg::check_table_exit("stats_conf_dashboard", &conn, ctx).await?
+        && conn
+            .count_by_sql(
+                &format!("SELECT 1 FROM {} WHERE own_paths = $1 AND items @> $2", package_table_name("stats_conf_dashboard", ctx)),
+                vec![Value::from(ctx.own_paths.clone()), Value::from(json!([{ "saved_query_key": saved_query_key }]))],
+            )
+            .await?
+            != 0
+    {
+        return Err(funs.err().conflict(
+            "saved_query_conf",
+            "delete",
+            "The saved query config is used by dashboards, please remove it from the dashboards and then delete it.",
+            "409-spi-stats-saved-query-conf-used",
+        ));
+    }
+    conn.execute_one(
+        &format!("DELETE FROM {table_name} WHERE key = $1 AND own_paths = $2"),
+        vec![Value::from(saved_query_key), Value::from(ctx.own_paths.clone())],
+    )
+    .await?;
+    conn.commit().await?;
+    Ok(())
+}
+
+pub(crate) async fn paginate(
+    saved_query_key: Option<String>,
+    show_name: Option<String>,
+    page_number: u32,
+    page_size: u32,
+    desc_by_create: Option<bool>,
+    desc_by_update: Option<bool>,
+    _funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<TardisPage<StatsConfSavedQueryInfoResp>> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, _) = stats_pg_initializer::init_conf_saved_query_table_and_conn(bs_inst, ctx, true).await?;
+
+    do_paginate(saved_query_key, show_name, page_number, page_size, desc_by_create, desc_by_update, &conn, ctx).await
+}
+
+/// Execute the saved query with the parameters bound to its placeholders.
+pub(crate) async fn execute(
+    saved_query_key: &str,
+    execute_req: &StatsQuerySavedMetricsReq,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<StatsQueryMetricsResp> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, _) = stats_pg_initializer::init_conf_saved_query_table_and_conn(bs_inst, ctx, true).await?;
+    let saved_query = get(saved_query_key, &conn, ctx)
+        .await?
+        .ok_or_else(|| funs.err().not_found("saved_query", "execute", "The saved query config does not exist.", "404-spi-stats-saved-query-conf-not-exist"))?;
+    let query = bind_params(&saved_query.query, execute_req.params.as_ref().unwrap_or(&HashMap::new())).map_err(|param_name| {
+        funs.err().bad_request(
+            "saved_query",
+            "execute",
+            &format!("The parameter [{param_name}] of the saved query is missing."),
+            "400-spi-stats-saved-query-param-missing",
+        )
+    })?;
+    // Parse in the same way as the request body, e.g. the field name of the filter is `where`
+    let query_req = StatsQueryMetricsReq::parse_from_json(Some(query)).map_err(|error| {
+        funs.err().bad_request(
+            "saved_query",
+            "execute",
+            &format!("The saved query is not a legal query after binding the parameters: {}", error.message()),
+            "400-spi-stats-saved-query-not-legal",
+        )
+    })?;
+    stats_pg_metric_serv::query_metrics(&query_req, funs, ctx, inst).await
+}
+
+pub(in crate::serv::pg) async fn get(saved_query_key: &str, conn: &TardisRelDBlConnection, ctx: &TardisContext) -> TardisResult<Option<StatsConfSavedQueryInfoResp>> {
+    do_paginate(Some(saved_query_key.to_string()), None, 1, 1, None, None, conn, ctx).await.map(|page| page.records.into_iter().next())
+}
+
+async fn do_paginate(
+    saved_query_key: Option<String>,
+    show_name: Option<String>,
+    page_number: u32,
+    page_size: u32,
+    desc_by_create: Option<bool>,
+    desc_by_update: Option<bool>,
+    conn: &TardisRelDBlConnection,
+    ctx: &TardisContext,
+) -> TardisResult<TardisPage<StatsConfSavedQueryInfoResp>> {
+    let table_name = package_table_name("stats_conf_saved_query", ctx);
+    let mut sql_where = vec!["own_paths = $1".to_string()];
+    let mut sql_order = vec![];
+    let mut params: Vec<Value> = vec![Value::from(ctx.own_paths.clone()), Value::from(page_size), Value::from((page_number - 1) * page_size)];
+    if let Some(saved_query_key) = &saved_query_key {
+        sql_where.push(format!("key = ${}", params.len() + 1));
+        params.push(Value::from(saved_query_key.to_string()));
+    }
+    if let Some(show_name) = &show_name {
+        sql_where.push(format!("show_name LIKE ${}", params.len() + 1));
+        params.push(Value::from(format!("%{show_name}%")));
+    }
+    if let Some(desc_by_create) = desc_by_create {
+        sql_order.push(format!("create_time {}", if desc_by_create { "DESC" } else { "ASC" }));
+    }
+    if let Some(desc_by_update) = desc_by_update {
+        sql_order.push(format!("update_time {}", if desc_by_update { "DESC" } else { "ASC" }));
+    }
+
+    let result = conn
+        .query_all(
+            &format!(
+                r#"SELECT key, show_name, query, owner, own_paths, remark, create_time, update_time, count(*) OVER() AS total
+FROM {table_name}
+WHERE
+    {}
+    {}
+LIMIT $2 OFFSET $3
+"#,
+                sql_where.join(" AND "),
+                if sql_order.is_empty() {
+                    "".to_string()
+                } else {
+                    format!("ORDER BY {}", sql_order.join(","))
+                }
+            ),
+            params,
+        )
+        .await?;
+
+    let mut total_size: i64 = 0;
+    let result = result
+        .into_iter()
+        .map(|item| {
+            if total_size == 0 {
+                total_size = item.try_get("", "total")?;
+            }
+            Ok(StatsConfSavedQueryInfoResp {
+                key: item.try_get("", "key")?,
+                show_name: item.try_get("", "show_name")?,
+                query: item.try_get("", "query")?,
+                owner: item.try_get("", "owner")?,
+                own_paths: item.try_get("", "own_paths")?,
+                remark: item.try_get("", "remark")?,
+                create_time: item.try_get("", "create_time")?,
+                update_time: item.try_get("", "update_time")?,
+            })
+        })
+        .collect::<TardisResult<_>>()?;
+    Ok(TardisPage {
+        page_size: page_size as u64,
+        page_number: page_number as u64,
+        total_size: total_size as u64,
+        records: result,
+    })
+}
+
+/// The query template must be an object whose `from` is an existing fact,
+/// and it must be a legal query after binding dummy values to the placeholders, see [`dummy_param`].
+async fn check_query(query: &serde_json::Value, op: &str, conn: &TardisRelDBlConnection, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    let Some(fact_conf_key) = query.get("from").and_then(|from| from.as_str()).filter(|from| parse_placeholder(from).is_none()) else {
+        return Err(funs.err().bad_request(
+            "saved_query_conf",
+            op,
+            "The [from] of the saved query must be a fact key.",
+            "400-spi-stats-saved-query-not-legal",
+        ));
+    };
+    if !common_pg::check_table_exit("stats_conf_fact", conn, ctx).await? || stats_pg_conf_fact_serv::get(fact_conf_key, conn, ctx).await?.is_none() {
+        return Err(funs.err().not_found("saved_query_conf", op, "The fact config does not exist.", "404-spi-stats-fact-conf-not-exist"));
+    }
+    let query = do_bind_params(query, "", &|_, field_name| Some(dummy_param(field_name))).unwrap_or_default();
+    if let Err(error) = StatsQueryMetricsReq::parse_from_json(Some(query)) {
+        return Err(funs.err().bad_request(
+            "saved_query_conf",
+            op,
+            &format!("The query template of the saved query is not legal: {}", error.message()),
+            "400-spi-stats-saved-query-not-legal",
+        ));
+    }
+    Ok(())
+}
+
+/// Replace the `${<param name>}` placeholders with the parameter values, returns the name of the missing parameter if any.
+fn bind_params(template: &serde_json::Value, params: &HashMap<String, serde_json::Value>) -> Result<serde_json::Value, String> {
+    do_bind_params(template, "", &|param_name, _| params.get(param_name).cloned())
+}
+
+/// `field_name` is the name of the field that the template is the value of, or an item of the value of.
+fn do_bind_params(
+    template: &serde_json::Value,
+    field_name: &str,
+    param_value: &impl Fn(&str, &str) -> Option<serde_json::Value>,
+) -> Result<serde_json::Value, String> {
+    match template {
+        serde_json::Value::String(value) => match parse_placeholder(value) {
+            Some(param_name) => param_value(param_name, field_name).ok_or_else(|| param_name.to_string()),
+            None => Ok(template.clone()),
+        },
+        serde_json::Value::Array(values) => {
+            values.iter().map(|value| do_bind_params(value, field_name, param_value)).collect::<Result<Vec<_>, _>>().map(serde_json::Value::Array)
+        }
+        serde_json::Value::Object(values) => values
+            .iter()
+            .map(|(key, value)| do_bind_params(value, key, param_value).map(|value| (key.clone(), value)))
+            .collect::<Result<serde_json::Map<_, _>, _>>()
+            .map(serde_json::Value::Object),
+        _ => Ok(template.clone()),
+    }
+}
+
+/// A legal value of the field of [`StatsQueryMetricsReq`] to check the query template.
+fn dummy_param(field_name: &str) -> serde_json::Value {
+    match field_name {
+        "start_time" | "end_time" => json!("1970-01-01T00:00:00.000Z"),
+        "limit" | "hierarchy" => json!(1),
+        "ignore_distinct" | "ignore_group_rollup" | "asc" => json!(false),
+        "fun" => json!("sum"),
+        "op" => json!("="),
+        "time_window" => json!("date"),
+        _ => json!(""),
+    }
+}
+
+fn parse_placeholder(value: &str) -> Option<&str> {
+    value.strip_prefix("${").and_then(|value| value.strip_suffix('}'))
+}
@@ -1,9 +1,22 @@
-use bios_basic::spi::{spi_funs::TypedSpiBsInst, spi_initializer};
+use bios_basic::spi::{
+    dto::spi_bs_dto::SpiBsCertResp,
+    spi_funs::{SpiBsInst, TypedSpiBsInst},
+    spi_initializer::{self, common_pg},
+};
 use tardis::{
     basic::{dto::TardisContext, result::TardisResult},
     db::reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
 };
 
+use crate::stats_constants;
+
+pub async fn init(bs_cert: &SpiBsCertResp, ctx: &TardisContext, mgr: bool) -> TardisResult<SpiBsInst> {
+    let mut inst = common_pg::init(bs_cert, ctx, mgr).await?;
+    // Metric stream listens on a dedicated connection
+    inst.ext.insert(stats_constants::CONN_URI_FLAG.to_string(), bs_cert.conn_uri.clone());
+    Ok(inst)
+}
+
 pub async fn init_conf_dim_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, ctx: &TardisContext, mgr: bool) -> TardisResult<(TardisRelDBlConnection, String)> {
     spi_initializer::common_pg::init_table_and_conn(
         bs_inst,
@@ -75,3 +88,80 @@ pub async fn init_conf_fact_col_table_and_conn(bs_inst: TypedSpiBsInst<'_, Tardi
     )
     .await
 }
+
+pub async fn init_conf_fact_rollup_table_and_conn(
+    bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>,
+    ctx: &TardisContext,
+    mgr: bool,
+) -> TardisResult<(TardisRelDBlConnection, String)> {
+    spi_initializer::common_pg::init_table_and_conn(
+        bs_inst,
+        ctx,
+        mgr,
+        None,
+        "stats_conf_fact_rollup",
+        r#"key character varying NOT NULL,
+    dims character varying[] NOT NULL,
+    time_window character varying NOT NULL,
+    measures character varying[] NOT NULL,
+    rel_conf_fact_key character varying NOT NULL,
+    remark character varying NOT NULL,
+    create_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    update_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    unique (key, rel_conf_fact_key)"#,
+        vec![("rel_conf_fact_key", "btree")],
+        None,
+        Some("update_time"),
+    )
+    .await
+}
+
+pub async fn init_conf_saved_query_table_and_conn(
+    bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>,
+    ctx: &TardisContext,
+    mgr: bool,
+) -> TardisResult<(TardisRelDBlConnection, String)> {
+    spi_initializer::common_pg::init_table_and_conn(
+        bs_inst,
+        ctx,
+        mgr,
+        None,
+        "stats_conf_saved_query",
+        r#"key character varying NOT NULL,
+    show_name character varying NOT NULL,
+    query jsonb NOT NULL,
+    owner character varying NOT NULL,
+    own_paths character varying NOT NULL,
+    remark character varying NOT NULL,
+    create_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    update_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    unique (key, own_paths)"#,
+        vec![("own_paths", "btree")],
+        None,
+        Some("update_time"),
+    )
+    .await
+}
+
+pub async fn init_conf_dashboard_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, ctx: &TardisContext, mgr: bool) -> TardisResult<(TardisRelDBlConnection, String)> {
+    spi_initializer::common_pg::init_table_and_conn(
+        bs_inst,
+        ctx,
+        mgr,
+        None,
+        "stats_conf_dashboard",
+        r#"key character varying NOT NULL,
+    show_name character varying NOT NULL,
+    items jsonb NOT NULL,
+    owner character varying NOT NULL,
+    own_paths character varying NOT NULL,
+    remark character varying NOT NULL,
+    create_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    update_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    unique (key, own_paths)"#,
+        vec![("own_paths", "btree"), ("items", "gin")],
+        None,
+        Some("update_time"),
+    )
+    .await
+}
@@ -7,6 +7,7 @@ use bios_basic::spi::{
 
 use tardis::{
     basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    chrono::{DateTime, Duration, Timelike, Utc},
     db::{
         reldb_client::TardisRelDBClient,
         sea_orm::{self, FromQueryResult, Value},
@@ -17,10 +18,15 @@ use tardis::{
 };
 
 use crate::{
-    dto::stats_query_dto::{StatsQueryMetricsReq, StatsQueryMetricsResp},
-    stats_enumeration::{StatsDataTypeKind, StatsFactColKind},
+    dto::{
+        stats_conf_dto::StatsConfFactRollupInfoResp,
+        stats_query_dto::{StatsQueryMetricsReq, StatsQueryMetricsResp},
+    },
+    stats_enumeration::{StatsDataTypeKind, StatsFactColKind, StatsQueryAggFunKind, StatsQueryTimeWindowKind},
 };
 
+use super::stats_pg_conf_fact_rollup_serv;
+
 const FUNCTION_SUFFIX_FLAG: &str = "__";
 
 /// 查询指标.
@@ -97,6 +103,42 @@ const FUNCTION_SUFFIX_FLAG: &str = "__";
 ///   2
 /// ```
 pub async fn query_metrics(query_req: &StatsQueryMetricsReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<StatsQueryMetricsResp> {
+    do_query_metrics(query_req, None, funs, ctx, inst)
+        .await?
+        .ok_or_else(|| funs.err().internal_error("metric", "query", "The query result is empty.", "500-spi-stats-internal-error"))
+}
+
+/// Incrementally recalculate the metrics with the newly loaded fact records, each record is identified by `(key, ct)`.
+///
+/// Only the queries of `sum`/`count`/`max`/`min` without deduplication, having, orders and limit are supported,
+/// the metrics of the records are merged into the previous result. Returns `None` if not supported.
+pub(in crate::serv::pg) async fn query_metrics_incrementally(
+    query_req: &StatsQueryMetricsReq,
+    prev_resp: &StatsQueryMetricsResp,
+    fact_records: &[(String, DateTime<Utc>)],
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<Option<StatsQueryMetricsResp>> {
+    let Some(delta_resp) = do_query_metrics(query_req, Some(fact_records), funs, ctx, inst).await? else {
+        return Ok(None);
+    };
+    let select_funs = query_req.select.iter().map(|select| (package_metric_alias_name(&select.code, &select.fun, &select.rel_code), &select.fun)).collect::<HashMap<_, _>>();
+    let mut group = prev_resp.group.clone();
+    if !merge_groups(&mut group, &delta_resp.group, &select_funs) {
+        return Ok(None);
+    }
+    Ok(Some(StatsQueryMetricsResp { group, ..delta_resp }))
+}
+
+/// Query all the records if `fact_records` is `None`, otherwise only the specified records.
+async fn do_query_metrics(
+    query_req: &StatsQueryMetricsReq,
+    fact_records: Option<&[(String, DateTime<Utc>)]>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<Option<StatsQueryMetricsResp>> {
     let bs_inst = inst.inst::<TardisRelDBClient>();
     let (conn, _) = common_pg::init_conn(bs_inst).await?;
 
@@ -117,7 +159,10 @@ pub async fn query_metrics(query_req: &StatsQueryMetricsReq, funs: &TardisFunsIn
     col.dim_multi_values as dim_multi_values,
     col.mes_data_distinct as mes_data_distinct,
     col.mes_data_type as mes_data_type,
+    col.dim_rel_conf_dim_key as dim_rel_conf_dim_key,
     dim.data_type as dim_data_type,
+    dim.stable_ds as dim_stable_ds,
+    dim.hierarchy as dim_hierarchy,
     fact.query_limit as query_limit
   FROM
     {fact_col_conf_table_name} col
@@ -150,6 +195,9 @@ pub async fn query_metrics(query_req: &StatsQueryMetricsReq, funs: &TardisFunsIn
                 } else {
                     Some(item.try_get("", "dim_data_type")?)
                 },
+                dim_rel_conf_dim_key: item.try_get("", "dim_rel_conf_dim_key")?,
+                dim_stable_ds: item.try_get("", "dim_stable_ds")?,
+                dim_hierarchy: item.try_get("", "dim_hierarchy")?,
                 query_limit: item.try_get("", "query_limit")?,
             })
         })
@@ -175,6 +223,9 @@ pub async fn query_metrics(query_req: &StatsQueryMetricsReq, funs: &TardisFunsIn
         mes_data_distinct: Some(true),
         mes_data_type: Some(StatsDataTypeKind::String),
         dim_data_type: None,
+        dim_rel_conf_dim_key: None,
+        dim_stable_ds: None,
+        dim_hierarchy: None,
         query_limit,
     });
     conf_info.push(StatsConfInfo {
@@ -185,6 +236,9 @@ pub async fn query_metrics(query_req: &StatsQueryMetricsReq, funs: &TardisFunsIn
         mes_data_distinct: Some(true),
         mes_data_type: None,
         dim_data_type: Some(StatsDataTypeKind::DateTime),
+        dim_rel_conf_dim_key: None,
+        dim_stable_ds: None,
+        dim_hierarchy: None,
         query_limit,
     });
     conf_info.push(StatsConfInfo {
@@ -195,12 +249,15 @@ pub async fn query_metrics(query_req: &StatsQueryMetricsReq, funs: &TardisFunsIn
         mes_data_distinct: Some(true),
         mes_data_type: Some(StatsDataTypeKind::Int),
         dim_data_type: None,
+        dim_rel_conf_dim_key: None,
+        dim_stable_ds: None,
+        dim_hierarchy: None,
         query_limit,
     });
 
     let conf_limit = query_limit;
     let conf_info = conf_info.into_iter().map(|v| (v.col_key.clone(), v)).collect::<HashMap<String, StatsConfInfo>>();
-    if query_req.select.iter().any(|i| !conf_info.contains_key(&i.code))
+    if query_req.select.iter().any(|i| !conf_info.contains_key(&i.code) || i.fun.is_rel_required() && !i.rel_code.as_ref().is_some_and(|rel_code| conf_info.contains_key(rel_code)))
         // should be equivalent: 
         // original: || query_req.group.iter().any(|i| !conf_info.contains_key(&i.code) || conf_info.get(&i.code).unwrap().col_kind != StatsFactColKind::Dimension))
         // (!contain || not_dim) => !(contain && is_dim)
@@ -208,17 +265,25 @@ pub async fn query_metrics(query_req: &StatsQueryMetricsReq, funs: &TardisFunsIn
         || query_req
             .group_order
             .as_ref()
-            .map(|orders| orders.iter().any(|order| !query_req.group.iter().any(|group| group.code == order.code && group.time_window == order.time_window)))
+            .map(|orders| {
+                orders.iter().any(|order| {
+                    !query_req.group.iter().any(|group| group.code == order.code && group.time_window == order.time_window && group.hierarchy == order.hierarchy)
+                })
+            })
             .unwrap_or(false)
         || query_req
             .metrics_order
             .as_ref()
-            .map(|orders| orders.iter().any(|order| !query_req.select.iter().any(|select| order.code == select.code && order.fun == select.fun)))
+            .map(|orders| {
+                orders.iter().any(|order| !query_req.select.iter().any(|select| order.code == select.code && order.fun == select.fun && order.rel_code == select.rel_code))
+            })
             .unwrap_or(false)
         || query_req
             .having
             .as_ref()
-            .map(|havings| havings.iter().any(|having| !query_req.select.iter().any(|select| having.code == select.code && having.fun == select.fun)))
+            .map(|havings| {
+                havings.iter().any(|having| !query_req.select.iter().any(|select| having.code == select.code && having.fun == select.fun && having.rel_code == select.rel_code))
+            })
             .unwrap_or(false)
         || query_req._where.as_ref().map(|or_wheres| or_wheres.iter().any(|and_wheres| and_wheres.iter().any(|where_| !conf_info.contains_key(&where_.code)))).unwrap_or(false)
     {
@@ -236,6 +301,39 @@ pub async fn query_metrics(query_req: &StatsQueryMetricsReq, funs: &TardisFunsIn
         false
     });
 
+    if fact_records.is_some()
+        && (query_req.select.iter().any(|select| !select.fun.is_incremental_supported())
+            || mes_distinct && !query_req.ignore_distinct.unwrap_or(false)
+            || query_req.having.is_some()
+            || query_req.group_order.is_some()
+            || query_req.metrics_order.is_some()
+            || query_req.limit.is_some())
+    {
+        return Ok(None);
+    }
+
+    // Answer from the smallest matching rollup if any.
+    // The rollups neither deduplicate the records nor exclude the deleted ones,
+    // so they are not used when the query deduplicates or there are records deleted in the time range.
+    let rollup_conf = if fact_records.is_some() || mes_distinct && !query_req.ignore_distinct.unwrap_or(false) {
+        None
+    } else {
+        match match_rollup(query_req, stats_pg_conf_fact_rollup_serv::find_by_fact_conf_key(&query_req.from, &conn, ctx).await?) {
+            Some(rollup_conf)
+                if conn
+                    .count_by_sql(
+                        &format!("SELECT 1 FROM {fact_inst_del_table_name} WHERE ct >= $1 AND ct <= $2"),
+                        vec![Value::from(query_req.start_time), Value::from(query_req.end_time)],
+                    )
+                    .await?
+                    == 0 =>
+            {
+                Some(rollup_conf)
+            }
+            _ => None,
+        }
+    };
+
     let mut params = vec![
         Value::from(format!("{}%", ctx.own_paths)),
         Value::from(query_req.start_time),
@@ -310,13 +408,64 @@ pub async fn query_metrics(query_req: &StatsQueryMetricsReq, funs: &TardisFunsIn
     // Package inner select
     // Add measures
     let mut sql_part_inner_selects = vec![];
-    for select in &query_req.select {
-        sql_part_inner_selects.push(format!("fact.{} AS {}", &select.code, &select.code));
+    let mut sql_part_inner_joins = vec![];
+    let mut select_codes: Vec<&String> = vec![];
+    for code in query_req.select.iter().flat_map(|select| std::iter::once(&select.code).chain(select.rel_code.iter().filter(|_| select.fun.is_rel_required()))) {
+        if !select_codes.contains(&code) {
+            select_codes.push(code);
+            if rollup_conf.is_some() {
+                for suffix in ["sum", "count", "max", "min"] {
+                    sql_part_inner_selects.push(format!("fact.{code}{FUNCTION_SUFFIX_FLAG}{suffix} AS {code}{FUNCTION_SUFFIX_FLAG}{suffix}"));
+                }
+            } else {
+                sql_part_inner_selects.push(format!("fact.{code} AS {code}"));
+            }
+        }
     }
-    for group in &query_req.group {
-        sql_part_inner_selects.push(format!("fact.{} AS {}", &group.code, &group.code));
+    for (group_idx, group) in query_req.group.iter().enumerate() {
+        if let Some(hierarchy) = group.hierarchy {
+            let col_conf = conf_info.get(&group.code).ok_or(funs.err().not_found(
+                "metric",
+                "query",
+                &format!("Missing config for group code [{code}] does not exist.", code = group.code),
+                "500-spi-stats-internal-error",
+            ))?;
+            let dim_conf_key = match (&col_conf.dim_rel_conf_dim_key, &col_conf.dim_hierarchy) {
+                (Some(dim_conf_key), Some(dim_hierarchy))
+                    if col_conf.dim_stable_ds.unwrap_or(false)
+                        && !col_conf.dim_multi_values.unwrap_or(false)
+                        && group.time_window.is_none()
+                        && (hierarchy as usize) < dim_hierarchy.len() =>
+                {
+                    dim_conf_key
+                }
+                _ => {
+                    return Err(funs.err().bad_request(
+                        "metric",
+                        "query",
+                        &format!("The group column=[{}] hierarchy=[{hierarchy}] is not legal.", &group.code),
+                        "400-spi-stats-metric-hierarchy-not-legal",
+                    ))
+                }
+            };
+            // The key of each level of the dimension record is recorded in `key<level>`,
+            // the deleted records are excluded and the latest one is taken in case a key has more than one record
+            let dim_inst_table_name = package_table_name(&format!("stats_inst_dim_{dim_conf_key}"), ctx);
+            let dim_alias_name = format!("_dim{group_idx}");
+            sql_part_inner_joins.push(format!(
+                "LEFT JOIN (SELECT DISTINCT ON (key) key, key{hierarchy} FROM {dim_inst_table_name} WHERE et IS NULL ORDER BY key, ct DESC) {dim_alias_name} ON {dim_alias_name}.key = fact.{}",
+                &group.code
+            ));
+            sql_part_inner_selects.push(format!(
+                "COALESCE({dim_alias_name}.key{hierarchy}, '') AS {}",
+                package_group_alias_name(&group.code, &None, Some(hierarchy))
+            ));
+        } else {
+            sql_part_inner_selects.push(format!("fact.{} AS {}", &group.code, &group.code));
+        }
     }
     let sql_part_inner_selects = sql_part_inner_selects.join(",");
+    let sql_part_inner_joins = sql_part_inner_joins.join(" ");
 
     // Package group
     // (column name with fun, alias name, show name)
@@ -334,12 +483,13 @@ pub async fn query_metrics(query_req: &StatsQueryMetricsReq, funs: &TardisFunsIn
             &format!("Missing col_data_type for group code [{code}] does not exist.", code = group.code),
             "500-spi-stats-internal-error",
         ))?;
-        if let Some(column_name_with_fun) = col_data_type.to_pg_group(&format!("_.{}", &group.code), col_conf.dim_multi_values.unwrap_or(false), &group.time_window) {
-            let alias_name = format!(
-                "{}{FUNCTION_SUFFIX_FLAG}{}",
-                group.code,
-                group.time_window.as_ref().map(|i| i.to_string().to_lowercase()).unwrap_or("".to_string())
-            );
+        if let Some(hierarchy) = group.hierarchy {
+            // The level is checked when packaging the inner select
+            let alias_name = package_group_alias_name(&group.code, &None, Some(hierarchy));
+            let show_name = col_conf.dim_hierarchy.as_ref().and_then(|dim_hierarchy| dim_hierarchy.get(hierarchy as usize)).cloned().unwrap_or_else(|| col_conf.show_name.clone());
+            sql_part_group_infos.push((format!("_.{alias_name}"), alias_name, show_name));
+        } else if let Some(column_name_with_fun) = col_data_type.to_pg_group(&format!("_.{}", &group.code), col_conf.dim_multi_values.unwrap_or(false), &group.time_window) {
+            let alias_name = package_group_alias_name(&group.code, &group.time_window, None);
             sql_part_group_infos.push((column_name_with_fun, alias_name, col_conf.show_name.clone()));
         } else {
             return Err(funs.err().not_found(
@@ -355,7 +505,57 @@ pub async fn query_metrics(query_req: &StatsQueryMetricsReq, funs: &TardisFunsIn
             ));
         }
     }
-    let sql_part_groups = sql_part_group_infos.iter().map(|group| group.1.clone()).collect::<Vec<String>>().join(",");
+    let sql_part_groups = sql_part_group_infos.iter().map(|group| group.1.clone()).collect::<Vec<String>>();
+    // The rollup of the n-th level is the grouping set of the levels before it
+    let group_ignore_rollups =
+        query_req.group.iter().map(|group| group.ignore_group_rollup.or(query_req.ignore_group_rollup).unwrap_or(false)).collect::<Vec<bool>>();
+    let sql_part_groups = if sql_part_groups.is_empty() {
+        "".to_string()
+    } else if group_ignore_rollups.iter().all(|ignore| *ignore) {
+        format!("GROUP BY {}", sql_part_groups.join(","))
+    } else if group_ignore_rollups.iter().all(|ignore| !*ignore) {
+        format!("GROUP BY ROLLUP({})", sql_part_groups.join(","))
+    } else {
+        let mut grouping_sets = vec![format!("({})", sql_part_groups.join(","))];
+        for (level, ignore) in group_ignore_rollups.iter().enumerate().rev() {
+            if !ignore {
+                grouping_sets.push(format!("({})", sql_part_groups[..level].join(",")));
+            }
+        }
+        format!("GROUP BY GROUPING SETS({})", grouping_sets.join(","))
+    };
+
+    // Package the window of growth, each period is compared with the previous one of the same other groups
+    let sql_part_growth_window = if query_req.select.iter().any(|select| select.fun == StatsQueryAggFunKind::Growth) {
+        let time_window_groups = query_req.group.iter().enumerate().filter(|(_, group)| group.time_window.is_some()).collect::<Vec<_>>();
+        let &[(time_window_group_idx, _)] = time_window_groups.as_slice() else {
+            return Err(funs.err().bad_request(
+                "metric",
+                "query",
+                "The growth function requires exactly one group with time window.",
+                "400-spi-stats-metric-growth-not-legal",
+            ));
+        };
+        if query_req.group.iter().any(|group| conf_info.get(&group.code).is_some_and(|conf| conf.dim_multi_values.unwrap_or(false))) {
+            return Err(funs.err().bad_request(
+                "metric",
+                "query",
+                "The growth function does not support groups with multiple values.",
+                "400-spi-stats-metric-growth-not-legal",
+            ));
+        }
+        // The rollup rows are partitioned separately by `GROUPING`
+        let sql_part_partitions = sql_part_group_infos
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| *idx != time_window_group_idx)
+            .map(|(_, (column_name_with_fun, _, _))| column_name_with_fun.clone())
+            .chain(sql_part_group_infos.iter().map(|(column_name_with_fun, _, _)| format!("GROUPING({column_name_with_fun})")))
+            .collect::<Vec<String>>();
+        Some(format!("PARTITION BY {} ORDER BY {}", sql_part_partitions.join(","), sql_part_group_infos[time_window_group_idx].0))
+    } else {
+        None
+    };
 
     // Package outer select
     // (column name with fun, alias name, show_name, is dimension)
@@ -376,8 +576,37 @@ pub async fn query_metrics(query_req: &StatsQueryMetricsReq, funs: &TardisFunsIn
             &format!("Missing col_data_type for select code [{code}] does not exist.", code = select.code),
             "500-spi-stats-internal-error",
         ))?;
-        let column_name_with_fun = col_data_type.to_pg_select(&format!("_.{}", &select.code), &select.fun);
-        let alias_name = format!("{}{FUNCTION_SUFFIX_FLAG}{}", select.code, select.fun.to_string().to_lowercase());
+        // The related measure must be numeric too
+        let rel_col_data_type = select.rel_code.as_ref().and_then(|rel_code| conf_info.get(rel_code)).and_then(|conf| conf.mes_data_type.as_ref());
+        if select.fun.is_numeric_only() && !col_data_type.is_numeric() || select.fun.is_rel_required() && !rel_col_data_type.is_some_and(StatsDataTypeKind::is_numeric) {
+            return Err(funs.err().bad_request(
+                "metric",
+                "query",
+                &format!(
+                    "The select column=[{}] type=[{}] fun=[{}] is not legal.",
+                    &select.code,
+                    col_data_type.to_string().to_lowercase(),
+                    select.fun.to_name()
+                ),
+                "400-spi-stats-metric-fun-not-legal",
+            ));
+        }
+        let rel_column_name = select.rel_code.as_ref().map(|rel_code| format!("_.{rel_code}"));
+        let column_name_with_fun = if rollup_conf.is_some() {
+            select.fun.to_rollup_sql(&format!("_.{}", &select.code), rel_column_name.as_deref(), col_data_type).ok_or(funs.err().internal_error(
+                "metric",
+                "query",
+                &format!("The select code [{}] fun [{}] is not supported by rollup.", select.code, select.fun.to_name()),
+                "500-spi-stats-internal-error",
+            ))?
+        } else {
+            col_data_type.to_pg_select(&format!("_.{}", &select.code), &select.fun, rel_column_name.as_deref())
+        };
+        let column_name_with_fun = match (&select.fun, &sql_part_growth_window) {
+            (StatsQueryAggFunKind::Growth, Some(window)) => StatsQueryAggFunKind::to_growth_sql(&column_name_with_fun, window),
+            _ => column_name_with_fun,
+        };
+        let alias_name = package_metric_alias_name(&select.code, &select.fun, &select.rel_code);
         sql_part_outer_select_infos.push((column_name_with_fun, alias_name, col_conf.show_name.clone(), false));
     }
     let sql_part_outer_selects =
@@ -393,17 +622,31 @@ pub async fn query_metrics(query_req: &StatsQueryMetricsReq, funs: &TardisFunsIn
                 &format!("Missing config for having code [{code}] does not exist.", code = having.code),
                 "500-spi-stats-internal-error",
             ))?;
-            if let Some((sql_part, value)) = col_conf
-                .mes_data_type
-                .as_ref()
-                .ok_or(funs.err().not_found(
-                    "metric",
-                    "query",
-                    &format!("Missing mes_data_type for having code [{code}] does not exist.", code = having.code),
-                    "500-spi-stats-internal-error",
-                ))?
-                .to_pg_having(false, &format!("_.{}", &having.code), &having.op, params.len() + 1, &having.value, Some(&having.fun))?
-            {
+            let mes_data_type = col_conf.mes_data_type.as_ref().ok_or(funs.err().not_found(
+                "metric",
+                "query",
+                &format!("Missing mes_data_type for having code [{code}] does not exist.", code = having.code),
+                "500-spi-stats-internal-error",
+            ))?;
+            let rel_column_name = having.rel_code.as_ref().map(|rel_code| format!("_.{rel_code}"));
+            let sql_part_having = if rollup_conf.is_some() {
+                // The aggregate function is applied to the rollup columns
+                match having.fun.to_rollup_sql(&format!("_.{}", &having.code), rel_column_name.as_deref(), mes_data_type) {
+                    Some(column_name_with_fun) => mes_data_type.to_pg_having(false, &column_name_with_fun, &having.op, params.len() + 1, &having.value, None, None)?,
+                    None => None,
+                }
+            } else {
+                mes_data_type.to_pg_having(
+                    false,
+                    &format!("_.{}", &having.code),
+                    &having.op,
+                    params.len() + 1,
+                    &having.value,
+                    Some(&having.fun),
+                    rel_column_name.as_deref(),
+                )?
+            };
+            if let Some((sql_part, value)) = sql_part_having {
                 value.iter().for_each(|v| params.push(v.clone()));
                 sql_part_havings.push(sql_part);
             } else {
@@ -415,7 +658,7 @@ pub async fn query_metrics(query_req: &StatsQueryMetricsReq, funs: &TardisFunsIn
                         &having.code,
                         col_conf.mes_data_type.as_ref().map(ToString::to_string).unwrap_or("None".into()).to_lowercase(),
                         &having.op.to_sql(),
-                        &having.fun.to_string().to_lowercase()
+                        having.fun.to_name()
                     ),
                     "404-spi-stats-metric-op-not-legal",
                 ));
@@ -442,9 +685,8 @@ pub async fn query_metrics(query_req: &StatsQueryMetricsReq, funs: &TardisFunsIn
                 .iter()
                 .map(|order| {
                     format!(
-                        "{}{FUNCTION_SUFFIX_FLAG}{} {}",
-                        order.code,
-                        order.time_window.as_ref().map(|i| i.to_string().to_lowercase()).unwrap_or("".to_string()),
+                        "{} {}",
+                        package_group_alias_name(&order.code, &order.time_window, order.hierarchy),
                         if order.asc { "ASC" } else { "DESC" }
                     )
                 })
@@ -454,14 +696,7 @@ pub async fn query_metrics(query_req: &StatsQueryMetricsReq, funs: &TardisFunsIn
         if let Some(orders) = &query_req.metrics_order {
             let metrics_orders = orders
                 .iter()
-                .map(|order| {
-                    format!(
-                        "{}{FUNCTION_SUFFIX_FLAG}{} {}",
-                        order.code,
-                        order.fun.to_string().to_lowercase(),
-                        if order.asc { "ASC" } else { "DESC" }
-                    )
-                })
+                .map(|order| format!("{} {}", package_metric_alias_name(&order.code, &order.fun, &order.rel_code), if order.asc { "ASC" } else { "DESC" }))
                 .collect::<Vec<String>>();
             sql_part_orders.extend(metrics_orders);
         }
@@ -472,54 +707,69 @@ pub async fn query_metrics(query_req: &StatsQueryMetricsReq, funs: &TardisFunsIn
     // package limit
     let query_limit = if let Some(limit) = &query_req.limit { format!("LIMIT {limit}") } else { "".to_string() };
 
-    let final_sql = format!(
-        r#"SELECT {sql_part_outer_selects}
-    FROM (
-        SELECT
-             {sql_part_inner_selects}
-             FROM(
-                SELECT {}fact.*, 1 as _count
+    let sql_part_source = if let Some(rollup_conf) = &rollup_conf {
+        // The ct of the rollup table is the bucket start time
+        format!(
+            r#"SELECT fact.*
+                FROM {} fact
+                WHERE
+                    fact.own_paths LIKE $1
+                    AND fact.ct >= $2 AND fact.ct <= $3"#,
+            stats_pg_conf_fact_rollup_serv::package_inst_table_name(&query_req.from, &rollup_conf.key, ctx)
+        )
+    } else {
+        let sql_part_records = if let Some(fact_records) = fact_records {
+            params.push(Value::from(fact_records.iter().map(|(key, _)| key.to_string()).collect::<Vec<_>>()));
+            // The create times are bound as timestamps, so they are encoded in the same way as when the records are loaded
+            params.push(Value::from(fact_records.iter().map(|(_, ct)| *ct).collect::<Vec<_>>()));
+            format!("AND (fact.key, fact.ct) IN (SELECT * FROM unnest(${}::text[], ${}::timestamptz[]))", params.len() - 1, params.len())
+        } else {
+            "".to_string()
+        };
+        format!(
+            r#"SELECT {}fact.*, 1 as _count
                 FROM {fact_inst_table_name} fact
                 LEFT JOIN {fact_inst_del_table_name} del ON del.key = fact.key AND del.ct >= $2 AND del.ct <= $3
                 WHERE
                     fact.own_paths LIKE $1
                     AND del.key IS NULL
                     AND fact.ct >= $2 AND fact.ct <= $3
-                ORDER BY {}fact.ct DESC
-             ) fact 
+                    {sql_part_records}
+                ORDER BY {}fact.ct DESC"#,
+            if query_req.ignore_distinct.unwrap_or(false) {
+                ""
+            } else if mes_distinct {
+                "DISTINCT ON (fact.key) fact.key AS _key,"
+            } else {
+                ""
+            },
+            if query_req.ignore_distinct.unwrap_or(false) {
+                ""
+            } else if mes_distinct {
+                "_key,"
+            } else {
+                ""
+            },
+        )
+    };
+
+    let final_sql = format!(
+        r#"SELECT {sql_part_outer_selects}
+    FROM (
+        SELECT
+             {sql_part_inner_selects}
+             FROM(
+                {sql_part_source}
+             ) fact {sql_part_inner_joins}
              where 1 = 1
             {sql_part_wheres}
             {sql_dimension_orders}
         LIMIT {conf_limit}
     ) _
-    {}
+    {sql_part_groups}
     {sql_part_havings}
     {sql_orders}
-    {query_limit}"#,
-        if query_req.ignore_distinct.unwrap_or(false) {
-            ""
-        } else if mes_distinct {
-            "DISTINCT ON (fact.key) fact.key AS _key,"
-        } else {
-            ""
-        },
-        if query_req.ignore_distinct.unwrap_or(false) {
-            ""
-        } else if mes_distinct {
-            "_key,"
-        } else {
-            ""
-        },
-        if sql_part_groups.is_empty() {
-            "".to_string()
-        } else {
-            #[allow(clippy::collapsible_else_if)]
-            if query_req.ignore_group_rollup.unwrap_or(false) {
-                format!("GROUP BY {sql_part_groups}")
-            } else {
-                format!("GROUP BY ROLLUP({sql_part_groups})")
-            }
-        }
+    {query_limit}"#
     );
 
     let result = conn
@@ -536,12 +786,77 @@ pub async fn query_metrics(query_req: &StatsQueryMetricsReq, funs: &TardisFunsIn
     let select_measure_keys =
         sql_part_outer_select_infos.iter().filter(|(_, _, _, is_dimension)| !*is_dimension).map(|(_, alias_name, _, _)| alias_name.to_string()).collect::<Vec<String>>();
     let show_names = sql_part_outer_select_infos.into_iter().map(|(_, alias_name, show_name, _)| (alias_name, show_name)).collect::<HashMap<String, String>>();
-    Ok(StatsQueryMetricsResp {
+    Ok(Some(StatsQueryMetricsResp {
         from: query_req.from.to_string(),
         show_names,
         group: package_groups(select_dimension_keys, &select_measure_keys, result)
             .map_err(|msg| TardisError::internal_error(&format!("Fail to package groups: {msg}"), "500-spi-stats-internal-error"))?,
-    })
+    }))
+}
+
+/// Find the smallest rollup that can answer the query.
+///
+/// The buckets of the rollup must be aligned with the query time range,
+/// and all the measures, groups and filters of the query must be calculable from it.
+fn match_rollup(query_req: &StatsQueryMetricsReq, rollup_conf_set: Vec<StatsConfFactRollupInfoResp>) -> Option<StatsConfFactRollupInfoResp> {
+    let is_dimension_matched = |rollup_conf: &StatsConfFactRollupInfoResp, code: &str, time_window: &Option<StatsQueryTimeWindowKind>| {
+        if code == "ct" {
+            time_window.as_ref().is_some_and(|time_window| time_window.is_coarser_or_equal(&rollup_conf.time_window))
+        } else {
+            rollup_conf.dims.iter().any(|dim| dim == code)
+        }
+    };
+    let is_measure_matched = |rollup_conf: &StatsConfFactRollupInfoResp, code: &str| code == "_count" || rollup_conf.measures.iter().any(|measure| measure == code);
+    rollup_conf_set
+        .into_iter()
+        .filter(|rollup_conf| {
+            is_bucket_start(&query_req.start_time, &rollup_conf.time_window)
+                && is_bucket_end(&query_req.end_time, &rollup_conf.time_window)
+                && query_req.select.iter().all(|select| {
+                    select.fun.is_rollup_supported()
+                        && is_measure_matched(rollup_conf, &select.code)
+                        && (!select.fun.is_rel_required() || select.rel_code.as_ref().is_some_and(|rel_code| is_measure_matched(rollup_conf, rel_code)))
+                })
+                && query_req.group.iter().all(|group| is_dimension_matched(rollup_conf, &group.code, &group.time_window))
+                && query_req
+                    ._where
+                    .as_ref()
+                    .map(|or_wheres| or_wheres.iter().flatten().all(|where_| is_dimension_matched(rollup_conf, &where_.code, &where_.time_window)))
+                    .unwrap_or(true)
+                && query_req
+                    .dimension_order
+                    .as_ref()
+                    .map(|orders| orders.iter().all(|order| order.code == "ct" || rollup_conf.dims.iter().any(|dim| dim == &order.code)))
+                    .unwrap_or(true)
+                && query_req.having.as_ref().map(|havings| havings.iter().all(|having| having.fun.is_having_supported())).unwrap_or(true)
+        })
+        .min_by_key(|rollup_conf| (rollup_conf.time_window == StatsQueryTimeWindowKind::Hour, rollup_conf.dims.len()))
+}
+
+fn is_bucket_start(time: &DateTime<Utc>, time_window: &StatsQueryTimeWindowKind) -> bool {
+    time.minute() == 0 && time.second() == 0 && time.nanosecond() == 0 && (time_window == &StatsQueryTimeWindowKind::Hour || time.hour() == 0)
+}
+
+/// The end time of the query is inclusive, so it should be the last millisecond or second of a bucket.
+fn is_bucket_end(time: &DateTime<Utc>, time_window: &StatsQueryTimeWindowKind) -> bool {
+    is_bucket_start(&(*time + Duration::milliseconds(1)), time_window) || is_bucket_start(&(*time + Duration::seconds(1)), time_window)
+}
+
+/// The format of the alias: `field name__<time window>` or `field name__hierarchy<level>`.
+fn package_group_alias_name(code: &str, time_window: &Option<StatsQueryTimeWindowKind>, hierarchy: Option<u8>) -> String {
+    if let Some(hierarchy) = hierarchy {
+        format!("{code}{FUNCTION_SUFFIX_FLAG}hierarchy{hierarchy}")
+    } else {
+        format!("{code}{FUNCTION_SUFFIX_FLAG}{}", time_window.as_ref().map(|i| i.to_string().to_lowercase()).unwrap_or("".to_string()))
+    }
+}
+
+/// The format of the alias: `field name__<function name>` or `field name__ratio__<related field name>`.
+fn package_metric_alias_name(code: &str, fun: &StatsQueryAggFunKind, rel_code: &Option<String>) -> String {
+    match rel_code {
+        Some(rel_code) if fun.is_rel_required() => format!("{code}{FUNCTION_SUFFIX_FLAG}{}{FUNCTION_SUFFIX_FLAG}{rel_code}", fun.to_name()),
+        _ => format!("{code}{FUNCTION_SUFFIX_FLAG}{}", fun.to_name()),
+    }
 }
 
 fn package_groups(curr_select_dimension_keys: Vec<String>, select_measure_keys: &Vec<String>, result: Vec<serde_json::Value>) -> Result<serde_json::Value, String> {
@@ -593,6 +908,35 @@ fn package_groups(curr_select_dimension_keys: Vec<String>, select_measure_keys:
     Ok(serde_json::Value::Object(node))
 }
 
+/// Merge the metrics of the newly loaded records into the previous groups.
+///
+/// Returns `false` if the groups can't be merged, e.g. the value is not a number.
+fn merge_groups(prev_group: &mut serde_json::Value, delta_group: &serde_json::Value, select_funs: &HashMap<String, &StatsQueryAggFunKind>) -> bool {
+    let (serde_json::Value::Object(prev_node), serde_json::Value::Object(delta_node)) = (prev_group, delta_group) else {
+        return false;
+    };
+    for (key, delta_value) in delta_node {
+        let merged = match (prev_node.get_mut(key), select_funs.get(key)) {
+            (None, _) => {
+                prev_node.insert(key.to_string(), delta_value.clone());
+                true
+            }
+            (Some(prev_value), None) => merge_groups(prev_value, delta_value, select_funs),
+            (Some(prev_value), Some(fun)) => match fun.merge_value(prev_value, delta_value) {
+                Some(value) => {
+                    *prev_value = value;
+                    true
+                }
+                None => false,
+            },
+        };
+        if !merged {
+            return false;
+        }
+    }
+    true
+}
+
 #[derive(sea_orm::FromQueryResult)]
 struct StatsConfInfo {
     pub col_key: String,
@@ -602,5 +946,8 @@ struct StatsConfInfo {
     pub mes_data_distinct: Option<bool>,
     pub mes_data_type: Option<StatsDataTypeKind>,
     pub dim_data_type: Option<StatsDataTypeKind>,
+    pub dim_rel_conf_dim_key: Option<String>,
+    pub dim_stable_ds: Option<bool>,
+    pub dim_hierarchy: Option<Vec<String>>,
     pub query_limit: i32,
 }
@@ -0,0 +1,268 @@
+use std::collections::{HashMap, HashSet};
+
+use bios_basic::spi::{
+    spi_funs::{SpiBsInst, SpiBsInstExtractor},
+    spi_initializer::common_pg::package_table_name,
+};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use tardis::{
+    basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    chrono::{DateTime, Utc},
+    db::{
+        reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
+        sea_orm::{sqlx::postgres::PgListener, Value},
+    },
+    futures::{SinkExt, StreamExt},
+    log::{info, warn},
+    serde_json,
+    tokio::{
+        self,
+        sync::{
+            broadcast::{self, error::RecvError},
+            RwLock,
+        },
+        time::{self, Duration, Instant},
+    },
+    web::poem::web::websocket::{BoxWebSocketUpgraded, Message, WebSocket},
+    TardisFuns, TardisFunsInst,
+};
+
+use crate::{
+    dto::stats_query_dto::{StatsQueryMetricsReq, StatsQueryMetricsResp},
+    stats_config::StatsConfig,
+    stats_constants, stats_initializer,
+};
+
+use super::stats_pg_metric_serv;
+
+const CHANNEL_CAPACITY: usize = 1024;
+// The payload of NOTIFY must be shorter than 8000 bytes
+const NOTIFY_PAYLOAD_MAX_LEN: usize = 7999;
+// Cache key prefix of the fact instance tables having subscribers on any node
+const SUBSCRIBED_CACHE_KEY_PREFIX: &str = "spi-stats:metric-stream:subscribed:";
+// The subscription mark is refreshed while the table has subscribers on this node, and expires after the last one leaves
+const SUBSCRIBED_EXP_SECS: usize = 60;
+const SUBSCRIBED_REFRESH_SECS: u64 = 20;
+
+lazy_static! {
+    // Senders of the changes of fact records, by fact instance table name, created by the first subscriber of the table
+    static ref SENDERS: RwLock<HashMap<String, broadcast::Sender<FactRecordChange>>> = RwLock::new(HashMap::new());
+    // Connection uris that are being listened on
+    static ref LISTENERS: RwLock<HashSet<String>> = RwLock::new(HashSet::new());
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct FactRecordChange {
+    // Fact instance table name with schema
+    table: String,
+    // `(key, ct)` of the loaded records, `None` if the records are deleted or cleaned, or too many to be sent
+    records: Option<Vec<(String, DateTime<Utc>)>>,
+}
+
+/// Notify the subscribers on all nodes of the changes of fact records, they are only delivered when the transaction is committed.
+///
+/// `fact_records` is the `(key, ct)` of the loaded records, `None` if the records are deleted or cleaned.
+/// One notification is sent per call, the loaded records are left out if the payload exceeds the limit of NOTIFY,
+/// and the subscribers recalculate all instead.
+///
+/// NOTIFY serializes the committing transactions, so it is skipped when no node subscribes the table.
+pub(in crate::serv::pg) async fn notify(
+    fact_conf_key: &str,
+    fact_records: Option<&[(String, DateTime<Utc>)]>,
+    conn: &TardisRelDBlConnection,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+) -> TardisResult<()> {
+    let table = package_table_name(&format!("stats_inst_fact_{fact_conf_key}"), ctx);
+    if !funs.cache().exists(&format!("{SUBSCRIBED_CACHE_KEY_PREFIX}{table}")).await? {
+        return Ok(());
+    }
+    let mut payload = TardisFuns::json.obj_to_string(&FactRecordChange {
+        table: table.clone(),
+        records: fact_records.map(|fact_records| fact_records.to_vec()),
+    })?;
+    if payload.len() > NOTIFY_PAYLOAD_MAX_LEN {
+        payload = TardisFuns::json.obj_to_string(&FactRecordChange { table, records: None })?;
+    }
+    conn.execute_one("SELECT pg_notify($1, $2)", vec![Value::from(stats_constants::METRIC_STREAM_NOTIFY_CHANNEL), Value::from(payload)]).await?;
+    Ok(())
+}
+
+/// Push the metrics through websocket, first the current result and then the updated results when the fact records change.
+///
+/// The changes are collected for `debounce_ms` before recalculating, the loaded records are recalculated incrementally if possible.
+pub async fn stream(
+    query_req: StatsQueryMetricsReq,
+    debounce_ms: Option<u64>,
+    websocket: WebSocket,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<BoxWebSocketUpgraded> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let conn_uri = bs_inst.1.get(stats_constants::CONN_URI_FLAG).ok_or_else(|| {
+        funs.err().internal_error(
+            "metric",
+            "stream",
+            "The connection uri of backend service is not found",
+            "500-spi-stats-conn-uri-not-found",
+        )
+    })?;
+    // Errors of the query are returned before upgrading
+    let mut prev_resp = stats_pg_metric_serv::query_metrics(&query_req, funs, ctx, inst).await?;
+    start_listener(conn_uri).await?;
+    let table = package_table_name(&format!("stats_inst_fact_{}", query_req.from), ctx);
+    let mut receiver = {
+        let mut senders = SENDERS.write().await;
+        if !senders.contains_key(&table) {
+            mark_subscribed(&table, funs).await?;
+        }
+        senders.entry(table.clone()).or_insert_with(|| broadcast::channel(CHANNEL_CAPACITY).0).subscribe()
+    };
+    let debounce = Duration::from_millis(debounce_ms.unwrap_or(funs.conf::<StatsConfig>().metric_stream_debounce_ms));
+    let incremental_max_records = funs.conf::<StatsConfig>().metric_stream_incremental_max_records;
+    let ctx = ctx.clone();
+    Ok(websocket
+        .on_upgrade(move |socket| async move {
+            let (mut sink, mut stream) = socket.split();
+            if sink.send(Message::Text(TardisFuns::json.obj_to_string(&prev_resp).unwrap_or_default())).await.is_ok() {
+                let mut loaded_records = vec![];
+                let mut full_refresh = false;
+                let mut deadline = None;
+                loop {
+                    tokio::select! {
+                        change = receiver.recv() => {
+                            match change {
+                                Ok(FactRecordChange { records: Some(records), .. }) => loaded_records.extend(records),
+                                Ok(FactRecordChange { records: None, .. }) => full_refresh = true,
+                                Err(RecvError::Lagged(skipped)) => {
+                                    warn!("[SPI-Stats] Metric stream subscriber is lagging, {skipped} changes are skipped");
+                                    full_refresh = true;
+                                }
+                                Err(RecvError::Closed) => break,
+                            }
+                            deadline.get_or_insert_with(|| Instant::now() + debounce);
+                        }
+                        _ = time::sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                            deadline = None;
+                            let fact_records = if full_refresh || loaded_records.len() > incremental_max_records { None } else { Some(loaded_records.as_slice()) };
+                            let resp = match refresh(&query_req, &prev_resp, fact_records, &ctx).await {
+                                Ok(resp) => resp,
+                                Err(e) => {
+                                    warn!("[SPI-Stats] Metric stream recalculate error: {e:?}");
+                                    break;
+                                }
+                            };
+                            loaded_records.clear();
+                            full_refresh = false;
+                            if resp.group == prev_resp.group {
+                                continue;
+                            }
+                            if sink.send(Message::Text(TardisFuns::json.obj_to_string(&resp).unwrap_or_default())).await.is_err() {
+                                break;
+                            }
+                            prev_resp = resp;
+                        }
+                        client_message = stream.next() => {
+                            match client_message {
+                                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                                _ => {}
+                            }
+                        }
+                    }
+                }
+            }
+            // Remove the sender of the table when the last subscriber leaves
+            drop(receiver);
+            let mut senders = SENDERS.write().await;
+            if senders.get(&table).is_some_and(|sender| sender.receiver_count() == 0) {
+                senders.remove(&table);
+            }
+        })
+        .boxed())
+}
+
+/// Mark the table as subscribed for the nodes changing fact records, and keep the mark until the table has no subscribers on this node
+async fn mark_subscribed(table: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+    let cache_key = format!("{SUBSCRIBED_CACHE_KEY_PREFIX}{table}");
+    let cache_client = funs.cache();
+    cache_client.set_ex(&cache_key, "", SUBSCRIBED_EXP_SECS).await?;
+    let table = table.to_string();
+    tokio::spawn(async move {
+        let mut interval = time::interval(Duration::from_secs(SUBSCRIBED_REFRESH_SECS));
+        // The first tick completes immediately
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            if !SENDERS.read().await.contains_key(&table) {
+                break;
+            }
+            if let Err(e) = cache_client.set_ex(&cache_key, "", SUBSCRIBED_EXP_SECS).await {
+                warn!("[SPI-Stats] Metric stream refresh subscription error: {e:?}");
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Recalculate the metrics, incrementally with the loaded records if specified and supported, otherwise all.
+async fn refresh(
+    query_req: &StatsQueryMetricsReq,
+    prev_resp: &StatsQueryMetricsResp,
+    fact_records: Option<&[(String, DateTime<Utc>)]>,
+    ctx: &TardisContext,
+) -> TardisResult<StatsQueryMetricsResp> {
+    let funs = crate::get_tardis_inst();
+    let inst = funs.init(ctx, false, stats_initializer::init_fun).await?;
+    if let Some(fact_records) = fact_records {
+        if let Some(resp) = stats_pg_metric_serv::query_metrics_incrementally(query_req, prev_resp, fact_records, &funs, ctx, inst).await? {
+            return Ok(resp);
+        }
+    }
+    stats_pg_metric_serv::query_metrics(query_req, &funs, ctx, inst).await
+}
+
+/// Listen on the notifications of the changes of fact records, one listener per database.
+///
+/// The listener exits on error and is started again by the next subscriber.
+async fn start_listener(conn_uri: &str) -> TardisResult<()> {
+    if LISTENERS.read().await.contains(conn_uri) {
+        return Ok(());
+    }
+    let mut listeners = LISTENERS.write().await;
+    if listeners.contains(conn_uri) {
+        return Ok(());
+    }
+    let mut listener = PgListener::connect(conn_uri)
+        .await
+        .map_err(|e| TardisError::internal_error(&format!("[SPI-Stats] Connect listener error: {e}"), "500-spi-stats-metric-stream-listen-error"))?;
+    listener
+        .listen(stats_constants::METRIC_STREAM_NOTIFY_CHANNEL)
+        .await
+        .map_err(|e| TardisError::internal_error(&format!("[SPI-Stats] Listen error: {e}"), "500-spi-stats-metric-stream-listen-error"))?;
+    listeners.insert(conn_uri.to_string());
+    let conn_uri = conn_uri.to_string();
+    tokio::spawn(async move {
+        info!("[SPI-Stats] Metric stream listener started");
+        loop {
+            let notification = match listener.recv().await {
+                Ok(notification) => notification,
+                Err(e) => {
+                    warn!("[SPI-Stats] Metric stream listener error: {e}");
+                    break;
+                }
+            };
+            let Ok(change) = serde_json::from_str::<FactRecordChange>(notification.payload()) else {
+                warn!("[SPI-Stats] Metric stream notification is illegal: {}", notification.payload());
+                continue;
+            };
+            if let Some(sender) = SENDERS.read().await.get(&change.table) {
+                // An error means there are no subscribers now, the sender is kept for the later ones
+                let _ = sender.send(change);
+            }
+        }
+        LISTENERS.write().await.remove(&conn_uri);
+    });
+    Ok(())
+}
@@ -1,3 +1,5 @@
+use std::collections::{HashMap, HashSet};
+
 use bios_basic::spi::{
     spi_funs::SpiBsInst,
     spi_initializer::common_pg::{self, package_table_name},
@@ -16,11 +18,14 @@ use tardis::{
 };
 
 use crate::{
-    dto::stats_record_dto::{StatsDimRecordAddReq, StatsFactRecordLoadReq, StatsFactRecordsLoadReq},
-    stats_enumeration::StatsFactColKind,
+    dto::{
+        stats_conf_dto::StatsConfDimInfoResp,
+        stats_record_dto::{StatsDimRecordAddReq, StatsFactRecordLoadReq, StatsFactRecordsLoadReq, StatsRecordImportErrorResp, StatsRecordImportResp},
+    },
+    stats_enumeration::{StatsDataTypeKind, StatsFactColKind},
 };
 
-use super::{stats_pg_conf_dim_serv, stats_pg_conf_fact_col_serv, stats_pg_conf_fact_serv};
+use super::{stats_pg_conf_dim_serv, stats_pg_conf_fact_col_serv, stats_pg_conf_fact_rollup_serv, stats_pg_conf_fact_serv, stats_pg_metric_stream_serv};
 
 pub(crate) async fn fact_record_load(
     fact_conf_key: &str,
@@ -39,6 +44,7 @@ pub(crate) async fn fact_record_load(
 
     let fact_col_conf_set = stats_pg_conf_fact_col_serv::find_by_fact_conf_key(fact_conf_key, &conn, ctx, inst).await?;
 
+    let fact_records = [(fact_record_key.to_string(), add_req.ct)];
     let mut fields = vec!["key".to_string(), "own_paths".to_string(), "ct".to_string()];
     let mut values = vec![Value::from(fact_record_key), Value::from(add_req.own_paths), Value::from(add_req.ct)];
     let req_data = add_req.data.as_object().ok_or(funs.err().bad_request(
@@ -151,6 +157,8 @@ VALUES
         values,
     )
     .await?;
+    stats_pg_conf_fact_rollup_serv::refresh_inst(fact_conf_key, Some(&[fact_record_key.to_string()]), &conn, ctx, inst).await?;
+    stats_pg_metric_stream_serv::notify(fact_conf_key, Some(&fact_records), &conn, funs, ctx).await?;
     conn.commit().await?;
     Ok(())
 }
@@ -174,6 +182,8 @@ pub(crate) async fn fact_records_load(
     let mut has_fields_init = false;
     let mut fields = vec!["key".to_string(), "own_paths".to_string(), "ct".to_string()];
     let mut value_sets = vec![];
+    let fact_record_keys = add_req_set.iter().map(|add_req| add_req.key.clone()).collect::<Vec<String>>();
+    let fact_records = add_req_set.iter().map(|add_req| (add_req.key.clone(), add_req.ct)).collect::<Vec<_>>();
 
     for add_req in add_req_set {
         let Some(req_data) =  add_req.data.as_object() else {
@@ -255,6 +265,8 @@ pub(crate) async fn fact_records_load(
         )
         .await?;
     }
+    stats_pg_conf_fact_rollup_serv::refresh_inst(fact_conf_key, Some(&fact_record_keys), &conn, ctx, inst).await?;
+    stats_pg_metric_stream_serv::notify(fact_conf_key, Some(&fact_records), &conn, funs, ctx).await?;
     conn.commit().await?;
     Ok(())
 }
@@ -279,6 +291,7 @@ VALUES
         vec![Value::from(fact_record_key)],
     )
     .await?;
+    stats_pg_metric_stream_serv::notify(fact_conf_key, None, &conn, funs, ctx).await?;
     conn.commit().await?;
     Ok(())
 }
@@ -305,6 +318,7 @@ pub(crate) async fn fact_records_delete(fact_conf_key: &str, fact_record_delete_
         )
         .await?;
     }
+    stats_pg_metric_stream_serv::notify(fact_conf_key, None, &conn, funs, ctx).await?;
     conn.commit().await?;
     Ok(())
 }
@@ -328,7 +342,7 @@ pub(crate) async fn fact_records_delete_by_dim_key(
         return Ok(());
     }
     let table_name = package_table_name(&format!("stats_inst_fact_{fact_conf_key}_del"), ctx);
-    for delete_key in fact_record_delete_keys {
+    for delete_key in &fact_record_delete_keys {
         conn.execute_one(
             &format!(
                 r#"INSERT INTO {table_name}
@@ -341,6 +355,7 @@ pub(crate) async fn fact_records_delete_by_dim_key(
         )
         .await?;
     }
+    stats_pg_metric_stream_serv::notify(fact_conf_key, None, &conn, funs, ctx).await?;
     conn.commit().await?;
     Ok(())
 }
@@ -405,10 +420,103 @@ pub(crate) async fn fact_records_clean(fact_conf_key: &str, before_ct: Option<Da
     } else {
         conn.execute_one(&format!("DELETE FROM {table_name}"), vec![]).await?;
     }
+    // The buckets of the cleaned records are unknown, so rebuild the whole rollups
+    stats_pg_conf_fact_rollup_serv::refresh_inst(fact_conf_key, None, &conn, ctx, inst).await?;
+    stats_pg_metric_stream_serv::notify(fact_conf_key, None, &conn, funs, ctx).await?;
     conn.commit().await?;
     Ok(())
 }
 
+/// Import the fact records, the illegal records are reported and the others are loaded as [`fact_records_load`].
+///
+/// The `own_paths` of the records is the current one if missing,
+/// the values of multi-valued dimensions can be json arrays in text.
+pub(crate) async fn fact_records_import(
+    fact_conf_key: &str,
+    records: Vec<(u64, serde_json::Map<String, serde_json::Value>)>,
+    dry_run: bool,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<StatsRecordImportResp> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, _) = common_pg::init_conn(bs_inst).await?;
+    if !stats_pg_conf_fact_serv::online(fact_conf_key, &conn, ctx).await? {
+        return Err(funs.err().conflict("fact_record", "import", "The fact config not online.", "409-spi-stats-fact-conf-not-online"));
+    }
+
+    // (column key, data type, multi values), the data type is `None` for the ext columns
+    let mut fact_col_data_types = vec![];
+    for fact_col_conf in stats_pg_conf_fact_col_serv::find_by_fact_conf_key(fact_conf_key, &conn, ctx, inst).await? {
+        let data_type = match fact_col_conf.kind {
+            StatsFactColKind::Dimension => {
+                let dim_conf_key = fact_col_conf.dim_rel_conf_dim_key.as_ref().ok_or_else(|| {
+                    funs.err().internal_error("fact_record", "import", "dim_rel_conf_dim_key unexpectedly being empty", "500-spi-stats-internal-error")
+                })?;
+                let dim_conf = stats_pg_conf_dim_serv::get(dim_conf_key, &conn, ctx, inst).await?.ok_or_else(|| {
+                    funs.err().internal_error(
+                        "fact_record",
+                        "import",
+                        &format!("key [{dim_conf_key}] missing corresponding config "),
+                        "500-spi-stats-internal-error",
+                    )
+                })?;
+                Some(dim_conf.data_type)
+            }
+            StatsFactColKind::Measure => Some(fact_col_conf.mes_data_type.clone().ok_or_else(|| {
+                funs.err().internal_error(
+                    "fact_record",
+                    "import",
+                    "Col_conf.mes_data_type shouldn't be empty while fact_col_conf.kind is Measure",
+                    "500-spi-stats-internal-error",
+                )
+            })?),
+            StatsFactColKind::Ext => None,
+        };
+        fact_col_data_types.push((fact_col_conf.key, data_type, fact_col_conf.dim_multi_values.unwrap_or(false)));
+    }
+
+    let mut resp = StatsRecordImportResp {
+        total: records.len() as u64,
+        ..Default::default()
+    };
+    let mut load_reqs = vec![];
+    for (line, record) in records {
+        let Some(key) = get_import_field(&record, "key") else {
+            resp.errors.push(package_import_error(line, None, "The key is missing."));
+            continue;
+        };
+        let own_paths = match get_import_field(&record, "own_paths") {
+            Some(own_paths) => match own_paths.as_str() {
+                Some(own_paths) => own_paths.to_string(),
+                None => {
+                    resp.errors.push(package_import_error(line, Some(key), "The own_paths is not a string."));
+                    continue;
+                }
+            },
+            None => ctx.own_paths.clone(),
+        };
+        let Some(ct) = get_import_field(&record, "ct").and_then(serde_json::Value::as_str).and_then(|ct| DateTime::parse_from_rfc3339(ct).ok()) else {
+            resp.errors.push(package_import_error(line, Some(key), "The ct is missing or not a RFC 3339 time."));
+            continue;
+        };
+        match package_import_fact_data(&record, &fact_col_data_types) {
+            Ok(data) => load_reqs.push(StatsFactRecordsLoadReq {
+                key: json_to_text(key),
+                own_paths,
+                ct: ct.with_timezone(&Utc),
+                data,
+            }),
+            Err(message) => resp.errors.push(package_import_error(line, Some(key), &message)),
+        }
+    }
+    resp.added = load_reqs.len() as u64;
+    if !dry_run && !load_reqs.is_empty() {
+        fact_records_load(fact_conf_key, load_reqs, funs, ctx, inst).await?;
+    }
+    Ok(resp)
+}
+
 pub(crate) async fn dim_record_add(dim_conf_key: String, add_req: StatsDimRecordAddReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
     let bs_inst = inst.inst::<TardisRelDBClient>();
     let (mut conn, _) = common_pg::init_conn(bs_inst).await?;
@@ -416,7 +524,9 @@ pub(crate) async fn dim_record_add(dim_conf_key: String, add_req: StatsDimRecord
     if !stats_pg_conf_dim_serv::online(&dim_conf_key, &conn, ctx).await? {
         return Err(funs.err().conflict("dim_record", "add", "The dimension config not online.", "409-spi-stats-dim-conf-not-online"));
     }
-    let dim_conf = stats_pg_conf_dim_serv::get(&dim_conf_key, &conn, ctx, inst).await?.expect("Fail to get dim_conf");
+    let dim_conf = stats_pg_conf_dim_serv::get(&dim_conf_key, &conn, ctx, inst)
+        .await?
+        .ok_or_else(|| funs.err().not_found("dim_record", "add", "The dimension config does not exist.", "404-spi-stats-dim-conf-not-exist"))?;
     if !dim_conf.stable_ds {
         return Err(funs.err().bad_request(
             "dim_record",
@@ -444,60 +554,27 @@ pub(crate) async fn dim_record_add(dim_conf_key: String, add_req: StatsDimRecord
             "409-spi-stats-dim-inst-record-exist",
         ));
     }
-    let mut sql_fields = vec![];
-    let mut params = vec![dim_record_key_value.clone(), Value::from(add_req.show_name.clone())];
-
-    if let Some(parent_key) = add_req.parent_key {
-        let parent_record = dim_record_get(&dim_conf_key, parent_key.clone(), &conn, funs, ctx, inst).await?.ok_or_else(|| {
+    let parent_record = if let Some(parent_key) = add_req.parent_key {
+        Some(dim_record_get(&dim_conf_key, parent_key.clone(), &conn, funs, ctx, inst).await?.ok_or_else(|| {
             funs.err().not_found(
                 "dim_record",
                 "add",
                 &format!("The parent dimension instance record [{parent_key}] not exists."),
                 "404-spi-stats-dim-inst-record-not-exist",
             )
-        })?;
-        let parent_hierarchy = parent_record.get("hierarchy").and_then(|x| x.as_u64()).expect("parent_hierarchy missing field hierarchy");
-        if (parent_hierarchy + 1) as usize >= dim_conf.hierarchy.len() {
-            return Err(funs.err().conflict(
-                "dim_record",
-                "add",
-                "The dimension instance record hierarchy is too deep.",
-                "409-spi-stats-dim-inst-record-hierarchy-too-deep",
-            ));
-        }
-        params.push(Value::from(parent_hierarchy + 1));
-        sql_fields.push("hierarchy".to_string());
-        params.push(dim_record_key_value);
-        sql_fields.push(format!("key{}", parent_hierarchy + 1));
-        for i in 0..parent_hierarchy + 1 {
-            if let Some(record) = parent_record.get(&format!("key{i}")).and_then(serde_json::Value::as_str) {
-                params.push(record.into());
-                sql_fields.push(format!("key{i}"));
-            }
-        }
-    } else if dim_conf.hierarchy.len() > 1 {
-        params.push(Value::from(0));
-        sql_fields.push("hierarchy".to_string());
-        params.push(dim_record_key_value);
-        sql_fields.push("key0".to_string());
-    }
-    conn.execute_one(
-        &format!(
-            r#"INSERT INTO {table_name}
-(key, show_name {})
-VALUES
-($1, $2 {})
-"#,
-            if sql_fields.is_empty() { "".to_string() } else { format!(",{}", sql_fields.join(",")) },
-            if sql_fields.is_empty() {
-                "".to_string()
-            } else {
-                format!(",{}", sql_fields.iter().enumerate().map(|(i, _)| format!("${}", i + 3)).collect::<Vec<String>>().join(","))
-            }
-        ),
-        params,
-    )
-    .await?;
+        })?)
+    } else {
+        None
+    };
+    let hierarchy_fields = package_dim_record_hierarchy(&dim_conf, dim_record_key_value.clone(), parent_record.as_ref()).ok_or_else(|| {
+        funs.err().conflict(
+            "dim_record",
+            "add",
+            "The dimension instance record hierarchy is too deep.",
+            "409-spi-stats-dim-inst-record-hierarchy-too-deep",
+        )
+    })?;
+    dim_do_record_add(&table_name, dim_record_key_value, &add_req.show_name, hierarchy_fields, &conn).await?;
     conn.commit().await?;
     Ok(())
 }
@@ -623,7 +700,9 @@ pub(crate) async fn dim_record_delete(dim_conf_key: String, dim_record_key: serd
     if !stats_pg_conf_dim_serv::online(&dim_conf_key, &conn, ctx).await? {
         return Err(funs.err().conflict("dim_record", "delete", "The dimension config not online.", "409-spi-stats-dim-conf-not-online"));
     }
-    let dim_conf = stats_pg_conf_dim_serv::get(&dim_conf_key, &conn, ctx, inst).await?.expect("Fail to get dim_conf");
+    let dim_conf = stats_pg_conf_dim_serv::get(&dim_conf_key, &conn, ctx, inst)
+        .await?
+        .ok_or_else(|| funs.err().not_found("dim_record", "delete", "The dimension config does not exist.", "404-spi-stats-dim-conf-not-exist"))?;
 
     let table_name = package_table_name(&format!("stats_inst_dim_{}", dim_conf.key), ctx);
     let values = vec![dim_conf.data_type.json_to_sea_orm_value(&dim_record_key, false)?];
@@ -642,6 +721,158 @@ WHERE key = $1
     Ok(())
 }
 
+/// Import the dimension records, the existing records are updated and the illegal records are reported.
+///
+/// The parents can be in the same file in any order, but the parent of an existing record can't be changed.
+/// The records are imported in a transaction, which is rolled back if it is a dry run.
+pub(crate) async fn dim_records_import(
+    dim_conf_key: &str,
+    records: Vec<(u64, serde_json::Map<String, serde_json::Value>)>,
+    dry_run: bool,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<StatsRecordImportResp> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (mut conn, _) = common_pg::init_conn(bs_inst).await?;
+    conn.begin().await?;
+    if !stats_pg_conf_dim_serv::online(dim_conf_key, &conn, ctx).await? {
+        return Err(funs.err().conflict("dim_record", "import", "The dimension config not online.", "409-spi-stats-dim-conf-not-online"));
+    }
+    let dim_conf = stats_pg_conf_dim_serv::get(dim_conf_key, &conn, ctx, inst)
+        .await?
+        .ok_or_else(|| funs.err().not_found("dim_record", "import", "The dimension config does not exist.", "404-spi-stats-dim-conf-not-exist"))?;
+    if !dim_conf.stable_ds {
+        return Err(funs.err().bad_request(
+            "dim_record",
+            "import",
+            &format!("The dimension config [{dim_conf_key}] stable_ds is false, so adding dimension records is not supported."),
+            "400-spi-stats-dim-conf-stable-ds-false",
+        ));
+    }
+
+    let table_name = package_table_name(&format!("stats_inst_dim_{}", dim_conf.key), ctx);
+    let mut resp = StatsRecordImportResp {
+        total: records.len() as u64,
+        ..Default::default()
+    };
+    // (line, key, show name, parent key)
+    let mut pending_records = vec![];
+    for (line, record) in records {
+        let Some(key) = get_import_field(&record, "key").and_then(|key| dim_conf.data_type.text_to_json_value(key)) else {
+            resp.errors.push(package_import_error(line, None, "The key is missing or illegal."));
+            continue;
+        };
+        if dim_conf.data_type.json_to_sea_orm_value(&key, false).is_err() {
+            resp.errors.push(package_import_error(line, Some(&key), "The key is illegal."));
+            continue;
+        }
+        let Some(show_name) = get_import_field(&record, "show_name").and_then(serde_json::Value::as_str) else {
+            resp.errors.push(package_import_error(line, Some(&key), "The show_name is missing or not a string."));
+            continue;
+        };
+        let parent_key = match get_import_field(&record, "parent_key") {
+            Some(parent_key) => match dim_conf.data_type.text_to_json_value(parent_key) {
+                Some(parent_key) => Some(parent_key),
+                None => {
+                    resp.errors.push(package_import_error(line, Some(&key), "The parent_key is illegal."));
+                    continue;
+                }
+            },
+            None => None,
+        };
+        if dim_conf.hierarchy.is_empty() && parent_key.is_some() {
+            resp.errors.push(package_import_error(line, Some(&key), &format!("The dimension config [{dim_conf_key}] not allow hierarchy.")));
+            continue;
+        }
+        pending_records.push((line, key, show_name.to_string(), parent_key));
+    }
+
+    // The existing records and the parents are fetched at once, the records to be imported are resolved in memory
+    let lookup_keys = pending_records.iter().flat_map(|(_, key, _, parent_key)| [Some(key), parent_key.as_ref()]).flatten().map(json_to_text).unique().collect_vec();
+    let existing_levels = dim_get_record_levels(&table_name, &dim_conf, lookup_keys, &conn).await?;
+    let mut added_levels: HashMap<String, Vec<String>> = HashMap::new();
+    // (key, show name, levels from the top one to the record itself)
+    let mut added_records = vec![];
+    let mut updated_records: HashMap<String, String> = HashMap::new();
+    // The records whose parents are pending are deferred to the next round
+    while !pending_records.is_empty() {
+        let pending_keys = pending_records.iter().map(|(_, key, _, _)| json_to_text(key)).collect::<HashSet<_>>();
+        let pending_size = pending_records.len();
+        let mut deferred_records = vec![];
+        for (line, key, show_name, parent_key) in pending_records {
+            let key_text = json_to_text(&key);
+            let parent_key_text = parent_key.as_ref().map(json_to_text);
+            let mut levels = match &parent_key_text {
+                Some(parent_key_text) => match added_levels.get(parent_key_text).or_else(|| existing_levels.get(parent_key_text)) {
+                    Some(parent_levels) => parent_levels.clone(),
+                    None if pending_keys.contains(parent_key_text) => {
+                        deferred_records.push((line, key, show_name, parent_key));
+                        continue;
+                    }
+                    None => {
+                        resp.errors.push(package_import_error(line, Some(&key), &format!("The parent dimension instance record [{parent_key_text}] not exists.")));
+                        continue;
+                    }
+                },
+                None => vec![],
+            };
+            levels.push(key_text.clone());
+            if levels.len() > dim_conf.hierarchy.len().max(1) {
+                resp.errors.push(package_import_error(line, Some(&key), "The dimension instance record hierarchy is too deep."));
+                continue;
+            }
+            if let Some(record_levels) = existing_levels.get(&key_text).or_else(|| added_levels.get(&key_text)) {
+                let existing_parent_key = record_levels.len().checked_sub(2).map(|idx| &record_levels[idx]);
+                if existing_parent_key != parent_key_text.as_ref() {
+                    resp.errors.push(package_import_error(line, Some(&key), "The parent of the existing dimension instance record can't be changed."));
+                    continue;
+                }
+                updated_records.insert(key_text, show_name);
+                resp.updated += 1;
+            } else {
+                added_levels.insert(key_text.clone(), levels.clone());
+                added_records.push((key_text, show_name, levels));
+                resp.added += 1;
+            }
+        }
+        if deferred_records.len() == pending_size {
+            for (line, key, _, parent_key) in deferred_records {
+                resp.errors.push(package_import_error(
+                    line,
+                    Some(&key),
+                    &format!("The parent dimension instance record [{}] is not imported or circularly referenced.", parent_key.unwrap_or_default()),
+                ));
+            }
+            break;
+        }
+        pending_records = deferred_records;
+    }
+    dim_do_records_add(&table_name, &dim_conf, added_records, &conn).await?;
+    if !updated_records.is_empty() {
+        // The deleted records are restored
+        let (keys, show_names): (Vec<String>, Vec<String>) = updated_records.into_iter().unzip();
+        conn.execute_one(
+            &format!(
+                r#"UPDATE {table_name} AS _dim
+SET show_name = _imp.show_name, et = NULL
+FROM unnest($1::text[], $2::text[]) AS _imp(key, show_name)
+WHERE _dim.key = _imp.key::{}"#,
+                dim_conf.data_type.to_pg_data_type()
+            ),
+            vec![Value::from(keys), Value::from(show_names)],
+        )
+        .await?;
+    }
+    resp.errors.sort_by_key(|error| error.line);
+    if dry_run {
+        conn.rollback().await?;
+    } else {
+        conn.commit().await?;
+    }
+    Ok(resp)
+}
+
 async fn fact_get_latest_record_raw(
     fact_conf_key: &str,
     dim_record_key: &str,
@@ -652,3 +883,182 @@ async fn fact_get_latest_record_raw(
     let result = conn.query_one(&format!("SELECT * FROM {table_name} WHERE key = $1 ORDER BY ct DESC"), vec![Value::from(dim_record_key)]).await?;
     Ok(result)
 }
+
+/// The hierarchy fields of the dimension record, `None` if the hierarchy is too deep.
+fn package_dim_record_hierarchy(dim_conf: &StatsConfDimInfoResp, dim_record_key_value: Value, parent_record: Option<&serde_json::Value>) -> Option<Vec<(String, Value)>> {
+    let mut fields = vec![];
+    if let Some(parent_record) = parent_record {
+        let parent_hierarchy = parent_record.get("hierarchy").and_then(|x| x.as_u64()).expect("parent_hierarchy missing field hierarchy");
+        if (parent_hierarchy + 1) as usize >= dim_conf.hierarchy.len() {
+            return None;
+        }
+        fields.push(("hierarchy".to_string(), Value::from(parent_hierarchy + 1)));
+        fields.push((format!("key{}", parent_hierarchy + 1), dim_record_key_value));
+        for i in 0..parent_hierarchy + 1 {
+            if let Some(record) = parent_record.get(&format!("key{i}")).and_then(serde_json::Value::as_str) {
+                fields.push((format!("key{i}"), record.into()));
+            }
+        }
+    } else if dim_conf.hierarchy.len() > 1 {
+        fields.push(("hierarchy".to_string(), Value::from(0)));
+        fields.push(("key0".to_string(), dim_record_key_value));
+    }
+    Some(fields)
+}
+
+async fn dim_do_record_add(
+    table_name: &str,
+    dim_record_key_value: Value,
+    show_name: &str,
+    hierarchy_fields: Vec<(String, Value)>,
+    conn: &TardisRelDBlConnection,
+) -> TardisResult<()> {
+    let mut sql_fields = vec!["key".to_string(), "show_name".to_string()];
+    let mut params = vec![dim_record_key_value, Value::from(show_name)];
+    for (field, value) in hierarchy_fields {
+        sql_fields.push(field);
+        params.push(value);
+    }
+    conn.execute_one(
+        &format!(
+            r#"INSERT INTO {table_name}
+({})
+VALUES
+({})
+"#,
+            sql_fields.join(","),
+            sql_fields.iter().enumerate().map(|(i, _)| format!("${}", i + 1)).collect::<Vec<String>>().join(",")
+        ),
+        params,
+    )
+    .await?;
+    Ok(())
+}
+
+/// The hierarchy levels of the existing dimension records by their keys in text, the levels are from the top one to the record itself.
+async fn dim_get_record_levels(
+    table_name: &str,
+    dim_conf: &StatsConfDimInfoResp,
+    dim_record_keys: Vec<String>,
+    conn: &TardisRelDBlConnection,
+) -> TardisResult<HashMap<String, Vec<String>>> {
+    let level_fields = if dim_conf.hierarchy.is_empty() {
+        "".to_string()
+    } else {
+        format!(", _dim.hierarchy, {}", (0..dim_conf.hierarchy.len()).map(|idx| format!("_dim.key{idx}")).join(", "))
+    };
+    let result = conn
+        .query_all(
+            &format!(
+                r#"SELECT DISTINCT ON (_imp.key) _imp.key AS imp_key{level_fields}
+FROM unnest($1::text[]) AS _imp(key)
+INNER JOIN {table_name} AS _dim ON _dim.key = _imp.key::{}
+ORDER BY _imp.key, _dim.ct DESC"#,
+                dim_conf.data_type.to_pg_data_type()
+            ),
+            vec![Value::from(dim_record_keys)],
+        )
+        .await?;
+    let mut levels = HashMap::new();
+    for item in result {
+        let key: String = item.try_get("", "imp_key")?;
+        let record_levels = if dim_conf.hierarchy.is_empty() {
+            vec![key.clone()]
+        } else {
+            let hierarchy: i16 = item.try_get("", "hierarchy")?;
+            (0..=hierarchy).map(|idx| item.try_get::<String>("", &format!("key{idx}"))).collect::<Result<Vec<_>, _>>()?
+        };
+        levels.insert(key, record_levels);
+    }
+    Ok(levels)
+}
+
+/// Add the dimension records in one statement, the records are `(key, show name, levels from the top one to the record itself)`.
+async fn dim_do_records_add(table_name: &str, dim_conf: &StatsConfDimInfoResp, records: Vec<(String, String, Vec<String>)>, conn: &TardisRelDBlConnection) -> TardisResult<()> {
+    if records.is_empty() {
+        return Ok(());
+    }
+    let mut sql_fields = vec!["key".to_string(), "show_name".to_string()];
+    let mut sql_types = vec!["text[]", "text[]"];
+    let mut params = vec![
+        Value::from(records.iter().map(|(key, _, _)| key.clone()).collect_vec()),
+        Value::from(records.iter().map(|(_, show_name, _)| show_name.clone()).collect_vec()),
+    ];
+    if !dim_conf.hierarchy.is_empty() {
+        sql_fields.push("hierarchy".to_string());
+        sql_types.push("integer[]");
+        params.push(Value::from(records.iter().map(|(_, _, levels)| levels.len() as i32 - 1).collect_vec()));
+        for idx in 0..dim_conf.hierarchy.len() {
+            sql_fields.push(format!("key{idx}"));
+            sql_types.push("text[]");
+            params.push(Value::from(records.iter().map(|(_, _, levels)| levels.get(idx).cloned().unwrap_or_default()).collect_vec()));
+        }
+    }
+    conn.execute_one(
+        &format!(
+            r#"INSERT INTO {table_name}
+({})
+SELECT _imp.key::{}, {}
+FROM unnest({}) AS _imp({})
+"#,
+            sql_fields.join(","),
+            dim_conf.data_type.to_pg_data_type(),
+            sql_fields.iter().skip(1).map(|field| format!("_imp.{field}")).join(", "),
+            sql_types.iter().enumerate().map(|(idx, sql_type)| format!("${}::{sql_type}", idx + 1)).join(", "),
+            sql_fields.join(", ")
+        ),
+        params,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Convert the values of the imported fact record to the ones of the column data types, `Err` is the reason if illegal.
+fn package_import_fact_data(
+    record: &serde_json::Map<String, serde_json::Value>,
+    fact_col_data_types: &[(String, Option<StatsDataTypeKind>, bool)],
+) -> Result<serde_json::Value, String> {
+    let mut data = serde_json::Map::new();
+    for (fact_col_key, data_type, multi_values) in fact_col_data_types {
+        let value = record.get(fact_col_key).filter(|value| !value.is_null()).ok_or_else(|| format!("The column [{fact_col_key}] is missing."))?;
+        let err_illegal = || format!("The value of the column [{fact_col_key}] is illegal.");
+        let value = match data_type {
+            Some(data_type) if *multi_values => {
+                let values = match value {
+                    serde_json::Value::String(text) => serde_json::from_str::<serde_json::Value>(text).map_err(|_| err_illegal())?,
+                    _ => value.clone(),
+                };
+                let values = values.as_array().ok_or_else(err_illegal)?.iter().map(|value| data_type.text_to_json_value(value)).collect::<Option<Vec<_>>>();
+                let values = serde_json::Value::from(values.ok_or_else(err_illegal)?);
+                data_type.json_to_sea_orm_value_array(&values, false).map_err(|_| err_illegal())?;
+                values
+            }
+            Some(data_type) => {
+                let value = data_type.text_to_json_value(value).ok_or_else(err_illegal)?;
+                data_type.json_to_sea_orm_value(&value, false).map_err(|_| err_illegal())?;
+                value
+            }
+            None if value.is_string() => value.clone(),
+            None => return Err(err_illegal()),
+        };
+        data.insert(fact_col_key.to_string(), value);
+    }
+    Ok(serde_json::Value::Object(data))
+}
+
+/// The field of the imported record, `None` if it is missing, null or empty.
+fn get_import_field<'a>(record: &'a serde_json::Map<String, serde_json::Value>, field: &str) -> Option<&'a serde_json::Value> {
+    record.get(field).filter(|value| !value.is_null() && value.as_str() != Some(""))
+}
+
+fn json_to_text(value: &serde_json::Value) -> String {
+    value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string())
+}
+
+fn package_import_error(line: u64, key: Option<&serde_json::Value>, message: &str) -> StatsRecordImportErrorResp {
+    StatsRecordImportErrorResp {
+        line,
+        key: key.map(json_to_text),
+        message: message.to_string(),
+    }
+}
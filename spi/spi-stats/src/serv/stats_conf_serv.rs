@@ -6,8 +6,9 @@ use tardis::web::web_resp::TardisPage;
 use tardis::TardisFunsInst;
 
 use crate::dto::stats_conf_dto::{
-    StatsConfDimAddReq, StatsConfDimInfoResp, StatsConfDimModifyReq, StatsConfFactAddReq, StatsConfFactColAddReq, StatsConfFactColInfoResp, StatsConfFactColModifyReq,
-    StatsConfFactInfoResp, StatsConfFactModifyReq,
+    StatsConfDashboardAddReq, StatsConfDashboardInfoResp, StatsConfDashboardModifyReq, StatsConfDimAddReq, StatsConfDimInfoResp, StatsConfDimModifyReq, StatsConfFactAddReq,
+    StatsConfFactColAddReq, StatsConfFactColInfoResp, StatsConfFactColModifyReq, StatsConfFactInfoResp, StatsConfFactModifyReq, StatsConfFactRollupAddReq,
+    StatsConfFactRollupInfoResp, StatsConfSavedQueryAddReq, StatsConfSavedQueryInfoResp, StatsConfSavedQueryModifyReq,
 };
 use crate::stats_initializer;
 
@@ -181,3 +182,144 @@ pub async fn fact_col_add(fact_conf_key: &str, add_req: &StatsConfFactColAddReq,
         kind_code => Err(funs.bs_not_implemented(kind_code)),
     }
 }
+
+pub async fn fact_rollup_add(fact_conf_key: &str, add_req: &StatsConfFactRollupAddReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    let inst = funs.init(ctx, true, stats_initializer::init_fun).await?;
+    match inst.kind_code() {
+        #[cfg(feature = "spi-pg")]
+        spi_constants::SPI_PG_KIND_CODE => pg::stats_pg_conf_fact_rollup_serv::add(fact_conf_key, add_req, funs, ctx, inst).await,
+        kind_code => Err(funs.bs_not_implemented(kind_code)),
+    }
+}
+
+pub async fn fact_rollup_delete(fact_conf_key: &str, fact_rollup_conf_key: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    let inst = funs.init(ctx, true, stats_initializer::init_fun).await?;
+    match inst.kind_code() {
+        #[cfg(feature = "spi-pg")]
+        spi_constants::SPI_PG_KIND_CODE => pg::stats_pg_conf_fact_rollup_serv::delete(fact_conf_key, fact_rollup_conf_key, funs, ctx, inst).await,
+        kind_code => Err(funs.bs_not_implemented(kind_code)),
+    }
+}
+
+pub async fn fact_rollup_refresh(fact_conf_key: &str, fact_rollup_conf_key: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    let inst = funs.init(ctx, true, stats_initializer::init_fun).await?;
+    match inst.kind_code() {
+        #[cfg(feature = "spi-pg")]
+        spi_constants::SPI_PG_KIND_CODE => pg::stats_pg_conf_fact_rollup_serv::refresh(fact_conf_key, fact_rollup_conf_key, funs, ctx, inst).await,
+        kind_code => Err(funs.bs_not_implemented(kind_code)),
+    }
+}
+
+pub async fn fact_rollup_paginate(
+    fact_conf_key: String,
+    fact_rollup_conf_key: Option<String>,
+    page_number: u32,
+    page_size: u32,
+    desc_by_create: Option<bool>,
+    desc_by_update: Option<bool>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+) -> TardisResult<TardisPage<StatsConfFactRollupInfoResp>> {
+    let inst = funs.init(ctx, true, stats_initializer::init_fun).await?;
+    match inst.kind_code() {
+        #[cfg(feature = "spi-pg")]
+        spi_constants::SPI_PG_KIND_CODE => {
+            pg::stats_pg_conf_fact_rollup_serv::paginate(fact_conf_key, fact_rollup_conf_key, page_number, page_size, desc_by_create, desc_by_update, funs, ctx, inst).await
+        }
+        kind_code => Err(funs.bs_not_implemented(kind_code)),
+    }
+}
+
+pub async fn saved_query_add(add_req: &StatsConfSavedQueryAddReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    let inst = funs.init(ctx, true, stats_initializer::init_fun).await?;
+    match inst.kind_code() {
+        #[cfg(feature = "spi-pg")]
+        spi_constants::SPI_PG_KIND_CODE => pg::stats_pg_conf_saved_query_serv::add(add_req, funs, ctx, inst).await,
+        kind_code => Err(funs.bs_not_implemented(kind_code)),
+    }
+}
+
+pub async fn saved_query_modify(saved_query_key: &str, modify_req: &StatsConfSavedQueryModifyReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    let inst = funs.init(ctx, true, stats_initializer::init_fun).await?;
+    match inst.kind_code() {
+        #[cfg(feature = "spi-pg")]
+        spi_constants::SPI_PG_KIND_CODE => pg::stats_pg_conf_saved_query_serv::modify(saved_query_key, modify_req, funs, ctx, inst).await,
+        kind_code => Err(funs.bs_not_implemented(kind_code)),
+    }
+}
+
+pub async fn saved_query_delete(saved_query_key: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    let inst = funs.init(ctx, true, stats_initializer::init_fun).await?;
+    match inst.kind_code() {
+        #[cfg(feature = "spi-pg")]
+        spi_constants::SPI_PG_KIND_CODE => pg::stats_pg_conf_saved_query_serv::delete(saved_query_key, funs, ctx, inst).await,
+        kind_code => Err(funs.bs_not_implemented(kind_code)),
+    }
+}
+
+pub async fn saved_query_paginate(
+    saved_query_key: Option<String>,
+    show_name: Option<String>,
+    page_number: u32,
+    page_size: u32,
+    desc_by_create: Option<bool>,
+    desc_by_update: Option<bool>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+) -> TardisResult<TardisPage<StatsConfSavedQueryInfoResp>> {
+    let inst = funs.init(ctx, true, stats_initializer::init_fun).await?;
+    match inst.kind_code() {
+        #[cfg(feature = "spi-pg")]
+        spi_constants::SPI_PG_KIND_CODE => {
+            pg::stats_pg_conf_saved_query_serv::paginate(saved_query_key, show_name, page_number, page_size, desc_by_create, desc_by_update, funs, ctx, inst).await
+        }
+        kind_code => Err(funs.bs_not_implemented(kind_code)),
+    }
+}
+
+pub async fn dashboard_add(add_req: &StatsConfDashboardAddReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    let inst = funs.init(ctx, true, stats_initializer::init_fun).await?;
+    match inst.kind_code() {
+        #[cfg(feature = "spi-pg")]
+        spi_constants::SPI_PG_KIND_CODE => pg::stats_pg_conf_dashboard_serv::add(add_req, funs, ctx, inst).await,
+        kind_code => Err(funs.bs_not_implemented(kind_code)),
+    }
+}
+
+pub async fn dashboard_modify(dashboard_key: &str, modify_req: &StatsConfDashboardModifyReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    let inst = funs.init(ctx, true, stats_initializer::init_fun).await?;
+    match inst.kind_code() {
+        #[cfg(feature = "spi-pg")]
+        spi_constants::SPI_PG_KIND_CODE => pg::stats_pg_conf_dashboard_serv::modify(dashboard_key, modify_req, funs, ctx, inst).await,
+        kind_code => Err(funs.bs_not_implemented(kind_code)),
+    }
+}
+
+pub async fn dashboard_delete(dashboard_key: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    let inst = funs.init(ctx, true, stats_initializer::init_fun).await?;
+    match inst.kind_code() {
+        #[cfg(feature = "spi-pg")]
+        spi_constants::SPI_PG_KIND_CODE => pg::stats_pg_conf_dashboard_serv::delete(dashboard_key, funs, ctx, inst).await,
+        kind_code => Err(funs.bs_not_implemented(kind_code)),
+    }
+}
+
+pub async fn dashboard_paginate(
+    dashboard_key: Option<String>,
+    show_name: Option<String>,
+    page_number: u32,
+    page_size: u32,
+    desc_by_create: Option<bool>,
+    desc_by_update: Option<bool>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+) -> TardisResult<TardisPage<StatsConfDashboardInfoResp>> {
+    let inst = funs.init(ctx, true, stats_initializer::init_fun).await?;
+    match inst.kind_code() {
+        #[cfg(feature = "spi-pg")]
+        spi_constants::SPI_PG_KIND_CODE => {
+            pg::stats_pg_conf_dashboard_serv::paginate(dashboard_key, show_name, page_number, page_size, desc_by_create, desc_by_update, funs, ctx, inst).await
+        }
+        kind_code => Err(funs.bs_not_implemented(kind_code)),
+    }
+}
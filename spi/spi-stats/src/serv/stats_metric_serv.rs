@@ -2,9 +2,10 @@ use bios_basic::spi::spi_constants;
 use bios_basic::spi::spi_funs::SpiBsInstExtractor;
 use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
+use tardis::web::poem::web::websocket::{BoxWebSocketUpgraded, WebSocket};
 use tardis::TardisFunsInst;
 
-use crate::dto::stats_query_dto::{StatsQueryMetricsReq, StatsQueryMetricsResp};
+use crate::dto::stats_query_dto::{StatsQueryMetricsReq, StatsQueryMetricsResp, StatsQuerySavedMetricsReq};
 use crate::stats_initializer;
 
 use super::pg;
@@ -17,3 +18,27 @@ pub async fn query_metrics(query_req: &StatsQueryMetricsReq, funs: &TardisFunsIn
         kind_code => Err(funs.bs_not_implemented(kind_code)),
     }
 }
+
+pub async fn query_saved_metrics(saved_query_key: &str, query_req: &StatsQuerySavedMetricsReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<StatsQueryMetricsResp> {
+    let inst = funs.init(ctx, true, stats_initializer::init_fun).await?;
+    match inst.kind_code() {
+        #[cfg(feature = "spi-pg")]
+        spi_constants::SPI_PG_KIND_CODE => pg::stats_pg_conf_saved_query_serv::execute(saved_query_key, query_req, funs, ctx, inst).await,
+        kind_code => Err(funs.bs_not_implemented(kind_code)),
+    }
+}
+
+pub async fn stream(
+    query_req: StatsQueryMetricsReq,
+    debounce_ms: Option<u64>,
+    websocket: WebSocket,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+) -> TardisResult<BoxWebSocketUpgraded> {
+    let inst = funs.init(ctx, true, stats_initializer::init_fun).await?;
+    match inst.kind_code() {
+        #[cfg(feature = "spi-pg")]
+        spi_constants::SPI_PG_KIND_CODE => pg::stats_pg_metric_stream_serv::stream(query_req, debounce_ms, websocket, funs, ctx, inst).await,
+        kind_code => Err(funs.bs_not_implemented(kind_code)),
+    }
+}
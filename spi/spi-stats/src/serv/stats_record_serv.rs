@@ -1,12 +1,18 @@
-use crate::dto::stats_record_dto::{StatsDimRecordAddReq, StatsFactRecordLoadReq, StatsFactRecordsLoadReq};
+use crate::dto::stats_record_dto::{
+    StatsDimRecordAddReq, StatsFactRecordLoadReq, StatsFactRecordsLoadReq, StatsRecordImportErrorResp, StatsRecordImportFormatKind, StatsRecordImportReq, StatsRecordImportResp,
+};
+use crate::stats_config::StatsConfig;
 use crate::stats_initializer;
 use bios_basic::spi::spi_constants;
 use bios_basic::spi::spi_funs::SpiBsInstExtractor;
 use bios_basic::spi_dispatch_service;
+use bios_sdk_invoke::clients::spi_object_client::SpiObjectClient;
+use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
 use tardis::chrono::{DateTime, Utc};
-use tardis::serde_json::{self, Value};
+use tardis::serde_json::{self, Map, Value};
 use tardis::web::web_resp::TardisPage;
+use tardis::TardisFunsInst;
 
 use super::pg;
 spi_dispatch_service! {
@@ -23,6 +29,7 @@ spi_dispatch_service! {
         fact_records_delete(fact_conf_key: &str, fact_record_delete_keys: &[String]) -> TardisResult<()>;
         fact_records_delete_by_dim_key(fact_conf_key: &str, dim_conf_key: &str,dim_record_key: Option<serde_json::Value>) -> TardisResult<()>;
         fact_records_clean(fact_conf_key: &str, before_ct: Option<DateTime<Utc>>) -> TardisResult<()>;
+        fact_records_import(fact_conf_key: &str, records: Vec<(u64, Map<String, Value>)>, dry_run: bool) -> TardisResult<StatsRecordImportResp>;
         dim_record_add(dim_conf_key: String, add_req: StatsDimRecordAddReq) -> TardisResult<()>;
         dim_record_paginate(
             dim_conf_key: String,
@@ -34,5 +41,131 @@ spi_dispatch_service! {
             desc_by_update: Option<bool>
         ) -> TardisResult<TardisPage<Value>>;
         dim_record_delete(dim_conf_key: String, dim_record_key: Value) -> TardisResult<()>;
+        dim_records_import(dim_conf_key: &str, records: Vec<(u64, Map<String, Value>)>, dry_run: bool) -> TardisResult<StatsRecordImportResp>;
     }
 }
+
+/// Import the fact records from a CSV or JSON lines file, the records are validated and the illegal ones are reported.
+pub async fn import_fact_records(fact_conf_key: &str, import_req: StatsRecordImportReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<StatsRecordImportResp> {
+    let dry_run = import_req.dry_run.unwrap_or(false);
+    let (records, errors) = parse_import_records(import_req, "fact_record", funs, ctx).await?;
+    let resp = fact_records_import(fact_conf_key, records, dry_run, funs, ctx).await?;
+    Ok(merge_import_errors(resp, errors))
+}
+
+/// Import the dimension records from a CSV or JSON lines file, the existing records are updated and the illegal ones are reported.
+///
+/// The columns are `key`, `show_name` and `parent_key`, the hierarchy of the records is built from their parents.
+pub async fn import_dim_records(dim_conf_key: &str, import_req: StatsRecordImportReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<StatsRecordImportResp> {
+    let dry_run = import_req.dry_run.unwrap_or(false);
+    let (records, errors) = parse_import_records(import_req, "dim_record", funs, ctx).await?;
+    let resp = dim_records_import(dim_conf_key, records, dry_run, funs, ctx).await?;
+    Ok(merge_import_errors(resp, errors))
+}
+
+/// Parse the records of the file with their line numbers, and the errors of the lines that can't be parsed.
+async fn parse_import_records(
+    import_req: StatsRecordImportReq,
+    obj: &str,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+) -> TardisResult<(Vec<(u64, Map<String, Value>)>, Vec<StatsRecordImportErrorResp>)> {
+    let max_size = funs.conf::<StatsConfig>().import_max_size;
+    let err_too_large = || funs.err().bad_request(obj, "import", &format!("The file is larger than {max_size} bytes."), "400-spi-stats-import-file-too-large");
+    let content = match (import_req.content, import_req.object_path) {
+        (Some(content), _) => {
+            if content.len() > max_size {
+                return Err(err_too_large());
+            }
+            content
+        }
+        (None, Some(object_path)) => {
+            let url = SpiObjectClient::presign_obj_url("view", &object_path, funs.conf::<StatsConfig>().import_url_exp_secs, import_req.private.unwrap_or(true), funs, ctx)
+                .await?
+                .ok_or_else(|| funs.err().internal_error(obj, "import", "Failed to presign the download URL", "500-spi-stats-import-presign-failed"))?;
+            let mut resp = funs.web_client().raw().get(&url).send().await?;
+            if !resp.status().is_success() {
+                return Err(funs.err().bad_request(
+                    obj,
+                    "import",
+                    &format!("Failed to read the file [{object_path}], status: {}", resp.status()),
+                    "400-spi-stats-import-file-read-failed",
+                ));
+            }
+            if resp.content_length().unwrap_or(0) > max_size as u64 {
+                return Err(err_too_large());
+            }
+            // The content length may be absent, so the size is also checked while reading
+            let mut body = vec![];
+            while let Some(chunk) = resp.chunk().await? {
+                body.extend_from_slice(&chunk);
+                if body.len() > max_size {
+                    return Err(err_too_large());
+                }
+            }
+            String::from_utf8(body)
+                .map_err(|_| funs.err().bad_request(obj, "import", &format!("The file [{object_path}] is not UTF-8 encoded."), "400-spi-stats-import-file-read-failed"))?
+        }
+        (None, None) => return Err(funs.err().bad_request(obj, "import", "Either content or object_path is required", "400-spi-stats-invalid-request")),
+    };
+    let mut records = vec![];
+    let mut errors = vec![];
+    match import_req.format {
+        StatsRecordImportFormatKind::Csv => {
+            // The blank lines are skipped, and the quoted fields can contain commas, line breaks and quotes escaped as `""`
+            let mut reader = csv::ReaderBuilder::new().has_headers(false).flexible(true).from_reader(content.as_bytes());
+            let mut rows = reader.records();
+            let header = match rows.next() {
+                Some(Ok(header)) => header.iter().map(|column| column.trim_start_matches('\u{feff}').trim().to_string()).collect::<Vec<_>>(),
+                Some(Err(error)) => return Err(funs.err().bad_request(obj, "import", &format!("The header of the file is illegal: {error}"), "400-spi-stats-import-file-illegal")),
+                None => return Ok((records, errors)),
+            };
+            for row in rows {
+                let row = match row {
+                    Ok(row) => row,
+                    Err(error) => {
+                        errors.push(StatsRecordImportErrorResp {
+                            line: error.position().map_or(0, csv::Position::line),
+                            key: None,
+                            message: format!("The line can't be parsed: {error}"),
+                        });
+                        continue;
+                    }
+                };
+                let line = row.position().map_or(0, csv::Position::line);
+                if row.len() != header.len() {
+                    errors.push(StatsRecordImportErrorResp {
+                        line,
+                        key: None,
+                        message: format!("The number of the fields [{}] doesn't match the header [{}].", row.len(), header.len()),
+                    });
+                    continue;
+                }
+                records.push((line, header.iter().cloned().zip(row.iter().map(Value::from)).collect()));
+            }
+        }
+        StatsRecordImportFormatKind::Jsonl => {
+            for (idx, text) in content.lines().enumerate() {
+                if text.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str::<Value>(text) {
+                    Ok(Value::Object(record)) => records.push((idx as u64 + 1, record)),
+                    _ => errors.push(StatsRecordImportErrorResp {
+                        line: idx as u64 + 1,
+                        key: None,
+                        message: "The line is not a json object.".to_string(),
+                    }),
+                }
+            }
+        }
+    }
+    Ok((records, errors))
+}
+
+fn merge_import_errors(mut resp: StatsRecordImportResp, errors: Vec<StatsRecordImportErrorResp>) -> StatsRecordImportResp {
+    resp.total += errors.len() as u64;
+    resp.errors.extend(errors);
+    resp.errors.sort_by_key(|error| error.line);
+    resp
+}
@@ -1,9 +1,32 @@
 use bios_basic::rbum::rbum_config::RbumConfig;
+use bios_sdk_invoke::invoke_config::InvokeConfig;
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 
-#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(default)]
 pub struct StatsConfig {
     pub rbum: RbumConfig,
+    // Default time to wait for more changes of fact records before pushing the metrics to the stream subscribers
+    pub metric_stream_debounce_ms: u64,
+    // Maximum number of the loaded fact records to recalculate the metrics incrementally, otherwise recalculate all
+    pub metric_stream_incremental_max_records: usize,
+    // Expiration of the presigned URL to read the imported file from spi-object
+    pub import_url_exp_secs: u32,
+    // Maximum size in bytes of the imported file
+    pub import_max_size: usize,
+    pub invoke: InvokeConfig,
+}
+
+impl Default for StatsConfig {
+    fn default() -> Self {
+        StatsConfig {
+            rbum: Default::default(),
+            metric_stream_debounce_ms: 1000,
+            metric_stream_incremental_max_records: 1000,
+            import_url_exp_secs: 60,
+            import_max_size: 10 * 1024 * 1024,
+            invoke: InvokeConfig::default(),
+        }
+    }
 }
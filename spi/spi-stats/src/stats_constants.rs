@@ -1 +1,4 @@
 pub const DOMAIN_CODE: &str = "spi-stats";
+pub(crate) const CONN_URI_FLAG: &str = "__conn_uri__";
+// Channel of PG NOTIFY to push the changes of fact records to the metric stream subscribers on all nodes
+pub(crate) const METRIC_STREAM_NOTIFY_CHANNEL: &str = "spi_stats_metric_stream";
@@ -35,6 +35,25 @@ impl StatsDataTypeKind {
         )
     }
 
+    pub fn is_numeric(&self) -> bool {
+        self == &StatsDataTypeKind::Int || self == &StatsDataTypeKind::Float
+    }
+
+    /// Convert the text value, e.g. from CSV, to the json value of the data type, the other json values are returned as is.
+    ///
+    /// Returns `None` if the text can't be parsed.
+    pub fn text_to_json_value(&self, json_value: &serde_json::Value) -> Option<serde_json::Value> {
+        let Some(text) = json_value.as_str() else {
+            return Some(json_value.clone());
+        };
+        match self {
+            StatsDataTypeKind::Int => text.trim().parse::<i64>().ok().map(serde_json::Value::from),
+            StatsDataTypeKind::Float => text.trim().parse::<f64>().ok().map(serde_json::Value::from),
+            StatsDataTypeKind::Boolean => text.trim().parse::<bool>().ok().map(serde_json::Value::from),
+            _ => Some(json_value.clone()),
+        }
+    }
+
     pub fn to_pg_data_type(&self) -> &str {
         match self {
             StatsDataTypeKind::String => "character varying",
@@ -188,6 +207,7 @@ impl StatsDataTypeKind {
         param_idx: usize,
         value: &serde_json::Value,
         fun: Option<&StatsQueryAggFunKind>,
+        rel_column_name: Option<&str>,
     ) -> TardisResult<Option<(String, Vec<sea_orm::Value>)>> {
         let value = if (self == &StatsDataTypeKind::DateTime || self != &StatsDataTypeKind::Date) && value.is_string() {
             let value = self.json_to_sea_orm_value(value, op == &BasicQueryOpKind::Like)?;
@@ -206,6 +226,7 @@ impl StatsDataTypeKind {
                 || self == &StatsDataTypeKind::Boolean && (op != &BasicQueryOpKind::Eq && op != &BasicQueryOpKind::Ne)
                 || self == &StatsDataTypeKind::Date && (op == &BasicQueryOpKind::In || op == &BasicQueryOpKind::Like)
                 || self == &StatsDataTypeKind::DateTime && (op == &BasicQueryOpKind::In || op == &BasicQueryOpKind::Like)
+                || fun.is_some_and(|fun| !fun.is_having_supported() || fun.is_numeric_only() && !self.is_numeric())
             {
                 None
             } else if multi_values {
@@ -220,7 +241,7 @@ impl StatsDataTypeKind {
                     .collect::<Vec<_>>();
                 Some((format!("({})", param_sql.join(" or ")), value))
             } else if let Some(fun) = fun {
-                value.pop().map(|value| (format!("{} {} ${param_idx}", fun.to_sql(column_name), op.to_sql()), vec![value]))
+                value.pop().map(|value| (format!("{} {} ${param_idx}", fun.to_sql(column_name, rel_column_name), op.to_sql()), vec![value]))
             } else if op == &BasicQueryOpKind::In {
                 let mut index = 0;
                 let param_sql = value
@@ -252,8 +273,8 @@ impl StatsDataTypeKind {
         }
     }
 
-    pub(crate) fn to_pg_select(&self, column_name: &str, fun: &StatsQueryAggFunKind) -> String {
-        fun.to_sql(column_name)
+    pub(crate) fn to_pg_select(&self, column_name: &str, fun: &StatsQueryAggFunKind, rel_column_name: Option<&str>) -> String {
+        fun.to_sql(column_name, rel_column_name)
     }
 }
 
@@ -301,16 +322,152 @@ pub enum StatsQueryAggFunKind {
     Min,
     #[oai(rename = "count")]
     Count,
+    /// Count of the distinct values.
+    ///
+    /// The count is exact (`count(DISTINCT ...)`), approximate algorithms such as HyperLogLog need the `hll` extension,
+    /// which is not required by the backend service.
+    #[oai(rename = "distinct_count")]
+    DistinctCount,
+    /// 50th percentile (median), continuous
+    #[oai(rename = "p50")]
+    P50,
+    /// 90th percentile, continuous
+    #[oai(rename = "p90")]
+    P90,
+    /// 99th percentile, continuous
+    #[oai(rename = "p99")]
+    P99,
+    /// Ratio of the sum of the measure to the sum of the related measure
+    #[oai(rename = "ratio")]
+    Ratio,
+    /// Growth rate of the sum of the measure compared with the previous period of the time window group
+    #[oai(rename = "growth")]
+    Growth,
 }
 
 impl StatsQueryAggFunKind {
-    pub(crate) fn to_sql(&self, column_name: &str) -> String {
+    /// The name in the requests, also used in the alias names of the results
+    pub fn to_name(&self) -> &'static str {
+        match self {
+            StatsQueryAggFunKind::Sum => "sum",
+            StatsQueryAggFunKind::Avg => "avg",
+            StatsQueryAggFunKind::Max => "max",
+            StatsQueryAggFunKind::Min => "min",
+            StatsQueryAggFunKind::Count => "count",
+            StatsQueryAggFunKind::DistinctCount => "distinct_count",
+            StatsQueryAggFunKind::P50 => "p50",
+            StatsQueryAggFunKind::P90 => "p90",
+            StatsQueryAggFunKind::P99 => "p99",
+            StatsQueryAggFunKind::Ratio => "ratio",
+            StatsQueryAggFunKind::Growth => "growth",
+        }
+    }
+
+    /// For `Ratio`, `rel_column_name` is the column of the denominator.
+    ///
+    /// For `Growth`, only the sum of the current period is returned here,
+    /// the comparison with the previous period needs a window over the groups, see [`StatsQueryAggFunKind::to_growth_sql`].
+    pub(crate) fn to_sql(&self, column_name: &str, rel_column_name: Option<&str>) -> String {
         match self {
             StatsQueryAggFunKind::Sum => format!("sum({column_name})"),
             StatsQueryAggFunKind::Avg => format!("avg({column_name})"),
             StatsQueryAggFunKind::Max => format!("max({column_name})"),
             StatsQueryAggFunKind::Min => format!("min({column_name})"),
             StatsQueryAggFunKind::Count => format!("count({column_name})"),
+            StatsQueryAggFunKind::DistinctCount => format!("count(DISTINCT {column_name})"),
+            StatsQueryAggFunKind::P50 => format!("percentile_cont(0.5) WITHIN GROUP (ORDER BY {column_name})"),
+            StatsQueryAggFunKind::P90 => format!("percentile_cont(0.9) WITHIN GROUP (ORDER BY {column_name})"),
+            StatsQueryAggFunKind::P99 => format!("percentile_cont(0.99) WITHIN GROUP (ORDER BY {column_name})"),
+            StatsQueryAggFunKind::Ratio => format!("(sum({column_name})::double precision / NULLIF(sum({}), 0))", rel_column_name.unwrap_or(column_name)),
+            StatsQueryAggFunKind::Growth => format!("sum({column_name})"),
+        }
+    }
+
+    /// Aggregate from the rollup columns `<column>__sum`, `<column>__count`, `<column>__max` and `<column>__min`.
+    ///
+    /// Returns `None` if the function cannot be answered by the rollup, the result types are the same as [`StatsQueryAggFunKind::to_sql`].
+    pub(crate) fn to_rollup_sql(&self, column_name: &str, rel_column_name: Option<&str>, data_type: &StatsDataTypeKind) -> Option<String> {
+        let sum_sql = if data_type == &StatsDataTypeKind::Int {
+            format!("sum({column_name}__sum)::bigint")
+        } else {
+            format!("sum({column_name}__sum)")
+        };
+        match self {
+            StatsQueryAggFunKind::Sum | StatsQueryAggFunKind::Growth => Some(sum_sql),
+            StatsQueryAggFunKind::Avg if data_type == &StatsDataTypeKind::Int => Some(format!("(sum({column_name}__sum)::numeric / NULLIF(sum({column_name}__count), 0))")),
+            StatsQueryAggFunKind::Avg => Some(format!("(sum({column_name}__sum) / NULLIF(sum({column_name}__count), 0))")),
+            StatsQueryAggFunKind::Max => Some(format!("max({column_name}__max)")),
+            StatsQueryAggFunKind::Min => Some(format!("min({column_name}__min)")),
+            StatsQueryAggFunKind::Count => Some(format!("sum({column_name}__count)::bigint")),
+            StatsQueryAggFunKind::Ratio => rel_column_name.map(|rel_column_name| format!("(sum({column_name}__sum)::double precision / NULLIF(sum({rel_column_name}__sum), 0))")),
+            StatsQueryAggFunKind::DistinctCount | StatsQueryAggFunKind::P50 | StatsQueryAggFunKind::P90 | StatsQueryAggFunKind::P99 => None,
+        }
+    }
+
+    /// `(current - previous) / previous`, `NULL` for the first period or when the previous period is `0`.
+    ///
+    /// The previous period is the previous group that has records, the periods without records are skipped rather than treated as `0`,
+    /// e.g. with records on `2023-01-01` and `2023-01-03` only, the growth of `2023-01-03` is compared with `2023-01-01`.
+    ///
+    /// `sum_sql` is the sum of the current period, `window` is the window definition, e.g. `PARTITION BY _.status ORDER BY date(timezone('UTC', _.ct))`.
+    pub(crate) fn to_growth_sql(sum_sql: &str, window: &str) -> String {
+        format!("(({sum_sql} - lag({sum_sql}) OVER ({window}))::double precision / NULLIF(lag({sum_sql}) OVER ({window}), 0))")
+    }
+
+    /// Whether the function requires a related measure
+    pub fn is_rel_required(&self) -> bool {
+        self == &StatsQueryAggFunKind::Ratio
+    }
+
+    /// Whether the function can only be applied to the numeric measures
+    pub fn is_numeric_only(&self) -> bool {
+        matches!(
+            self,
+            StatsQueryAggFunKind::P50
+                | StatsQueryAggFunKind::P90
+                | StatsQueryAggFunKind::P99
+                | StatsQueryAggFunKind::Ratio
+                | StatsQueryAggFunKind::Growth
+        )
+    }
+
+    /// Window functions are evaluated after `HAVING`, so they cannot be used as filters
+    pub fn is_having_supported(&self) -> bool {
+        self != &StatsQueryAggFunKind::Growth
+    }
+
+    /// Whether the function can be calculated from the `__sum`/`__count`/`__max`/`__min` columns of a rollup.
+    pub fn is_rollup_supported(&self) -> bool {
+        !matches!(
+            self,
+            StatsQueryAggFunKind::DistinctCount | StatsQueryAggFunKind::P50 | StatsQueryAggFunKind::P90 | StatsQueryAggFunKind::P99
+        )
+    }
+
+    /// Whether the result of the function can be merged from the results of two sets of records.
+    pub fn is_incremental_supported(&self) -> bool {
+        matches!(
+            self,
+            StatsQueryAggFunKind::Sum | StatsQueryAggFunKind::Count | StatsQueryAggFunKind::Max | StatsQueryAggFunKind::Min
+        )
+    }
+
+    /// Merge the results of the same group calculated from two sets of records, `None` if they can't be merged.
+    pub fn merge_value(&self, prev_value: &serde_json::Value, delta_value: &serde_json::Value) -> Option<serde_json::Value> {
+        if prev_value.is_null() {
+            return Some(delta_value.clone());
+        }
+        if delta_value.is_null() {
+            return Some(prev_value.clone());
+        }
+        match self {
+            StatsQueryAggFunKind::Sum | StatsQueryAggFunKind::Count => match (prev_value.as_i64(), delta_value.as_i64()) {
+                (Some(prev_value), Some(delta_value)) => prev_value.checked_add(delta_value).map(serde_json::Value::from),
+                _ => Some(serde_json::Value::from(prev_value.as_f64()? + delta_value.as_f64()?)),
+            },
+            StatsQueryAggFunKind::Max => Some(if delta_value.as_f64()? > prev_value.as_f64()? { delta_value } else { prev_value }.clone()),
+            StatsQueryAggFunKind::Min => Some(if delta_value.as_f64()? < prev_value.as_f64()? { delta_value } else { prev_value }.clone()),
+            _ => None,
         }
     }
 }
@@ -343,6 +500,20 @@ pub enum StatsQueryTimeWindowKind {
 }
 
 impl StatsQueryTimeWindowKind {
+    /// The unit of `date_trunc` for the rollup buckets, only `Hour` and `Date` are supported.
+    pub(crate) fn to_rollup_trunc_unit(&self) -> Option<&str> {
+        match self {
+            StatsQueryTimeWindowKind::Hour => Some("hour"),
+            StatsQueryTimeWindowKind::Date => Some("day"),
+            _ => None,
+        }
+    }
+
+    /// Whether this time window can be calculated from the buckets of the rollup time window.
+    pub(crate) fn is_coarser_or_equal(&self, rollup_time_window: &StatsQueryTimeWindowKind) -> bool {
+        rollup_time_window == &StatsQueryTimeWindowKind::Hour || self != &StatsQueryTimeWindowKind::Hour
+    }
+
     pub fn to_sql(&self, column_name: &str, is_date_time: bool) -> String {
         if is_date_time {
             match self {
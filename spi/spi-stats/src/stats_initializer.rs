@@ -1,4 +1,5 @@
 use bios_basic::spi::{api::spi_ci_bs_api, dto::spi_bs_dto::SpiBsCertResp, spi_constants, spi_funs::SpiBsInst, spi_initializer};
+use bios_sdk_invoke::invoke_initializer;
 use tardis::{
     basic::{dto::TardisContext, result::TardisResult},
     web::web_server::TardisWebServer,
@@ -7,11 +8,14 @@ use tardis::{
 
 use crate::{
     api::ci::{stats_ci_conf_api, stats_ci_metric_api, stats_ci_record_api},
+    serv,
+    stats_config::StatsConfig,
     stats_constants::DOMAIN_CODE,
 };
 
 pub async fn init(web_server: &TardisWebServer) -> TardisResult<()> {
     let mut funs = crate::get_tardis_inst();
+    invoke_initializer::init(funs.module_code(), funs.conf::<StatsConfig>().invoke.clone())?;
     funs.begin().await?;
     let ctx = spi_initializer::init(DOMAIN_CODE, &funs).await?;
     init_db(&funs, &ctx).await?;
@@ -42,7 +46,7 @@ async fn init_api(web_server: &TardisWebServer) -> TardisResult<()> {
 pub async fn init_fun(bs_cert: SpiBsCertResp, ctx: &TardisContext, mgr: bool) -> TardisResult<SpiBsInst> {
     match bs_cert.kind_code.as_str() {
         #[cfg(feature = "spi-pg")]
-        spi_constants::SPI_PG_KIND_CODE => spi_initializer::common_pg::init(&bs_cert, ctx, mgr).await,
+        spi_constants::SPI_PG_KIND_CODE => serv::pg::stats_pg_initializer::init(&bs_cert, ctx, mgr).await,
         _ => Err(bs_cert.bs_not_implemented())?,
     }
 }
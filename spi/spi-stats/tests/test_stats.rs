@@ -7,17 +7,22 @@ use bios_basic::spi::dto::spi_bs_dto::SpiBsAddReq;
 use bios_basic::spi::spi_constants;
 use bios_basic::test::init_rbum_test_container;
 use bios_basic::test::test_http_client::TestHttpClient;
+use bios_spi_object::object_constants;
+use bios_spi_object::object_initializer;
 use bios_spi_stats::stats_constants::DOMAIN_CODE;
 use bios_spi_stats::stats_initializer;
 use tardis::basic::dto::TardisContext;
 use tardis::basic::field::TrimString;
 use tardis::basic::result::TardisResult;
+use tardis::test::test_container::TardisTestContainer;
 use tardis::tokio::time::sleep;
 use tardis::web::web_resp::Void;
 use tardis::{testcontainers, tokio, TardisFuns};
 mod test_stats_conf;
 mod test_stats_metric;
+mod test_stats_metric_stream;
 mod test_stats_record;
+mod test_stats_record_import;
 
 #[tokio::test]
 async fn test_stats() -> TardisResult<()> {
@@ -25,19 +30,23 @@ async fn test_stats() -> TardisResult<()> {
 
     let docker = testcontainers::clients::Cli::default();
     let _x = init_rbum_test_container::init(&docker, None).await?;
+    let minio = TardisTestContainer::minio_custom(&docker);
+    let minio_url = format!("http://127.0.0.1:{}", minio.get_host_port_ipv4(9000));
 
-    init_data().await?;
+    init_data(&minio_url).await?;
 
     Ok(())
 }
 
-async fn init_data() -> TardisResult<()> {
+async fn init_data(minio_url: &str) -> TardisResult<()> {
     // Initialize RBUM
     bios_basic::rbum::rbum_initializer::init(DOMAIN_CODE, RbumConfig::default()).await?;
 
     let web_server = TardisFuns::web_server();
     // Initialize SPI Stats
     stats_initializer::init(web_server).await.unwrap();
+    // Initialize SPI Object to import records from
+    object_initializer::init(web_server).await.unwrap();
 
     tokio::spawn(async move {
         web_server.start().await.unwrap();
@@ -78,6 +87,26 @@ async fn init_data() -> TardisResult<()> {
 
     let _: Void = client.put(&format!("/ci/manage/bs/{}/rel/app001", bs_id), &Void {}).await;
 
+    let mut object_client = TestHttpClient::new(format!("https://localhost:8080/{}", object_constants::DOMAIN_CODE));
+    object_client.set_auth(&ctx)?;
+    let object_kind_id = RbumKindServ::get_rbum_kind_id_by_code(object_constants::SPI_S3_KIND_CODE, &funs).await?.unwrap();
+    let object_bs_id: String = object_client
+        .post(
+            "/ci/manage/bs",
+            &SpiBsAddReq {
+                name: TrimString("test-spi-object".to_string()),
+                kind_id: TrimString(object_kind_id),
+                conn_uri: minio_url.to_string(),
+                ak: TrimString("minioadmin".to_string()),
+                sk: TrimString("minioadmin".to_string()),
+                ext: r#"{"region":"us-east-1"}"#.to_string(),
+                private: false,
+                disabled: None,
+            },
+        )
+        .await;
+    let _: Void = object_client.put(&format!("/ci/manage/bs/{}/rel/app001", object_bs_id), &Void {}).await;
+
     client.set_auth(&TardisContext {
         own_paths: "t1/a1".to_string(),
         ak: "".to_string(),
@@ -90,6 +119,8 @@ async fn init_data() -> TardisResult<()> {
     test_stats_conf::test(&mut client).await?;
     test_stats_record::test(&mut client).await?;
     test_stats_metric::test(&mut client).await?;
+    test_stats_metric_stream::test(&mut client).await?;
+    test_stats_record_import::test(&mut client).await?;
 
     Ok(())
 }
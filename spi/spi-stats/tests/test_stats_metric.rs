@@ -6,7 +6,7 @@ use tardis::basic::result::TardisResult;
 use tardis::chrono::Utc;
 use tardis::serde_json::{json, Value};
 use tardis::tokio::time::sleep;
-use tardis::web::web_resp::Void;
+use tardis::web::web_resp::{TardisPage, Void};
 
 pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
     let data = vec![
@@ -48,6 +48,8 @@ pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
 
     test_metric_query_check(client).await?;
     test_metric_query(client).await?;
+    test_metric_query_with_rollup(client).await?;
+    test_metric_query_with_saved_query(client).await?;
 
     Ok(())
 }
@@ -198,6 +200,42 @@ pub async fn test_metric_query_check(client: &mut TestHttpClient) -> TardisResul
             .code,
         "404-spi-stats-metric-query"
     );
+
+    // ratio function without related measure error
+    assert_eq!(
+        client
+            .put_resp::<Value, StatsQueryMetricsResp>(
+                "/ci/metric",
+                &json!({
+                    "from":"req",
+                    "select":[{"code":"act_hours","fun":"ratio"}],
+                    "group":[{"code":"source"}],
+                    "start_time":"2023-01-01T12:00:00.000Z",
+                    "end_time":"2023-02-01T12:00:00.000Z"
+                }),
+            )
+            .await
+            .code,
+        "404-spi-stats-metric-query"
+    );
+
+    // growth function without time window error
+    assert_eq!(
+        client
+            .put_resp::<Value, StatsQueryMetricsResp>(
+                "/ci/metric",
+                &json!({
+                    "from":"req",
+                    "select":[{"code":"act_hours","fun":"growth"}],
+                    "group":[{"code":"source"}],
+                    "start_time":"2023-01-01T12:00:00.000Z",
+                    "end_time":"2023-02-01T12:00:00.000Z"
+                }),
+            )
+            .await
+            .code,
+        "400-spi-stats-metric-query"
+    );
     Ok(())
 }
 
@@ -288,6 +326,46 @@ pub async fn test_metric_query(client: &mut TestHttpClient) -> TardisResult<()>
     assert_eq!(resp.group.as_object().unwrap()["hangzhou"]["close"]["act_hours__sum"], 10);
     assert_eq!(resp.group.as_object().unwrap()["hangzhou"]["close"]["plan_hours__max"], 20);
 
+    // test dimension hierarchy levels
+    let resp: StatsQueryMetricsResp = client
+        .put(
+            "/ci/metric",
+            &json!({
+                "from":"req",
+                "select":[{"code":"act_hours","fun":"sum"}],
+                "group":[{"code":"source","hierarchy":1},{"code":"source","hierarchy":2,"ignore_group_rollup":true}],
+                "start_time":"2023-01-01T12:00:00.000Z",
+                "end_time":"2023-02-01T12:00:00.000Z"
+            }),
+        )
+        .await;
+    assert_eq!(resp.show_names.len(), 3);
+    assert_eq!(resp.show_names["source__hierarchy1"].as_str(), "省");
+    assert_eq!(resp.show_names["source__hierarchy2"].as_str(), "市");
+    assert_eq!(resp.group.as_object().unwrap().len(), 2);
+    assert_eq!(resp.group.as_object().unwrap()["ROLLUP"]["act_hours__sum"], 100);
+    assert_eq!(resp.group.as_object().unwrap()["zhejiang"].as_object().unwrap().len(), 3);
+    assert!(resp.group.as_object().unwrap()["zhejiang"].get("ROLLUP").is_none());
+    assert_eq!(resp.group.as_object().unwrap()["zhejiang"][""]["act_hours__sum"], 10);
+    assert_eq!(resp.group.as_object().unwrap()["zhejiang"]["hangzhou"]["act_hours__sum"], 80);
+    assert_eq!(resp.group.as_object().unwrap()["zhejiang"]["taizhou"]["act_hours__sum"], 10);
+    assert_eq!(
+        client
+            .put_resp::<Value, StatsQueryMetricsResp>(
+                "/ci/metric",
+                &json!({
+                    "from":"req",
+                    "select":[{"code":"act_hours","fun":"sum"}],
+                    "group":[{"code":"source","hierarchy":3}],
+                    "start_time":"2023-01-01T12:00:00.000Z",
+                    "end_time":"2023-02-01T12:00:00.000Z"
+                }),
+            )
+            .await
+            .code,
+        "400-spi-stats-metric-query"
+    );
+
     // TODO test tree dimensions with multiple values
     // let resp: StatsQueryMetricsResp = client
     //     .put(
@@ -326,6 +404,56 @@ pub async fn test_metric_query(client: &mut TestHttpClient) -> TardisResult<()>
     assert_eq!(resp.group.as_object().unwrap()["2023-01-01"]["open"]["act_hours__avg"], 10.0);
     assert_eq!(resp.group.as_object().unwrap()["2023-01-01"]["open"]["plan_hours__avg"], 20.0);
 
+    // test percentile, distinct count, ratio and growth
+    let resp: StatsQueryMetricsResp = client
+        .put(
+            "/ci/metric",
+            &json!({
+                "from":"req",
+                "select":[
+                    {"code":"act_hours","fun":"p90"},
+                    {"code":"key","fun":"distinct_count"},
+                    {"code":"act_hours","fun":"ratio","rel_code":"plan_hours"},
+                    {"code":"act_hours","fun":"growth"}
+                ],
+                "group":[{"code":"ct","time_window":"date"}],
+                "start_time":"2023-01-01T12:00:00.000Z",
+                "end_time":"2023-02-01T12:00:00.000Z",
+                "metrics_order": [{"code":"act_hours","fun":"ratio","rel_code":"plan_hours","asc": false}],
+                "having": [{"code":"act_hours","fun":"ratio","rel_code":"plan_hours","op":">=","value":0.5}]
+            }),
+        )
+        .await;
+    assert_eq!(resp.show_names.len(), 5);
+    assert_eq!(resp.group.as_object().unwrap().len(), 4);
+    assert_eq!(resp.group.as_object().unwrap()["ROLLUP"]["act_hours__p90"], 10.0);
+    assert_eq!(resp.group.as_object().unwrap()["ROLLUP"]["key__distinct_count"], 10);
+    assert_eq!(resp.group.as_object().unwrap()["ROLLUP"]["act_hours__ratio__plan_hours"], 0.5);
+    assert!(resp.group.as_object().unwrap()["ROLLUP"]["act_hours__growth"].is_null());
+    assert_eq!(resp.group.as_object().unwrap()["2023-01-01"]["key__distinct_count"], 8);
+    assert!(resp.group.as_object().unwrap()["2023-01-01"]["act_hours__growth"].is_null());
+    assert_eq!(resp.group.as_object().unwrap()["2023-01-02"]["act_hours__growth"], -0.875);
+    assert_eq!(resp.group.as_object().unwrap()["2023-01-03"]["act_hours__growth"], 0.0);
+
+    // test growth with a gap, the periods without records are skipped
+    let resp: StatsQueryMetricsResp = client
+        .put(
+            "/ci/metric",
+            &json!({
+                "from":"req",
+                "select":[{"code":"act_hours","fun":"growth"}],
+                "group":[{"code":"ct","time_window":"date"}],
+                "where":[[{"code":"status","op":"!=","value":"progress"}]],
+                "start_time":"2023-01-01T12:00:00.000Z",
+                "end_time":"2023-02-01T12:00:00.000Z"
+            }),
+        )
+        .await;
+    assert_eq!(resp.group.as_object().unwrap().len(), 3);
+    assert!(resp.group.as_object().unwrap().get("2023-01-02").is_none());
+    assert!(resp.group.as_object().unwrap()["2023-01-01"]["act_hours__growth"].is_null());
+    assert_eq!(resp.group.as_object().unwrap()["2023-01-03"]["act_hours__growth"], -0.875);
+
     // test two dimensions with limit
     let resp: StatsQueryMetricsResp = client
         .put(
@@ -517,3 +645,274 @@ pub async fn test_metric_query(client: &mut TestHttpClient) -> TardisResult<()>
 
     Ok(())
 }
+
+pub async fn test_metric_query_with_rollup(client: &mut TestHttpClient) -> TardisResult<()> {
+    // time window not supported error
+    assert_eq!(
+        client
+            .put_resp::<Value, Void>(
+                "/ci/conf/fact/req/rollup",
+                &json!({
+                    "key":"monthly",
+                    "dims":["status"],
+                    "time_window":"month",
+                    "measures":["act_hours","plan_hours"]
+                }),
+            )
+            .await
+            .code,
+        "400-spi-stats-fact_rollup_conf-add"
+    );
+    // dimension not exist error
+    assert_eq!(
+        client
+            .put_resp::<Value, Void>(
+                "/ci/conf/fact/req/rollup",
+                &json!({
+                    "key":"daily",
+                    "dims":["xxx"],
+                    "time_window":"date",
+                    "measures":["act_hours","plan_hours"]
+                }),
+            )
+            .await
+            .code,
+        "400-spi-stats-fact_rollup_conf-add"
+    );
+    let _: Void = client
+        .put(
+            "/ci/conf/fact/req/rollup",
+            &json!({
+                "key":"daily",
+                "dims":["status"],
+                "time_window":"date",
+                "measures":["act_hours","plan_hours"],
+                "remark":"按天统计"
+            }),
+        )
+        .await;
+    let list: TardisPage<Value> = client.get("/ci/conf/fact/req/rollup?page_number=1&page_size=10").await;
+    assert_eq!(list.total_size, 1);
+    assert_eq!(list.records[0]["key"].as_str().unwrap(), "daily");
+
+    // test query answered from the rollup, the result is the same as the one from the fact records,
+    // r011 is deleted out of the time range, so it is included
+    let query = json!({
+        "from":"req",
+        "select":[{"code":"act_hours","fun":"sum"},{"code":"plan_hours","fun":"sum"}],
+        "group":[{"code":"ct","time_window":"date"},{"code":"status"}],
+        "start_time":"2023-01-01T00:00:00.000Z",
+        "end_time":"2023-01-31T23:59:59.999Z"
+    });
+    let resp: StatsQueryMetricsResp = client.put("/ci/metric", &query).await;
+    assert_eq!(resp.from, "req");
+    assert_eq!(resp.show_names.len(), 4);
+    assert_eq!(resp.group.as_object().unwrap()["ROLLUP"]["ROLLUP"]["act_hours__sum"], 100);
+    assert_eq!(resp.group.as_object().unwrap()["ROLLUP"]["ROLLUP"]["plan_hours__sum"], 200);
+    assert_eq!(resp.group.as_object().unwrap()["2023-01-01"]["open"]["act_hours__sum"], 80);
+    assert_eq!(resp.group.as_object().unwrap()["2023-01-02"]["progress"]["act_hours__sum"], 10);
+    assert_eq!(resp.group.as_object().unwrap()["2023-01-03"]["close"]["act_hours__sum"], 10);
+    // the end time is not aligned with the buckets, so it is answered from the fact records
+    let mut fact_query = query.clone();
+    fact_query["end_time"] = json!("2023-01-31T12:00:00.000Z");
+    let fact_resp: StatsQueryMetricsResp = client.put("/ci/metric", &fact_query).await;
+    assert_eq!(resp.group, fact_resp.group);
+
+    // test incremental maintenance
+    let _: Void = client
+        .put(
+            "/ci/record/fact/req/r012",
+            &json!({
+                "own_paths":"t1/a1",
+                "ct":"2023-01-02T08:00:00.000Z",
+                "data": {
+                    "source":"hangzhou",
+                    "status":"progress",
+                    "priority":1,
+                    "tag":["t1"],
+                    "creator":"acc001",
+                    "act_hours":20,
+                    "plan_hours":40
+                }
+            }),
+        )
+        .await;
+    let resp: StatsQueryMetricsResp = client.put("/ci/metric", &query).await;
+    assert_eq!(resp.group.as_object().unwrap()["ROLLUP"]["ROLLUP"]["act_hours__sum"], 120);
+    assert_eq!(resp.group.as_object().unwrap()["2023-01-02"]["progress"]["act_hours__sum"], 30);
+    assert_eq!(resp.group.as_object().unwrap()["2023-01-02"]["progress"]["plan_hours__sum"], 60);
+
+    // the deleted records are kept in the rollup
+    assert_eq!(client.delete_resp("/ci/record/fact/req/r012").await.code, "200");
+    let resp: StatsQueryMetricsResp = client.put("/ci/metric", &query).await;
+    assert_eq!(resp.group.as_object().unwrap()["ROLLUP"]["ROLLUP"]["act_hours__sum"], 120);
+    assert_eq!(resp.group.as_object().unwrap()["2023-01-02"]["progress"]["act_hours__sum"], 30);
+    // there are records deleted in the time range, so it is answered from the fact records
+    let mut deleted_query = query.clone();
+    deleted_query["end_time"] = json!(format!("{}T23:59:59.999Z", Utc::now().format("%Y-%m-%d")));
+    let resp: StatsQueryMetricsResp = client.put("/ci/metric", &deleted_query).await;
+    assert_eq!(resp.group.as_object().unwrap()["ROLLUP"]["ROLLUP"]["act_hours__sum"], 90);
+    assert_eq!(resp.group.as_object().unwrap()["2023-01-02"]["progress"]["act_hours__sum"], 10);
+    assert!(resp.group.as_object().unwrap().get("2023-01-03").is_none());
+
+    // test refresh
+    let _: Void = client.put("/ci/conf/fact/req/rollup/daily/refresh", &Void {}).await;
+    let resp: StatsQueryMetricsResp = client.put("/ci/metric", &query).await;
+    assert_eq!(resp.group.as_object().unwrap()["ROLLUP"]["ROLLUP"]["act_hours__sum"], 120);
+
+    // test delete
+    assert_eq!(client.delete_resp("/ci/conf/fact/req/rollup/daily").await.code, "200");
+    let list: TardisPage<Value> = client.get("/ci/conf/fact/req/rollup?page_number=1&page_size=10").await;
+    assert_eq!(list.total_size, 0);
+
+    Ok(())
+}
+
+pub async fn test_metric_query_with_saved_query(client: &mut TestHttpClient) -> TardisResult<()> {
+    // fact not exist error
+    assert_eq!(
+        client
+            .put_resp::<Value, Void>(
+                "/ci/conf/saved_query",
+                &json!({
+                    "key":"req_hours",
+                    "show_name":"需求工时",
+                    "query":{"from":"xxx"}
+                }),
+            )
+            .await
+            .code,
+        "404-spi-stats-saved_query_conf-add"
+    );
+    // illegal query template error
+    assert_eq!(
+        client
+            .put_resp::<Value, Void>(
+                "/ci/conf/saved_query",
+                &json!({
+                    "key":"req_hours",
+                    "show_name":"需求工时",
+                    "query":{
+                        "from":"req",
+                        "select":[{"code":"act_hours","fun":"xxx"}],
+                        "group":[{"code":"source"}],
+                        "start_time":"${start_time}",
+                        "end_time":"${end_time}"
+                    }
+                }),
+            )
+            .await
+            .code,
+        "400-spi-stats-saved_query_conf-add"
+    );
+    let _: Void = client
+        .put(
+            "/ci/conf/saved_query",
+            &json!({
+                "key":"req_hours",
+                "show_name":"需求工时",
+                "query":{
+                    "from":"req",
+                    "select":[{"code":"act_hours","fun":"sum"}],
+                    "group":[{"code":"source"}],
+                    "where":[[{"code":"status","op":"=","value":"${status}"}]],
+                    "start_time":"${start_time}",
+                    "end_time":"${end_time}"
+                }
+            }),
+        )
+        .await;
+    assert_eq!(
+        client
+            .put_resp::<Value, Void>(
+                "/ci/conf/saved_query",
+                &json!({
+                    "key":"req_hours",
+                    "show_name":"需求工时",
+                    "query":{"from":"req"}
+                }),
+            )
+            .await
+            .code,
+        "409-spi-stats-saved_query_conf-add"
+    );
+    let _: Void = client.patch("/ci/conf/saved_query/req_hours", &json!({"remark":"按来源统计工时"})).await;
+    let list: TardisPage<Value> = client.get("/ci/conf/saved_query?page_number=1&page_size=10&show_name=工时").await;
+    assert_eq!(list.total_size, 1);
+    assert_eq!(list.records[0]["remark"].as_str().unwrap(), "按来源统计工时");
+    assert_eq!(list.records[0]["own_paths"].as_str().unwrap(), "t1/a1");
+
+    // execute
+    let resp: StatsQueryMetricsResp = client
+        .put(
+            "/ci/metric/saved/req_hours",
+            &json!({
+                "params":{
+                    "status":"open",
+                    "start_time":"2023-01-01T12:00:00.000Z",
+                    "end_time":"2023-02-01T12:00:00.000Z"
+                }
+            }),
+        )
+        .await;
+    assert_eq!(resp.from, "req");
+    assert_eq!(resp.group.as_object().unwrap()["ROLLUP"]["act_hours__sum"], 80);
+    // parameter missing error
+    assert_eq!(
+        client
+            .put_resp::<Value, StatsQueryMetricsResp>(
+                "/ci/metric/saved/req_hours",
+                &json!({
+                    "params":{
+                        "status":"open"
+                    }
+                }),
+            )
+            .await
+            .code,
+        "400-spi-stats-saved_query-execute"
+    );
+
+    // dashboard
+    assert_eq!(
+        client
+            .put_resp::<Value, Void>(
+                "/ci/conf/dashboard",
+                &json!({
+                    "key":"req_overview",
+                    "show_name":"需求概览",
+                    "items":[{"saved_query_key":"xxx"}]
+                }),
+            )
+            .await
+            .code,
+        "404-spi-stats-dashboard_conf-add"
+    );
+    let _: Void = client
+        .put(
+            "/ci/conf/dashboard",
+            &json!({
+                "key":"req_overview",
+                "show_name":"需求概览",
+                "items":[{
+                    "saved_query_key":"req_hours",
+                    "params":{"status":"open"},
+                    "layout":{"x":0,"y":0,"w":6,"h":4,"chart":"bar"}
+                }]
+            }),
+        )
+        .await;
+    let list: TardisPage<Value> = client.get("/ci/conf/dashboard?page_number=1&page_size=10&key=req_overview").await;
+    assert_eq!(list.total_size, 1);
+    assert_eq!(list.records[0]["items"][0]["saved_query_key"].as_str().unwrap(), "req_hours");
+    assert_eq!(list.records[0]["items"][0]["layout"]["chart"].as_str().unwrap(), "bar");
+
+    // can't delete the saved query used by dashboards error
+    assert_eq!(client.delete_resp("/ci/conf/saved_query/req_hours").await.code, "409-spi-stats-saved_query_conf-delete");
+    assert_eq!(client.delete_resp("/ci/conf/dashboard/req_overview").await.code, "200");
+    assert_eq!(client.delete_resp("/ci/conf/saved_query/req_hours").await.code, "200");
+    let list: TardisPage<Value> = client.get("/ci/conf/saved_query?page_number=1&page_size=10").await;
+    assert_eq!(list.total_size, 0);
+
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use std::time::Duration;
+
+use bios_basic::test::test_http_client::TestHttpClient;
+use bios_spi_stats::dto::stats_query_dto::StatsQueryMetricsResp;
+use bios_spi_stats::stats_enumeration::StatsQueryAggFunKind;
+use tardis::basic::result::TardisResult;
+use tardis::futures::{SinkExt, StreamExt};
+use tardis::serde_json::{json, Value};
+use tardis::tokio::net::TcpStream;
+use tardis::tokio::time::{sleep, timeout};
+use tardis::web::tokio_tungstenite::tungstenite::Message;
+use tardis::web::tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use tardis::web::web_resp::Void;
+use tardis::TardisFuns;
+
+pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
+    assert!(StatsQueryAggFunKind::Sum.is_incremental_supported());
+    assert!(StatsQueryAggFunKind::Count.is_incremental_supported());
+    assert!(StatsQueryAggFunKind::Max.is_incremental_supported());
+    assert!(StatsQueryAggFunKind::Min.is_incremental_supported());
+    assert!(!StatsQueryAggFunKind::Avg.is_incremental_supported());
+
+    assert_eq!(StatsQueryAggFunKind::Sum.merge_value(&json!(80), &json!(20)), Some(json!(100)));
+    assert_eq!(StatsQueryAggFunKind::Sum.merge_value(&json!(1.5), &json!(2)), Some(json!(3.5)));
+    assert_eq!(StatsQueryAggFunKind::Count.merge_value(&json!(9), &json!(1)), Some(json!(10)));
+    assert_eq!(StatsQueryAggFunKind::Count.merge_value(&Value::Null, &json!(1)), Some(json!(1)));
+    assert_eq!(StatsQueryAggFunKind::Max.merge_value(&json!(40), &json!(60)), Some(json!(60)));
+    assert_eq!(StatsQueryAggFunKind::Max.merge_value(&json!(40), &json!(20)), Some(json!(40)));
+    assert_eq!(StatsQueryAggFunKind::Min.merge_value(&json!(40), &json!(20)), Some(json!(20)));
+    assert_eq!(StatsQueryAggFunKind::Min.merge_value(&json!(40), &Value::Null), Some(json!(40)));
+    assert_eq!(StatsQueryAggFunKind::Sum.merge_value(&json!(40), &json!("20")), None);
+    assert_eq!(StatsQueryAggFunKind::Avg.merge_value(&json!(40), &json!(20)), None);
+
+    // Push the metrics through websocket
+    load(client, "s001", "open", 10, "2023-02-01T08:00:00.000Z").await;
+    let query = json!({
+        "from":"req",
+        "select":[{"code":"act_hours","fun":"sum"},{"code":"act_hours","fun":"max"}],
+        "group":[{"code":"status"}],
+        "start_time":"2023-02-01T00:00:00.000Z",
+        "end_time":"2099-12-31T23:59:59.999Z"
+    });
+    let mut socket = client.ws_connect(&format!("/ci/metric/stream?debounce_ms=500&query={}", encode_query_value(&query))).await;
+    let resp = recv(&mut socket).await?;
+    assert_eq!(resp.group["ROLLUP"]["act_hours__sum"], 10);
+    assert_eq!(resp.group["open"]["act_hours__sum"], 10);
+    assert!(resp.group.get("close").is_none());
+    // The nodes changing fact records know that the table is subscribed
+    let table_name: String = TardisFuns::reldb()
+        .conn()
+        .query_one("SELECT schemaname || '.' || tablename AS table_name FROM pg_tables WHERE tablename LIKE '%\\_stats\\_inst\\_fact\\_req'", vec![])
+        .await?
+        .unwrap()
+        .try_get("", "table_name")?;
+    assert!(TardisFuns::cache().exists(&format!("spi-stats:metric-stream:subscribed:{table_name}")).await?);
+
+    // The changes in the debounce time are recalculated together, incrementally with the loaded records.
+    // The create time has more precision than the database, the loaded record is still found by the incremental query.
+    load(client, "s002", "open", 20, "2023-02-02T08:00:00.123456789Z").await;
+    load(client, "s003", "close", 5, "2023-02-03T08:00:00.000Z").await;
+    let resp = recv(&mut socket).await?;
+    assert_eq!(resp.group["ROLLUP"]["act_hours__sum"], 35);
+    assert_eq!(resp.group["ROLLUP"]["act_hours__max"], 20);
+    assert_eq!(resp.group["open"]["act_hours__sum"], 30);
+    assert_eq!(resp.group["close"]["act_hours__sum"], 5);
+    assert!(timeout(Duration::from_millis(1000), socket.next()).await.is_err());
+
+    // The deleted records are recalculated all
+    assert_eq!(client.delete_resp("/ci/record/fact/req/s002").await.code, "200");
+    let resp = recv(&mut socket).await?;
+    assert_eq!(resp.group["ROLLUP"]["act_hours__sum"], 15);
+    assert_eq!(resp.group["open"]["act_hours__max"], 10);
+
+    // The records out of the time range don't change the metrics, nothing is pushed
+    load(client, "s004", "open", 100, "2023-01-20T08:00:00.000Z").await;
+    assert!(timeout(Duration::from_millis(1000), socket.next()).await.is_err());
+
+    socket.send(Message::Close(None)).await.unwrap();
+    sleep(Duration::from_millis(100)).await;
+
+    Ok(())
+}
+
+async fn load(client: &mut TestHttpClient, key: &str, status: &str, act_hours: i32, ct: &str) {
+    let _: Void = client
+        .put(
+            &format!("/ci/record/fact/req/{key}"),
+            &json!({
+                "own_paths":"t1/a1",
+                "ct":ct,
+                "data": {
+                    "source":"hangzhou",
+                    "status":status,
+                    "priority":1,
+                    "tag":["t1"],
+                    "creator":"acc001",
+                    "act_hours":act_hours,
+                    "plan_hours":act_hours * 2
+                }
+            }),
+        )
+        .await;
+}
+
+async fn recv(socket: &mut WebSocketStream<MaybeTlsStream<TcpStream>>) -> TardisResult<StatsQueryMetricsResp> {
+    let Message::Text(message) = timeout(Duration::from_secs(5), socket.next()).await.unwrap().unwrap().unwrap() else {
+        panic!("unexpected message");
+    };
+    TardisFuns::json.str_to_obj::<StatsQueryMetricsResp>(&message)
+}
+
+fn encode_query_value(value: &Value) -> String {
+    value.to_string().bytes().map(|b| if b.is_ascii_alphanumeric() || b"-_.~".contains(&b) { (b as char).to_string() } else { format!("%{b:02X}") }).collect()
+}
@@ -0,0 +1,160 @@
+use bios_basic::test::test_http_client::TestHttpClient;
+use bios_spi_stats::dto::stats_query_dto::StatsQueryMetricsResp;
+use bios_spi_object::object_constants;
+use bios_spi_stats::dto::stats_record_dto::StatsRecordImportResp;
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::serde_json::{json, Value};
+use tardis::web::web_resp::TardisPage;
+use tardis::TardisFuns;
+
+pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
+    test_dim_records_import(client).await?;
+    test_fact_records_import(client).await?;
+    test_object_records_import(client).await?;
+    Ok(())
+}
+
+async fn test_dim_records_import(client: &mut TestHttpClient) -> TardisResult<()> {
+    // content missing error
+    assert_eq!(
+        client.put_resp::<Value, StatsRecordImportResp>("/ci/record/dim/address/batch/import", &json!({"format":"csv"})).await.code,
+        "400-spi-stats-dim_record-import"
+    );
+    // stable_ds = false error
+    assert_eq!(
+        client
+            .put_resp::<Value, StatsRecordImportResp>("/ci/record/dim/account/batch/import", &json!({"format":"csv","content":"key,show_name\nacc001,账号1"}))
+            .await
+            .code,
+        "400-spi-stats-dim_record-import"
+    );
+
+    // the parent is after the child, update the existing record and report the illegal ones
+    let content = r#"key,show_name,parent_key
+suzhou,"苏州, 姑苏",jiangsu
+jiangsu,江苏,cn
+hangzhou,杭州市,zhejiang
+taizhou,台州,cn
+linhai,临海,taizhou
+wuxi,无锡,xxx
+wrong
+"#;
+    let resp: StatsRecordImportResp = client.put("/ci/record/dim/address/batch/import", &json!({"format":"csv","content":content,"dry_run":true})).await;
+    assert_eq!(resp.total, 7);
+    assert_eq!(resp.added, 2);
+    assert_eq!(resp.updated, 1);
+    assert_eq!(resp.errors.iter().map(|error| error.line).collect::<Vec<_>>(), vec![5, 6, 7, 8]);
+    let list: TardisPage<Value> = client.get("/ci/record/dim/address?show_name=江苏&page_number=1&page_size=10").await;
+    assert_eq!(list.total_size, 0);
+
+    let resp: StatsRecordImportResp = client.put("/ci/record/dim/address/batch/import", &json!({"format":"csv","content":content})).await;
+    assert_eq!(resp.added, 2);
+    assert_eq!(resp.updated, 1);
+    assert_eq!(resp.errors[0].key.as_deref(), Some("taizhou"));
+    let list: TardisPage<Value> = client.get("/ci/record/dim/address?show_name=姑苏&page_number=1&page_size=10").await;
+    assert_eq!(list.total_size, 1);
+    assert_eq!(list.records[0]["key"].as_str().unwrap(), "suzhou");
+    assert_eq!(list.records[0]["show_name"].as_str().unwrap(), "苏州, 姑苏");
+    assert_eq!(list.records[0]["hierarchy"].as_i64().unwrap(), 2);
+    assert_eq!(list.records[0]["key0"].as_str().unwrap(), "cn");
+    assert_eq!(list.records[0]["key1"].as_str().unwrap(), "jiangsu");
+    let list: TardisPage<Value> = client.get("/ci/record/dim/address?show_name=杭州市&page_number=1&page_size=10").await;
+    assert_eq!(list.total_size, 1);
+
+    // json lines, the text keys are converted to the data type
+    let content = r#"{"key":5,"show_name":"低"}
+{"key":"6","show_name":"很低"}
+
+{"key":"x","show_name":"错误"}
+not json
+"#;
+    let resp: StatsRecordImportResp = client.put("/ci/record/dim/req_priority/batch/import", &json!({"format":"jsonl","content":content})).await;
+    assert_eq!(resp.total, 4);
+    assert_eq!(resp.added, 2);
+    assert_eq!(resp.errors.iter().map(|error| error.line).collect::<Vec<_>>(), vec![4, 5]);
+    let list: TardisPage<Value> = client.get("/ci/record/dim/req_priority?show_name=很低&page_number=1&page_size=10").await;
+    assert_eq!(list.records[0]["key"].as_i64().unwrap(), 6);
+
+    Ok(())
+}
+
+async fn test_fact_records_import(client: &mut TestHttpClient) -> TardisResult<()> {
+    // fact not exist error
+    assert_eq!(
+        client.put_resp::<Value, StatsRecordImportResp>("/ci/record/fact/xx/batch/import", &json!({"format":"csv","content":"key"})).await.code,
+        "409-spi-stats-fact_record-import"
+    );
+
+    let content = r#"key,own_paths,ct,status,priority,tag,creator,source,act_hours,plan_hours
+imp1,t1/a1,2023-03-01T10:00:00.000Z,open,1,"[""t1"",""t2""]",acc001,hangzhou,10,12
+imp2,,2023-03-01T11:00:00.000Z,close,2,[],acc002,suzhou,5,6
+imp3,t1/a1,2023-03-01,open,1,[],acc001,hangzhou,1,1
+imp4,t1/a1,2023-03-01T12:00:00.000Z,open,high,[],acc001,hangzhou,1,1
+imp5,t1/a1,2023-03-01T12:00:00.000Z,open,1,t1,acc001,hangzhou,1,1
+"#;
+    let resp: StatsRecordImportResp = client.put("/ci/record/fact/req/batch/import", &json!({"format":"csv","content":content,"dry_run":true})).await;
+    assert_eq!(resp.total, 5);
+    assert_eq!(resp.added, 2);
+    assert_eq!(resp.errors.iter().map(|error| error.line).collect::<Vec<_>>(), vec![4, 5, 6]);
+    assert_eq!(resp.errors[1].key.as_deref(), Some("imp4"));
+    assert!(resp.errors[1].message.contains("priority"));
+    let query = json!({
+        "from":"req",
+        "select":[{"code":"act_hours","fun":"sum"}],
+        "group":[{"code":"status"}],
+        "start_time":"2023-03-01T00:00:00.000Z",
+        "end_time":"2023-03-02T00:00:00.000Z"
+    });
+    let resp: StatsQueryMetricsResp = client.put("/ci/metric", &query).await;
+    assert!(resp.group.as_object().unwrap().get("open").is_none());
+
+    let resp: StatsRecordImportResp = client.put("/ci/record/fact/req/batch/import", &json!({"format":"csv","content":content})).await;
+    assert_eq!(resp.added, 2);
+    let resp: StatsQueryMetricsResp = client.put("/ci/metric", &query).await;
+    assert_eq!(resp.group.as_object().unwrap()["open"]["act_hours__sum"], 10);
+    assert_eq!(resp.group.as_object().unwrap()["close"]["act_hours__sum"], 5);
+
+    Ok(())
+}
+
+async fn test_object_records_import(client: &mut TestHttpClient) -> TardisResult<()> {
+    // file too large error
+    let content = format!("key,show_name\n{}", "k,v\n".repeat(1024));
+    assert_eq!(
+        client.put_resp::<Value, StatsRecordImportResp>("/ci/record/dim/address/batch/import", &json!({"format":"csv","content":content})).await.code,
+        "400-spi-stats-dim_record-import"
+    );
+    // file not exist error
+    assert_eq!(
+        client
+            .put_resp::<Value, StatsRecordImportResp>("/ci/record/dim/address/batch/import", &json!({"format":"csv","object_path":"spi-stats/import/xx.csv"}))
+            .await
+            .code,
+        "400-spi-stats-dim_record-import"
+    );
+
+    let mut object_client = TestHttpClient::new(format!("https://localhost:8080/{}", object_constants::DOMAIN_CODE));
+    object_client.set_auth(&TardisContext {
+        own_paths: "t1/a1".to_string(),
+        ak: "".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: "app001".to_string(),
+        ..Default::default()
+    })?;
+    let put_url: String = object_client.get("/ci/obj/presign/put?object_path=spi-stats/import/address.csv&exp_secs=60&private=true").await;
+    let resp = TardisFuns::web_client().put_str_to_str(&put_url, "key,show_name,parent_key\r\nningbo,宁波,zhejiang\r\n", None).await?;
+    assert_eq!(resp.code, 200);
+
+    let resp: StatsRecordImportResp = client.put("/ci/record/dim/address/batch/import", &json!({"format":"csv","object_path":"spi-stats/import/address.csv"})).await;
+    assert_eq!(resp.total, 1);
+    assert_eq!(resp.added, 1);
+    assert!(resp.errors.is_empty());
+    let list: TardisPage<Value> = client.get("/ci/record/dim/address?show_name=宁波&page_number=1&page_size=10").await;
+    assert_eq!(list.total_size, 1);
+    assert_eq!(list.records[0]["key"].as_str().unwrap(), "ningbo");
+    assert_eq!(list.records[0]["key1"].as_str().unwrap(), "zhejiang");
+
+    Ok(())
+}
@@ -44,6 +44,24 @@ pub enum LogParamTag {
     Token,
 }
 
+impl LogParamTag {
+    /// All the tags of the IAM audit logs
+    pub fn all() -> [LogParamTag; 10] {
+        [
+            LogParamTag::IamTenant,
+            LogParamTag::IamOrg,
+            LogParamTag::IamAccount,
+            LogParamTag::IamRole,
+            LogParamTag::IamRes,
+            LogParamTag::IamSystem,
+            LogParamTag::SecurityAlarm,
+            LogParamTag::SecurityVisit,
+            LogParamTag::Log,
+            LogParamTag::Token,
+        ]
+    }
+}
+
 impl From<LogParamTag> for String {
     fn from(val: LogParamTag) -> Self {
         match val {
@@ -2,7 +2,7 @@ use async_trait::async_trait;
 use bios_basic::rbum::rbum_config::RbumConfigApi;
 use bios_basic::rbum::rbum_enumeration::RbumRelFromKind;
 use bios_sdk_invoke::clients::spi_search_client::SpiSearchClient;
-use bios_sdk_invoke::dto::search_item_dto::{SearchItemAddReq, SearchItemModifyReq, SearchItemVisitKeysReq};
+use bios_sdk_invoke::dto::search_item_dto::{SearchItemAddReq, SearchItemBulkReq, SearchItemModifyReq, SearchItemVisitKeysReq};
 use itertools::Itertools;
 
 use std::collections::{HashMap, HashSet};
@@ -51,6 +51,9 @@ use super::clients::mail_client::MailClient;
 use super::clients::sms_client::SmsClient;
 use super::iam_app_serv::IamAppServ;
 
+// Number of accounts added to search in one bulk request
+pub const ACCOUNT_SEARCH_BULK_SIZE: usize = 500;
+
 pub struct IamAccountServ;
 
 #[async_trait]
@@ -866,6 +869,55 @@ impl IamAccountServ {
         funs: &TardisFunsInst,
         ctx: &TardisContext,
     ) -> TardisResult<()> {
+        let add_req = Self::package_account_search_req(account_resp, logout_msg, funs, ctx).await?;
+        //add or modify search
+        if *is_modify {
+            let tag = add_req.tag;
+            let key = add_req.key.to_string();
+            let modify_req = SearchItemModifyReq {
+                kind: Some(add_req.kind),
+                title: Some(add_req.title),
+                name: add_req.name,
+                content: Some(add_req.content),
+                owner: add_req.owner,
+                own_paths: add_req.own_paths,
+                create_time: add_req.create_time,
+                update_time: add_req.update_time,
+                ext: add_req.ext,
+                ext_override: Some(true),
+                visit_keys: add_req.visit_keys,
+                vector: add_req.vector,
+            };
+            SpiSearchClient::modify_item(&tag, &key, &modify_req, funs, ctx).await?;
+        } else {
+            SpiSearchClient::add_item(&add_req, funs, ctx).await?;
+        }
+        Ok(())
+    }
+
+    // account 全局搜索批量埋点方法, 已存在的会被覆盖
+    pub async fn bulk_add_or_modify_account_search(add_reqs: Vec<SearchItemAddReq>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        let mut add_reqs = add_reqs.into_iter().peekable();
+        while add_reqs.peek().is_some() {
+            let bulk_req = SearchItemBulkReq {
+                upserts: Some(add_reqs.by_ref().take(ACCOUNT_SEARCH_BULK_SIZE).collect()),
+                deletes: None,
+            };
+            if let Some(resp) = SpiSearchClient::bulk(&bulk_req, funs, ctx).await? {
+                if !resp.errors.is_empty() {
+                    return Err(funs.err().internal_error(
+                        "iam_account",
+                        "bulk_add_or_modify_account_search",
+                        &format!("failed to add accounts to search: {}", resp.errors.iter().map(|error| format!("{}({})", error.key, error.msg)).join(",")),
+                        "500-iam-account-search-bulk-error",
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn package_account_search_req(account_resp: IamAccountDetailAggResp, logout_msg: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<SearchItemAddReq> {
         let account_id = account_resp.id.as_str();
         let account_certs = account_resp.certs.iter().map(|m| m.1.clone()).collect::<Vec<String>>();
         let account_app_ids: Vec<String> = account_resp.apps.iter().map(|a| a.app_id.clone()).collect();
@@ -911,82 +963,42 @@ impl IamAccountServ {
             }
         }
         let account_roles = roles_set.into_iter().collect_vec();
-        //add or modify search
-        if *is_modify {
-            let modify_req = SearchItemModifyReq {
-                kind: Some(funs.conf::<IamConfig>().spi.search_account_tag.clone()),
-                title: Some(account_resp.name.clone()),
-                name: Some(account_resp.name.clone()),
-                content: Some(format!("{},{:?}", account_resp.name, account_certs,)),
-                owner: Some(account_resp.owner),
-                own_paths: if !account_resp.own_paths.is_empty() {
-                    Some(account_resp.own_paths.clone())
-                } else {
-                    None
-                },
-                create_time: Some(account_resp.create_time),
-                update_time: Some(account_resp.update_time),
-                ext: Some(json!({
-                    "status": account_resp.status,
-                    "temporary":account_resp.temporary,
-                    "lock_status": account_resp.lock_status,
-                    "role_id": account_roles,
-                    "dept_id": account_resp_dept_id,
-                    "project_id": account_app_ids,
-                    "create_time": account_resp.create_time.to_rfc3339(),
-                    "certs":account_resp.certs,
-                    "icon":account_resp.icon,
-                    "logout_msg":logout_msg,
-                })),
-                ext_override: Some(true),
-                visit_keys: Some(SearchItemVisitKeysReq {
-                    accounts: None,
-                    apps: Some(account_app_ids),
-                    tenants: Some([account_resp.own_paths].to_vec()),
-                    roles: Some(account_roles),
-                    groups: Some(account_resp_dept_id),
-                }),
-            };
-            SpiSearchClient::modify_item(&tag, &key, &modify_req, funs, ctx).await?;
-        } else {
-            let add_req = SearchItemAddReq {
-                tag,
-                kind: funs.conf::<IamConfig>().spi.search_account_tag.clone(),
-                key: TrimString(key),
-                title: account_resp.name.clone(),
-                name: Some(account_resp.name.clone()),
-                content: format!("{},{:?}", account_resp.name, account_certs,),
-                owner: Some(account_resp.owner),
-                own_paths: if !account_resp.own_paths.is_empty() {
-                    Some(account_resp.own_paths.clone())
-                } else {
-                    None
-                },
-                create_time: Some(account_resp.create_time),
-                update_time: Some(account_resp.update_time),
-                ext: Some(json!({
-                    "status": account_resp.status,
-                    "temporary":account_resp.temporary,
-                    "lock_status": account_resp.lock_status,
-                    "role_id": account_roles,
-                    "dept_id": account_resp_dept_id,
-                    "project_id": account_app_ids,
-                    "create_time": account_resp.create_time.to_rfc3339(),
-                    "certs":account_resp.certs,
-                    "icon":account_resp.icon,
-                    "logout_msg":logout_msg,
-                })),
-                visit_keys: Some(SearchItemVisitKeysReq {
-                    accounts: None,
-                    apps: Some(account_app_ids),
-                    tenants: Some([account_resp.own_paths].to_vec()),
-                    roles: Some(account_roles),
-                    groups: Some(account_resp_dept_id),
-                }),
-            };
-            SpiSearchClient::add_item(&add_req, funs, ctx).await?;
-        }
-        Ok(())
+        Ok(SearchItemAddReq {
+            tag,
+            kind: funs.conf::<IamConfig>().spi.search_account_tag.clone(),
+            key: TrimString(key),
+            title: account_resp.name.clone(),
+            name: Some(account_resp.name.clone()),
+            content: format!("{},{:?}", account_resp.name, account_certs,),
+            owner: Some(account_resp.owner),
+            own_paths: if !account_resp.own_paths.is_empty() {
+                Some(account_resp.own_paths.clone())
+            } else {
+                None
+            },
+            create_time: Some(account_resp.create_time),
+            update_time: Some(account_resp.update_time),
+            ext: Some(json!({
+                "status": account_resp.status,
+                "temporary":account_resp.temporary,
+                "lock_status": account_resp.lock_status,
+                "role_id": account_roles,
+                "dept_id": account_resp_dept_id,
+                "project_id": account_app_ids,
+                "create_time": account_resp.create_time.to_rfc3339(),
+                "certs":account_resp.certs,
+                "icon":account_resp.icon,
+                "logout_msg":logout_msg,
+            })),
+            visit_keys: Some(SearchItemVisitKeysReq {
+                accounts: None,
+                apps: Some(account_app_ids),
+                tenants: Some([account_resp.own_paths].to_vec()),
+                roles: Some(account_roles),
+                groups: Some(account_resp_dept_id),
+            }),
+            vector: None,
+        })
     }
 
     // account 全局搜索删除埋点方法
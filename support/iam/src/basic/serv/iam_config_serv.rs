@@ -3,12 +3,14 @@ use bios_basic::rbum::{
     dto::rbum_filer_dto::RbumBasicFilterReq,
     serv::rbum_crud_serv::{RbumCrudOperation, RbumCrudQueryPackage},
 };
+use bios_sdk_invoke::clients::spi_log_client::SpiLogClient;
 use tardis::{
     basic::{dto::TardisContext, result::TardisResult},
     db::{
         reldb_client::IdResp,
         sea_orm::{sea_query::*, EntityName, Set},
     },
+    log::warn,
     TardisFuns, TardisFunsInst,
 };
 
@@ -146,6 +148,23 @@ impl IamConfigServ {
             if !op_describe.is_empty() {
                 let _ = IamLogClient::add_ctx_task(LogParamTag::SecurityAlarm, None, op_describe, Some(op_kind), ctx).await;
             }
+            // The audit log capacity (GB) is enforced by the size-based retention policy of each IAM audit log tag, 0 means unlimited
+            let audit_log_capacity_mb = if config.code == IamConfigKind::AuditLogCapacity {
+                Some(if config.disabled == Some(true) {
+                    0
+                } else {
+                    config.value1.as_deref().and_then(|value| value.trim().parse::<u64>().ok()).and_then(|value| value.checked_mul(1024)).ok_or_else(|| {
+                        funs.err().bad_request(
+                            &Self::get_obj_name(),
+                            "add_or_modify_batch",
+                            "The audit log capacity must be a non-negative integer and not too large",
+                            "400-iam-config-audit-log-capacity-illegal",
+                        )
+                    })?
+                })
+            } else {
+                None
+            };
             if let Some(id) = config_id {
                 Self::modify_rbum(
                     &id,
@@ -180,6 +199,15 @@ impl IamConfigServ {
                 )
                 .await?;
             }
+            if let Some(audit_log_capacity_mb) = audit_log_capacity_mb {
+                // Best-effort, the config is kept even if the log service is unavailable or doesn't support retention
+                for tag in LogParamTag::all() {
+                    let tag: String = tag.into();
+                    if let Err(e) = SpiLogClient::modify_retention(Some(&tag), None, Some(audit_log_capacity_mb), funs, ctx).await {
+                        warn!("[IAM] Modify the retention policy of audit logs with tag {tag} error: {e:?}");
+                    }
+                }
+            }
         }
         Ok(())
     }
@@ -10,7 +10,7 @@ use tardis::web::poem_openapi;
 use tardis::web::web_resp::{TardisApiResult, TardisResp};
 use tardis::TardisFunsInst;
 
-use crate::basic::serv::iam_account_serv::IamAccountServ;
+use crate::basic::serv::iam_account_serv::{IamAccountServ, ACCOUNT_SEARCH_BULK_SIZE};
 use crate::basic::serv::iam_app_serv::IamAppServ;
 use crate::basic::serv::iam_tenant_serv::IamTenantServ;
 use crate::iam_config::IamConfig;
@@ -27,7 +27,7 @@ impl IamCsSpiDataApi {
     async fn init_spi_data(&self, ctx: TardisContextExtractor) -> TardisApiResult<Option<String>> {
         let mut funs = iam_constants::get_tardis_inst();
         funs.begin().await?;
-        Self::do_init_spi_data(&funs, &ctx.0, Box::new(false)).await?;
+        Self::do_init_spi_data(&funs, &ctx.0).await?;
         funs.commit().await?;
         if let Some(task_id) = TaskProcessor::get_task_id_with_ctx(&ctx.0).await? {
             TardisResp::accepted(Some(task_id))
@@ -41,7 +41,7 @@ impl IamCsSpiDataApi {
     async fn update_spi_data(&self, ctx: TardisContextExtractor) -> TardisApiResult<Option<String>> {
         let mut funs = iam_constants::get_tardis_inst();
         funs.begin().await?;
-        Self::do_init_spi_data(&funs, &ctx.0, Box::new(true)).await?;
+        Self::do_init_spi_data(&funs, &ctx.0).await?;
         funs.commit().await?;
         if let Some(task_id) = TaskProcessor::get_task_id_with_ctx(&ctx.0).await? {
             TardisResp::accepted(Some(task_id))
@@ -50,7 +50,7 @@ impl IamCsSpiDataApi {
         }
     }
 
-    async fn do_init_spi_data(funs: &TardisFunsInst, ctx: &TardisContext, is_modify: Box<bool>) -> TardisResult<()> {
+    async fn do_init_spi_data(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
         #[cfg(feature = "spi_kv")]
         {
             let task_ctx = ctx.clone();
@@ -117,41 +117,56 @@ impl IamCsSpiDataApi {
                     }
 
                     //account kv
-                    let list = IamAccountServ::find_items(
-                        &IamAccountFilterReq {
-                            basic: RbumBasicFilterReq {
-                                ignore_scope: false,
-                                rel_ctx_owner: false,
-                                own_paths: Some(task_ctx.own_paths.clone()),
-                                with_sub_own_paths: true,
-                                ..Default::default()
-                            },
-                            ..Default::default()
-                        },
-                        None,
-                        None,
-                        &funs,
-                        &task_ctx,
-                    )
-                    .await?;
-                    for account in list {
-                        let account_resp = IamAccountServ::get_account_detail_aggs(
-                            &account.id,
+                    // The accounts are added to search page by page, so that the requests are not held in memory all at once
+                    let mut page_number = 1;
+                    loop {
+                        let page = IamAccountServ::paginate_items(
                             &IamAccountFilterReq {
                                 basic: RbumBasicFilterReq {
-                                    ignore_scope: true,
+                                    ignore_scope: false,
+                                    rel_ctx_owner: false,
+                                    own_paths: Some(task_ctx.own_paths.clone()),
                                     with_sub_own_paths: true,
                                     ..Default::default()
                                 },
                                 ..Default::default()
                             },
-                            true,
-                            true,
+                            page_number,
+                            ACCOUNT_SEARCH_BULK_SIZE as u32,
+                            Some(false),
+                            None,
                             &funs,
                             &task_ctx,
                         )
                         .await?;
-                        IamAccountServ::add_or_modify_account_search(account_resp, is_modify.clone(), "", &funs, &task_ctx).await?;
+                        let mut add_reqs = Vec::with_capacity(page.records.len());
+                        for account in page.records {
+                            let account_resp = IamAccountServ::get_account_detail_aggs(
+                                &account.id,
+                                &IamAccountFilterReq {
+                                    basic: RbumBasicFilterReq {
+                                        ignore_scope: true,
+                                        with_sub_own_paths: true,
+                                        ..Default::default()
+                                    },
+                                    ..Default::default()
+                                },
+                                true,
+                                true,
+                                &funs,
+                                &task_ctx,
+                            )
+                            .await?;
+                            add_reqs.push(IamAccountServ::package_account_search_req(account_resp, "", &funs, &task_ctx).await?);
+                        }
+                        if add_reqs.is_empty() {
+                            break;
+                        }
+                        IamAccountServ::bulk_add_or_modify_account_search(add_reqs, &funs, &task_ctx).await?;
+                        if page_number as u64 * ACCOUNT_SEARCH_BULK_SIZE as u64 >= page.total_size {
+                            break;
+                        }
+                        page_number += 1;
                     }
                     funs.commit().await?;
                     Ok(())
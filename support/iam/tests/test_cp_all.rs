@@ -11,17 +11,20 @@ use bios_basic::rbum::serv::rbum_item_serv::RbumItemCrudOperation;
 use bios_iam::basic::dto::iam_account_dto::IamAccountSelfModifyReq;
 use bios_iam::basic::dto::iam_cert_conf_dto::IamCertConfUserPwdAddOrModifyReq;
 use bios_iam::basic::dto::iam_cert_dto::{IamCertMailVCodeAddReq, IamCertUserNameNewReq, IamCertUserPwdModifyReq, IamContextFetchReq};
+use bios_iam::basic::dto::iam_config_dto::IamConfigAggOrModifyReq;
 use bios_iam::basic::dto::iam_filer_dto::IamAccountFilterReq;
 use bios_iam::basic::dto::iam_tenant_dto::{IamTenantAggAddReq, IamTenantConfigReq};
 use bios_iam::basic::serv::iam_account_serv::IamAccountServ;
 use bios_iam::basic::serv::iam_cert_mail_vcode_serv::IamCertMailVCodeServ;
 use bios_iam::basic::serv::iam_cert_serv::IamCertServ;
+use bios_iam::basic::serv::iam_config_serv::IamConfigServ;
 use bios_iam::basic::serv::iam_key_cache_serv::IamIdentCacheServ;
 use bios_iam::basic::serv::iam_tenant_serv::IamTenantServ;
 use bios_iam::console_passport::dto::iam_cp_cert_dto::{IamCpMailVCodeLoginReq, IamCpUserPwdLoginReq};
 use bios_iam::console_passport::serv::iam_cp_cert_mail_vcode_serv::IamCpCertMailVCodeServ;
 use bios_iam::console_passport::serv::iam_cp_cert_user_pwd_serv::IamCpCertUserPwdServ;
 use bios_iam::iam_constants;
+use bios_iam::iam_enumeration::{IamConfigDataTypeKind, IamConfigKind};
 
 pub async fn test(sysadmin_info: (&str, &str), system_admin_context: &TardisContext) -> TardisResult<()> {
     let mut funs = iam_constants::get_tardis_inst();
@@ -104,6 +107,25 @@ pub async fn test(sysadmin_info: (&str, &str), system_admin_context: &TardisCont
     )
     .await?;
 
+    info!("【test_cp_all】 : Modify the audit log capacity, the capacity is too large");
+    assert!(IamConfigServ::add_or_modify_batch(
+        &tenant_id,
+        vec![IamConfigAggOrModifyReq {
+            name: None,
+            data_type: IamConfigDataTypeKind::Number,
+            note: None,
+            value1: Some(u64::MAX.to_string()),
+            value2: None,
+            ext: None,
+            disabled: None,
+            code: IamConfigKind::AuditLogCapacity,
+        }],
+        &funs,
+        tenant_ctx,
+    )
+    .await
+    .is_err());
+
     info!("【test_cp_all】 : Login by Username and Password, Password error");
     assert!(IamCpCertUserPwdServ::login_by_user_pwd(
         &IamCpUserPwdLoginReq {